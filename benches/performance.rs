@@ -11,20 +11,28 @@ fn benchmark_router_matching(c: &mut Criterion) {
         .dev_server("http://localhost:3000")
         .build();
 
-    let mut router =
+    let router =
         Router::new(config.routes().to_vec(), Mode::Development).expect("Failed to create router");
 
-    c.bench_function("router_match_cached", |b| {
+    c.bench_function("router_match", |b| {
         b.iter(|| {
             black_box(router.match_route("/app/dashboard"));
         })
     });
 
-    c.bench_function("router_match_uncached", |b| {
+    let mut many = Heisenberg::new().spa("./dist").pattern("/*").build();
+    for i in 0..50 {
+        many = many
+            .spa(format!("./app{}/dist", i))
+            .pattern(format!("/app{}/*", i))
+            .build();
+    }
+    let many_router =
+        Router::new(many.routes().to_vec(), Mode::Development).expect("Failed to create router");
+
+    c.bench_function("router_match_many_routes", |b| {
         b.iter(|| {
-            let mut fresh_router = Router::new(config.routes().to_vec(), Mode::Development)
-                .expect("Failed to create router");
-            black_box(fresh_router.match_route("/app/dashboard"));
+            black_box(many_router.match_route("/app42/users/settings"));
         })
     });
 }
@@ -41,7 +49,7 @@ fn benchmark_pattern_compilation(c: &mut Criterion) {
                 .pattern("/*")
                 .build();
 
-            black_box(Router::new(config.routes().to_vec(), Mode::Development));
+            let _ = black_box(Router::new(config.routes().to_vec(), Mode::Development));
         })
     });
 }
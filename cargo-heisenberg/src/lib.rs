@@ -0,0 +1,222 @@
+//! # cargo-heisenberg
+//!
+//! Scaffolds a Vite frontend into an existing Rust web project, along with the
+//! `build.rs` and `Heisenberg::new().spa(...)` wiring needed to serve it.
+//!
+//! The binary is a thin argument parser over [`scaffold`], which is exposed so
+//! the generated layout can be tested without spawning a process.
+
+#![warn(missing_docs)]
+
+pub mod templates;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Frontend template to scaffold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// Vite + React
+    React,
+    /// Vite + Svelte
+    Svelte,
+    /// Vite with plain JavaScript
+    Vanilla,
+}
+
+impl Template {
+    /// All available templates
+    pub const ALL: [Template; 3] = [Template::React, Template::Svelte, Template::Vanilla];
+
+    /// Template name as accepted on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Template::React => "react",
+            Template::Svelte => "svelte",
+            Template::Vanilla => "vanilla",
+        }
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Template {
+    type Err = ScaffoldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let name = name.strip_prefix("vite-").unwrap_or(&name);
+        Template::ALL
+            .into_iter()
+            .find(|template| template.name() == name)
+            .ok_or_else(|| {
+                ScaffoldError::usage(
+                    format!("Unknown template: {}", s),
+                    "• Available templates: react, svelte, vanilla\n• Example: cargo heisenberg init --template svelte",
+                )
+            })
+    }
+}
+
+/// Options for [`scaffold`]
+#[derive(Debug, Clone)]
+pub struct ScaffoldOptions {
+    /// Rust project root (the directory containing `Cargo.toml`)
+    pub project_dir: PathBuf,
+    /// Frontend directory, relative to the project root
+    pub frontend_dir: String,
+    /// Frontend template
+    pub template: Template,
+    /// Port the Vite dev server listens on
+    pub port: u16,
+    /// Overwrite existing files instead of refusing to scaffold
+    pub force: bool,
+}
+
+impl Default for ScaffoldOptions {
+    fn default() -> Self {
+        Self {
+            project_dir: PathBuf::from("."),
+            frontend_dir: "web".to_string(),
+            template: Template::React,
+            port: 5173,
+            force: false,
+        }
+    }
+}
+
+/// What [`scaffold`] did
+#[derive(Debug, Clone)]
+pub struct ScaffoldReport {
+    /// Files written, relative to the project root
+    pub created: Vec<PathBuf>,
+    /// Whether a `build.rs` was written (an existing one is never touched)
+    pub build_rs_written: bool,
+    /// Whether the project's Cargo.toml depends on axum
+    pub axum_detected: bool,
+    /// Rust code wiring the frontend into an Axum router
+    pub wiring: String,
+    /// `build.rs` contents for building the frontend in release mode
+    pub build_rs: String,
+}
+
+/// Errors produced while scaffolding
+#[derive(Error, Debug)]
+pub enum ScaffoldError {
+    /// Invalid command-line usage
+    #[error("{message}\n\nTroubleshooting:\n{hint}")]
+    Usage {
+        /// The error message
+        message: String,
+        /// Troubleshooting hint
+        hint: String,
+    },
+
+    /// The target project can't be scaffolded into
+    #[error("Cannot scaffold project: {message}\n\nTroubleshooting:\n{hint}")]
+    Project {
+        /// The error message
+        message: String,
+        /// Troubleshooting hint
+        hint: String,
+    },
+
+    /// IO error while writing files
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ScaffoldError {
+    /// Create a usage error with troubleshooting hints
+    pub fn usage(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self::Usage {
+            message: message.into(),
+            hint: hint.into(),
+        }
+    }
+
+    /// Create a project error with troubleshooting hints
+    pub fn project(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self::Project {
+            message: message.into(),
+            hint: hint.into(),
+        }
+    }
+}
+
+/// Scaffold a frontend and Heisenberg wiring into an existing Rust project
+pub fn scaffold(options: &ScaffoldOptions) -> Result<ScaffoldReport, ScaffoldError> {
+    let cargo_toml = options.project_dir.join("Cargo.toml");
+    let manifest = std::fs::read_to_string(&cargo_toml).map_err(|_| {
+        ScaffoldError::project(
+            format!("No Cargo.toml found in {}", options.project_dir.display()),
+            "• Run this command from the root of your Rust project\n• Create a project first with 'cargo new my-app'",
+        )
+    })?;
+
+    let frontend_dir = options.project_dir.join(&options.frontend_dir);
+    if !options.force && is_non_empty_dir(&frontend_dir) {
+        return Err(ScaffoldError::project(
+            format!("{} already exists and is not empty", frontend_dir.display()),
+            "• Choose another directory with --dir\n• Pass --force to overwrite the scaffolded files",
+        ));
+    }
+
+    let mut created = Vec::new();
+    for (path, content) in templates::files(options.template) {
+        let relative = Path::new(&options.frontend_dir).join(path);
+        write_file(
+            &options.project_dir.join(&relative),
+            &templates::render(content, options),
+        )?;
+        created.push(relative);
+    }
+
+    let build_rs = build_rs_snippet(options);
+    let build_rs_path = options.project_dir.join("build.rs");
+    let build_rs_written = !build_rs_path.exists();
+    if build_rs_written {
+        write_file(&build_rs_path, &build_rs)?;
+        created.push(PathBuf::from("build.rs"));
+    }
+
+    Ok(ScaffoldReport {
+        created,
+        build_rs_written,
+        axum_detected: manifest
+            .lines()
+            .any(|line| line.trim_start().starts_with("axum")),
+        wiring: wiring_snippet(options),
+        build_rs,
+    })
+}
+
+/// Rust code wiring the scaffolded frontend into an Axum router
+pub fn wiring_snippet(options: &ScaffoldOptions) -> String {
+    templates::render(templates::WIRING, options)
+}
+
+/// `build.rs` that builds the frontend whenever assets will be embedded
+pub fn build_rs_snippet(options: &ScaffoldOptions) -> String {
+    templates::render(templates::BUILD_RS, options)
+}
+
+fn is_non_empty_dir(path: &Path) -> bool {
+    std::fs::read_dir(path)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false)
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), ScaffoldError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
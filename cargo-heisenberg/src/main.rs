@@ -0,0 +1,136 @@
+//! `cargo heisenberg` command-line entry point
+
+use cargo_heisenberg::{scaffold, ScaffoldError, ScaffoldOptions};
+use std::process::ExitCode;
+
+const USAGE: &str = "Scaffold a frontend and Heisenberg wiring into an existing Rust project
+
+Usage: cargo heisenberg init [OPTIONS]
+
+Options:
+  --template <NAME>  Frontend template: react, svelte, vanilla [default: react]
+  --dir <PATH>       Frontend directory relative to the project root [default: web]
+  --port <PORT>      Vite dev server port [default: 5173]
+  --force            Overwrite files in an existing frontend directory
+  -h, --help         Print help";
+
+fn main() -> ExitCode {
+    // Cargo invokes subcommands as `cargo-heisenberg heisenberg <args>`
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .skip_while(|arg| arg == "heisenberg")
+        .collect();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), ScaffoldError> {
+    let (command, rest) = match args.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => ("--help", &[][..]),
+    };
+
+    match command {
+        "init" => {
+            let options = parse_init_options(rest)?;
+            init(&options)
+        }
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => Err(ScaffoldError::usage(
+            format!("Unknown command: {}", other),
+            "• Run 'cargo heisenberg init' to scaffold a frontend\n• Run 'cargo heisenberg --help' for all options",
+        )),
+    }
+}
+
+fn parse_init_options(args: &[String]) -> Result<ScaffoldOptions, ScaffoldError> {
+    let mut options = ScaffoldOptions::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next().cloned())
+                .ok_or_else(|| {
+                    ScaffoldError::usage(
+                        format!("Missing value for {}", name),
+                        format!("• Example: cargo heisenberg init {} <value>", name),
+                    )
+                })
+        };
+
+        match flag {
+            "--template" => options.template = value("--template")?.parse()?,
+            "--dir" => options.frontend_dir = value("--dir")?,
+            "--port" => {
+                let port = value("--port")?;
+                options.port = port.parse().map_err(|_| {
+                    ScaffoldError::usage(
+                        format!("Invalid port: {}", port),
+                        "• Ports must be numbers between 1 and 65535\n• Vite's default is 5173",
+                    )
+                })?;
+            }
+            "--force" => options.force = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => {
+                return Err(ScaffoldError::usage(
+                    format!("Unknown option: {}", other),
+                    "• Run 'cargo heisenberg --help' for all options",
+                ))
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+fn init(options: &ScaffoldOptions) -> Result<(), ScaffoldError> {
+    let report = scaffold(options)?;
+
+    println!(
+        "Scaffolded a {} frontend in {}/",
+        options.template, options.frontend_dir
+    );
+    for path in &report.created {
+        println!("  created {}", path.display());
+    }
+
+    if !report.build_rs_written {
+        println!("\nbuild.rs already exists, so it was left alone. Add this to it to build the frontend for release:\n");
+        println!("{}", report.build_rs);
+    }
+
+    println!("\nAdd Heisenberg to your dependencies:\n");
+    println!("  cargo add heisenberg");
+    if !report.axum_detected {
+        println!("  cargo add axum tokio --features tokio/full");
+    }
+
+    println!("\nThen wire the frontend into your router:\n");
+    println!("{}", report.wiring);
+
+    println!("Next steps:\n");
+    println!("  cd {} && npm install", options.frontend_dir);
+    println!("  cargo run");
+
+    Ok(())
+}
@@ -0,0 +1,275 @@
+//! File templates for scaffolded frontends
+//!
+//! Templates use `{{PORT}}` and `{{FRONTEND_DIR}}` placeholders, filled in by
+//! [`render`].
+
+use crate::{ScaffoldOptions, Template};
+
+/// Fill in template placeholders
+pub fn render(content: &str, options: &ScaffoldOptions) -> String {
+    content
+        .replace("{{PORT}}", &options.port.to_string())
+        .replace("{{FRONTEND_DIR}}", &options.frontend_dir)
+}
+
+/// Files for a template as `(path relative to the frontend dir, contents)`
+pub fn files(template: Template) -> Vec<(&'static str, &'static str)> {
+    let mut files = vec![(".gitignore", GITIGNORE)];
+    match template {
+        Template::React => files.extend([
+            ("package.json", REACT_PACKAGE_JSON),
+            ("vite.config.js", REACT_VITE_CONFIG),
+            ("index.html", REACT_INDEX_HTML),
+            ("src/main.jsx", REACT_MAIN),
+            ("src/App.jsx", REACT_APP),
+        ]),
+        Template::Svelte => files.extend([
+            ("package.json", SVELTE_PACKAGE_JSON),
+            ("vite.config.js", SVELTE_VITE_CONFIG),
+            ("index.html", VANILLA_INDEX_HTML),
+            ("src/main.js", SVELTE_MAIN),
+            ("src/App.svelte", SVELTE_APP),
+        ]),
+        Template::Vanilla => files.extend([
+            ("package.json", VANILLA_PACKAGE_JSON),
+            ("vite.config.js", VANILLA_VITE_CONFIG),
+            ("index.html", VANILLA_INDEX_HTML),
+            ("src/main.js", VANILLA_MAIN),
+        ]),
+    }
+    files
+}
+
+/// Axum wiring printed after scaffolding
+pub const WIRING: &str = r#"use axum::{routing::get, Router};
+use heisenberg::{Heisenberg, HeisenbergLayer};
+
+let heisenberg = Heisenberg::new()
+    .spa("./{{FRONTEND_DIR}}/dist")
+    .dev_server("http://localhost:{{PORT}}")
+    .dev_command(["npm", "run", "dev"])
+    .working_dir("./{{FRONTEND_DIR}}")
+    .build();
+
+let app = Router::new()
+    .route("/api/hello", get(|| async { "Hello API!" }))
+    .layer(HeisenbergLayer::new(heisenberg));
+"#;
+
+/// `build.rs` that builds the frontend when assets will be embedded
+pub const BUILD_RS: &str = r#"fn main() {
+    println!("cargo:rerun-if-env-changed=HEISENBERG_MODE");
+    println!("cargo:rerun-if-changed={{FRONTEND_DIR}}/src");
+    println!("cargo:rerun-if-changed={{FRONTEND_DIR}}/index.html");
+
+    let mode = std::env::var("HEISENBERG_MODE").unwrap_or_else(|_| {
+        if std::env::var("PROFILE").as_deref() == Ok("release") {
+            "embed".to_string()
+        } else {
+            "proxy".to_string()
+        }
+    });
+
+    if mode == "embed" {
+        let status = std::process::Command::new("npm")
+            .args(["run", "build"])
+            .current_dir("{{FRONTEND_DIR}}")
+            .status()
+            .expect("Failed to run `npm run build` in {{FRONTEND_DIR}}/");
+        assert!(status.success(), "Frontend build failed");
+    }
+}
+"#;
+
+const GITIGNORE: &str = "node_modules\ndist\n";
+
+const REACT_PACKAGE_JSON: &str = r#"{
+  "name": "frontend",
+  "private": true,
+  "version": "0.0.0",
+  "type": "module",
+  "scripts": {
+    "dev": "vite --port {{PORT}} --strictPort",
+    "build": "vite build",
+    "preview": "vite preview"
+  },
+  "dependencies": {
+    "react": "^18.3.1",
+    "react-dom": "^18.3.1"
+  },
+  "devDependencies": {
+    "@vitejs/plugin-react": "^4.3.1",
+    "vite": "^5.4.0"
+  }
+}
+"#;
+
+const REACT_VITE_CONFIG: &str = r#"import { defineConfig } from 'vite'
+import react from '@vitejs/plugin-react'
+
+export default defineConfig({
+  plugins: [react()],
+  server: {
+    port: {{PORT}},
+    strictPort: true,
+  },
+  build: {
+    outDir: 'dist',
+  },
+})
+"#;
+
+const REACT_INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="UTF-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+    <title>Heisenberg App</title>
+  </head>
+  <body>
+    <div id="root"></div>
+    <script type="module" src="/src/main.jsx"></script>
+  </body>
+</html>
+"#;
+
+const REACT_MAIN: &str = r#"import React from 'react'
+import ReactDOM from 'react-dom/client'
+import App from './App.jsx'
+
+ReactDOM.createRoot(document.getElementById('root')).render(
+  <React.StrictMode>
+    <App />
+  </React.StrictMode>,
+)
+"#;
+
+const REACT_APP: &str = r#"import { useEffect, useState } from 'react'
+
+export default function App() {
+  const [message, setMessage] = useState('Loading...')
+
+  useEffect(() => {
+    fetch('/api/hello')
+      .then((res) => res.text())
+      .then(setMessage)
+      .catch(() => setMessage('API unavailable'))
+  }, [])
+
+  return (
+    <main>
+      <h1>Heisenberg + React</h1>
+      <p>API says: {message}</p>
+    </main>
+  )
+}
+"#;
+
+const SVELTE_PACKAGE_JSON: &str = r#"{
+  "name": "frontend",
+  "private": true,
+  "version": "0.0.0",
+  "type": "module",
+  "scripts": {
+    "dev": "vite --port {{PORT}} --strictPort",
+    "build": "vite build",
+    "preview": "vite preview"
+  },
+  "devDependencies": {
+    "@sveltejs/vite-plugin-svelte": "^3.1.0",
+    "svelte": "^4.2.0",
+    "vite": "^5.4.0"
+  }
+}
+"#;
+
+const SVELTE_VITE_CONFIG: &str = r#"import { defineConfig } from 'vite'
+import { svelte } from '@sveltejs/vite-plugin-svelte'
+
+export default defineConfig({
+  plugins: [svelte()],
+  server: {
+    port: {{PORT}},
+    strictPort: true,
+  },
+  build: {
+    outDir: 'dist',
+  },
+})
+"#;
+
+const SVELTE_MAIN: &str = r#"import App from './App.svelte'
+
+const app = new App({
+  target: document.getElementById('app'),
+})
+
+export default app
+"#;
+
+const SVELTE_APP: &str = r#"<script>
+  let message = 'Loading...'
+
+  fetch('/api/hello')
+    .then((res) => res.text())
+    .then((text) => (message = text))
+    .catch(() => (message = 'API unavailable'))
+</script>
+
+<main>
+  <h1>Heisenberg + Svelte</h1>
+  <p>API says: {message}</p>
+</main>
+"#;
+
+const VANILLA_PACKAGE_JSON: &str = r#"{
+  "name": "frontend",
+  "private": true,
+  "version": "0.0.0",
+  "type": "module",
+  "scripts": {
+    "dev": "vite --port {{PORT}} --strictPort",
+    "build": "vite build",
+    "preview": "vite preview"
+  },
+  "devDependencies": {
+    "vite": "^5.4.0"
+  }
+}
+"#;
+
+const VANILLA_VITE_CONFIG: &str = r#"import { defineConfig } from 'vite'
+
+export default defineConfig({
+  server: {
+    port: {{PORT}},
+    strictPort: true,
+  },
+  build: {
+    outDir: 'dist',
+  },
+})
+"#;
+
+const VANILLA_INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="UTF-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1.0" />
+    <title>Heisenberg App</title>
+  </head>
+  <body>
+    <div id="app"></div>
+    <script type="module" src="/src/main.js"></script>
+  </body>
+</html>
+"#;
+
+const VANILLA_MAIN: &str = r#"const app = document.getElementById('app')
+app.innerHTML = '<h1>Heisenberg + Vite</h1><p>API says: <span id="message">Loading...</span></p>'
+
+fetch('/api/hello')
+  .then((res) => res.text())
+  .then((text) => (document.getElementById('message').textContent = text))
+  .catch(() => (document.getElementById('message').textContent = 'API unavailable'))
+"#;
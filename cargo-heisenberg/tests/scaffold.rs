@@ -0,0 +1,117 @@
+//! Tests for frontend scaffolding
+
+use cargo_heisenberg::{scaffold, ScaffoldError, ScaffoldOptions, Template};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn rust_project(manifest: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Cargo.toml"), manifest).unwrap();
+    dir
+}
+
+const AXUM_MANIFEST: &str = "[package]\nname = \"app\"\n\n[dependencies]\naxum = \"0.7\"\n";
+
+#[test]
+fn test_template_parsing() {
+    assert_eq!("react".parse::<Template>().unwrap(), Template::React);
+    assert_eq!("vite-svelte".parse::<Template>().unwrap(), Template::Svelte);
+    assert_eq!("Vanilla".parse::<Template>().unwrap(), Template::Vanilla);
+    assert!("angular".parse::<Template>().is_err());
+}
+
+#[test]
+fn test_scaffold_react_project() {
+    let project = rust_project(AXUM_MANIFEST);
+    let options = ScaffoldOptions {
+        project_dir: project.path().to_path_buf(),
+        port: 5180,
+        ..Default::default()
+    };
+
+    let report = scaffold(&options).unwrap();
+
+    assert!(report.created.contains(&PathBuf::from("web/package.json")));
+    assert!(report.created.contains(&PathBuf::from("web/src/App.jsx")));
+    assert!(report.created.contains(&PathBuf::from("build.rs")));
+    assert!(report.build_rs_written);
+    assert!(report.axum_detected);
+
+    let package_json = fs::read_to_string(project.path().join("web/package.json")).unwrap();
+    assert!(package_json.contains("vite --port 5180"));
+
+    let build_rs = fs::read_to_string(project.path().join("build.rs")).unwrap();
+    assert!(build_rs.contains(".current_dir(\"web\")"));
+
+    assert!(report.wiring.contains(".spa(\"./web/dist\")"));
+    assert!(report.wiring.contains("http://localhost:5180"));
+}
+
+#[test]
+fn test_scaffold_custom_dir_and_template() {
+    let project = rust_project("[package]\nname = \"app\"\n");
+    let options = ScaffoldOptions {
+        project_dir: project.path().to_path_buf(),
+        frontend_dir: "frontend".to_string(),
+        template: Template::Svelte,
+        ..Default::default()
+    };
+
+    let report = scaffold(&options).unwrap();
+
+    assert!(project.path().join("frontend/src/App.svelte").exists());
+    assert!(!report.axum_detected);
+    assert!(report.wiring.contains(".working_dir(\"./frontend\")"));
+}
+
+#[test]
+fn test_existing_build_rs_is_preserved() {
+    let project = rust_project(AXUM_MANIFEST);
+    fs::write(project.path().join("build.rs"), "fn main() {}\n").unwrap();
+
+    let options = ScaffoldOptions {
+        project_dir: project.path().to_path_buf(),
+        ..Default::default()
+    };
+    let report = scaffold(&options).unwrap();
+
+    assert!(!report.build_rs_written);
+    assert!(report.build_rs.contains("npm"));
+    assert_eq!(
+        fs::read_to_string(project.path().join("build.rs")).unwrap(),
+        "fn main() {}\n"
+    );
+}
+
+#[test]
+fn test_refuses_non_empty_frontend_dir() {
+    let project = rust_project(AXUM_MANIFEST);
+    fs::create_dir_all(project.path().join("web")).unwrap();
+    fs::write(project.path().join("web/keep.txt"), "mine").unwrap();
+
+    let mut options = ScaffoldOptions {
+        project_dir: project.path().to_path_buf(),
+        ..Default::default()
+    };
+    assert!(matches!(
+        scaffold(&options),
+        Err(ScaffoldError::Project { .. })
+    ));
+
+    options.force = true;
+    assert!(scaffold(&options).is_ok());
+    assert!(project.path().join("web/keep.txt").exists());
+}
+
+#[test]
+fn test_requires_cargo_project() {
+    let dir = TempDir::new().unwrap();
+    let options = ScaffoldOptions {
+        project_dir: dir.path().to_path_buf(),
+        ..Default::default()
+    };
+
+    let err = scaffold(&options).unwrap_err();
+    assert!(err.to_string().contains("No Cargo.toml found"));
+}
@@ -4,8 +4,11 @@
 //! with Actix-web applications.
 
 use crate::core::config::Heisenberg;
-use crate::core::mode::{detect_mode, Mode};
+use crate::core::mode::Mode;
+use crate::services::proxy::HOP_BY_HOP_HEADERS;
+use actix_web::body::SizedStream;
 use actix_web::{HttpRequest, HttpResponse, Result as ActixResult};
+use tokio_util::io::ReaderStream;
 
 /// Serve SPA content through Actix-web
 ///
@@ -48,7 +51,7 @@ use actix_web::{HttpRequest, HttpResponse, Result as ActixResult};
 /// ```
 pub async fn serve_spa(req: &HttpRequest, config: &Heisenberg) -> ActixResult<HttpResponse> {
     let path = req.path();
-    let mode = detect_mode();
+    let mode = config.mode();
 
     // Find matching route configuration
     let route_config = config
@@ -57,9 +60,38 @@ pub async fn serve_spa(req: &HttpRequest, config: &Heisenberg) -> ActixResult<Ht
         .find(|route| path_matches(&route.pattern, path))
         .ok_or_else(|| actix_web::error::ErrorNotFound("No matching SPA route found"))?;
 
-    match mode {
-        Mode::Development => proxy_request(req, route_config).await,
-        Mode::Production => serve_embedded_asset(path, route_config).await,
+    let mut response = match mode {
+        Mode::Development => proxy_request(req, route_config).await?,
+        Mode::Production => {
+            serve_embedded_asset(&route_config.static_path(path), route_config).await?
+        }
+    };
+    add_route_headers(&mut response, route_config);
+    Ok(response)
+}
+
+/// Set the headers configured with `.header()` on a route's response
+fn add_route_headers(
+    response: &mut HttpResponse,
+    route_config: &crate::core::config::SpaRouteConfig,
+) {
+    use actix_web::http::header::{HeaderName, HeaderValue};
+
+    let headers: Vec<_> = route_config
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            ))
+        })
+        .collect();
+    for (name, _) in &headers {
+        response.headers_mut().remove(name);
+    }
+    for (name, value) in headers {
+        response.headers_mut().append(name, value);
     }
 }
 
@@ -79,11 +111,19 @@ async fn proxy_request(
     req: &HttpRequest,
     route_config: &crate::core::config::SpaRouteConfig,
 ) -> ActixResult<HttpResponse> {
-    let client = reqwest::Client::new();
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Proxy error: {}", e)))?;
     let target_url = format!("{}{}", route_config.dev_proxy_url, req.path());
 
-    let response = client
-        .get(&target_url)
+    // Forward the browser's cookies so dev server sessions work
+    let mut request = client.get(&target_url);
+    for value in req.headers().get_all(actix_web::http::header::COOKIE) {
+        request = request.header(reqwest::header::COOKIE, value.as_bytes());
+    }
+
+    let response = request
         .send()
         .await
         .map_err(|e| actix_web::error::ErrorBadGateway(format!("Proxy error: {}", e)))?;
@@ -100,15 +140,18 @@ async fn proxy_request(
             .unwrap_or(actix_web::http::StatusCode::INTERNAL_SERVER_ERROR),
     );
 
-    // Copy relevant headers
+    // Copy end-to-end headers, appending so every `Set-Cookie` survives
     for (name, value) in headers.iter() {
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
         if let Ok(header_name) =
             actix_web::http::header::HeaderName::from_bytes(name.as_str().as_bytes())
         {
             if let Ok(header_value) =
                 actix_web::http::header::HeaderValue::from_bytes(value.as_bytes())
             {
-                actix_response.insert_header((header_name, header_value));
+                actix_response.append_header((header_name, header_value));
             }
         }
     }
@@ -123,51 +166,59 @@ async fn serve_embedded_asset(
 ) -> ActixResult<HttpResponse> {
     // Normalize the path - remove leading slash and handle root
     let file_path = if path == "/" || path.is_empty() {
-        route_config.fallback_file.as_deref().unwrap_or("index.html")
+        route_config
+            .fallback_file
+            .as_deref()
+            .unwrap_or("index.html")
     } else {
         path.strip_prefix('/').unwrap_or(path)
     };
-    
+
     // Build full file path
     let full_path = route_config.embed_dir.join(file_path);
-    
-    // Try to read the file
-    match tokio::fs::read(&full_path).await {
-        Ok(contents) => {
-            // Determine content type from file extension
-            let content_type = match full_path.extension().and_then(|ext| ext.to_str()) {
-                Some("html") => "text/html; charset=utf-8",
-                Some("css") => "text/css; charset=utf-8", 
-                Some("js") => "application/javascript; charset=utf-8",
-                Some("json") => "application/json; charset=utf-8",
-                Some("png") => "image/png",
-                Some("jpg") | Some("jpeg") => "image/jpeg",
-                Some("gif") => "image/gif",
-                Some("svg") => "image/svg+xml",
-                Some("ico") => "image/x-icon",
-                Some("woff") => "font/woff",
-                Some("woff2") => "font/woff2",
-                Some("ttf") => "font/ttf",
-                _ => "application/octet-stream",
-            };
-            
-            Ok(HttpResponse::Ok()
-                .content_type(content_type)
-                .body(contents))
-        }
-        Err(_) => {
-            // File not found, try fallback for SPA routing
-            if let Some(fallback) = &route_config.fallback_file {
-                let fallback_path = route_config.embed_dir.join(fallback);
-                match tokio::fs::read(&fallback_path).await {
-                    Ok(contents) => Ok(HttpResponse::Ok()
-                        .content_type("text/html; charset=utf-8")
-                        .body(contents)),
-                    Err(_) => Err(actix_web::error::ErrorNotFound("File not found"))
-                }
-            } else {
-                Err(actix_web::error::ErrorNotFound("File not found"))
-            }
+
+    // Determine content type from file extension
+    let content_type = match full_path.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "application/javascript; charset=utf-8",
+        Some("json") => "application/json; charset=utf-8",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        _ => "application/octet-stream",
+    };
+
+    if let Some(response) = stream_file(&full_path, content_type).await {
+        return Ok(response);
+    }
+
+    // File not found, try fallback for SPA routing
+    if let Some(fallback) = &route_config.fallback_file {
+        let fallback_path = route_config.embed_dir.join(fallback);
+        if let Some(response) = stream_file(&fallback_path, "text/html; charset=utf-8").await {
+            return Ok(response);
         }
     }
+
+    Err(actix_web::error::ErrorNotFound("File not found"))
+}
+
+/// Stream a file from disk in chunks, with its length as `Content-Length`
+///
+/// Returns `None` if the path isn't a readable file.
+async fn stream_file(path: &std::path::Path, content_type: &str) -> Option<HttpResponse> {
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let metadata = file.metadata().await.ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let body = SizedStream::new(metadata.len(), ReaderStream::new(file));
+    Some(HttpResponse::Ok().content_type(content_type).body(body))
 }
@@ -4,7 +4,8 @@
 //! with Rocket applications.
 
 use crate::core::config::Heisenberg;
-use crate::core::mode::{detect_mode, Mode};
+use crate::core::mode::Mode;
+use crate::services::proxy::HOP_BY_HOP_HEADERS;
 use rocket::response::{Responder, Response};
 use rocket::Request;
 use std::io::Cursor;
@@ -52,7 +53,7 @@ pub async fn serve_spa(
     config: &Heisenberg,
 ) -> Result<RocketResponse, rocket::http::Status> {
     let path_str = path.to_string_lossy();
-    let mode = detect_mode();
+    let mode = config.mode();
 
     // Find matching route configuration
     let route_config = config
@@ -61,9 +62,24 @@ pub async fn serve_spa(
         .find(|route| path_matches(&route.pattern, &path_str))
         .ok_or(rocket::http::Status::NotFound)?;
 
-    match mode {
-        Mode::Development => proxy_request(path, route_config).await,
-        Mode::Production => serve_embedded_asset(path, route_config).await,
+    let mut response = match mode {
+        Mode::Development => proxy_request(path, route_config).await?,
+        Mode::Production => serve_embedded_asset(path, route_config).await?,
+    };
+    add_route_headers(&mut response.inner, route_config);
+    Ok(response)
+}
+
+/// Set the headers configured with `.header()` on a route's response
+fn add_route_headers(
+    response: &mut Response<'static>,
+    route_config: &crate::core::config::SpaRouteConfig,
+) {
+    for (name, _) in &route_config.headers {
+        response.remove_header(name);
+    }
+    for (name, value) in &route_config.headers {
+        response.adjoin_raw_header(name.clone(), value.clone());
     }
 }
 
@@ -94,7 +110,10 @@ async fn proxy_request(
     path: &Path,
     route_config: &crate::core::config::SpaRouteConfig,
 ) -> Result<RocketResponse, rocket::http::Status> {
-    let client = reqwest::Client::new();
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(|_| rocket::http::Status::InternalServerError)?;
     let path_str = path.to_string_lossy();
     let target_url = format!(
         "{}/{}",
@@ -109,6 +128,7 @@ async fn proxy_request(
         .map_err(|_| rocket::http::Status::BadGateway)?;
 
     let status_code = response.status().as_u16();
+    let headers = response.headers().clone();
     let body = response
         .bytes()
         .await
@@ -117,10 +137,20 @@ async fn proxy_request(
     let rocket_status = rocket::http::Status::from_code(status_code)
         .unwrap_or(rocket::http::Status::InternalServerError);
 
-    let response = Response::build()
-        .status(rocket_status)
-        .sized_body(body.len(), Cursor::new(body))
-        .finalize();
+    let mut builder = Response::build();
+    builder.status(rocket_status);
+
+    // Copy end-to-end headers, adjoining so every `Set-Cookie` survives
+    for (name, value) in headers.iter() {
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        if let Ok(value) = value.to_str() {
+            builder.raw_header_adjoin(name.as_str().to_string(), value.to_string());
+        }
+    }
+
+    let response = builder.sized_body(body.len(), Cursor::new(body)).finalize();
 
     Ok(RocketResponse { inner: response })
 }
@@ -0,0 +1,258 @@
+//! Building a configuration from environment variables
+//!
+//! For a generic binary whose SPA wiring is injected by the orchestrator, see
+//! [`Heisenberg::from_env`].
+
+use crate::core::config::{Heisenberg, SpaRouteBuilder};
+use crate::error::HeisenbergError;
+use crate::utils::package_json::{parse_command, ScriptCommand};
+use serde_json::Value;
+use std::net::SocketAddr;
+
+/// Route options `HEISENBERG_ROUTES` accepts in JSON
+const ROUTE_KEYS: &[&str] = &[
+    "dir",
+    "pattern",
+    "dev_server",
+    "dev_command",
+    "dev_shell",
+    "working_dir",
+    "fallback_file",
+    "fallback_dev_server",
+    "staging_url",
+    "build_command",
+    "open_browser",
+    "preserve_host",
+];
+
+impl Heisenberg {
+    /// Build a configuration from environment variables alone.
+    ///
+    /// `HEISENBERG_ROUTES` lists the routes, either as JSON or as
+    /// `PATTERN=DIR[@DEV_SERVER]` entries separated by `;` or newlines:
+    ///
+    /// ```text
+    /// HEISENBERG_ROUTES='/admin/*=./admin/dist@http://localhost:5174;/*=./dist'
+    /// HEISENBERG_ROUTES='[{"pattern": "/admin/*", "dir": "./admin/dist", "dev_command": "npm run dev"}]'
+    /// ```
+    ///
+    /// JSON routes are objects, or a single one, with a required `dir` and
+    /// optional `pattern`, `dev_server`, `dev_command` and `build_command`
+    /// (a string split like a shell would, or an array of arguments), with
+    /// variables a `dev_command` string assigns set for the dev server,
+    /// `dev_shell`, `working_dir`, `fallback_file`,
+    /// `fallback_dev_server`, `staging_url`, `open_browser` and
+    /// `preserve_host`. Anything a route leaves out is inferred as for
+    /// [`spa`](Self::spa). `HEISENBERG_SERVER_ADDR` sets the
+    /// [server address](Self::server_addr), and the variables read by every
+    /// configuration, like `HEISENBERG_MODE`, apply as usual.
+    ///
+    /// The configuration is validated as by
+    /// [`try_build`](SpaRouteBuilder::try_build).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::Heisenberg;
+    ///
+    /// std::env::set_var("HEISENBERG_ROUTES", "/admin/*=./admin/dist@http://localhost:5174");
+    /// let config = Heisenberg::from_env().unwrap();
+    /// assert_eq!(config.routes()[0].pattern, "/admin/*");
+    /// assert_eq!(config.routes()[0].dev_proxy_url, "http://localhost:5174");
+    /// ```
+    pub fn from_env() -> Result<Self, HeisenbergError> {
+        let routes = std::env::var("HEISENBERG_ROUTES").unwrap_or_default();
+        let mut config = Heisenberg::new();
+        if let Ok(addr) = std::env::var("HEISENBERG_SERVER_ADDR") {
+            let addr: SocketAddr = addr.trim().parse().map_err(|_| {
+                HeisenbergError::config(
+                    format!("HEISENBERG_SERVER_ADDR is not a socket address: {}", addr),
+                    "• Use an address and port, like '0.0.0.0:3000' or '[::1]:3000'",
+                )
+            })?;
+            config = config.server_addr(addr);
+        }
+
+        let trimmed = routes.trim();
+        let builder = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            json_routes(config, trimmed)?
+        } else {
+            dsl_routes(config, trimmed)?
+        };
+        builder
+            .ok_or_else(|| {
+                HeisenbergError::config(
+                    "HEISENBERG_ROUTES lists no routes",
+                    "• Set HEISENBERG_ROUTES to entries like '/*=./dist@http://localhost:5173'\n• Or to a JSON array like '[{\"dir\": \"./dist\"}]'",
+                )
+            })?
+            .try_build()
+    }
+}
+
+/// Add the routes of `PATTERN=DIR[@DEV_SERVER]` entries
+fn dsl_routes(
+    mut config: Heisenberg,
+    routes: &str,
+) -> Result<Option<SpaRouteBuilder>, HeisenbergError> {
+    let mut builder = None;
+    for entry in routes
+        .split([';', '\n'])
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let Some((pattern, target)) = entry.split_once('=') else {
+            return Err(HeisenbergError::config(
+                format!("HEISENBERG_ROUTES entry has no '=': {}", entry),
+                "• Write entries as PATTERN=DIR or PATTERN=DIR@DEV_SERVER, like '/admin/*=./admin/dist@http://localhost:5174'\n• Separate entries with ';'",
+            ));
+        };
+        let (dir, dev_server) = match target.split_once('@') {
+            Some((dir, dev_server)) => (dir, Some(dev_server.trim())),
+            None => (target, None),
+        };
+        let mut route = add_route(&mut config, builder.take(), dir.trim()).pattern(pattern.trim());
+        if let Some(dev_server) = dev_server {
+            route = route.dev_server(dev_server);
+        }
+        builder = Some(route);
+    }
+    Ok(builder)
+}
+
+/// Add the routes of a JSON array of route objects, or a single one
+fn json_routes(
+    mut config: Heisenberg,
+    routes: &str,
+) -> Result<Option<SpaRouteBuilder>, HeisenbergError> {
+    let routes: Value = serde_json::from_str(routes).map_err(|e| {
+        HeisenbergError::config(
+            format!("HEISENBERG_ROUTES is not valid JSON: {}", e),
+            "• Check the quoting; shells need the JSON in single quotes\n• Use an array of objects like '[{\"dir\": \"./dist\"}]'",
+        )
+        .with_source(e)
+    })?;
+    let routes = match routes {
+        Value::Array(routes) => routes,
+        route => vec![route],
+    };
+
+    let mut builder = None;
+    for route in &routes {
+        let error = |message: String| {
+            HeisenbergError::config(
+                message,
+                format!(
+                    "• Each route is an object with a \"dir\" and optional {}",
+                    ROUTE_KEYS[1..].join(", ")
+                ),
+            )
+        };
+        let Some(object) = route.as_object() else {
+            return Err(error(format!(
+                "HEISENBERG_ROUTES route is not an object: {}",
+                route
+            )));
+        };
+        if let Some(key) = object
+            .keys()
+            .find(|key| !ROUTE_KEYS.contains(&key.as_str()))
+        {
+            return Err(error(format!(
+                "Unknown HEISENBERG_ROUTES route option: {}",
+                key
+            )));
+        }
+        let text = |key: &str| match object.get(key) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.as_str())),
+            Some(value) => Err(error(format!("\"{}\" must be a string: {}", key, value))),
+        };
+        let flag = |key: &str| match object.get(key) {
+            None => Ok(None),
+            Some(Value::Bool(value)) => Ok(Some(*value)),
+            Some(value) => Err(error(format!(
+                "\"{}\" must be true or false: {}",
+                key, value
+            ))),
+        };
+        let command = |key: &str| match object.get(key) {
+            None => Ok(None),
+            Some(Value::String(line)) => Ok(Some(parse_command(line))),
+            Some(Value::Array(args)) if args.iter().all(Value::is_string) => {
+                Ok(Some(ScriptCommand {
+                    env: Vec::new(),
+                    args: args
+                        .iter()
+                        .filter_map(|arg| arg.as_str().map(String::from))
+                        .collect(),
+                }))
+            }
+            Some(value) => Err(error(format!(
+                "\"{}\" must be a string or an array of strings: {}",
+                key, value
+            ))),
+        };
+
+        let Some(dir) = text("dir")? else {
+            return Err(error(format!(
+                "HEISENBERG_ROUTES route has no \"dir\": {}",
+                route
+            )));
+        };
+        let mut spa = add_route(&mut config, builder.take(), dir);
+        if let Some(pattern) = text("pattern")? {
+            spa = spa.pattern(pattern);
+        }
+        if let Some(url) = text("dev_server")? {
+            spa = spa.dev_server(url);
+        }
+        if let Some(dev_command) = command("dev_command")? {
+            spa = spa.dev_command(dev_command.args).dev_env(dev_command.env);
+        }
+        if let Some(script) = text("dev_shell")? {
+            spa = spa.dev_shell(script);
+        }
+        if let Some(dir) = text("working_dir")? {
+            spa = spa.working_dir(dir);
+        }
+        if let Some(file) = text("fallback_file")? {
+            spa = spa.fallback_file(file);
+        }
+        if let Some(url) = text("fallback_dev_server")? {
+            spa = spa.fallback_dev_server(url);
+        }
+        if let Some(url) = text("staging_url")? {
+            spa = spa.staging_url(url);
+        }
+        if let Some(build_command) = command("build_command")? {
+            if !build_command.env.is_empty() {
+                return Err(error(
+                    "\"build_command\" can't assign variables; set them in the environment"
+                        .to_string(),
+                ));
+            }
+            spa = spa.build_command(build_command.args);
+        }
+        if let Some(open) = flag("open_browser")? {
+            spa = spa.open_browser(open);
+        }
+        if let Some(preserve) = flag("preserve_host")? {
+            spa = spa.preserve_host(preserve);
+        }
+        builder = Some(spa);
+    }
+    Ok(builder)
+}
+
+/// Add a route after the one being built, or else the first one to `config`
+fn add_route(
+    config: &mut Heisenberg,
+    builder: Option<SpaRouteBuilder>,
+    dir: &str,
+) -> SpaRouteBuilder {
+    match builder {
+        Some(builder) => builder.spa(dir),
+        None => std::mem::take(config).spa(dir),
+    }
+}
@@ -0,0 +1,75 @@
+//! Lifecycle events
+
+use crate::core::mode::Mode;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before the slowest one starts missing them
+pub(crate) const EVENT_CAPACITY: usize = 64;
+
+/// Something that happened inside Heisenberg, delivered to every receiver
+/// returned by [`Heisenberg::events`](crate::Heisenberg::events)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HeisenbergEvent {
+    /// A layer resolved the operating mode
+    ModeDetected {
+        /// The mode requests are served in
+        mode: Mode,
+    },
+    /// A dev server process was spawned
+    ProcessStarted {
+        /// Route pattern the dev server belongs to
+        pattern: String,
+        /// Process id of the dev command
+        pid: u32,
+    },
+    /// A dev server process exited or was stopped
+    ProcessExited {
+        /// Route pattern the dev server belongs to
+        pattern: String,
+        /// Exit code, if the process exited normally
+        code: Option<i32>,
+    },
+    /// A dev server announced it listens on a different port than configured,
+    /// and requests are now proxied there
+    DevServerMoved {
+        /// Route pattern the dev server belongs to
+        pattern: String,
+        /// URL requests are proxied to from now on
+        dev_server: String,
+    },
+    /// A dev server was first seen responding or not, or that changed
+    HealthChanged {
+        /// URL of the dev server
+        dev_server: String,
+        /// Whether it responds now
+        healthy: bool,
+    },
+    /// A production build directory is missing or older than its sources
+    OutdatedBuild {
+        /// Route pattern the build belongs to
+        pattern: String,
+        /// The source file newer than the build, or `None` if it is missing
+        newest_source: Option<std::path::PathBuf>,
+    },
+    /// A request couldn't be proxied to the dev server
+    ProxyError {
+        /// URL of the dev server
+        dev_server: String,
+        /// What went wrong
+        error: String,
+    },
+}
+
+/// Sending half shared by everything built from one configuration
+pub(crate) type EventSender = broadcast::Sender<HeisenbergEvent>;
+
+/// Create a channel nobody is subscribed to yet
+pub(crate) fn channel() -> EventSender {
+    broadcast::channel(EVENT_CAPACITY).0
+}
+
+/// Send an event, ignoring that there may be no receivers
+pub(crate) fn emit(events: &EventSender, event: HeisenbergEvent) {
+    let _ = events.send(event);
+}
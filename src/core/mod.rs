@@ -1,5 +1,8 @@
 //! Core Heisenberg functionality
 
 pub mod config;
+mod env_config;
+pub mod events;
 pub mod mode;
 pub mod router;
+pub mod stats;
@@ -1,7 +1,7 @@
 //! Mode detection for development vs production
 
 /// Operating mode for Heisenberg
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     /// Development mode - proxy to dev servers
     Development,
@@ -15,7 +15,7 @@ pub fn detect_mode() -> Mode {
     if let Ok(mode) = std::env::var("HEISENBERG_MODE") {
         match mode.to_lowercase().as_str() {
             "production" | "prod" | "embed" => return Mode::Production,
-            "development" | "dev" | "proxy" => return Mode::Development,
+            "development" | "dev" | "proxy" | "staging" | "stage" => return Mode::Development,
             _ => {} // Fall through to default detection
         }
     }
@@ -27,3 +27,96 @@ pub fn detect_mode() -> Mode {
         Mode::Production
     }
 }
+
+/// Check whether the process runs attached to a terminal
+///
+/// `HEISENBERG_INTERACTIVE` decides when set: `1`, `true` or `yes` force
+/// interactive behavior on and `0`, `false` or `no` turn it off. Otherwise
+/// this is whether stdout is a terminal, which it isn't under CI runners,
+/// systemd or `docker logs`.
+pub fn detect_interactive() -> bool {
+    if let Ok(value) = std::env::var("HEISENBERG_INTERACTIVE") {
+        match value.trim().to_lowercase().as_str() {
+            "1" | "true" | "yes" => return true,
+            "0" | "false" | "no" => return false,
+            _ => {}
+        }
+    }
+    std::io::IsTerminal::is_terminal(&std::io::stdout())
+}
+
+/// Check whether `HEISENBERG_CI` asks for CI mode
+///
+/// Accepts `1`, `true` or `yes` (case-insensitive); anything else, including
+/// an unset variable, leaves CI mode off.
+pub fn detect_ci_mode() -> bool {
+    std::env::var("HEISENBERG_CI")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Check whether `HEISENBERG_OFFLINE` asks for offline mode
+///
+/// Accepts the same values as [`detect_ci_mode`].
+pub fn detect_offline_mode() -> bool {
+    std::env::var("HEISENBERG_OFFLINE")
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Routes `HEISENBERG_DISABLE_ROUTES` asks to leave out in development
+///
+/// A comma-separated list of route patterns such as `/docs/*`, or their
+/// prefixes with or without the slash, such as `admin`. See
+/// [`SpaRouteConfig::is_named`](crate::core::config::SpaRouteConfig::is_named).
+pub fn detect_disabled_routes() -> Vec<String> {
+    std::env::var("HEISENBERG_DISABLE_ROUTES")
+        .map(|value| {
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check whether `HEISENBERG_MODE` selects the staging profile
+///
+/// `staging` or `stage` (case-insensitive) proxy routes with a staging URL
+/// to it; see [`Heisenberg::staging`](crate::Heisenberg::staging).
+pub fn detect_staging() -> bool {
+    std::env::var("HEISENBERG_MODE")
+        .map(|mode| matches!(mode.trim().to_lowercase().as_str(), "staging" | "stage"))
+        .unwrap_or(false)
+}
+
+/// Check whether the process runs in a Docker or Podman container
+///
+/// Looks for the `/.dockerenv` and `/run/.containerenv` files they create.
+pub fn detect_container() -> bool {
+    std::path::Path::new("/.dockerenv").exists()
+        || std::path::Path::new("/run/.containerenv").exists()
+}
+
+/// Check whether the process runs under Windows Subsystem for Linux
+///
+/// WSL sets `WSL_DISTRO_NAME`, and its kernel names itself in
+/// `/proc/sys/kernel/osrelease`, as in `5.15.153.1-microsoft-standard-WSL2`.
+pub fn detect_wsl() -> bool {
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|release| release.to_lowercase().contains("microsoft"))
+}
+
+/// The host `HEISENBERG_DOCKER_HOST` names for dev servers on `localhost`
+///
+/// Empty or whitespace values count as unset. See
+/// [`DockerHost`](crate::core::config::DockerHost).
+pub fn detect_docker_host() -> Option<String> {
+    std::env::var("HEISENBERG_DOCKER_HOST")
+        .ok()
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+}
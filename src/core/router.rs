@@ -7,22 +7,34 @@ use crate::error::HeisenbergError;
 use tracing::{debug, info, warn};
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
 
 /// Router for matching requests to SPA routes
+///
+/// Patterns are compiled into a trie keyed by path segment, so a lookup walks
+/// the request path once regardless of how many routes are registered.
+///
+/// A segment like `:tenant` matches any one path segment, capturing it as a
+/// [`RouteParams`] value; literal segments win over parameters, so
+/// `/admin/*` still serves `/admin/app/` next to `/:tenant/app/*`.
 #[derive(Debug, Clone)]
 pub struct Router {
-    routes: Vec<RouteEntry>,
+    routes: Vec<SpaRouteConfig>,
     mode: Mode,
-    pattern_cache: HashMap<String, usize>, // Cache for pattern matching
+    root: TrieNode,
 }
 
-/// Internal route entry with compiled matcher
-#[derive(Debug, Clone)]
-struct RouteEntry {
-    matcher: PathMatcher,
-    config: SpaRouteConfig,
-    #[allow(dead_code)] // Will be used for debugging/logging
-    priority: usize, // Lower number = higher priority
+/// One path segment in the route trie
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    children: HashMap<String, TrieNode>,
+    /// Route whose exact pattern ends at this node
+    exact: Option<usize>,
+    /// Route whose prefix pattern covers this node and everything below it
+    prefix: Option<usize>,
+    /// Routes continuing with a `:name` segment here
+    param: Option<Box<TrieNode>>,
 }
 
 /// Path matcher for route patterns
@@ -45,6 +57,126 @@ pub enum RouteHandler {
     StaticFiles(SpaRouteConfig),
 }
 
+/// Which kind of handler served a request
+///
+/// The Tower service records this in the extensions of every response it
+/// produces itself; responses from the inner service carry none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerKind {
+    /// Proxied to the development server
+    Proxy,
+    /// Served from the built frontend assets
+    StaticFiles,
+}
+
+/// Values captured by the `:name` segments of a route pattern
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::core::config::SpaRouteConfig;
+///
+/// let route = SpaRouteConfig {
+///     pattern: "/:tenant/app/*".to_string(),
+///     ..Default::default()
+/// };
+/// let params = route.params("/acme/app/settings");
+/// assert_eq!(params.get("tenant"), Some("acme"));
+/// assert_eq!(params.get("app"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteParams {
+    values: Vec<(String, String)>,
+}
+
+impl RouteParams {
+    /// The segment captured by the parameter `name`, as it appears in the
+    /// request path
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every parameter with its captured segment, in pattern order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values
+            .iter()
+            .map(|(param, value)| (param.as_str(), value.as_str()))
+    }
+
+    /// Whether the pattern has no parameters
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl FromIterator<(String, String)> for RouteParams {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Where a request on a route is served from, for a [`ParamResolver`] to
+/// change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteTarget {
+    /// Directory static files are served from in production
+    pub embed_dir: PathBuf,
+    /// Path and query requested from the dev server in development
+    pub upstream_path: String,
+}
+
+/// A callback that picks where requests are served from by the values a
+/// route's pattern captures
+///
+/// See [`SpaRouteBuilder::resolve_params`](crate::core::config::SpaRouteBuilder::resolve_params).
+#[derive(Clone)]
+pub struct ParamResolver(Arc<ResolveFn>);
+
+type ResolveFn = dyn Fn(&RouteParams, &mut RouteTarget) + Send + Sync;
+
+impl ParamResolver {
+    /// Wrap a callback taking the captured values and the target to change
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(&RouteParams, &mut RouteTarget) + Send + Sync + 'static,
+    {
+        Self(Arc::new(resolve))
+    }
+
+    /// Let the callback change `target` for the values in `params`
+    pub fn resolve(&self, params: &RouteParams, target: &mut RouteTarget) {
+        (self.0)(params, target)
+    }
+}
+
+impl std::fmt::Debug for ParamResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ParamResolver(..)")
+    }
+}
+
+impl RouteHandler {
+    /// The kind of this handler
+    pub fn kind(&self) -> HandlerKind {
+        match self {
+            RouteHandler::Proxy(_) => HandlerKind::Proxy,
+            RouteHandler::StaticFiles(_) => HandlerKind::StaticFiles,
+        }
+    }
+
+    /// The route configuration this handler serves
+    pub fn config(&self) -> &SpaRouteConfig {
+        match self {
+            RouteHandler::Proxy(config) | RouteHandler::StaticFiles(config) => config,
+        }
+    }
+}
+
 impl Router {
     /// Create a new router with the given routes and mode
     pub fn new(routes: Vec<SpaRouteConfig>, mode: Mode) -> Result<Self, HeisenbergError> {
@@ -55,75 +187,89 @@ impl Router {
             "Creating Heisenberg router"
         );
 
-        // Validate routes for conflicts at build time
-        Self::validate_routes(&routes)?;
+        let router = Self::compile(routes, mode)?;
 
-        let mut route_entries = Vec::new();
+        #[cfg(feature = "logging")]
+        info!(
+            route_count = router.routes.len(),
+            "Router created successfully"
+        );
+
+        Ok(router)
+    }
 
-        // Sort routes by specificity (most specific first)
-        let mut sorted_routes: Vec<_> = routes.into_iter().enumerate().collect();
-        sorted_routes.sort_by(|(_, a), (_, b)| {
-            Self::route_priority(&a.pattern).cmp(&Self::route_priority(&b.pattern))
-        });
+    /// Validate routes and build the trie
+    fn compile(routes: Vec<SpaRouteConfig>, mode: Mode) -> Result<Self, HeisenbergError> {
+        // Validate routes for conflicts at build time
+        Self::validate_routes(&routes)?;
 
-        for (original_index, route) in sorted_routes {
+        let mut root = TrieNode::default();
+        for (index, route) in routes.iter().enumerate() {
             let matcher = Self::compile_pattern(&route.pattern)?;
             #[cfg(feature = "logging")]
             debug!(
                 pattern = %route.pattern,
                 embed_dir = %route.embed_dir.display(),
                 dev_proxy_url = %route.dev_proxy_url,
-                priority = original_index,
+                priority = index,
                 "Registered route"
             );
-            route_entries.push(RouteEntry {
-                matcher,
-                config: route,
-                priority: original_index,
-            });
+            root.insert(&matcher, index);
         }
 
-        #[cfg(feature = "logging")]
-        info!(
-            route_count = route_entries.len(),
-            "Router created successfully"
-        );
+        Ok(Self { routes, mode, root })
+    }
+
+    /// Add a route, validated as [`Router::new`] validates routes
+    ///
+    /// On error the router is left unchanged.
+    pub fn insert(&mut self, route: SpaRouteConfig) -> Result<(), HeisenbergError> {
+        let mut routes = self.routes.clone();
+        routes.push(route);
+        *self = Self::compile(routes, self.mode)?;
+        Ok(())
+    }
 
-        Ok(Self {
-            routes: route_entries,
-            mode,
-            pattern_cache: HashMap::new(),
-        })
+    /// Remove the route with this pattern, returning it
+    pub fn remove(&mut self, pattern: &str) -> Option<SpaRouteConfig> {
+        let index = self
+            .routes
+            .iter()
+            .position(|route| route.pattern == pattern)?;
+        let mut routes = self.routes.clone();
+        let removed = routes.remove(index);
+        // The remaining routes were valid together, and stay so without one
+        *self = Self::compile(routes, self.mode).ok()?;
+        Some(removed)
     }
 
     /// Match a request path to a route
-    pub fn match_route(&mut self, path: &str) -> Option<&SpaRouteConfig> {
-        // Check cache first
-        if let Some(&route_index) = self.pattern_cache.get(path) {
-            #[cfg(feature = "logging")]
-            debug!(path = %path, route_index, "Route match found in cache");
-            return self.routes.get(route_index).map(|entry| &entry.config);
-        }
+    ///
+    /// Exact patterns win over prefix patterns, and longer prefixes over
+    /// shorter ones, with the catch-all `/*` matching last.
+    pub fn match_route(&self, path: &str) -> Option<&SpaRouteConfig> {
+        let matched = self
+            .root
+            .lookup(path)
+            .and_then(|index| self.routes.get(index));
 
-        // Find matching route
-        for (index, entry) in self.routes.iter().enumerate() {
-            if entry.matcher.matches(path) {
-                #[cfg(feature = "logging")]
-                debug!(
-                    path = %path,
-                    pattern = %entry.config.pattern,
-                    mode = ?self.mode,
-                    "Route matched"
-                );
-                // Cache the result
-                self.pattern_cache.insert(path.to_string(), index);
-                return Some(&entry.config);
-            }
+        #[cfg(feature = "logging")]
+        match matched {
+            Some(route) => debug!(
+                path = %path,
+                pattern = %route.pattern,
+                mode = ?self.mode,
+                "Route matched"
+            ),
+            None => warn!(path = %path, "No route matched request"),
         }
 
-        #[cfg(feature = "logging")]
-        warn!(path = %path, "No route matched request");
-        None
+        matched
+    }
+
+    /// The routes, in the order they were registered
+    pub fn routes(&self) -> &[SpaRouteConfig] {
+        &self.routes
     }
 
     /// Get the current mode
@@ -132,7 +278,7 @@ impl Router {
     }
 
     /// Determine which handler should handle a request path
-    pub fn route_handler(&mut self, path: &str) -> Option<RouteHandler> {
+    pub fn route_handler(&self, path: &str) -> Option<RouteHandler> {
         let mode = self.mode;
         if let Some(route_config) = self.match_route(path) {
             match mode {
@@ -192,6 +338,19 @@ impl Router {
             ));
         }
 
+        let mut names = std::collections::HashSet::new();
+        for segment in segments(pattern.strip_suffix("/*").unwrap_or(pattern)) {
+            let Some(name) = segment.strip_prefix(':') else {
+                continue;
+            };
+            if name.is_empty() || !names.insert(name) {
+                return Err(HeisenbergError::config(
+                    format!("Invalid parameter '{}' in route pattern: {}", segment, pattern),
+                    "• Name each parameter, like '/:tenant/app/*'\n• Give every parameter of a pattern a different name",
+                ));
+            }
+        }
+
         if pattern == "/*" {
             Ok(PathMatcher::CatchAll)
         } else if let Some(prefix) = pattern.strip_suffix("/*") {
@@ -204,32 +363,156 @@ impl Router {
             Ok(PathMatcher::Exact(pattern.to_string()))
         }
     }
+}
 
-    /// Calculate route priority (lower = higher priority)
-    fn route_priority(pattern: &str) -> usize {
-        if pattern == "/*" {
-            // Catch-all has lowest priority
-            1000
-        } else if let Some(prefix) = pattern.strip_suffix("/*") {
-            // Prefix routes: longer prefix = higher priority
-            // Use prefix.len() directly
-            1000 - prefix.len()
-        } else {
-            // Exact matches have highest priority
-            0
+/// Shared router whose routes can change after startup
+///
+/// For plugin systems that register frontends late. Clones share the same
+/// router. Every change swaps in a fully rebuilt router, so lookups in flight
+/// keep the snapshot they started with.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::core::config::SpaRouteConfig;
+/// use heisenberg::core::mode::Mode;
+/// use heisenberg::core::router::{Router, RouterHandle};
+///
+/// let handle = RouterHandle::new(Router::new(Vec::new(), Mode::Production)?);
+/// handle.insert(SpaRouteConfig {
+///     pattern: "/plugins/charts/*".to_string(),
+///     ..Default::default()
+/// })?;
+/// assert!(handle.route_handler("/plugins/charts/").is_some());
+///
+/// handle.remove("/plugins/charts/*")?;
+/// assert!(handle.route_handler("/plugins/charts/").is_none());
+/// # Ok::<(), heisenberg::HeisenbergError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RouterHandle {
+    router: Arc<RwLock<Arc<Router>>>,
+}
+
+impl RouterHandle {
+    /// Share a router
+    pub fn new(router: Router) -> Self {
+        Self {
+            router: Arc::new(RwLock::new(Arc::new(router))),
         }
     }
+
+    /// The current router, unaffected by later changes
+    pub fn snapshot(&self) -> Arc<Router> {
+        self.router.read().unwrap().clone()
+    }
+
+    /// Add a route, validated as [`Router::new`] validates routes
+    pub fn insert(&self, route: SpaRouteConfig) -> Result<(), HeisenbergError> {
+        let mut current = self.router.write().unwrap();
+        let mut router = Router::clone(&current);
+        router.insert(route)?;
+        *current = Arc::new(router);
+        Ok(())
+    }
+
+    /// Remove the route with this pattern, returning it
+    pub fn remove(&self, pattern: &str) -> Result<SpaRouteConfig, HeisenbergError> {
+        let mut current = self.router.write().unwrap();
+        let mut router = Router::clone(&current);
+        let removed = router
+            .remove(pattern)
+            .ok_or_else(|| unknown_pattern(pattern, current.routes()))?;
+        *current = Arc::new(router);
+        Ok(removed)
+    }
+
+    /// Determine which handler should handle a request path, with the
+    /// current routes
+    pub fn route_handler(&self, path: &str) -> Option<RouteHandler> {
+        self.snapshot().route_handler(path)
+    }
 }
-impl PathMatcher {
-    /// Check if this matcher matches the given path
-    fn matches(&self, path: &str) -> bool {
-        match self {
-            PathMatcher::Exact(exact) => path == exact,
-            PathMatcher::Prefix(prefix) => {
-                path.starts_with(prefix)
-                    && (path.len() == prefix.len() || path.chars().nth(prefix.len()) == Some('/'))
+
+impl TrieNode {
+    /// Register a route under the node its pattern ends at
+    fn insert(&mut self, matcher: &PathMatcher, index: usize) {
+        let (path, is_prefix) = match matcher {
+            PathMatcher::Exact(exact) => (exact.as_str(), false),
+            PathMatcher::Prefix(prefix) => (prefix.as_str(), true),
+            PathMatcher::CatchAll => {
+                self.prefix = Some(index);
+                return;
             }
-            PathMatcher::CatchAll => true,
+        };
+
+        let mut node = self;
+        for segment in segments(path) {
+            node = match param_name(segment) {
+                Some(_) => node.param.get_or_insert_with(Box::default),
+                None => node.children.entry(segment.to_string()).or_default(),
+            };
+        }
+        if is_prefix {
+            node.prefix = Some(index);
+        } else {
+            node.exact = Some(index);
         }
     }
+
+    /// Find the route for a path: an exact match at the end of the walk, or
+    /// failing that the deepest prefix passed on the way
+    fn lookup(&self, path: &str) -> Option<usize> {
+        self.find(segments(path))
+    }
+
+    /// [`lookup`](Self::lookup) for the segments left below this node,
+    /// trying a literal child before a parameter
+    fn find(&self, mut rest: std::str::Split<'_, char>) -> Option<usize> {
+        let Some(segment) = rest.next() else {
+            return self.exact.or(self.prefix);
+        };
+        let literal = self
+            .children
+            .get(segment)
+            .and_then(|child| child.find(rest.clone()));
+        literal
+            .or_else(|| {
+                let child = self.param.as_ref().filter(|_| capturable(segment))?;
+                child.find(rest)
+            })
+            .or(self.prefix)
+    }
+}
+
+/// Error for a pattern that names none of the routes
+pub(crate) fn unknown_pattern(pattern: &str, routes: &[SpaRouteConfig]) -> HeisenbergError {
+    let known: Vec<&str> = routes.iter().map(|route| route.pattern.as_str()).collect();
+    HeisenbergError::config(
+        format!("No route with pattern '{}'", pattern),
+        format!(
+            "• Known patterns: {}\n• Pass the pattern exactly as configured, such as '/admin/*'",
+            known.join(", ")
+        ),
+    )
+}
+
+/// The name of a `:name` pattern segment
+pub(crate) fn param_name(segment: &str) -> Option<&str> {
+    segment.strip_prefix(':').filter(|name| !name.is_empty())
+}
+
+/// Whether a path segment can be captured by a parameter
+///
+/// Empty segments and `.` or `..` never are, so a captured value can't
+/// climb out of a directory it names.
+pub(crate) fn capturable(segment: &str) -> bool {
+    !matches!(segment, "" | "." | "..")
+}
+
+/// Split a path into the segments used as trie keys
+///
+/// `/` is a single empty segment, so it stays distinct from the trie root.
+pub(crate) fn segments(path: &str) -> std::str::Split<'_, char> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
 }
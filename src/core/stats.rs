@@ -0,0 +1,154 @@
+//! Per-route latency statistics
+//!
+//! Each route records how long it takes to proxy requests to the dev server
+//! and to serve static files, in fixed-bucket histograms cheap enough to
+//! update on every request. Read them with
+//! [`HeisenbergLayer::stats`](crate::HeisenbergLayer::stats) to show serving
+//! performance in an application's own admin pages.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets, in milliseconds
+///
+/// Slower requests are counted in one more bucket without an upper bound.
+pub const BUCKET_BOUNDS_MS: [u64; 14] = [
+    1, 2, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000,
+];
+
+/// A latency histogram updated without locking
+#[derive(Debug, Default)]
+pub(crate) struct LatencyHistogram {
+    buckets: [AtomicU64; BUCKET_BOUNDS_MS.len() + 1],
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl LatencyHistogram {
+    /// Count one request that took `elapsed`
+    pub(crate) fn record(&self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|&bound| micros <= bound * 1_000)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// The counts so far
+    pub(crate) fn snapshot(&self) -> LatencyStats {
+        LatencyStats {
+            count: self.count.load(Ordering::Relaxed),
+            total: Duration::from_micros(self.total_micros.load(Ordering::Relaxed)),
+            max: Duration::from_micros(self.max_micros.load(Ordering::Relaxed)),
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+        }
+    }
+}
+
+/// Latencies recorded for one kind of request
+///
+/// Counts are read one at a time while requests are served, so a snapshot
+/// taken under load can be off by the requests finishing meanwhile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Requests recorded
+    pub count: u64,
+    /// Time spent on all of them
+    pub total: Duration,
+    /// The slowest request
+    pub max: Duration,
+    /// Requests per bucket of [`BUCKET_BOUNDS_MS`], plus a last bucket for
+    /// slower ones
+    pub buckets: Vec<u64>,
+}
+
+impl LatencyStats {
+    /// Average latency, or `None` before any request
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| {
+            Duration::from_micros(
+                u64::try_from(self.total.as_micros() / u128::from(self.count)).unwrap_or(u64::MAX),
+            )
+        })
+    }
+
+    /// Latency at or below which `quantile` of requests finished, e.g. `0.99`
+    ///
+    /// Resolution is the bucket: the result is the upper bound of the bucket
+    /// the quantile falls into, capped at [`max`](Self::max). `None` before
+    /// any request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::core::stats::LatencyStats;
+    /// use std::time::Duration;
+    ///
+    /// let stats = LatencyStats {
+    ///     count: 4,
+    ///     total: Duration::from_millis(40),
+    ///     max: Duration::from_millis(30),
+    ///     // Three requests up to 2ms, one between 25ms and 50ms
+    ///     buckets: vec![0, 3, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    /// };
+    /// assert_eq!(stats.quantile(0.5), Some(Duration::from_millis(2)));
+    /// assert_eq!(stats.quantile(0.99), Some(Duration::from_millis(30)));
+    /// ```
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let bound = BUCKET_BOUNDS_MS
+                    .get(index)
+                    .map_or(self.max, |&ms| Duration::from_millis(ms));
+                return Some(bound.min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+/// Latencies of the SPA requests one route served
+///
+/// Only requests Heisenberg answered are counted; the time the inner service
+/// spent deciding it had no response isn't included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteStats {
+    /// The route's pattern, e.g. `"/admin/*"`
+    pub pattern: String,
+    /// Requests proxied to the dev server, including failed ones
+    pub proxy: LatencyStats,
+    /// Requests served from the build directory
+    pub static_files: LatencyStats,
+}
+
+/// Histograms for one route
+#[derive(Debug, Default)]
+pub(crate) struct RouteLatencies {
+    pub(crate) proxy: LatencyHistogram,
+    pub(crate) static_files: LatencyHistogram,
+}
+
+impl RouteLatencies {
+    pub(crate) fn snapshot(&self, pattern: &str) -> RouteStats {
+        RouteStats {
+            pattern: pattern.to_string(),
+            proxy: self.proxy.snapshot(),
+            static_files: self.static_files.snapshot(),
+        }
+    }
+}
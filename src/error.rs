@@ -1,7 +1,12 @@
 //! Error types for Heisenberg
 
+use hyper::body::Bytes;
+use hyper::{header, Response, StatusCode};
 use thiserror::Error;
 
+/// A boxed error kept as the cause of a [`HeisenbergError`]
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 /// Main error type for Heisenberg operations
 #[derive(Error, Debug)]
 pub enum HeisenbergError {
@@ -12,6 +17,9 @@ pub enum HeisenbergError {
         message: String,
         /// Troubleshooting hint
         hint: String,
+        /// The underlying error, if there is one
+        #[source]
+        source: Option<BoxError>,
     },
 
     /// File not found with helpful suggestions
@@ -21,6 +29,9 @@ pub enum HeisenbergError {
         path: String,
         /// Troubleshooting hint
         hint: String,
+        /// The underlying error, if there is one
+        #[source]
+        source: Option<BoxError>,
     },
 
     /// No route matched the request
@@ -34,6 +45,9 @@ pub enum HeisenbergError {
         message: String,
         /// Troubleshooting hint
         hint: String,
+        /// The underlying error, if there is one
+        #[source]
+        source: Option<BoxError>,
     },
 
     /// Health check failure with debugging info
@@ -43,6 +57,21 @@ pub enum HeisenbergError {
         message: String,
         /// Troubleshooting hint
         hint: String,
+        /// The underlying error, if there is one
+        #[source]
+        source: Option<BoxError>,
+    },
+
+    /// Browser could not be opened
+    #[error("Browser error: {message}\n\nTroubleshooting:\n{hint}")]
+    Browser {
+        /// The error message
+        message: String,
+        /// Troubleshooting hint
+        hint: String,
+        /// The underlying error, if there is one
+        #[source]
+        source: Option<BoxError>,
     },
 
     /// Proxy request failed
@@ -62,12 +91,131 @@ pub enum HeisenbergError {
     PackageJsonParse(#[from] serde_json::Error),
 }
 
+/// Stable category of a [`HeisenbergError`]
+///
+/// Error messages are prose meant for people and may change between releases;
+/// branch on the kind instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The configuration is invalid or couldn't be inferred
+    ConfigInvalid,
+    /// A requested asset doesn't exist
+    AssetMissing,
+    /// No route matched the request
+    NoRouteMatch,
+    /// A dev server process couldn't be started
+    ProcessSpawnFailed,
+    /// The dev server didn't respond
+    DevServerUnreachable,
+    /// A proxied request failed for a reason other than connectivity
+    ProxyFailed,
+    /// The browser couldn't be opened
+    BrowserOpenFailed,
+    /// package.json couldn't be parsed
+    PackageJsonInvalid,
+    /// An HTTP protocol error
+    Http,
+    /// An IO error
+    Io,
+}
+
 impl HeisenbergError {
+    /// The stable category of this error, for programmatic handling
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::error::ErrorKind;
+    /// use heisenberg::Heisenberg;
+    ///
+    /// let err = Heisenberg::new()
+    ///     .spa("./dist")
+    ///     .dev_server("localhost:5173")
+    ///     .try_build()
+    ///     .unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Config { .. } => ErrorKind::ConfigInvalid,
+            Self::FileNotFound { .. } => ErrorKind::AssetMissing,
+            Self::NoRouteMatch => ErrorKind::NoRouteMatch,
+            Self::Process { .. } => ErrorKind::ProcessSpawnFailed,
+            Self::HealthCheck { .. } => ErrorKind::DevServerUnreachable,
+            Self::Browser { .. } => ErrorKind::BrowserOpenFailed,
+            Self::ProxyError(e) if e.is_connect() || e.is_timeout() => {
+                ErrorKind::DevServerUnreachable
+            }
+            Self::ProxyError(_) => ErrorKind::ProxyFailed,
+            Self::HttpError(_) => ErrorKind::Http,
+            Self::IoError(_) => ErrorKind::Io,
+            Self::PackageJsonParse(_) => ErrorKind::PackageJsonInvalid,
+        }
+    }
+
+    /// The HTTP status a response reporting this error should have
+    ///
+    /// Missing assets and unmatched routes are `404`, an unreachable or
+    /// unstartable dev server `503`, other proxy failures `502`, and
+    /// everything else `500`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::HeisenbergError;
+    /// use hyper::StatusCode;
+    ///
+    /// let err = HeisenbergError::file_not_found("dist/app.js", "• Run the build");
+    /// assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+    /// ```
+    pub fn status_code(&self) -> StatusCode {
+        match self.kind() {
+            ErrorKind::AssetMissing | ErrorKind::NoRouteMatch => StatusCode::NOT_FOUND,
+            ErrorKind::DevServerUnreachable | ErrorKind::ProcessSpawnFailed => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            ErrorKind::ProxyFailed => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Attach the error that caused this one, returned by
+    /// [`source()`](std::error::Error::source)
+    ///
+    /// Keeps the cause chain intact for reports from `anyhow` or `eyre`.
+    /// Errors without troubleshooting text, like
+    /// [`NoRouteMatch`](Self::NoRouteMatch), are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::HeisenbergError;
+    /// use std::error::Error;
+    ///
+    /// let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "permission denied");
+    /// let err = HeisenbergError::config("Cannot read package.json", "• Check permissions")
+    ///     .with_source(io);
+    /// assert_eq!(err.source().unwrap().to_string(), "permission denied");
+    /// ```
+    pub fn with_source(mut self, error: impl Into<BoxError>) -> Self {
+        match &mut self {
+            Self::Config { source, .. }
+            | Self::FileNotFound { source, .. }
+            | Self::Process { source, .. }
+            | Self::HealthCheck { source, .. }
+            | Self::Browser { source, .. } => *source = Some(error.into()),
+            _ => {}
+        }
+        self
+    }
+
     /// Create a configuration error with helpful troubleshooting
     pub fn config(message: impl Into<String>, hint: impl Into<String>) -> Self {
         Self::Config {
             message: message.into(),
             hint: hint.into(),
+            source: None,
         }
     }
 
@@ -76,6 +224,7 @@ impl HeisenbergError {
         Self::FileNotFound {
             path: path.into(),
             hint: hint.into(),
+            source: None,
         }
     }
 
@@ -84,6 +233,16 @@ impl HeisenbergError {
         Self::Process {
             message: message.into(),
             hint: hint.into(),
+            source: None,
+        }
+    }
+
+    /// Create a browser error with troubleshooting guidance
+    pub fn browser(message: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self::Browser {
+            message: message.into(),
+            hint: hint.into(),
+            source: None,
         }
     }
 
@@ -92,6 +251,51 @@ impl HeisenbergError {
         Self::HealthCheck {
             message: message.into(),
             hint: hint.into(),
+            source: None,
         }
     }
 }
+
+/// A plain-text response with the error's [`status_code`](HeisenbergError::status_code)
+/// and its message and troubleshooting text as the body
+///
+/// For handlers built on Heisenberg's services, like
+/// [`StaticFileService`](crate::services::StaticFileService) and
+/// [`ProxyService`](crate::services::ProxyService).
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::HeisenbergError;
+/// use hyper::body::Bytes;
+/// use hyper::Response;
+///
+/// let err = HeisenbergError::health_check("http://localhost:5173 is not responding", "• Start the dev server");
+/// let response = Response::<Bytes>::from(err);
+/// assert_eq!(response.status(), 503);
+/// assert!(String::from_utf8_lossy(response.body()).contains("Start the dev server"));
+/// ```
+impl From<HeisenbergError> for Response<Bytes> {
+    fn from(err: HeisenbergError) -> Self {
+        Response::builder()
+            .status(err.status_code())
+            .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Bytes::from(err.to_string()))
+            .unwrap()
+    }
+}
+
+/// Lets Axum handlers return `Result<_, HeisenbergError>`, with the same
+/// response as the [`Response`] conversion
+#[cfg(feature = "axum")]
+impl axum_core::response::IntoResponse for HeisenbergError {
+    fn into_response(self) -> axum_core::response::Response {
+        let status = self.status_code();
+        (
+            status,
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            self.to_string(),
+        )
+            .into_response()
+    }
+}
@@ -30,6 +30,7 @@
 //! - **Process Management**: Handles frontend dev server lifecycle
 //! - **SPA Support**: Client-side routing with fallback to index.html
 //! - **Optional Logging**: Structured diagnostics with `tracing` (enable `logging` feature)
+//! - **Test Utilities**: Mock dev server and SPA fixtures in [`test`] (enable `test-utils` feature)
 //!
 //! ## Mode Detection
 //!
@@ -47,16 +48,20 @@ pub mod utils;
 #[cfg(feature = "tower")]
 pub mod tower;
 
+#[cfg(feature = "test-utils")]
+pub mod test;
+
 // Framework adapters
 #[cfg(any(feature = "actix", feature = "rocket"))]
 pub mod adapters;
 
 // Re-export main types
 pub use crate::core::config::{Heisenberg, SpaRouteBuilder};
-pub use crate::error::HeisenbergError;
+pub use crate::core::events::HeisenbergEvent;
+pub use crate::error::{ErrorKind, HeisenbergError};
 
 #[cfg(feature = "tower")]
-pub use crate::tower::{HeisenbergLayer, HeisenbergService};
+pub use crate::tower::{HeisenbergLayer, HeisenbergService, ShutdownGuard};
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,157 @@
+//! Production build checks
+
+use crate::core::config::{MissingBuild, SpaRouteConfig};
+use crate::core::events::{emit, EventSender, HeisenbergEvent};
+use crate::error::HeisenbergError;
+use crate::utils::freshness::{build_state, BuildState};
+use std::process::Command;
+#[cfg(feature = "logging")]
+use tracing::{info, warn};
+
+/// Apply the route's [`MissingBuild`] policy to its build directory
+///
+/// With [`MissingBuild::Build`] the build command runs in the working
+/// directory and its output goes to the terminal. In CI mode nothing is
+/// spawned and a missing or stale build is an error instead.
+pub fn ensure_built(route: &SpaRouteConfig, ci_mode: bool) -> Result<(), HeisenbergError> {
+    if route.missing_build == MissingBuild::Serve {
+        return Ok(());
+    }
+
+    let Some(problem) = describe(route, &build_state(&route.embed_dir, &route.working_dir)) else {
+        return Ok(());
+    };
+    let command = route.build_command.join(" ");
+
+    if route.missing_build == MissingBuild::Fail || ci_mode {
+        return Err(HeisenbergError::config(
+            format!("Frontend build for {} is out of date: {}", route.pattern, problem),
+            format!(
+                "• Run `{}` in {}\n• Or use .on_missing_build(MissingBuild::Build) to build at startup",
+                command,
+                route.working_dir.display()
+            ),
+        ));
+    }
+
+    if route.build_command.is_empty() {
+        return Err(HeisenbergError::config(
+            format!(
+                "Frontend build for {} is out of date: {}",
+                route.pattern, problem
+            ),
+            "• Set the build command with .build_command([\"npm\", \"run\", \"build\"])",
+        ));
+    }
+
+    #[cfg(feature = "logging")]
+    info!(pattern = %route.pattern, command = %command, reason = %problem, "Building frontend");
+    #[cfg(not(feature = "logging"))]
+    println!(
+        "Building frontend for {} ({}): {}",
+        route.pattern, problem, command
+    );
+
+    let build_command = route.command_for(&route.build_command);
+    let status = Command::new(&build_command[0])
+        .args(&build_command[1..])
+        .current_dir(&route.working_dir)
+        .status()
+        .map_err(|e| {
+            HeisenbergError::process(
+                format!("Failed to run build command '{}': {}", command, e),
+                "• Ensure the command exists (npm, yarn, pnpm)\n• Verify the working directory path is correct",
+            ).with_source(e)
+        })?;
+    if !status.success() {
+        return Err(HeisenbergError::process(
+            format!("Build command '{}' failed with {}", command, status),
+            format!(
+                "• Run `{}` in {} to see the full output\n• Fix the build errors and restart",
+                command,
+                route.working_dir.display()
+            ),
+        ));
+    }
+
+    if build_state(&route.embed_dir, &route.working_dir) == BuildState::Missing {
+        return Err(HeisenbergError::config(
+            format!(
+                "Build command '{}' succeeded but {} is still empty",
+                command,
+                route.embed_dir.display()
+            ),
+            "• Check that the build writes to the route's embed directory\n• Point .spa() at the directory your bundler outputs to",
+        ));
+    }
+    Ok(())
+}
+
+/// Check a route's build directory as its [`MissingBuild`] policy says
+///
+/// Routes with [`MissingBuild::Serve`] only get a warning.
+pub(crate) fn check_build(
+    route: &SpaRouteConfig,
+    ci_mode: bool,
+    events: &EventSender,
+) -> Result<(), HeisenbergError> {
+    if route.missing_build == MissingBuild::Serve {
+        warn_if_outdated(route, events);
+        Ok(())
+    } else {
+        ensure_built(route, ci_mode)
+    }
+}
+
+/// Warn loudly if the route's build directory looks outdated
+///
+/// For routes with the default [`MissingBuild::Serve`] policy, which would
+/// otherwise serve old assets silently. Also sends a
+/// [`HeisenbergEvent::OutdatedBuild`] event.
+pub(crate) fn warn_if_outdated(route: &SpaRouteConfig, events: &EventSender) {
+    let state = build_state(&route.embed_dir, &route.working_dir);
+    let Some(problem) = describe(route, &state) else {
+        return;
+    };
+    let command = route.build_command.join(" ");
+
+    #[cfg(feature = "logging")]
+    warn!(
+        pattern = %route.pattern,
+        reason = %problem,
+        build_command = %command,
+        "Embedded frontend assets look outdated"
+    );
+    #[cfg(not(feature = "logging"))]
+    eprintln!(
+        "\n  ⚠  Frontend assets for {} look outdated: {}\n     Changes to the frontend won't show until you run `{}` in {}\n",
+        route.pattern,
+        problem,
+        command,
+        route.working_dir.display()
+    );
+
+    emit(
+        events,
+        HeisenbergEvent::OutdatedBuild {
+            pattern: route.pattern.clone(),
+            newest_source: match state {
+                BuildState::Stale { newest_source } => Some(newest_source),
+                _ => None,
+            },
+        },
+    );
+}
+
+/// What's wrong with a build, or `None` if it's fresh
+fn describe(route: &SpaRouteConfig, state: &BuildState) -> Option<String> {
+    match state {
+        BuildState::Fresh => None,
+        BuildState::Missing => Some(format!("{} is missing or empty", route.embed_dir.display())),
+        BuildState::Stale { newest_source } => Some(format!(
+            "{} is older than {}",
+            route.embed_dir.display(),
+            newest_source.display()
+        )),
+    }
+}
@@ -0,0 +1,127 @@
+//! Netlify-style `_headers` rules for production serving
+
+use crate::core::config::HeaderRule;
+use hyper::header::{HeaderName, HeaderValue};
+use std::path::Path;
+#[cfg(feature = "logging")]
+use tracing::warn;
+
+/// Name of the rules file looked for in a build directory
+pub const HEADERS_FILE: &str = "_headers";
+
+/// Parse the rules in a `_headers` file
+///
+/// Each unindented line is a path, followed by indented `Name: value` lines
+/// for the files under it. A `*` in the path matches anything, including
+/// slashes, and a `:name` placeholder one segment, so `/assets/*` becomes the
+/// glob `/assets/**`. A header given twice under one path is sent with both
+/// values, comma-separated. Comments, blank lines and headers that aren't
+/// valid are skipped.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::headers::parse_headers;
+///
+/// let rules = parse_headers("/assets/*\n  Cache-Control: public, max-age=31536000, immutable\n");
+/// assert_eq!(rules[0].glob, "/assets/**");
+/// assert_eq!(rules[0].value, "public, max-age=31536000, immutable");
+/// ```
+pub fn parse_headers(text: &str) -> Vec<HeaderRule> {
+    parse(text, |_, _| {})
+}
+
+/// Load the `_headers` file of a build directory, if there is one
+///
+/// Lines that can't be applied are reported and skipped.
+pub fn load_headers(dir: &Path) -> Vec<HeaderRule> {
+    let file = dir.join(HEADERS_FILE);
+    let Ok(text) = std::fs::read_to_string(&file) else {
+        return Vec::new();
+    };
+
+    parse(&text, |number, reason| {
+        #[cfg(feature = "logging")]
+        warn!(file = %file.display(), line = number, reason = %reason, "Skipping header rule");
+        #[cfg(not(feature = "logging"))]
+        eprintln!(
+            "Warning: Skipping rule on line {} of {}: {}",
+            number,
+            file.display(),
+            reason
+        );
+    })
+}
+
+/// Parse rules, reporting skipped lines by their 1-based number
+fn parse(text: &str, mut skipped: impl FnMut(usize, &str)) -> Vec<HeaderRule> {
+    let mut rules: Vec<HeaderRule> = Vec::new();
+    let mut glob = None;
+    // Rules of the current path start here, for joining repeated headers
+    let mut block_start = 0;
+
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) {
+            glob = path_glob(trimmed);
+            block_start = rules.len();
+            if glob.is_none() {
+                skipped(index + 1, "paths must start with '/'");
+            }
+            continue;
+        }
+
+        let Some(glob) = &glob else {
+            skipped(index + 1, "header without a path above it");
+            continue;
+        };
+        let Some((name, value)) = trimmed.split_once(':') else {
+            skipped(index + 1, "expected 'Name: value'");
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        if HeaderName::from_bytes(name.as_bytes()).is_err() || HeaderValue::from_str(value).is_err()
+        {
+            skipped(index + 1, "not a valid header");
+            continue;
+        }
+
+        match rules[block_start..]
+            .iter_mut()
+            .find(|rule| rule.name.eq_ignore_ascii_case(name))
+        {
+            Some(rule) => rule.value = format!("{}, {}", rule.value, value),
+            None => rules.push(HeaderRule {
+                glob: glob.clone(),
+                name: name.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+    rules
+}
+
+/// The file glob for a `_headers` path, with directories meaning their
+/// `index.html`
+fn path_glob(path: &str) -> Option<String> {
+    if !path.starts_with('/') {
+        return None;
+    }
+    let glob = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(_) => "*".to_string(),
+            None => segment.replace('*', "**"),
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    Some(if glob.ends_with('/') {
+        format!("{}index.html", glob)
+    } else {
+        glob
+    })
+}
@@ -1,29 +1,132 @@
 //! Health checking for development servers
 
 use crate::error::HeisenbergError;
+use crate::services::client::{default_client, HEALTH_CHECK_TIMEOUT};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use hyper::StatusCode;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 #[cfg(feature = "logging")]
 use tracing::{debug, info};
 
+/// What a route's health check requests and accepts
+///
+/// By default the dev server URL itself is requested, and any response but a
+/// 5xx means the dev server is up.
+#[derive(Debug, Clone, Default)]
+pub struct HealthCheck {
+    /// Path requested instead, relative to the dev server URL (e.g. `/healthz`)
+    pub path: Option<String>,
+    /// Decides instead whether the response means the dev server is healthy
+    pub ok_when: Option<HealthPredicate>,
+    /// Replaces the request altogether, making `path` and `ok_when` unused
+    pub custom: Option<CustomHealthCheck>,
+}
+
+/// A test of a health check response's status and headers
+///
+/// See [`SpaRouteBuilder::health_ok_when`](crate::core::config::SpaRouteBuilder::health_ok_when).
+#[derive(Clone)]
+pub struct HealthPredicate(Arc<HealthFn>);
+
+type HealthFn = dyn Fn(StatusCode, &HeaderMap) -> bool + Send + Sync;
+
+impl HealthPredicate {
+    /// Wrap a test of a response's status and headers
+    pub fn new<F>(ok: F) -> Self
+    where
+        F: Fn(StatusCode, &HeaderMap) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(ok))
+    }
+
+    /// Whether a response with `status` and `headers` means healthy
+    pub fn accepts(&self, status: StatusCode, headers: &HeaderMap) -> bool {
+        (self.0)(status, headers)
+    }
+}
+
+impl std::fmt::Debug for HealthPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HealthPredicate(..)")
+    }
+}
+
+/// An async function deciding whether the dev server at a URL is healthy
+///
+/// See [`SpaRouteBuilder::health_check_with`](crate::core::config::SpaRouteBuilder::health_check_with).
+#[derive(Clone)]
+pub struct CustomHealthCheck(Arc<CustomHealthFn>);
+
+type CustomHealthFn = dyn Fn(String) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync;
+
+impl CustomHealthCheck {
+    /// Wrap an async function of the dev server URL
+    pub fn new<F, Fut>(check: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        Self(Arc::new(move |url| Box::pin(check(url))))
+    }
+
+    /// Whether the dev server at `url` is healthy
+    pub async fn check(&self, url: String) -> bool {
+        (self.0)(url).await
+    }
+}
+
+impl std::fmt::Debug for CustomHealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomHealthCheck(..)")
+    }
+}
+
 /// Health checker for development servers
+#[derive(Debug)]
 pub struct HealthChecker {
     target_url: String,
     client: reqwest::Client,
+    ok_when: Option<HealthPredicate>,
+    custom: Option<CustomHealthCheck>,
 }
 
 impl HealthChecker {
-    /// Create a new health checker
+    /// Create a new health checker with a client of its own
     pub fn new(target_url: String) -> Self {
+        Self::with_client(target_url, default_client())
+    }
+
+    /// Create a health checker that sends its requests through `client`,
+    /// sharing its connection pool
+    pub fn with_client(target_url: String, client: reqwest::Client) -> Self {
         Self {
             target_url,
-            client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(5))
-                .build()
-                .unwrap(),
+            client,
+            ok_when: None,
+            custom: None,
         }
     }
 
+    /// Request `check`'s path and judge responses with its predicate, or
+    /// run its custom check instead
+    pub fn with_check(mut self, check: &HealthCheck) -> Self {
+        if check.custom.is_some() {
+            self.custom = check.custom.clone();
+        } else if let Some(path) = &check.path {
+            self.target_url = format!(
+                "{}/{}",
+                self.target_url.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            );
+        }
+        self.ok_when = check.ok_when.clone();
+        self
+    }
+
     /// Check if the target server is healthy
     pub async fn is_healthy(&self) -> bool {
         self.check_health().await.is_ok()
@@ -32,10 +135,49 @@ impl HealthChecker {
     /// Perform a health check
     pub async fn check_health(&self) -> Result<(), HeisenbergError> {
         let health_check = async {
-            let response = self.client.get(&self.target_url).send().await?;
+            if let Some(custom) = &self.custom {
+                return if custom.check(self.target_url.clone()).await {
+                    Ok(())
+                } else {
+                    Err(HeisenbergError::health_check(
+                        format!("Custom health check of {} failed", self.target_url),
+                        "• The route's .health_check_with() function reported the dev server unhealthy\n• Check the dev server logs for issues",
+                    ))
+                };
+            }
+
+            let response = self
+                .client
+                .get(&self.target_url)
+                .timeout(HEALTH_CHECK_TIMEOUT)
+                .send()
+                .await?;
+
+            if let Some(ok_when) = &self.ok_when {
+                let status = StatusCode::from_u16(response.status().as_u16())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                let mut headers = HeaderMap::new();
+                for (name, value) in response.headers() {
+                    if let (Ok(name), Ok(value)) = (
+                        HeaderName::from_bytes(name.as_str().as_bytes()),
+                        HeaderValue::from_bytes(value.as_bytes()),
+                    ) {
+                        headers.append(name, value);
+                    }
+                }
+                return if ok_when.accepts(status, &headers) {
+                    Ok(())
+                } else {
+                    Err(HeisenbergError::health_check(
+                        format!("Health check of {} not accepted: status {}", self.target_url, status),
+                        "• The route's .health_ok_when() check rejected the response\n• Request the URL yourself to see the status and headers it returns\n• Check the dev server logs for issues",
+                    ))
+                };
+            }
 
-            if response.status().is_success() || response.status().is_client_error() {
-                // Any response (even 404) means the server is running
+            if !response.status().is_server_error() {
+                // Any response (even 404, or a redirect, which isn't
+                // followed) means the server is running
                 Ok(())
             } else {
                 Err(HeisenbergError::health_check(
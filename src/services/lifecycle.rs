@@ -0,0 +1,254 @@
+//! Explicit dev server lifecycle
+
+use crate::core::config::{Heisenberg, SpaRouteConfig};
+use crate::core::mode::Mode;
+use crate::core::router::unknown_pattern;
+use crate::error::HeisenbergError;
+use crate::services::client;
+use crate::services::health::HealthChecker;
+use crate::services::process::{ProcessManager, ProcessStats};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "logging")]
+use tracing::info;
+
+/// Handle to the dev servers started by [`Heisenberg::start`] or managed by a
+/// [`HeisenbergLayer`](crate::HeisenbergLayer)
+///
+/// The dev servers keep running until [`shutdown`](Self::shutdown) is called
+/// or the last clone of the handle is dropped. A layer built from the same
+/// configuration finds them already healthy and proxies to them without
+/// spawning its own.
+#[derive(Debug, Clone)]
+pub struct DevServerHandle {
+    routes: Arc<[SpaRouteConfig]>,
+    processes: Arc<ProcessManager>,
+    /// False in production mode, where nothing is ever spawned
+    spawn: bool,
+    /// Set by shutdown, after which nothing is spawned again
+    closed: Arc<AtomicBool>,
+    /// Client for each route's health checks, shared with the
+    /// configuration's proxies unless the route has root CAs of its own
+    clients: Arc<HashMap<String, reqwest::Client>>,
+}
+
+/// Snapshot of one route's dev server, as reported by [`DevServerHandle::status`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DevServerStatus {
+    /// Route pattern the dev server belongs to
+    pub pattern: String,
+    /// URL the dev server listens on, as configured or as it announced
+    pub dev_server: String,
+    /// Whether the handle's process for this route is still running
+    pub running: bool,
+    /// Whether the dev server URL currently responds
+    pub healthy: bool,
+    /// How long the process has been running
+    pub uptime: Option<Duration>,
+    /// The process's PID and resource use, while it runs
+    pub stats: Option<ProcessStats>,
+    /// The last lines the dev server wrote to stdout or stderr
+    pub output: Vec<String>,
+}
+
+impl DevServerHandle {
+    /// Start every route's dev server and wait for each to become healthy
+    ///
+    /// Dev servers that already respond are reused. Nothing is spawned in
+    /// production mode or CI mode. If one dev server fails to start, the ones
+    /// started before it are stopped and the error is returned.
+    pub async fn start(config: &Heisenberg) -> Result<Self, HeisenbergError> {
+        config.validate()?;
+
+        let handle = Self::new(config);
+        if !handle.spawn || handle.processes.is_ci_mode() {
+            return Ok(handle);
+        }
+
+        for route in handle.routes.iter() {
+            if handle
+                .health_checker(route, route.dev_proxy_url.clone())
+                .is_healthy()
+                .await
+            {
+                #[cfg(feature = "logging")]
+                info!(pattern = %route.pattern, "Dev server already running, reusing it");
+                continue;
+            }
+            handle.start_route(route).await?;
+        }
+
+        Ok(handle)
+    }
+
+    /// Create a handle without starting anything
+    pub(crate) fn new(config: &Heisenberg) -> Self {
+        let settings = config.global_settings();
+        Self {
+            routes: config.routes().into(),
+            processes: Arc::new(ProcessManager::with_settings(settings)),
+            spawn: config.mode() == Mode::Development,
+            closed: Arc::default(),
+            clients: Arc::new(
+                config
+                    .routes()
+                    .iter()
+                    .map(|route| {
+                        let client =
+                            client::for_route(route, &settings.http_client, settings.system_proxy);
+                        (route.pattern.clone(), client)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Spawn a route's dev server and wait for it to become healthy
+    pub(crate) async fn start_route(&self, route: &SpaRouteConfig) -> Result<(), HeisenbergError> {
+        if !self.spawn || route.uses_staging() || route.docker_host.is_some() {
+            return Ok(());
+        }
+        if self.is_shut_down() {
+            return Err(HeisenbergError::process(
+                format!("Not starting the dev server for {}: shutting down", route.pattern),
+                "• The server is shutting down, so dev servers aren't started again\n• Restart the server to bring the dev servers back",
+            ));
+        }
+        self.processes.start_process(&route.pattern, route).await
+    }
+
+    /// Start stopping a route's dev server, whether or not the route was
+    /// configured, without waiting for it to exit
+    pub(crate) fn stop_route(&self, pattern: &str) {
+        self.processes.stop_process_in_background(pattern)
+    }
+
+    pub(crate) fn dev_server_url_if_moved(&self, pattern: &str) -> Option<String> {
+        self.processes.dev_server_url(pattern)
+    }
+
+    /// Stop the route's dev server and start it again
+    ///
+    /// Waits for the restarted dev server to become healthy.
+    pub async fn restart(&self, pattern: &str) -> Result<(), HeisenbergError> {
+        let route = self.route(pattern)?;
+        self.processes.stop_process(pattern).await?;
+        self.start_route(route).await
+    }
+
+    /// Stop the route's dev server, waiting until it has exited
+    ///
+    /// A layer doesn't start it again on later requests; use
+    /// [`restart`](Self::restart) for that.
+    pub async fn stop(&self, pattern: &str) -> Result<(), HeisenbergError> {
+        self.route(pattern)?;
+        self.processes.stop_process(pattern).await
+    }
+
+    /// Stop every dev server this handle manages
+    ///
+    /// Every clone of the handle, and a layer sharing it, stops starting dev
+    /// servers too: later requests get the error page instead of a new
+    /// process, and [`restart`](Self::restart) fails.
+    pub async fn shutdown(self) -> Result<(), HeisenbergError> {
+        self.close().await
+    }
+
+    /// Stop every dev server and refuse to start new ones
+    pub(crate) async fn close(&self) -> Result<(), HeisenbergError> {
+        self.closed.store(true, Ordering::SeqCst);
+        self.processes.stop_all_processes().await
+    }
+
+    /// Like [`close`](Self::close), without waiting for the dev servers to
+    /// exit
+    pub(crate) fn close_in_background(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.processes.stop_all_processes_in_background();
+    }
+
+    /// Whether [`shutdown`](Self::shutdown) was called on this handle or a
+    /// clone
+    pub fn is_shut_down(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Whether this handle has a running process for the route
+    pub fn is_running(&self, pattern: &str) -> bool {
+        self.processes.is_process_running(pattern)
+    }
+
+    /// How long the route's dev server has been running
+    pub fn uptime(&self, pattern: &str) -> Option<Duration> {
+        self.processes.get_process_uptime(pattern)
+    }
+
+    /// The PID and CPU and memory use of the route's dev server, while it
+    /// runs
+    ///
+    /// See [`ProcessStats`].
+    pub fn process_stats(&self, pattern: &str) -> Option<ProcessStats> {
+        self.processes.process_stats(pattern)
+    }
+
+    /// The last lines the route's dev server wrote to stdout or stderr
+    ///
+    /// Kept after the dev server exits, so crash output can be inspected.
+    pub fn output(&self, pattern: &str) -> Vec<String> {
+        self.processes.recent_output(pattern)
+    }
+
+    /// Where the route's dev server listens
+    ///
+    /// The configured URL, unless the dev server announced a different port
+    /// in its output, as Vite does when its port is taken.
+    pub fn dev_server_url(&self, pattern: &str) -> String {
+        self.processes.dev_server_url(pattern).unwrap_or_else(|| {
+            self.routes
+                .iter()
+                .find(|route| route.pattern == pattern)
+                .map(|route| route.dev_proxy_url.clone())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Route patterns managed by this handle
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.routes.iter().map(|route| route.pattern.as_str())
+    }
+
+    /// Current state of every route's dev server
+    pub async fn status(&self) -> Vec<DevServerStatus> {
+        let mut statuses = Vec::with_capacity(self.routes.len());
+        for route in self.routes.iter() {
+            let dev_server = self.dev_server_url(&route.pattern);
+            statuses.push(DevServerStatus {
+                pattern: route.pattern.clone(),
+                running: self.processes.is_process_running(&route.pattern),
+                healthy: self
+                    .health_checker(route, dev_server.clone())
+                    .is_healthy()
+                    .await,
+                dev_server,
+                uptime: self.processes.get_process_uptime(&route.pattern),
+                stats: self.processes.process_stats(&route.pattern),
+                output: self.processes.recent_output(&route.pattern),
+            });
+        }
+        statuses
+    }
+
+    fn health_checker(&self, route: &SpaRouteConfig, url: String) -> HealthChecker {
+        HealthChecker::with_client(url, self.clients[&route.pattern].clone())
+            .with_check(&route.health_check)
+    }
+
+    fn route(&self, pattern: &str) -> Result<&SpaRouteConfig, HeisenbergError> {
+        self.routes
+            .iter()
+            .find(|route| route.pattern == pattern)
+            .ok_or_else(|| unknown_pattern(pattern, &self.routes))
+    }
+}
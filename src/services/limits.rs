@@ -0,0 +1,174 @@
+//! Resource limits for dev server processes
+
+use std::time::Duration;
+
+/// Caps on what a route's dev server may use
+///
+/// Keeps a runaway webpack build from starving the backend, say during a
+/// profiling session. Limits apply to the dev command and, since they are
+/// inherited, to the processes it starts. On Unix they are set with `nice`
+/// and `setrlimit` as the dev command starts; on Windows the process joins a
+/// job object right after it is spawned.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::limits::ResourceLimits;
+/// use std::time::Duration;
+///
+/// let limits = ResourceLimits::new()
+///     .nice(10)
+///     .max_memory(2 * 1024 * 1024 * 1024)
+///     .max_cpu_time(Duration::from_secs(3600));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// Niceness added to the dev server's, as with `nice -n`, from 1 to 19;
+    /// higher values leave more CPU to the backend. On Windows, up to 9 maps
+    /// to the below-normal priority class and above that to idle.
+    pub nice: Option<i32>,
+    /// Memory cap in bytes: the data segment (`RLIMIT_DATA`) of each
+    /// process on Unix, and the memory of all of them together on Windows.
+    /// Allocations beyond it fail, which Node.js reports as running out of
+    /// memory.
+    pub max_memory: Option<u64>,
+    /// CPU time after which a process is stopped: per process on Unix
+    /// (`RLIMIT_CPU`), for all of them together on Windows
+    pub max_cpu_time: Option<Duration>,
+}
+
+impl ResourceLimits {
+    /// No limits, as by default
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lower the dev server's scheduling priority
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Cap the memory the dev server may allocate, in bytes
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// Stop the dev server once it has used this much CPU time
+    pub fn max_cpu_time(mut self, cpu_time: Duration) -> Self {
+        self.max_cpu_time = Some(cpu_time);
+        self
+    }
+
+    /// Whether any limit is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Have `command` apply `limits` to itself when it starts
+#[cfg(unix)]
+pub(crate) fn apply_on_start(command: &mut tokio::process::Command, limits: ResourceLimits) {
+    if limits.is_empty() {
+        return;
+    }
+    let limit = |resource, value: u64| {
+        let value = libc::rlim_t::try_from(value).unwrap_or(libc::RLIM_INFINITY);
+        let limit = libc::rlimit {
+            rlim_cur: value,
+            rlim_max: value,
+        };
+        // SAFETY: setrlimit only reads the struct it is given
+        match unsafe { libc::setrlimit(resource, &limit) } {
+            0 => Ok(()),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    };
+    // SAFETY: the closure only makes async-signal-safe system calls, as
+    // required between fork and exec
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(nice) = limits.nice {
+                // -1 is also a valid niceness, and lowering priority can't
+                // fail for lack of permissions, so the result is ignored
+                libc::nice(nice);
+            }
+            if let Some(bytes) = limits.max_memory {
+                limit(libc::RLIMIT_DATA, bytes)?;
+            }
+            if let Some(cpu_time) = limits.max_cpu_time {
+                limit(libc::RLIMIT_CPU, cpu_time.as_secs().max(1))?;
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Put the process `pid` in a job object enforcing `limits`
+///
+/// Processes it started before joining aren't covered.
+#[cfg(windows)]
+pub(crate) fn apply_to_process(pid: u32, limits: ResourceLimits) -> std::io::Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_JOB_MEMORY,
+        JOB_OBJECT_LIMIT_JOB_TIME, JOB_OBJECT_LIMIT_PRIORITY_CLASS,
+    };
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, PROCESS_SET_QUOTA,
+        PROCESS_TERMINATE,
+    };
+
+    if limits.is_empty() {
+        return Ok(());
+    }
+    // SAFETY: the structure is plain data, for which zeroes mean no limits
+    let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    if let Some(nice) = limits.nice.filter(|&nice| nice > 0) {
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+        info.BasicLimitInformation.PriorityClass = if nice < 10 {
+            BELOW_NORMAL_PRIORITY_CLASS
+        } else {
+            IDLE_PRIORITY_CLASS
+        };
+    }
+    if let Some(bytes) = limits.max_memory {
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+        info.JobMemoryLimit = usize::try_from(bytes).unwrap_or(usize::MAX);
+    }
+    if let Some(cpu_time) = limits.max_cpu_time {
+        info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_TIME;
+        // In units of 100 nanoseconds
+        info.BasicLimitInformation.PerJobUserTimeLimit =
+            i64::try_from(cpu_time.as_nanos() / 100).unwrap_or(i64::MAX);
+    }
+
+    // SAFETY: handles are checked before use and closed once; the job lives
+    // on while processes are assigned to it
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let set = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const JOBOBJECT_EXTENDED_LIMIT_INFORMATION as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        );
+        let process = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, 0, pid);
+        let assigned = set != 0 && process != 0 && AssignProcessToJobObject(job, process) != 0;
+        let result = if assigned {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        };
+        if process != 0 {
+            CloseHandle(process);
+        }
+        CloseHandle(job);
+        result
+    }
+}
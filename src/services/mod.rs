@@ -1,11 +1,24 @@
 //! Service implementations for Heisenberg
 
+pub mod build;
+pub mod client;
+pub mod headers;
 pub mod health;
+pub mod lifecycle;
+pub mod limits;
+pub mod preflight;
 pub mod process;
 pub mod proxy;
+pub mod redirects;
+pub mod spawner;
 pub mod static_files;
+pub mod theme;
+pub mod websocket;
 
 pub use health::HealthChecker;
-pub use process::ProcessManager;
+pub use lifecycle::{DevServerHandle, DevServerStatus};
+pub use process::{ProcessManager, ProcessStats};
 pub use proxy::ProxyService;
-pub use static_files::StaticFileService;
+pub use redirects::RedirectRule;
+pub use static_files::{AssetCache, StaticFileService};
+pub use theme::ErrorPageTheme;
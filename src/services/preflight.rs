@@ -0,0 +1,287 @@
+//! Toolchain checks before a dev server is spawned
+//!
+//! A missing `pnpm` otherwise surfaces as `No such file or directory`, with
+//! nothing saying which program was missing or how to get it.
+
+use crate::core::config::SpaRouteConfig;
+use crate::error::HeisenbergError;
+use crate::utils::paths::find_program;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+#[cfg(feature = "logging")]
+use tracing::{info, warn};
+
+/// Programs that need Node.js to run
+const NODE_TOOLS: &[&str] = &["node", "npm", "npx", "pnpm", "pnpx", "yarn", "corepack"];
+
+/// Package managers whose version `engines` may constrain
+const PACKAGE_MANAGERS: &[&str] = &["npm", "pnpm", "yarn"];
+
+/// How long `--version` may take, which for corepack includes a download
+const VERSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Check that the programs the route's dev server needs are installed
+///
+/// Every command's program must be on `PATH`, as must Node.js for the
+/// package managers. The versions of Node.js and the package manager are
+/// logged, and compared with `engines` in the working directory's
+/// `package.json`; like npm without `engine-strict`, a mismatch is only a
+/// warning.
+pub async fn check_toolchain(
+    route: &SpaRouteConfig,
+    commands: &[Vec<String>],
+) -> Result<(), HeisenbergError> {
+    let working_dir = route.working_dir.as_path();
+    for command in commands {
+        let Some(program) = command.first() else {
+            continue;
+        };
+        if find_program(program).is_none() {
+            return Err(missing_program(program, working_dir));
+        }
+    }
+
+    let Some(command) = commands.last() else {
+        return Ok(());
+    };
+    // The package manager, as run by corepack or directly
+    let corepack = command.len() > 1 && program_name(&command[0]) == "corepack";
+    let manager = program_name(&command[usize::from(corepack)]);
+    if !NODE_TOOLS.contains(&manager.as_str()) {
+        return Ok(());
+    }
+    if find_program("node").is_none() {
+        return Err(missing_program("node", working_dir));
+    }
+
+    let engines = read_engines(working_dir);
+    let mut checks = vec![("node", vec!["node".to_string()])];
+    if let Some(&name) = PACKAGE_MANAGERS.iter().find(|name| **name == manager) {
+        // Through corepack, the version is that of the pinned manager
+        let program = command[..=usize::from(corepack)].to_vec();
+        checks.push((name, program));
+    }
+
+    for (name, program) in checks {
+        let Some(version) = version(&program, working_dir).await else {
+            continue;
+        };
+        #[cfg(feature = "logging")]
+        info!(pattern = %route.pattern, tool = name, version = %version, "Found dev toolchain");
+
+        let Some(range) = engines.get(name).and_then(Value::as_str) else {
+            continue;
+        };
+        if !satisfies(&version, range) {
+            #[cfg(feature = "logging")]
+            warn!(
+                pattern = %route.pattern,
+                tool = name,
+                version = %version,
+                engines = %range,
+                "Dev toolchain doesn't satisfy package.json engines"
+            );
+            #[cfg(not(feature = "logging"))]
+            eprintln!(
+                "Warning: {} {} doesn't satisfy engines.{} \"{}\" in package.json for route {}",
+                name, version, name, range, route.pattern
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether `version`, like `20.11.1` or `v20.11.1`, is in the npm semver
+/// `range`, like `^18 || >=20.0.0 <21`
+///
+/// Versions or ranges that can't be read count as satisfied, so they don't
+/// cause false warnings.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::preflight::satisfies;
+///
+/// assert!(satisfies("v20.11.1", "^18 || >=20"));
+/// assert!(satisfies("18.19.0", "18.x"));
+/// assert!(!satisfies("16.20.2", ">=18.0.0"));
+/// assert!(!satisfies("9.1.0", "~8.15.0"));
+/// ```
+pub fn satisfies(version: &str, range: &str) -> bool {
+    let Some(version) = parse_version(version) else {
+        return true;
+    };
+    range
+        .split("||")
+        .map(|set| set_satisfied(version, set.trim()))
+        .try_fold(false, |any, satisfied| Some(any || satisfied?))
+        .unwrap_or(true)
+}
+
+type Version = (u64, u64, u64);
+
+/// Whether `version` meets every comparator in `set`, or `None` if `set`
+/// can't be read
+fn set_satisfied(version: Version, set: &str) -> Option<bool> {
+    if let Some((low, high)) = set.split_once(" - ") {
+        let low = parse_partial(low.trim())?;
+        let high = parse_partial(high.trim())?;
+        return Some(version >= lower(&low) && below_upper(version, &high));
+    }
+    let mut comparators = Vec::new();
+    let mut pending = String::new();
+    for word in set.split_whitespace() {
+        pending.push_str(word);
+        // An operator written apart from its version, as in `>= 18`
+        if !word
+            .chars()
+            .all(|c| matches!(c, '<' | '>' | '=' | '^' | '~'))
+        {
+            comparators.push(std::mem::take(&mut pending));
+        }
+    }
+    comparators.iter().try_fold(true, |all, comparator| {
+        Some(all && comparator_satisfied(version, comparator)?)
+    })
+}
+
+fn comparator_satisfied(version: Version, comparator: &str) -> Option<bool> {
+    let operator_len = comparator
+        .find(|c: char| !matches!(c, '<' | '>' | '=' | '^' | '~' | 'v'))
+        .unwrap_or(comparator.len());
+    let (operator, partial) = comparator.split_at(operator_len);
+    let partial = parse_partial(partial)?;
+    let operator = operator.trim_end_matches('v');
+    let low = lower(&partial);
+    let full = partial.iter().all(Option::is_some);
+    Some(match operator {
+        "" | "=" => version >= low && below_upper(version, &partial),
+        ">=" => version >= low,
+        ">" if full => version > low,
+        ">" => !below_upper(version, &partial),
+        "<" => version < low,
+        "<=" => below_upper(version, &partial),
+        "~" => {
+            let upper = match partial {
+                [Some(major), Some(minor), _] => (major, minor + 1, 0),
+                [Some(major), ..] => (major + 1, 0, 0),
+                _ => return Some(true),
+            };
+            version >= low && version < upper
+        }
+        "^" => {
+            let upper = match partial {
+                [Some(0), Some(0), Some(patch)] => (0, 0, patch + 1),
+                [Some(0), Some(0), None] => (0, 1, 0),
+                [Some(0), Some(minor), _] => (0, minor + 1, 0),
+                [Some(major), ..] => (major + 1, 0, 0),
+                _ => return Some(true),
+            };
+            version >= low && version < upper
+        }
+        _ => return None,
+    })
+}
+
+/// Whether `version` is at most the X-range `partial`: up to it when it is
+/// complete, and below the version after it when parts are missing
+fn below_upper(version: Version, partial: &[Option<u64>; 3]) -> bool {
+    match *partial {
+        [Some(major), Some(minor), Some(patch)] => version <= (major, minor, patch),
+        [Some(major), Some(minor), None] => version < (major, minor + 1, 0),
+        [Some(major), None, _] => version < (major + 1, 0, 0),
+        [None, ..] => true,
+    }
+}
+
+/// The lowest version in the X-range `partial`
+fn lower(partial: &[Option<u64>; 3]) -> Version {
+    (
+        partial[0].unwrap_or(0),
+        partial[1].unwrap_or(0),
+        partial[2].unwrap_or(0),
+    )
+}
+
+/// Parse a version that may leave out or wildcard parts, like `18`, `18.x`
+/// or `*`
+fn parse_partial(partial: &str) -> Option<[Option<u64>; 3]> {
+    let partial = partial.trim_start_matches(['v', '=']);
+    let partial = partial.split(['-', '+']).next().unwrap_or_default();
+    let mut parts = [None; 3];
+    if partial.is_empty() {
+        return Some(parts);
+    }
+    for (i, part) in partial.split('.').enumerate() {
+        match part {
+            _ if i >= 3 => return None,
+            "x" | "X" | "*" => break,
+            part => parts[i] = Some(part.parse().ok()?),
+        }
+    }
+    Some(parts)
+}
+
+fn parse_version(version: &str) -> Option<Version> {
+    match parse_partial(version.trim())? {
+        [Some(major), Some(minor), Some(patch)] => Some((major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// The `engines` of the `package.json` in `dir`
+fn read_engines(dir: &Path) -> Value {
+    std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|package_json| serde_json::from_str::<Value>(&package_json).ok())
+        .and_then(|package_json| package_json.get("engines").cloned())
+        .unwrap_or(Value::Null)
+}
+
+/// The version `program --version` prints, without a leading `v`
+async fn version(program: &[String], working_dir: &Path) -> Option<String> {
+    let output = tokio::process::Command::new(&program[0])
+        .args(&program[1..])
+        .arg("--version")
+        .current_dir(working_dir)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(VERSION_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()
+        .filter(|output| output.status.success())?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    let version = version.lines().next()?.trim().trim_start_matches('v');
+    Some(version.to_string()).filter(|version| !version.is_empty())
+}
+
+/// The error for a program that isn't installed
+fn missing_program(program: &str, working_dir: &Path) -> HeisenbergError {
+    let hint = match program_name(program).as_str() {
+        "node" | "npm" | "npx" => {
+            "• Install Node.js, which includes npm, from https://nodejs.org or with a version manager like nvm or fnm\n• Open a new terminal after installing so PATH is updated".to_string()
+        }
+        name @ ("pnpm" | "yarn") => format!(
+            "• Run `corepack enable` to get {} with Node.js\n• Or install it with `npm install -g {}`\n• Or point .dev_command() at another package manager",
+            name, name
+        ),
+        "corepack" => "• Corepack ships with Node.js 16.9 to 24; install it with `npm install -g corepack` on newer ones".to_string(),
+        _ if working_dir.join("node_modules/.bin").join(program).exists() => format!(
+            "• {} is installed in node_modules/.bin, which isn't on PATH\n• Run it through a package script, like .dev_command([\"npm\", \"run\", \"dev\"])",
+            program
+        ),
+        _ => "• Check the spelling of the command\n• Install the program, or run it through a package script like .dev_command([\"npm\", \"run\", \"dev\"])\n• Run `npm install` in the working directory if it comes from a dependency".to_string(),
+    };
+    HeisenbergError::process(format!("Dev command not found: {}", program), hint)
+}
+
+/// The name of `program` without its directory and extension
+fn program_name(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
@@ -1,49 +1,156 @@
 //! Frontend process management
 
+use crate::core::config::{GlobalSettings, SpaRouteConfig};
+use crate::core::events::{emit, EventSender, HeisenbergEvent};
 use crate::error::HeisenbergError;
+use crate::services::client::{self, SystemProxy};
 use crate::services::health::HealthChecker;
-use crate::utils::open_browser;
-use std::collections::HashMap;
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
+use crate::services::limits::ResourceLimits;
+use crate::services::spawner::{OutputStream, ProcessSpawner, SpawnCommand, SpawnedProcess};
+use crate::utils::dev_output::{announced_url, is_ready_line, strip_ansi};
+use crate::utils::network::browser_url;
+use crate::utils::{load_env_files, open_browser_with};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::sync::{oneshot, Notify};
 #[cfg(feature = "logging")]
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+/// Tells apart the processes a route has had, across restarts
+static NEXT_PROCESS_ID: AtomicU64 = AtomicU64::new(0);
 
 /// Process manager for frontend dev servers
+///
+/// Each process is owned by a task that waits for it to exit, so nothing
+/// blocks the runtime: the map of running processes is only locked for
+/// lookups and updates, never while waiting on a process.
 pub struct ProcessManager {
     processes: Arc<Mutex<HashMap<String, ProcessHandle>>>,
+    /// What each route's dev server printed, kept after the process exits so
+    /// crash output can still be shown
+    output: Arc<Mutex<HashMap<String, ProcessOutput>>>,
+    startup_timeout: Duration,
+    output_lines: usize,
+    ci_mode: bool,
+    /// Open browsers, which needs someone at a terminal
+    interactive: bool,
+    /// Client for the health checks while a dev server starts
+    client: reqwest::Client,
+    /// For building clients of routes with root CAs of their own
+    system_proxy: SystemProxy,
+    spawner: Arc<dyn ProcessSpawner>,
+    /// Process table for [`ProcessManager::process_stats`], kept between
+    /// readings since CPU use is measured from one to the next
+    #[cfg(feature = "process-stats")]
+    system: Mutex<sysinfo::System>,
+    events: EventSender,
+}
+
+/// A running dev server's process ID and resource use
+///
+/// CPU and memory cover the process along with the processes it started,
+/// like the node process behind `npm run dev`. They are `None` without the
+/// `process-stats` feature, and for processes a custom
+/// [`ProcessSpawner`] runs on another machine, which have no PID either.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ProcessStats {
+    /// Process ID of the dev command
+    pub pid: Option<u32>,
+    /// CPU use since the previous reading, in percent of one core, so it can
+    /// exceed 100; the first reading is 0
+    pub cpu_percent: Option<f32>,
+    /// Resident memory in bytes
+    pub memory_bytes: Option<u64>,
+}
+
+/// What one dev server process printed, filled in by its reader tasks
+#[derive(Clone)]
+struct ProcessOutput {
+    /// Ring buffer of the last lines written to stdout or stderr
+    lines: Arc<Mutex<VecDeque<String>>>,
+    /// Most lines to keep
+    limit: usize,
+    /// Notified on the first line saying the dev server accepts requests
+    ready: Arc<Notify>,
+    /// The dev server URL if it announced a different port than configured
+    moved_to: Arc<Mutex<Option<String>>>,
+    /// Configured dev server URL, to compare announced ports against
+    configured_url: String,
+    route_id: String,
+    events: EventSender,
 }
 
 /// Handle for a managed process
+///
+/// The process itself belongs to its [`supervise`] task. Sending on `stop`,
+/// or dropping it, makes that task terminate the process.
 struct ProcessHandle {
-    child: Child,
+    id: u64,
+    /// The process ID, when the process runs on this machine
+    local_pid: Option<u32>,
     startup_time: Instant,
+    /// Takes a channel for the exit code, sent once the process is gone
+    stop: oneshot::Sender<oneshot::Sender<Option<i32>>>,
 }
 
 impl ProcessManager {
-    /// Create a new process manager
+    /// Create a new process manager with default settings
     pub fn new() -> Self {
+        Self::with_settings(&GlobalSettings::default())
+    }
+
+    /// Create a process manager using the startup timeout and CI mode from
+    /// the global settings
+    pub fn with_settings(settings: &GlobalSettings) -> Self {
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
+            output: Arc::new(Mutex::new(HashMap::new())),
+            startup_timeout: settings.process_startup_timeout,
+            output_lines: settings.dev_output_lines,
+            ci_mode: settings.ci_mode_enabled(),
+            interactive: settings.interactive_enabled(),
+            client: settings.http_client.clone(),
+            system_proxy: settings.system_proxy,
+            spawner: settings.process_spawner.clone(),
+            #[cfg(feature = "process-stats")]
+            system: Mutex::new(sysinfo::System::new()),
+            events: settings.events.clone(),
         }
     }
 
-    /// Start a frontend dev server process
+    /// Whether this manager is in CI mode and will never spawn processes
+    pub fn is_ci_mode(&self) -> bool {
+        self.ci_mode
+    }
+
+    /// Start the dev server for a route and wait for it to become healthy
+    ///
+    /// The route's setup commands run to completion first. In CI mode this
+    /// returns immediately without spawning anything.
     pub async fn start_process(
         &self,
         route_id: &str,
-        command: &[String],
-        working_dir: &std::path::Path,
-        dev_server_url: &str,
-        open_browser_flag: bool,
+        route: &SpaRouteConfig,
     ) -> Result<(), HeisenbergError> {
+        if self.ci_mode {
+            #[cfg(feature = "logging")]
+            info!(route_id = %route_id, "CI mode: not starting frontend dev server");
+            return Ok(());
+        }
+
+        let command = &route.command_for(&route.dev_command);
+        let working_dir = route.working_dir.as_path();
+        let dev_server_url = route.dev_proxy_url.as_str();
+
         #[cfg(feature = "logging")]
         info!(
             command = ?command,
             working_dir = %working_dir.display(),
             dev_server_url = %dev_server_url,
-            open_browser = open_browser_flag,
+            open_browser = route.open_browser,
             "Starting frontend dev server process"
         );
 
@@ -54,34 +161,142 @@ impl ProcessManager {
             ));
         }
 
-        let mut cmd = Command::new(&command[0]);
-        cmd.args(&command[1..])
-            .current_dir(working_dir)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+        let env_vars = if route.load_env_files {
+            let vars = load_env_files(working_dir);
+            #[cfg(feature = "logging")]
+            debug!(
+                count = vars.len(),
+                working_dir = %working_dir.display(),
+                "Loaded variables from .env files"
+            );
+            vars
+        } else {
+            Vec::new()
+        };
+
+        let output = ProcessOutput {
+            lines: Arc::default(),
+            limit: self.output_lines,
+            ready: Arc::new(Notify::new()),
+            moved_to: Arc::default(),
+            configured_url: dev_server_url.to_string(),
+            route_id: route_id.to_string(),
+            events: self.events.clone(),
+        };
+        self.output
+            .lock()
+            .unwrap()
+            .insert(route_id.to_string(), output.clone());
+
+        let mut commands: Vec<Vec<String>> = route
+            .setup_commands
+            .iter()
+            .map(|step| route.command_for(step))
+            .collect();
+        commands.push(command.clone());
+        if self.spawner.is_local() {
+            crate::services::preflight::check_toolchain(route, &commands).await?;
+        }
+
+        for step in &commands[..commands.len() - 1] {
+            run_setup_step(self.spawner.as_ref(), step, working_dir, &env_vars, &output).await?;
+        }
 
-        let child = cmd.spawn().map_err(|e| {
+        let mut env = env_vars;
+        env.extend(route.dev_env.iter().cloned());
+        let spawn = SpawnCommand {
+            program: command[0].clone(),
+            args: command[1..].to_vec(),
+            working_dir: working_dir.to_path_buf(),
+            env,
+            resource_limits: route.resource_limits,
+            inherit_stdin: true,
+        };
+        let mut process = self.spawner.spawn(&spawn).map_err(|e| {
             HeisenbergError::process(
                 format!("Failed to start process '{}': {}", command.join(" "), e),
                 "• Ensure the command exists (npm, yarn, pnpm)\n• Check if package.json exists in the working directory\n• Verify the working directory path is correct\n• Try running the command manually to test it"
-            )
+            ).with_source(e)
         })?;
 
+        for stream in process.take_output() {
+            capture_output(stream, output.clone());
+        }
+
+        let pid = process.id();
+        let (stop, stop_requests) = oneshot::channel();
+        let id = NEXT_PROCESS_ID.fetch_add(1, Ordering::Relaxed);
         let handle = ProcessHandle {
-            child,
+            id,
+            local_pid: pid.filter(|_| self.spawner.is_local()),
             startup_time: Instant::now(),
+            stop,
         };
 
+        // A restart's old process is stopped by dropping its handle
         self.processes
             .lock()
             .unwrap()
             .insert(route_id.to_string(), handle);
+        emit(
+            &self.events,
+            HeisenbergEvent::ProcessStarted {
+                pattern: route_id.to_string(),
+                pid: pid.unwrap_or_default(),
+            },
+        );
+        tokio::spawn(supervise(
+            process,
+            id,
+            route_id.to_string(),
+            Arc::downgrade(&self.processes),
+            stop_requests,
+            self.events.clone(),
+        ));
 
-        // Wait for the dev server to become healthy
-        let health_checker = HealthChecker::new(dev_server_url.to_string());
-        health_checker
-            .wait_for_healthy(Duration::from_secs(30))
-            .await?;
+        // Wait for the dev server to print a ready line or answer HTTP,
+        // giving up early if the process exits first
+        let health_checker = HealthChecker::with_client(
+            dev_server_url.to_string(),
+            client::for_route(route, &self.client, self.system_proxy),
+        )
+        .with_check(&route.health_check);
+        let deadline = Instant::now() + self.startup_timeout;
+        while !health_checker.is_healthy().await {
+            if !self.is_process_running(route_id) {
+                return Err(HeisenbergError::process(
+                    format!(
+                        "Dev server '{}' exited before {} became reachable",
+                        command.join(" "),
+                        dev_server_url
+                    ),
+                    "• Run the command manually in the working directory to see its output\n• Verify dependencies are installed (npm install)\n• Check that package.json has the script you configured",
+                ));
+            }
+            if Instant::now() >= deadline {
+                return Err(HeisenbergError::health_check(
+                    format!("Server did not become healthy within {:?}", self.startup_timeout),
+                    "• The dev server is taking too long to start\n• Check if the dev command is correct\n• Verify the dev server URL matches the port it listens on\n• Some servers need more time - try increasing .process_startup_timeout()",
+                ));
+            }
+            tokio::select! {
+                _ = output.ready.notified() => {
+                    #[cfg(feature = "logging")]
+                    debug!(route_id = %route_id, "Dev server printed a ready line");
+                    // Vite prints "ready in" just before the line with its URL
+                    for _ in 0..4 {
+                        if output.moved_to.lock().unwrap().is_some() {
+                            break;
+                        }
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                    }
+                    break;
+                }
+                _ = tokio::time::sleep(Duration::from_millis(250)) => {}
+            }
+        }
+        let moved_to = output.moved_to.lock().unwrap().clone();
+        let dev_server_url = moved_to.as_deref().unwrap_or(dev_server_url);
 
         #[cfg(feature = "logging")]
         info!(
@@ -90,14 +305,22 @@ impl ProcessManager {
             "Frontend dev server is healthy and ready"
         );
 
-        // Open browser if requested
-        if open_browser_flag {
-            if let Err(e) = open_browser(dev_server_url).await {
-                #[cfg(feature = "logging")]
-                warn!(error = %e, "Failed to open browser");
-                #[cfg(not(feature = "logging"))]
-                eprintln!("Warning: Failed to open browser: {}", e);
-            }
+        // Open browser if requested, in the background so a configured delay
+        // doesn't hold up the caller
+        if route.open_browser && !self.interactive {
+            #[cfg(feature = "logging")]
+            info!(route_id = %route_id, "Not opening a browser: stdout is not a terminal");
+        } else if route.open_browser {
+            let url = browser_url(&route.browser_policy.target_url(dev_server_url));
+            let policy = route.browser_policy.clone();
+            tokio::spawn(async move {
+                if let Err(e) = open_browser_with(&url, &policy).await {
+                    #[cfg(feature = "logging")]
+                    warn!(error = %e, "Failed to open browser");
+                    #[cfg(not(feature = "logging"))]
+                    eprintln!("Warning: Failed to open browser: {}", e);
+                }
+            });
         }
 
         Ok(())
@@ -105,53 +328,127 @@ impl ProcessManager {
 
     /// Check if a process is running
     pub fn is_process_running(&self, route_id: &str) -> bool {
-        let mut processes = self.processes.lock().unwrap();
-
-        if let Some(handle) = processes.get_mut(route_id) {
-            // Check if process is still alive
-            match handle.child.try_wait() {
-                Ok(Some(_)) => {
-                    // Process has exited, remove it
-                    processes.remove(route_id);
-                    false
-                }
-                Ok(None) => {
-                    // Process is still running
-                    true
-                }
-                Err(_) => {
-                    // Error checking status, assume dead
-                    processes.remove(route_id);
-                    false
-                }
-            }
-        } else {
-            false
-        }
+        self.processes.lock().unwrap().contains_key(route_id)
     }
 
-    /// Stop a specific process
-    pub fn stop_process(&self, route_id: &str) -> Result<(), HeisenbergError> {
-        let mut processes = self.processes.lock().unwrap();
-
-        if let Some(mut handle) = processes.remove(route_id) {
-            let _ = handle.child.kill();
-            let _ = handle.child.wait();
+    /// Stop a specific process, waiting until it and its children are gone
+    pub async fn stop_process(&self, route_id: &str) -> Result<(), HeisenbergError> {
+        let handle = self.processes.lock().unwrap().remove(route_id);
+        if let Some(handle) = handle {
+            handle.terminate().await;
         }
+        Ok(())
+    }
 
+    /// Stop all managed processes, waiting until they are gone
+    pub async fn stop_all_processes(&self) -> Result<(), HeisenbergError> {
+        let handles: Vec<ProcessHandle> = self
+            .processes
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, handle)| handle)
+            .collect();
+        for handle in handles {
+            handle.terminate().await;
+        }
         Ok(())
     }
 
-    /// Stop all managed processes
-    pub fn stop_all_processes(&self) -> Result<(), HeisenbergError> {
-        let mut processes = self.processes.lock().unwrap();
+    /// Start stopping a process without waiting for it to exit
+    ///
+    /// For callers that can't wait, such as `Drop` implementations.
+    pub fn stop_process_in_background(&self, route_id: &str) {
+        let handle = self.processes.lock().unwrap().remove(route_id);
+        if let Some(handle) = handle {
+            handle.terminate_in_background();
+        }
+    }
 
-        for (_, mut handle) in processes.drain() {
-            let _ = handle.child.kill();
-            let _ = handle.child.wait();
+    /// Start stopping every process without waiting for them to exit
+    pub fn stop_all_processes_in_background(&self) {
+        for (_, handle) in self.processes.lock().unwrap().drain() {
+            handle.terminate_in_background();
         }
+    }
 
-        Ok(())
+    /// The last lines a route's dev server wrote to stdout or stderr
+    ///
+    /// Still available after the process exits, until it is started again.
+    pub fn recent_output(&self, route_id: &str) -> Vec<String> {
+        self.output
+            .lock()
+            .unwrap()
+            .get(route_id)
+            .map(|output| output.lines.lock().unwrap().iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Where a route's dev server actually listens, if it announced a
+    /// different port than configured
+    ///
+    /// Vite, for example, moves to the next free port when its own is taken.
+    pub fn dev_server_url(&self, route_id: &str) -> Option<String> {
+        self.output
+            .lock()
+            .unwrap()
+            .get(route_id)
+            .and_then(|output| output.moved_to.lock().unwrap().clone())
+    }
+
+    /// The PID and resource use of a route's dev server, or `None` if it
+    /// isn't running
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::services::ProcessManager;
+    ///
+    /// let manager = ProcessManager::new();
+    /// assert_eq!(manager.process_stats("/*"), None);
+    /// ```
+    pub fn process_stats(&self, route_id: &str) -> Option<ProcessStats> {
+        let pid = self.processes.lock().unwrap().get(route_id)?.local_pid;
+        #[cfg(feature = "process-stats")]
+        let usage = pid.and_then(|pid| self.tree_usage(pid));
+        #[cfg(not(feature = "process-stats"))]
+        let usage: Option<(f32, u64)> = None;
+        Some(ProcessStats {
+            pid,
+            cpu_percent: usage.map(|(cpu_percent, _)| cpu_percent),
+            memory_bytes: usage.map(|(_, memory_bytes)| memory_bytes),
+        })
+    }
+
+    /// CPU percentage and memory of `pid` and its descendants
+    #[cfg(feature = "process-stats")]
+    fn tree_usage(&self, pid: u32) -> Option<(f32, u64)> {
+        use sysinfo::{Pid, ProcessRefreshKind};
+
+        let mut system = self.system.lock().unwrap();
+        system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_memory());
+        let processes = system.processes();
+        let root = Pid::from_u32(pid);
+        processes.get(&root)?;
+
+        let mut tree = vec![root];
+        let mut next = 0;
+        while let Some(&parent) = tree.get(next) {
+            tree.extend(
+                processes
+                    .iter()
+                    .filter(|(_, process)| process.parent() == Some(parent))
+                    .map(|(pid, _)| *pid),
+            );
+            next += 1;
+        }
+        Some(
+            tree.iter()
+                .filter_map(|pid| processes.get(pid))
+                .fold((0.0, 0), |(cpu, memory), process| {
+                    (cpu + process.cpu_usage(), memory + process.memory())
+                }),
+        )
     }
 
     /// Get process uptime
@@ -164,6 +461,248 @@ impl ProcessManager {
     }
 }
 
+impl ProcessHandle {
+    /// Stop the process and wait until it and its children are gone
+    async fn terminate(self) {
+        let (reply, exited) = oneshot::channel();
+        if self.stop.send(reply).is_ok() {
+            let _ = exited.await;
+        }
+    }
+
+    /// Ask the process group to exit now, leaving the wait and the forced
+    /// kill to the supervising task
+    ///
+    /// The signal is sent right away, so dev servers get it even if the
+    /// runtime shuts down before the task runs again.
+    fn terminate_in_background(self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.local_pid {
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGTERM);
+            }
+        }
+        drop(self.stop);
+    }
+}
+
+/// Own a dev server process until it exits or is told to stop
+///
+/// Reports the exit either way. A process that exits on its own is removed
+/// from `processes` unless a restart already replaced it.
+async fn supervise(
+    mut process: Box<dyn SpawnedProcess>,
+    id: u64,
+    route_id: String,
+    processes: Weak<Mutex<HashMap<String, ProcessHandle>>>,
+    stop_requests: oneshot::Receiver<oneshot::Sender<Option<i32>>>,
+    events: EventSender,
+) {
+    tokio::select! {
+        code = process.wait() => {
+            if let Some(processes) = processes.upgrade() {
+                let mut processes = processes.lock().unwrap();
+                if processes.get(&route_id).map(|handle| handle.id) == Some(id) {
+                    processes.remove(&route_id);
+                }
+            }
+            emit_exit(&events, &route_id, code.ok().flatten());
+        }
+        // A dropped handle stops the process too
+        reply = stop_requests => {
+            let code = process.terminate().await;
+            emit_exit(&events, &route_id, code);
+            if let Ok(reply) = reply {
+                let _ = reply.send(code);
+            }
+        }
+    }
+}
+
+fn emit_exit(events: &EventSender, route_id: &str, code: Option<i32>) {
+    emit(
+        events,
+        HeisenbergEvent::ProcessExited {
+            pattern: route_id.to_string(),
+            code,
+        },
+    );
+}
+
+/// Run one setup command to completion, keeping its output with the route's
+async fn run_setup_step(
+    spawner: &dyn ProcessSpawner,
+    step: &[String],
+    working_dir: &std::path::Path,
+    env_vars: &[(String, String)],
+    output: &ProcessOutput,
+) -> Result<(), HeisenbergError> {
+    #[cfg(feature = "logging")]
+    info!(command = ?step, "Running dev setup command");
+
+    let failed = |e: std::io::Error| {
+        HeisenbergError::process(
+            format!("Failed to run setup command '{}': {}", step.join(" "), e),
+            "• Ensure the command exists and is on your PATH\n• Verify the working directory path is correct",
+        ).with_source(e)
+    };
+    let mut process = spawner
+        .spawn(&SpawnCommand {
+            program: step[0].clone(),
+            args: step[1..].to_vec(),
+            working_dir: working_dir.to_path_buf(),
+            env: env_vars.to_vec(),
+            resource_limits: ResourceLimits::default(),
+            inherit_stdin: false,
+        })
+        .map_err(failed)?;
+    // Read the streams together, so neither pipe fills up while the other
+    // is being read
+    let readers: Vec<_> = process
+        .take_output()
+        .into_iter()
+        .map(|mut stream| {
+            tokio::spawn(async move {
+                let mut text = Vec::new();
+                let _ = stream.read_to_end(&mut text).await;
+                text
+            })
+        })
+        .collect();
+    let mut streams = Vec::new();
+    for reader in readers {
+        streams.push(reader.await.unwrap_or_default());
+    }
+    let code = process.wait().await.map_err(failed)?;
+
+    let mut last_lines = Vec::new();
+    for stream in &streams {
+        for line in String::from_utf8_lossy(stream).lines() {
+            let line = strip_ansi(line.trim_end());
+            output.push_line(line.clone());
+            last_lines.push(line);
+        }
+    }
+
+    if code != Some(0) {
+        let tail_start = last_lines.len().saturating_sub(10);
+        let mut hint = format!(
+            "• Run '{}' manually in the working directory to see what fails\n• The dev server is only started once every setup command succeeds",
+            step.join(" ")
+        );
+        if tail_start < last_lines.len() {
+            hint.push_str("\n• Last output:\n    ");
+            hint.push_str(&last_lines[tail_start..].join("\n    "));
+        }
+        let status = code.map_or_else(
+            || "no exit code".to_string(),
+            |code| format!("exit code {}", code),
+        );
+        return Err(HeisenbergError::process(
+            format!("Setup command '{}' failed with {}", step.join(" "), status),
+            hint,
+        ));
+    }
+    Ok(())
+}
+
+/// Read a child's output stream into its [`ProcessOutput`]
+///
+/// Reading also keeps the pipe drained, so a chatty dev server never blocks
+/// on a full pipe.
+fn capture_output(stream: OutputStream, output: ProcessOutput) {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        let mut seen_ready = false;
+        while matches!(reader.read_until(b'\n', &mut line).await, Ok(n) if n > 0) {
+            let text = strip_ansi(String::from_utf8_lossy(&line).trim_end());
+            if let Some(announced) = announced_url(&text) {
+                output.record_announced_url(&announced);
+            }
+            if !seen_ready && is_ready_line(&text) {
+                seen_ready = true;
+                output.ready.notify_one();
+            }
+            output.push_line(text);
+            line.clear();
+        }
+    });
+}
+
+impl ProcessOutput {
+    fn push_line(&self, text: String) {
+        if self.limit == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == self.limit {
+            lines.pop_front();
+        }
+        lines.push_back(text);
+    }
+
+    /// Note the URL a ready line announced, if its port isn't the configured one
+    ///
+    /// The configured host is kept, since dev servers announce hosts like
+    /// `0.0.0.0` that can't be connected to.
+    fn record_announced_url(&self, announced: &str) {
+        let (Ok(configured), Ok(announced)) = (
+            reqwest::Url::parse(&self.configured_url),
+            reqwest::Url::parse(announced),
+        ) else {
+            return;
+        };
+        if announced.port_or_known_default() == configured.port_or_known_default() {
+            return;
+        }
+        let mut moved = configured;
+        if moved.set_port(announced.port_or_known_default()).is_err() {
+            return;
+        }
+        let moved = moved.as_str().trim_end_matches('/').to_string();
+
+        let mut moved_to = self.moved_to.lock().unwrap();
+        if moved_to.as_deref() == Some(moved.as_str()) {
+            return;
+        }
+        #[cfg(feature = "logging")]
+        info!(
+            route_id = %self.route_id,
+            configured = %self.configured_url,
+            dev_server_url = %moved,
+            "Dev server announced a different port, proxying there"
+        );
+        #[cfg(not(feature = "logging"))]
+        eprintln!(
+            "Dev server for {} is listening on {} instead of {}",
+            self.route_id, moved, self.configured_url
+        );
+        emit(
+            &self.events,
+            HeisenbergEvent::DevServerMoved {
+                pattern: self.route_id.clone(),
+                dev_server: moved.clone(),
+            },
+        );
+        *moved_to = Some(moved);
+    }
+}
+
+impl std::fmt::Debug for ProcessManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let routes: Vec<String> = self.processes.lock().unwrap().keys().cloned().collect();
+        f.debug_struct("ProcessManager")
+            .field("routes", &routes)
+            .field("startup_timeout", &self.startup_timeout)
+            .field("output_lines", &self.output_lines)
+            .field("ci_mode", &self.ci_mode)
+            .field("interactive", &self.interactive)
+            .field("spawner", &self.spawner)
+            .finish()
+    }
+}
+
 impl Default for ProcessManager {
     fn default() -> Self {
         Self::new()
@@ -172,6 +711,6 @@ impl Default for ProcessManager {
 
 impl Drop for ProcessManager {
     fn drop(&mut self) {
-        let _ = self.stop_all_processes();
+        self.stop_all_processes_in_background();
     }
 }
@@ -1,79 +1,959 @@
 //! Proxy service for development mode
 
+use crate::core::events::{emit, EventSender, HeisenbergEvent};
 use crate::error::HeisenbergError;
-use crate::services::health::HealthChecker;
-use hyper::{Response, StatusCode};
-use std::sync::Arc;
+use crate::services::client::{default_client, PROXY_TIMEOUT};
+use crate::services::health::{HealthCheck, HealthChecker};
+use crate::services::theme::ErrorPageTheme;
+use crate::services::websocket::{self, WebSocketKeepalive};
+use futures_util::stream::{BoxStream, StreamExt};
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::BodyExt;
+use hyper::body::{Body, Bytes, Frame};
+use hyper::header::{self, HeaderMap};
+use hyper::{Method, Response, StatusCode};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+
+/// Response headers that describe the upstream connection rather than the
+/// payload, so they must not be copied onto the proxied response
+pub(crate) const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "trailer",
+    "content-length",
+];
+
+/// Request headers that are not forwarded to the dev server. The client sets
+/// `host` and `content-length` itself, and `accept-encoding` is dropped so
+/// responses arrive uncompressed.
+const SKIPPED_REQUEST_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+    "trailer",
+    "host",
+    "content-length",
+    "accept-encoding",
+];
+
+/// Marker in the extensions of responses describing a proxy failure rather
+/// than a response from the dev server
+#[derive(Debug, Clone, Copy)]
+pub struct ProxyFailure;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The body of a response from [`ProxyService::forward_streaming`]
+#[derive(Debug)]
+pub enum ProxyBody {
+    /// The whole body, read before the response was returned
+    Full(Bytes),
+    /// The body as the dev server sends it. It ends with an error if the
+    /// connection to the dev server fails or the body grows past
+    /// [`max_response_body`](ProxyService::max_response_body).
+    Streaming(UnsyncBoxBody<Bytes, BoxError>),
+}
+
+impl ProxyBody {
+    /// Read the whole body
+    pub async fn collect(self) -> Result<Bytes, BoxError> {
+        match self {
+            ProxyBody::Full(body) => Ok(body),
+            ProxyBody::Streaming(body) => Ok(body.collect().await?.to_bytes()),
+        }
+    }
+}
+
+/// A request body on its way to the dev server
+#[derive(Debug)]
+pub enum ForwardedBody {
+    /// The request has no body
+    Empty,
+    /// The whole body, which can be sent again to a fallback dev server
+    Full(Bytes),
+    /// The body as the browser sends it
+    Streaming(reqwest::Body),
+}
+
+impl From<Option<Bytes>> for ForwardedBody {
+    fn from(body: Option<Bytes>) -> Self {
+        body.map_or(ForwardedBody::Empty, ForwardedBody::Full)
+    }
+}
+
+/// A request proxied to the dev server, as a [`BodyInspector`] sees it
+#[derive(Debug, Clone, Copy)]
+pub struct ProxiedRequest<'a> {
+    /// Request method
+    pub method: &'a Method,
+    /// Path and query string, as requested from the backend
+    pub path: &'a str,
+    /// Headers the browser sent
+    pub headers: &'a HeaderMap,
+    /// Request body, if there was one and it was read in full
+    pub body: Option<&'a Bytes>,
+}
+
+/// A callback shown every request proxied to the dev server along with the
+/// response going back to the browser
+///
+/// See [`SpaRouteBuilder::inspect_proxied`](crate::core::config::SpaRouteBuilder::inspect_proxied).
+#[derive(Clone)]
+pub struct BodyInspector(Arc<InspectFn>);
+
+type InspectFn = dyn Fn(&ProxiedRequest<'_>, &Response<Bytes>) + Send + Sync;
+
+impl BodyInspector {
+    /// Wrap a callback taking the proxied request and its response
+    pub fn new<F>(inspect: F) -> Self
+    where
+        F: Fn(&ProxiedRequest<'_>, &Response<Bytes>) + Send + Sync + 'static,
+    {
+        Self(Arc::new(inspect))
+    }
+
+    /// Show the callback a request and its response
+    pub fn inspect(&self, request: &ProxiedRequest<'_>, response: &Response<Bytes>) {
+        (self.0)(request, response)
+    }
+}
+
+impl std::fmt::Debug for BodyInspector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BodyInspector(..)")
+    }
+}
 
 /// Proxy service for forwarding requests to dev servers
+#[derive(Debug)]
 pub struct ProxyService {
-    target_url: String,
+    target_url: RwLock<String>,
     client: reqwest::Client,
-    health_checker: Arc<HealthChecker>,
+    health_checker: RwLock<Arc<HealthChecker>>,
+    /// What the dev server's health checks request and accept
+    health_check: HealthCheck,
+    check_health: bool,
+    preserve_host: bool,
+    rewrite_dev_urls: bool,
+    /// Pings and idle timeout of proxied WebSocket connections
+    websocket_keepalive: WebSocketKeepalive,
+    /// Shown each proxied request and its response
+    body_inspector: Option<BodyInspector>,
+    /// Largest response body passed on from the dev server
+    max_response_body: Option<usize>,
+    /// Read whole response bodies before returning them, even from
+    /// [`forward_streaming`](Self::forward_streaming)
+    buffer_responses: bool,
+    /// Read whole successful HTML responses, so the error overlay can be
+    /// injected into them
+    buffer_html: bool,
+    /// How long requests wait for the dev server's response headers
+    timeout: std::time::Duration,
+    /// How often error pages check whether the dev server is back
+    refresh_interval: std::time::Duration,
+    theme: ErrorPageTheme,
+    last_error: Mutex<Option<String>>,
+    /// Health as last observed, to report changes
+    healthy: Mutex<Option<bool>>,
+    events: Option<EventSender>,
+    /// Where requests go while the dev server is down
+    fallback: Option<Box<ProxyService>>,
 }
 
 impl ProxyService {
-    /// Create a new proxy service
+    /// Create a new proxy service with a client of its own
     pub fn new(target_url: String) -> Self {
-        let health_checker = Arc::new(HealthChecker::new(target_url.clone()));
+        Self::with_client(target_url, default_client())
+    }
 
-        // Configure client for optimal connection pooling
-        let client = reqwest::Client::builder()
-            .pool_max_idle_per_host(10)
-            .pool_idle_timeout(std::time::Duration::from_secs(30))
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
+    /// Create a proxy service that sends requests and health checks through
+    /// `client`, sharing its connection pool
+    ///
+    /// The client should not follow redirects, so they reach the browser
+    /// along with any cookies they set; see
+    /// [`default_client`](crate::services::client::default_client).
+    pub fn with_client(target_url: String, client: reqwest::Client) -> Self {
+        let health_checker = Arc::new(HealthChecker::with_client(
+            target_url.clone(),
+            client.clone(),
+        ));
 
         Self {
-            target_url,
+            target_url: RwLock::new(target_url),
             client,
-            health_checker,
+            health_checker: RwLock::new(health_checker),
+            health_check: HealthCheck::default(),
+            check_health: true,
+            preserve_host: false,
+            rewrite_dev_urls: false,
+            websocket_keepalive: WebSocketKeepalive::default(),
+            body_inspector: None,
+            max_response_body: None,
+            buffer_responses: false,
+            buffer_html: false,
+            timeout: PROXY_TIMEOUT,
+            refresh_interval: std::time::Duration::from_secs(2),
+            theme: ErrorPageTheme::default(),
+            last_error: Mutex::new(None),
+            healthy: Mutex::new(None),
+            events: None,
+            fallback: None,
         }
     }
 
-    /// Proxy a request to the target server
-    pub async fn proxy_request(&self, path: &str) -> Result<Response<String>, HeisenbergError> {
+    /// The dev server URL requests are proxied to
+    pub fn target_url(&self) -> String {
+        self.target_url.read().unwrap().clone()
+    }
+
+    /// Point the proxy at a different dev server URL
+    ///
+    /// Used when a dev server announces it listens somewhere other than
+    /// configured, like Vite moving to the next port when its own is taken.
+    pub fn set_target_url(&self, url: String) {
+        *self.health_checker.write().unwrap() = Arc::new(
+            HealthChecker::with_client(url.clone(), self.client.clone())
+                .with_check(&self.health_check),
+        );
+        *self.target_url.write().unwrap() = url;
+    }
+
+    /// Check whether the dev server is responding right now
+    pub async fn is_healthy(&self) -> bool {
+        let health_checker = self.health_checker.read().unwrap().clone();
+        let healthy = health_checker.is_healthy().await;
+        self.observe_health(healthy);
+        healthy
+    }
+
+    /// Why the most recent request failed, if it did
+    ///
+    /// Cleared as soon as a request reaches the dev server again.
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Skip the health check before each request and proxy straight away
+    pub fn without_health_check(mut self) -> Self {
+        self.check_health = false;
+        self
+    }
+
+    /// Request the path and apply the predicate of `check` in health checks
+    pub fn with_health_check(mut self, check: HealthCheck) -> Self {
+        self.health_checker = RwLock::new(Arc::new(
+            HealthChecker::with_client(self.target_url(), self.client.clone()).with_check(&check),
+        ));
+        self.health_check = check;
+        self
+    }
+
+    /// Send the browser's original `Host` header instead of the dev server's
+    ///
+    /// Off by default, since dev servers with host checking (Vite's
+    /// `server.allowedHosts`, webpack's `allowedHosts`) reject hosts they don't
+    /// know about.
+    pub fn preserve_host(mut self, preserve: bool) -> Self {
+        self.preserve_host = preserve;
+        self
+    }
+
+    /// Ping proxied WebSocket connections and close idle ones as `keepalive`
+    /// says
+    pub fn websocket_keepalive(mut self, keepalive: WebSocketKeepalive) -> Self {
+        self.websocket_keepalive = keepalive;
+        self
+    }
+
+    /// Show `inspector` every request the dev server answers, with the
+    /// response as it goes to the browser
+    ///
+    /// Only buffered responses are shown: those from
+    /// [`forward`](Self::forward), or from
+    /// [`forward_streaming`](Self::forward_streaming) with
+    /// [`buffer_responses`](Self::buffer_responses) on.
+    pub fn with_body_inspector(mut self, inspector: BodyInspector) -> Self {
+        self.body_inspector = Some(inspector);
+        self
+    }
+
+    /// Answer `502 Bad Gateway` instead of passing on responses whose body
+    /// is larger than `limit` bytes, or pass on any response with `None`,
+    /// the default
+    pub fn max_response_body(mut self, limit: Option<usize>) -> Self {
+        self.max_response_body = limit;
+        self
+    }
+
+    /// Have [`forward_streaming`](Self::forward_streaming) read whole
+    /// response bodies before returning them, as [`forward`](Self::forward)
+    /// does
+    ///
+    /// Off by default. See
+    /// [`SpaRouteBuilder::buffer_proxied`](crate::core::config::SpaRouteBuilder::buffer_proxied).
+    pub fn buffer_responses(mut self, buffer: bool) -> Self {
+        self.buffer_responses = buffer;
+        self
+    }
+
+    /// Read whole successful HTML responses in
+    /// [`forward_streaming`](Self::forward_streaming), so the error overlay
+    /// script can be injected into them
+    pub(crate) fn buffer_html(mut self, buffer: bool) -> Self {
+        self.buffer_html = buffer;
+        self
+    }
+
+    /// Fail requests whose response headers haven't arrived within `timeout`
+    ///
+    /// [`PROXY_TIMEOUT`] by default. The body isn't timed, so streamed
+    /// responses can run as long as the dev server keeps them open.
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How often the error page shown while the dev server is down checks
+    /// whether it is back, reloading once it is
+    ///
+    /// Two seconds by default; `Duration::ZERO` leaves the page as it is.
+    pub fn refresh_interval(mut self, interval: std::time::Duration) -> Self {
+        self.refresh_interval = interval;
+        self
+    }
+
+    /// Style the error pages with a theme's colors, logo, title and extra
+    /// troubleshooting bullets
+    pub fn with_theme(mut self, theme: ErrorPageTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Proxy to another server, such as a shared dev server or a staging
+    /// deployment, while this one is down
+    ///
+    /// Requests the dev server can't take, because it fails its health check
+    /// or refuses the connection, are forwarded to `fallback` instead of
+    /// getting an error page. Only while `fallback` is down as well is its
+    /// error page returned.
+    pub fn with_fallback(mut self, fallback: ProxyService) -> Self {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+
+    /// A redirect target on the dev server's origin, as a path on the
+    /// origin the browser is already talking to
+    ///
+    /// A dev server redirecting to `http://localhost:5173/login` would
+    /// otherwise send the browser around Heisenberg and the backend.
+    /// `localhost`, `127.0.0.1` and `[::1]` count as the same host. For a
+    /// target with a path, like `https://staging.example.com/app`, the path is
+    /// removed again, and redirects elsewhere on its host are left alone.
+    fn local_location(&self, location: &str) -> Option<String> {
+        let target = reqwest::Url::parse(&self.target_url()).ok()?;
+        let location = if location.starts_with('/') && !location.starts_with("//") {
+            target.join(location).ok()?
+        } else {
+            reqwest::Url::parse(location).ok()?
+        };
+        let same_host = location.host_str() == target.host_str()
+            || (is_loopback(location.host_str()) && is_loopback(target.host_str()));
+        if location.scheme() != target.scheme()
+            || !same_host
+            || location.port_or_known_default() != target.port_or_known_default()
+        {
+            return None;
+        }
+
+        let base = target.path().trim_end_matches('/');
+        let mut path = match location.path().strip_prefix(base)? {
+            "" => "/".to_string(),
+            rest if rest.starts_with('/') => rest.to_string(),
+            _ => return None,
+        };
+        if let Some(query) = location.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        if let Some(fragment) = location.fragment() {
+            path.push('#');
+            path.push_str(fragment);
+        }
+        Some(path)
+    }
+
+    /// A `Set-Cookie` value from a remote target without its `Domain`
+    ///
+    /// The browser would reject a cookie for `staging.example.com` set on
+    /// `localhost`, so it's left to default to the backend's host.
+    fn local_cookie(&self, cookie: &str) -> Option<String> {
+        self.remote_target()?;
+        let mut attributes = cookie.split(';');
+        let mut kept = vec![attributes.next()?];
+        kept.extend(attributes.filter(|attribute| {
+            let name = attribute.split('=').next().unwrap_or("").trim();
+            !name.eq_ignore_ascii_case("domain")
+        }));
+        Some(kept.join(";"))
+    }
+
+    /// The target, when it runs on another machine rather than this one
+    fn remote_target(&self) -> Option<reqwest::Url> {
+        let target = reqwest::Url::parse(&self.target_url()).ok()?;
+        (!is_loopback(target.host_str())).then_some(target)
+    }
+
+    /// The URL on the target server for a request path
+    ///
+    /// A trailing slash on the target, as in `https://staging.example.com/`,
+    /// isn't doubled.
+    fn upstream_url(&self, path: &str) -> String {
+        format!("{}{}", self.target_url().trim_end_matches('/'), path)
+    }
+
+    /// The request headers sent to the target server
+    ///
+    /// `Host` is the target's own, so remote HTTPS servers get the name they
+    /// serve a certificate for, unless the browser's is preserved. Remote
+    /// targets, like a staging deployment, often check `Origin` and `Referer`
+    /// to guard against cross-site requests; when these name the backend,
+    /// they are rewritten to the target, and the backend's host is passed on
+    /// in `X-Forwarded-Host` and `X-Forwarded-Proto` instead.
+    fn upstream_headers(&self, headers: &HeaderMap, upgrade: bool) -> reqwest::header::HeaderMap {
+        use reqwest::header::{HeaderName, HeaderValue};
+
+        let mut upstream = reqwest::header::HeaderMap::new();
+        for (name, value) in headers {
+            let forwarded = (upgrade && name == header::UPGRADE)
+                || !SKIPPED_REQUEST_HEADERS.contains(&name.as_str());
+            if let (true, Ok(name), Ok(value)) = (
+                forwarded,
+                HeaderName::from_bytes(name.as_str().as_bytes()),
+                HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                upstream.append(name, value);
+            }
+        }
+        let host = headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok());
+        if self.preserve_host {
+            if let Some(Ok(host)) = host.map(HeaderValue::from_str) {
+                upstream.insert(reqwest::header::HOST, host);
+            }
+            return upstream;
+        }
+        let (Some(target), Some(host)) = (self.remote_target(), host) else {
+            return upstream;
+        };
+
+        let proto = headers
+            .get("x-forwarded-proto")
+            .and_then(|proto| proto.to_str().ok())
+            .unwrap_or("http");
+        let backend = format!("{}://{}", proto, host);
+        let origin = headers
+            .get(header::ORIGIN)
+            .and_then(|origin| origin.to_str().ok());
+        if origin.is_some_and(|origin| origin.eq_ignore_ascii_case(&backend)) {
+            if let Ok(origin) = HeaderValue::from_str(&target.origin().ascii_serialization()) {
+                upstream.insert(reqwest::header::ORIGIN, origin);
+            }
+        }
+        let referer = headers
+            .get(header::REFERER)
+            .and_then(|referer| referer.to_str().ok())
+            .and_then(|referer| referer.strip_prefix(&backend))
+            .filter(|path| path.is_empty() || path.starts_with('/'));
+        if let Some(Ok(referer)) =
+            referer.map(|path| HeaderValue::from_str(&self.upstream_url(path)))
+        {
+            upstream.insert(reqwest::header::REFERER, referer);
+        }
+        for (name, value) in [("x-forwarded-host", host), ("x-forwarded-proto", proto)] {
+            if let (false, Ok(value)) = (upstream.contains_key(name), HeaderValue::from_str(value))
+            {
+                upstream.insert(name, value);
+            }
+        }
+        upstream
+    }
+
+    /// Replace the dev server's origin in HTML, JavaScript and CSS responses
+    /// with the origin of the request's `Host` header
+    ///
+    /// Off by default. See
+    /// [`SpaRouteBuilder::rewrite_dev_urls`](crate::core::config::SpaRouteBuilder::rewrite_dev_urls).
+    pub fn rewrite_dev_urls(mut self, rewrite: bool) -> Self {
+        self.rewrite_dev_urls = rewrite;
+        self
+    }
+
+    /// Whether rewriting is on and applies to bodies of `content_type`
+    fn rewrites(&self, content_type: Option<&str>) -> bool {
+        self.rewrite_dev_urls
+            && content_type.is_some_and(|content_type| {
+                let essence = content_type.split(';').next().unwrap_or("").trim();
+                [
+                    "text/html",
+                    "text/css",
+                    "text/javascript",
+                    "application/javascript",
+                ]
+                .iter()
+                .any(|kind| essence.eq_ignore_ascii_case(kind))
+            })
+    }
+
+    /// A response body with the dev server's origin replaced by the one the
+    /// browser used, if rewriting is on and the body mentions it
+    fn with_backend_origin(
+        &self,
+        content_type: Option<&str>,
+        body: &Bytes,
+        headers: &HeaderMap,
+    ) -> Option<Bytes> {
+        if !self.rewrites(content_type) {
+            return None;
+        }
+        let host = headers.get(header::HOST)?.to_str().ok()?;
+        let secure = headers
+            .get("x-forwarded-proto")
+            .and_then(|proto| proto.to_str().ok())
+            .is_some_and(|proto| proto.eq_ignore_ascii_case("https"));
+        let target = reqwest::Url::parse(&self.target_url()).ok()?;
+        let port = target.port_or_known_default()?;
+        let dev_hosts = if is_loopback(target.host_str()) {
+            LOOPBACK_HOSTS.to_vec()
+        } else {
+            vec![target.host_str()?]
+        };
+        let dev_secure = target.scheme() == "https";
+        let scheme = |secure: bool, web_socket: bool| match (secure, web_socket) {
+            (false, false) => "http",
+            (true, false) => "https",
+            (false, true) => "ws",
+            (true, true) => "wss",
+        };
+
+        let text = std::str::from_utf8(body).ok()?;
+        let mut rewritten = text.to_string();
+        for dev_host in dev_hosts {
+            for web_socket in [false, true] {
+                rewritten = replace_origin(
+                    &rewritten,
+                    &format!("{}://{}:{}", scheme(dev_secure, web_socket), dev_host, port),
+                    &format!("{}://{}", scheme(secure, web_socket), host),
+                );
+            }
+        }
+        (rewritten != text).then(|| Bytes::from(rewritten))
+    }
+
+    /// Publish health changes and proxy errors on a configuration's event
+    /// channel
+    pub(crate) fn with_events(mut self, events: EventSender) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Proxy a GET request to the target server
+    ///
+    /// `path` may include a query string, which is forwarded as-is.
+    pub async fn proxy_request(&self, path: &str) -> Result<Response<Bytes>, HeisenbergError> {
+        self.forward(&Method::GET, path, &HeaderMap::new(), None)
+            .await
+    }
+
+    /// Forward a request to the target server
+    ///
+    /// The method, end-to-end headers (including `Content-Type` and cookies)
+    /// and body are passed through unchanged, so form posts and multipart
+    /// uploads reach the dev server exactly as the browser sent them. Response
+    /// bodies are read in full and returned as received, so binary payloads
+    /// such as gRPC-web frames are not altered. Responses larger than
+    /// [`max_response_body`](Self::max_response_body), or whose body can't be
+    /// read, become a 502 error page.
+    pub async fn forward(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<Response<Bytes>, HeisenbergError> {
+        let response = self.relay(method, path, headers, body.into(), true).await?;
+        Ok(response.map(|body| match body {
+            ProxyBody::Full(body) => body,
+            ProxyBody::Streaming(_) => unreachable!("buffered responses have a full body"),
+        }))
+    }
+
+    /// Forward a request to the target server like [`forward`](Self::forward),
+    /// returning the response as soon as its headers arrive
+    ///
+    /// The body is passed on as the dev server sends it, so server-sent
+    /// events and other long-lived responses reach the browser as they are
+    /// written. It is read in full first when
+    /// [`buffer_responses`](Self::buffer_responses) is on, and for responses
+    /// Heisenberg edits: HTML, JavaScript and CSS with
+    /// [`rewrite_dev_urls`](Self::rewrite_dev_urls), and `403`s, which are
+    /// checked for dev server host checks. A streamed request body isn't
+    /// sent again to the [fallback](Self::with_fallback) when the dev server
+    /// can't be reached.
+    pub async fn forward_streaming(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: ForwardedBody,
+    ) -> Result<Response<ProxyBody>, HeisenbergError> {
+        self.relay(method, path, headers, body, self.buffer_responses)
+            .await
+    }
+
+    async fn relay(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: ForwardedBody,
+        buffer: bool,
+    ) -> Result<Response<ProxyBody>, HeisenbergError> {
         // Quick health check before proxying
-        if !self.health_checker.is_healthy().await {
-            return Ok(Response::builder()
-                .status(StatusCode::SERVICE_UNAVAILABLE)
-                .header("content-type", "text/html")
-                .body(self.create_unavailable_error_page())
+        if self.check_health && !self.is_healthy().await {
+            self.record_error(format!("{} is not responding", self.target_url()));
+            if let Some(fallback) = &self.fallback {
+                return fallback
+                    .relay_boxed(method, path, headers, body, buffer)
+                    .await;
+            }
+            return Ok(self
+                .failure_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    self.create_unavailable_error_page(),
+                )
+                .map(ProxyBody::Full));
+        }
+
+        let target_url = self.upstream_url(path);
+
+        // reqwest uses its own copy of the http types, so convert by value
+        let upstream_method =
+            reqwest::Method::from_bytes(method.as_str().as_bytes()).map_err(|_| {
+                HeisenbergError::config(
+                    format!("Unsupported request method: {}", method),
+                    "• Only standard HTTP methods can be proxied to the dev server",
+                )
+            })?;
+        let mut request = self
+            .client
+            .request(upstream_method, &target_url)
+            .headers(self.upstream_headers(headers, false));
+        // A full body is kept for the inspector and the fallback
+        let full_body = match &body {
+            ForwardedBody::Full(body) => Some(body.clone()),
+            _ => None,
+        };
+        let streamed = matches!(body, ForwardedBody::Streaming(_));
+        match body {
+            ForwardedBody::Empty => {}
+            ForwardedBody::Full(body) => request = request.body(body),
+            ForwardedBody::Streaming(body) => request = request.body(body),
+        }
+
+        // The timeout covers the wait for the response headers only, so a
+        // streamed body can take as long as the dev server likes
+        let response = match tokio::time::timeout(self.timeout, request.send()).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                // Return enhanced error page when dev server unavailable
+                if e.is_connect() {
+                    self.observe_health(false);
+                }
+                self.record_error(e.to_string());
+                if let (true, false, Some(fallback)) = (e.is_connect(), streamed, &self.fallback) {
+                    let body = full_body.map_or(ForwardedBody::Empty, ForwardedBody::Full);
+                    return fallback
+                        .relay_boxed(method, path, headers, body, buffer)
+                        .await;
+                }
+                return Ok(self
+                    .failure_response(StatusCode::SERVICE_UNAVAILABLE, self.create_error_page(&e))
+                    .map(ProxyBody::Full));
+            }
+            Err(_) => {
+                let error = format!(
+                    "{} did not respond within {:?}",
+                    self.target_url(),
+                    self.timeout
+                );
+                self.record_error(error.clone());
+                return Ok(self
+                    .failure_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        self.create_error_page(&error),
+                    )
+                    .map(ProxyBody::Full));
+            }
+        };
+
+        self.last_error.lock().unwrap().take();
+        self.observe_health(true);
+        let status = response.status();
+        let mut builder = Response::builder().status(status.as_u16());
+        // Appending keeps every value of repeated headers like `Set-Cookie`
+        for (name, value) in response.headers() {
+            if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+                continue;
+            }
+            let rewritten = match value.to_str() {
+                Ok(location) if name == reqwest::header::LOCATION => self.local_location(location),
+                Ok(cookie) if name == reqwest::header::SET_COOKIE => self.local_cookie(cookie),
+                _ => None,
+            };
+            match rewritten {
+                Some(value) => builder = builder.header(name.as_str(), value),
+                None => builder = builder.header(name.as_str(), value.as_bytes()),
+            }
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        let html = status.is_success()
+            && content_type
+                .as_deref()
+                .is_some_and(|content_type| content_type.starts_with("text/html"));
+        let edited = status == reqwest::StatusCode::FORBIDDEN
+            || self.rewrites(content_type.as_deref())
+            || (self.buffer_html && html);
+        if !buffer && !edited {
+            if let Some(limit) = self.max_response_body {
+                if response
+                    .content_length()
+                    .is_some_and(|len| len > limit as u64)
+                {
+                    return Ok(self
+                        .body_failure(path, BodyError::TooLarge(limit))
+                        .map(ProxyBody::Full));
+                }
+            }
+            let body = UpstreamBody {
+                chunks: response.bytes_stream().boxed(),
+                remaining: self.max_response_body,
+            };
+            return Ok(builder
+                .body(ProxyBody::Streaming(body.boxed_unsync()))
                 .unwrap());
         }
 
-        let target_url = format!("{}{}", self.target_url, path);
+        let body = match read_body(response, self.max_response_body).await {
+            Ok(body) => body,
+            Err(e) => return Ok(self.body_failure(path, e).map(ProxyBody::Full)),
+        };
+
+        if status == reqwest::StatusCode::FORBIDDEN {
+            let text = String::from_utf8_lossy(&body);
+            if is_host_check_rejection(&text) {
+                self.record_error(format!(
+                    "{} rejected the request's Host header",
+                    self.target_url()
+                ));
+                return Ok(self
+                    .failure_response(StatusCode::FORBIDDEN, self.create_host_blocked_page(&text))
+                    .map(ProxyBody::Full));
+            }
+        }
 
-        match self.client.get(&target_url).send().await {
-            Ok(response) => {
-                let status = response.status();
-                let body = response.text().await.unwrap_or_default();
+        let body = self
+            .with_backend_origin(content_type.as_deref(), &body, headers)
+            .unwrap_or(body);
+        let response = builder.body(body).unwrap();
+        if let (true, Some(inspector)) = (buffer, &self.body_inspector) {
+            let request = ProxiedRequest {
+                method,
+                path,
+                headers,
+                body: full_body.as_ref(),
+            };
+            inspector.inspect(&request, &response);
+        }
+        Ok(response.map(ProxyBody::Full))
+    }
 
-                Ok(Response::builder()
-                    .status(status.as_u16())
-                    .header("content-type", "text/html")
-                    .body(body)
-                    .unwrap())
+    /// [`relay`](Self::relay) as a boxed future, for calling it on the
+    /// fallback from within `relay`
+    fn relay_boxed<'a>(
+        &'a self,
+        method: &'a Method,
+        path: &'a str,
+        headers: &'a HeaderMap,
+        body: ForwardedBody,
+        buffer: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<Response<ProxyBody>, HeisenbergError>> + Send + 'a>>
+    {
+        Box::pin(self.relay(method, path, headers, body, buffer))
+    }
+
+    /// Forward a WebSocket handshake (or another HTTP/1.1 upgrade) to the
+    /// target server
+    ///
+    /// When the server switches protocols, its `101` response is returned and
+    /// a task relays bytes between `client` and the server until either side
+    /// closes. Any other response is returned as it is.
+    pub async fn forward_upgrade(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+        client: hyper::upgrade::OnUpgrade,
+    ) -> Result<Response<Bytes>, HeisenbergError> {
+        let target_url = self.upstream_url(path);
+        let request = self
+            .client
+            .get(&target_url)
+            .header(reqwest::header::CONNECTION, "upgrade")
+            .headers(self.upstream_headers(headers, true));
+
+        let response = match tokio::time::timeout(self.timeout, request.send()).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                if e.is_connect() {
+                    self.observe_health(false);
+                }
+                self.record_error(e.to_string());
+                return Err(HeisenbergError::health_check(
+                    format!("WebSocket connection to {} failed: {}", target_url, e),
+                    "• Check that the dev server is running\n• Use .websocket_bypass() for WebSocket endpoints your app serves itself",
+                ).with_source(e));
             }
-            Err(e) => {
-                // Return enhanced error page when dev server unavailable
-                Ok(Response::builder()
-                    .status(StatusCode::SERVICE_UNAVAILABLE)
-                    .header("content-type", "text/html")
-                    .body(self.create_error_page(&e))
-                    .unwrap())
+            Err(_) => {
+                let error = format!("{} did not respond within {:?}", target_url, self.timeout);
+                self.record_error(error.clone());
+                return Err(HeisenbergError::health_check(
+                    format!("WebSocket connection failed: {}", error),
+                    "• Check that the dev server is running\n• Raise .proxy_timeout() if it is slow to answer",
+                ));
             }
+        };
+        self.last_error.lock().unwrap().take();
+        self.observe_health(true);
+
+        let status = response.status();
+        let mut builder = Response::builder().status(status.as_u16());
+        for (name, value) in response.headers() {
+            let upgrading = status == reqwest::StatusCode::SWITCHING_PROTOCOLS
+                && (name == reqwest::header::UPGRADE || name == reqwest::header::CONNECTION);
+            if upgrading || !HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+                builder = builder.header(name.as_str(), value.as_bytes());
+            }
+        }
+        if status != reqwest::StatusCode::SWITCHING_PROTOCOLS {
+            return Ok(match read_body(response, self.max_response_body).await {
+                Ok(body) => builder.body(body).unwrap(),
+                Err(e) => self.body_failure(path, e),
+            });
         }
+
+        // Other upgrades don't speak WebSocket frames to slip pings between
+        let websocket = response
+            .headers()
+            .get(reqwest::header::UPGRADE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|protocol| protocol.eq_ignore_ascii_case("websocket"));
+        let keepalive = if websocket {
+            self.websocket_keepalive
+        } else {
+            WebSocketKeepalive::default()
+        };
+        tokio::spawn(async move {
+            let (Ok(client), Ok(upstream)) = (client.await, response.upgrade().await) else {
+                return;
+            };
+            let client = hyper_util::rt::TokioIo::new(client);
+            websocket::relay(client, upstream, keepalive).await;
+        });
+        Ok(builder.body(Bytes::new()).unwrap())
+    }
+
+    pub(crate) fn record_error(&self, error: String) {
+        if let Some(events) = &self.events {
+            emit(
+                events,
+                HeisenbergEvent::ProxyError {
+                    dev_server: self.target_url(),
+                    error: error.clone(),
+                },
+            );
+        }
+        *self.last_error.lock().unwrap() = Some(error);
+    }
+
+    fn observe_health(&self, healthy: bool) {
+        let previous = self.healthy.lock().unwrap().replace(healthy);
+        if previous == Some(healthy) {
+            return;
+        }
+        if let Some(events) = &self.events {
+            emit(
+                events,
+                HeisenbergEvent::HealthChanged {
+                    dev_server: self.target_url(),
+                    healthy,
+                },
+            );
+        }
+    }
+
+    fn failure_response(&self, status: StatusCode, page: String) -> Response<Bytes> {
+        let mut response = Response::builder()
+            .status(status)
+            .header("content-type", "text/html")
+            .body(Bytes::from(page))
+            .unwrap();
+        response.extensions_mut().insert(ProxyFailure);
+        response
+    }
+
+    /// The `502 Bad Gateway` page for a response body that couldn't be
+    /// passed on
+    fn body_failure(&self, path: &str, error: BodyError) -> Response<Bytes> {
+        let page = match error {
+            BodyError::TooLarge(limit) => {
+                self.record_error(format!(
+                    "{} sent a response body larger than {} bytes",
+                    self.target_url(),
+                    limit
+                ));
+                self.create_response_too_large_page(path, limit)
+            }
+            BodyError::Read(e) => {
+                let error = format!("Failed to read the response to {}: {}", path, e);
+                self.record_error(error.clone());
+                self.create_error_page(&error)
+            }
+        };
+        self.failure_response(StatusCode::BAD_GATEWAY, page)
     }
 
     /// Create an enhanced error page for dev server unavailability
-    fn create_error_page(&self, error: &reqwest::Error) -> String {
+    fn create_error_page(&self, error: &dyn std::fmt::Display) -> String {
+        let target_url = self.target_url();
         format!(
             r#"<!DOCTYPE html>
 <html>
 <head>
-    <title>Development Server Unavailable</title>
+    <title>{title}</title>
     <style>
         body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 40px; }}
         .container {{ max-width: 600px; margin: 0 auto; }}
@@ -81,14 +961,16 @@ impl ProxyService {
         .info {{ background: #eff; border: 1px solid #cdf; padding: 20px; border-radius: 8px; margin-top: 20px; }}
         code {{ background: #f5f5f5; padding: 2px 4px; border-radius: 3px; }}
         ul {{ margin: 10px 0; }}
+        {css}
     </style>
 </head>
 <body>
     <div class="container">
+        {logo}
         <div class="error">
             <h1>🚫 Development Server Unavailable</h1>
-            <p><strong>Could not connect to:</strong> <code>{}</code></p>
-            <p><strong>Error:</strong> {}</p>
+            <p><strong>Could not connect to:</strong> <code>{target_url}</code></p>
+            <p><strong>Error:</strong> {error}</p>
         </div>
         
         <div class="info">
@@ -96,54 +978,292 @@ impl ProxyService {
             <p>The frontend development server is not responding. Here's what you can try:</p>
             <ul>
                 <li><strong>Check if the dev server is running:</strong> Look for a process running on the configured port</li>
-                <li><strong>Verify the URL:</strong> Make sure <code>{}</code> is correct</li>
+                <li><strong>Verify the URL:</strong> Make sure <code>{target_url}</code> is correct</li>
                 <li><strong>Start the dev server manually:</strong> Run <code>npm run dev</code> or <code>yarn dev</code> in your frontend directory</li>
                 <li><strong>Check for port conflicts:</strong> Another process might be using the same port</li>
                 <li><strong>Wait a moment:</strong> The dev server might still be starting up</li>
+                {bullets}
             </ul>
             <p><em>This page will automatically work once the development server is available.</em></p>
         </div>
     </div>
-    
-    <script>
-        // Auto-refresh every 3 seconds to check if dev server is back
-        setTimeout(() => {{ window.location.reload(); }}, 3000);
-    </script>
+    {refresh}
 </body>
 </html>"#,
-            self.target_url, error, self.target_url
+            title = self.theme.document_title("Development Server Unavailable"),
+            css = self.theme.css(),
+            logo = self.theme.logo_html(),
+            bullets = self.theme.bullets(),
+            refresh = self.refresh_script(),
         )
     }
 
     /// Create error page for when health check fails
     fn create_unavailable_error_page(&self) -> String {
+        let target_url = self.target_url();
+        let bullets = self.theme.bullets();
+        let tips = if bullets.is_empty() {
+            bullets
+        } else {
+            format!("<ul>{}</ul>", bullets)
+        };
         format!(
             r#"<!DOCTYPE html>
 <html>
 <head>
-    <title>Development Server Unavailable</title>
+    <title>{title}</title>
     <style>
         body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 40px; }}
         .container {{ max-width: 600px; margin: 0 auto; }}
         .error {{ background: #fee; border: 1px solid #fcc; padding: 20px; border-radius: 8px; }}
         code {{ background: #f5f5f5; padding: 2px 4px; border-radius: 3px; }}
+        {css}
     </style>
 </head>
 <body>
     <div class="container">
+        {logo}
         <div class="error">
             <h1>⏳ Development Server Starting</h1>
-            <p>The development server at <code>{}</code> is not ready yet.</p>
-            <p><em>This page will refresh automatically...</em></p>
+            <p>The development server at <code>{target_url}</code> is not ready yet.</p>
+            <p><em>This page will reload once it is ready...</em></p>
+            {tips}
         </div>
     </div>
-    
-    <script>
-        setTimeout(() => {{ window.location.reload(); }}, 2000);
-    </script>
+    {refresh}
 </body>
 </html>"#,
-            self.target_url
+            title = self.theme.document_title("Development Server Unavailable"),
+            css = self.theme.css(),
+            logo = self.theme.logo_html(),
+            tips = tips,
+            refresh = self.refresh_script(),
         )
     }
+
+    /// Script that reloads an error page once the dev server is healthy
+    ///
+    /// Polls the status endpoint every `refresh_interval` rather than
+    /// reloading blindly, so the page reloads exactly once. Without the
+    /// status endpoint, or for a proxy it doesn't list like a fallback, the
+    /// page reloads on every poll instead.
+    fn refresh_script(&self) -> String {
+        if self.refresh_interval.is_zero() {
+            return String::new();
+        }
+        format!(
+            r#"<script>
+        const DEV_SERVER = {};
+        async function reloadWhenReady() {{
+            try {{
+                const res = await fetch('/__heisenberg/status', {{ cache: 'no-store' }});
+                if (!res.ok) return window.location.reload();
+                const status = await res.json();
+                const route = status.routes.find((route) => route.dev_server === DEV_SERVER);
+                if (!route || route.healthy) return window.location.reload();
+            }} catch (e) {{}}
+            setTimeout(reloadWhenReady, {});
+        }}
+        setTimeout(reloadWhenReady, {});
+    </script>"#,
+            serde_json::Value::String(self.target_url()),
+            self.refresh_interval.as_millis(),
+            self.refresh_interval.as_millis(),
+        )
+    }
+
+    /// Create error page for when the dev server rejects the Host header
+    fn create_host_blocked_page(&self, detail: &str) -> String {
+        let detail = detail
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        let target_url = self.target_url();
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>{title}</title>
+    <style>
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 40px; }}
+        .container {{ max-width: 600px; margin: 0 auto; }}
+        .error {{ background: #fee; border: 1px solid #fcc; padding: 20px; border-radius: 8px; }}
+        .info {{ background: #eff; border: 1px solid #cdf; padding: 20px; border-radius: 8px; margin-top: 20px; }}
+        code {{ background: #f5f5f5; padding: 2px 4px; border-radius: 3px; }}
+        ul {{ margin: 10px 0; }}
+        {css}
+    </style>
+</head>
+<body>
+    <div class="container">
+        {logo}
+        <div class="error">
+            <h1>🔒 Host Not Allowed</h1>
+            <p>The development server at <code>{target_url}</code> rejected the request because of its <code>Host</code> header.</p>
+            <p><strong>Response:</strong> {detail}</p>
+        </div>
+
+        <div class="info">
+            <h2>💡 Troubleshooting</h2>
+            <ul>
+                <li><strong>Let the proxy rewrite the Host:</strong> Remove <code>.preserve_host(true)</code> from the route</li>
+                <li><strong>Vite:</strong> Add the host to <code>server.allowedHosts</code> in <code>vite.config.js</code></li>
+                <li><strong>webpack:</strong> Add the host to <code>devServer.allowedHosts</code></li>
+                {bullets}
+            </ul>
+        </div>
+    </div>
+</body>
+</html>"#,
+            title = self.theme.document_title("Host Not Allowed"),
+            css = self.theme.css(),
+            logo = self.theme.logo_html(),
+            bullets = self.theme.bullets(),
+        )
+    }
+
+    /// Create error page for a response too large to pass on
+    fn create_response_too_large_page(&self, path: &str, limit: usize) -> String {
+        let path = path
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>{title}</title>
+    <style>
+        body {{ font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif; margin: 40px; }}
+        .container {{ max-width: 600px; margin: 0 auto; }}
+        .error {{ background: #fee; border: 1px solid #fcc; padding: 20px; border-radius: 8px; }}
+        .info {{ background: #eff; border: 1px solid #cdf; padding: 20px; border-radius: 8px; margin-top: 20px; }}
+        code {{ background: #f5f5f5; padding: 2px 4px; border-radius: 3px; }}
+        ul {{ margin: 10px 0; }}
+        {css}
+    </style>
+</head>
+<body>
+    <div class="container">
+        {logo}
+        <div class="error">
+            <h1>📦 Response Too Large</h1>
+            <p>The development server at <code>{target_url}</code> answered <code>{path}</code> with a body larger than {limit} bytes.</p>
+        </div>
+
+        <div class="info">
+            <h2>💡 Troubleshooting</h2>
+            <ul>
+                <li><strong>Raise the limit:</strong> Pass a larger size to <code>.max_response_body()</code></li>
+                <li><strong>Check the dev server:</strong> A response that never ends may mean it is stuck</li>
+                {bullets}
+            </ul>
+        </div>
+    </div>
+</body>
+</html>"#,
+            title = self.theme.document_title("Response Too Large"),
+            css = self.theme.css(),
+            logo = self.theme.logo_html(),
+            target_url = self.target_url(),
+            bullets = self.theme.bullets(),
+        )
+    }
+}
+
+/// Why a dev server response body couldn't be passed on
+enum BodyError {
+    /// The body is larger than this many bytes
+    TooLarge(usize),
+    /// The connection failed while the body was read
+    Read(reqwest::Error),
+}
+
+/// The whole body of `response`, up to `limit` bytes
+///
+/// Reading stops at the limit, so a dev server streaming without end can't
+/// exhaust memory.
+async fn read_body(
+    mut response: reqwest::Response,
+    limit: Option<usize>,
+) -> Result<Bytes, BodyError> {
+    let Some(limit) = limit else {
+        return response.bytes().await.map_err(BodyError::Read);
+    };
+    if response
+        .content_length()
+        .is_some_and(|len| len > limit as u64)
+    {
+        return Err(BodyError::TooLarge(limit));
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(BodyError::Read)? {
+        if body.len() + chunk.len() > limit {
+            return Err(BodyError::TooLarge(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(body))
+}
+
+/// A dev server response body passed on chunk by chunk
+struct UpstreamBody {
+    chunks: BoxStream<'static, reqwest::Result<Bytes>>,
+    /// Bytes the body may still take before it is over the response limit
+    remaining: Option<usize>,
+}
+
+impl Body for UpstreamBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        let chunk = match self.chunks.poll_next_unpin(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(Some(Ok(chunk))) => chunk,
+        };
+        if let Some(remaining) = &mut self.remaining {
+            if chunk.len() > *remaining {
+                return Poll::Ready(Some(Err(
+                    "the dev server sent a response body larger than the limit".into(),
+                )));
+            }
+            *remaining -= chunk.len();
+        }
+        Poll::Ready(Some(Ok(Frame::data(chunk))))
+    }
+}
+
+/// Host names that all reach a dev server listening locally
+const LOOPBACK_HOSTS: &[&str] = &["localhost", "127.0.0.1", "[::1]"];
+
+fn is_loopback(host: Option<&str>) -> bool {
+    host.is_some_and(|host| LOOPBACK_HOSTS.contains(&host))
+}
+
+/// Replace an origin, leaving longer ports like `:51730` for `:5173` alone
+fn replace_origin(text: &str, from: &str, to: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(from) {
+        let end = start + from.len();
+        let continues_port = rest[end..].starts_with(|c: char| c.is_ascii_digit());
+        replaced.push_str(&rest[..start]);
+        replaced.push_str(if continues_port { from } else { to });
+        rest = &rest[end..];
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+/// Whether a 403 body comes from a dev server's host check
+fn is_host_check_rejection(body: &str) -> bool {
+    body.contains("Invalid Host header")
+        || body.contains("allowedHosts")
+        || (body.contains("Blocked request") && body.contains("host"))
 }
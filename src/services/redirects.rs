@@ -0,0 +1,173 @@
+//! Netlify-style `_redirects` rules for production serving
+
+use std::path::Path;
+#[cfg(feature = "logging")]
+use tracing::warn;
+
+/// Name of the rules file looked for in a build directory
+pub const REDIRECTS_FILE: &str = "_redirects";
+
+/// One line of a `_redirects` file: `from to [status][!]`
+///
+/// `from` may end in a `/*` splat and contain `:name` placeholders for
+/// single segments, which `to` can use as `:splat` and `:name`. A status of
+/// `200` rewrites to another file without changing the URL, `3xx` redirects
+/// and `4xx` serves `to` with that status. Without `!` a rule only applies
+/// when no file exists at the requested path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectRule {
+    /// Path pattern the rule matches
+    pub from: String,
+    /// Path or URL the request goes to
+    pub to: String,
+    /// Response status, `301` unless given
+    pub status: u16,
+    /// Apply even when a file exists at the requested path
+    pub force: bool,
+}
+
+impl RedirectRule {
+    /// Whether the rule redirects rather than rewrites
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.status)
+    }
+
+    /// Where a request path goes under this rule, if the rule matches it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::services::redirects::parse_redirects;
+    ///
+    /// let rules = parse_redirects("/blog/:year/*  /news/:year/:splat  302");
+    /// assert_eq!(rules[0].target("/blog/2024/hello/world").as_deref(), Some("/news/2024/hello/world"));
+    /// assert_eq!(rules[0].target("/blog"), None);
+    /// ```
+    pub fn target(&self, path: &str) -> Option<String> {
+        let pattern: Vec<&str> = segments(&self.from).collect();
+        let path: Vec<&str> = segments(path).collect();
+
+        let mut captures: Vec<(&str, String)> = Vec::new();
+        for (index, part) in pattern.iter().enumerate() {
+            if *part == "*" && index == pattern.len() - 1 {
+                captures.push(("splat", path.get(index..).unwrap_or_default().join("/")));
+                return Some(substitute(&self.to, &captures));
+            }
+            let segment = path.get(index)?;
+            match part.strip_prefix(':') {
+                Some(name) => captures.push((name, segment.to_string())),
+                None if part == segment => {}
+                None => return None,
+            }
+        }
+        (pattern.len() == path.len()).then(|| substitute(&self.to, &captures))
+    }
+}
+
+/// Parse the rules in a `_redirects` file
+///
+/// Comments and blank lines are skipped, as are rules this server can't
+/// apply: query parameter and country or language conditions, and domain
+/// redirects whose `from` is a full URL.
+pub fn parse_redirects(text: &str) -> Vec<RedirectRule> {
+    text.lines()
+        .filter_map(|line| parse_line(line).ok().flatten())
+        .collect()
+}
+
+/// Load the `_redirects` file of a build directory, if there is one
+///
+/// Lines that can't be applied are reported and skipped.
+pub fn load_redirects(dir: &Path) -> Vec<RedirectRule> {
+    let file = dir.join(REDIRECTS_FILE);
+    let Ok(text) = std::fs::read_to_string(&file) else {
+        return Vec::new();
+    };
+
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        match parse_line(line) {
+            Ok(Some(rule)) => rules.push(rule),
+            Ok(None) => {}
+            Err(reason) => {
+                #[cfg(feature = "logging")]
+                warn!(file = %file.display(), line = number + 1, reason = %reason, "Skipping redirect rule");
+                #[cfg(not(feature = "logging"))]
+                eprintln!(
+                    "Warning: Skipping rule on line {} of {}: {}",
+                    number + 1,
+                    file.display(),
+                    reason
+                );
+            }
+        }
+    }
+    rules
+}
+
+/// A rule, `None` for comments and blank lines, or why the line is skipped
+fn parse_line(line: &str) -> Result<Option<RedirectRule>, String> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return Ok(None);
+    }
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let (from, rest) = match tokens.split_first() {
+        None => return Ok(None),
+        Some(split) => split,
+    };
+    if !from.starts_with('/') {
+        return Err(format!(
+            "'{}' is not a path; domain redirects aren't supported",
+            from
+        ));
+    }
+    let Some((to, rest)) = rest.split_first() else {
+        return Err(format!("'{}' has no destination", from));
+    };
+    if to.contains('=') && !to.starts_with('/') && !to.contains("://") {
+        return Err("query parameter conditions aren't supported".to_string());
+    }
+
+    let mut status = 301;
+    let mut force = false;
+    if let Some((code, conditions)) = rest.split_first() {
+        if !conditions.is_empty() {
+            return Err("country, language and role conditions aren't supported".to_string());
+        }
+        let digits = code.strip_suffix('!').unwrap_or(code);
+        force = digits.len() < code.len();
+        status = digits
+            .parse()
+            .ok()
+            .filter(|status| (200..600).contains(status))
+            .ok_or_else(|| format!("'{}' is not a status code", code))?;
+    }
+    if status == 200 && to.contains("://") {
+        return Err("proxying to other servers isn't supported".to_string());
+    }
+
+    Ok(Some(RedirectRule {
+        from: from.to_string(),
+        to: to.to_string(),
+        status,
+        force,
+    }))
+}
+
+/// Path segments, ignoring leading, trailing and repeated slashes
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// Fill in `:name` placeholders, longest names first so `:splat` isn't
+/// mistaken for `:s`
+fn substitute(to: &str, captures: &[(&str, String)]) -> String {
+    let mut captures = captures.to_vec();
+    captures.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+    let mut target = to.to_string();
+    for (name, value) in captures {
+        target = target.replace(&format!(":{}", name), &value);
+    }
+    target
+}
@@ -0,0 +1,240 @@
+//! How dev server processes are started
+//!
+//! [`ProcessManager`](crate::services::ProcessManager) starts dev servers and
+//! their setup commands through a [`ProcessSpawner`], [`LocalSpawner`] unless
+//! configured otherwise. A spawner of your own can run them somewhere else,
+//! like a remote dev box or a container, or stand in for real processes in
+//! tests.
+
+use crate::services::limits::ResourceLimits;
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::Stdio;
+#[cfg(unix)]
+use std::time::Duration;
+use tokio::io::AsyncRead;
+use tokio::process::{Child, Command};
+
+/// How long a process group gets to exit after SIGTERM before it is killed
+#[cfg(unix)]
+const GROUP_SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// A boxed future, for the async methods of [`SpawnedProcess`]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// The stdout or stderr of a [`SpawnedProcess`]
+pub type OutputStream = Box<dyn AsyncRead + Send + Unpin>;
+
+/// A command for a [`ProcessSpawner`] to start
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpawnCommand {
+    /// The program, like `npm`
+    pub program: String,
+    /// Its arguments, like `["run", "dev"]`
+    pub args: Vec<String>,
+    /// Directory to run it in
+    pub working_dir: PathBuf,
+    /// Variables to set on top of the inherited environment
+    pub env: Vec<(String, String)>,
+    /// Caps on what the process may use
+    pub resource_limits: ResourceLimits,
+    /// Whether the process reads the terminal, as dev servers do for their
+    /// keyboard shortcuts; setup commands get no input
+    pub inherit_stdin: bool,
+}
+
+/// Starts the processes of dev servers
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::spawner::{LocalSpawner, ProcessSpawner, SpawnCommand, SpawnedProcess};
+/// use heisenberg::Heisenberg;
+///
+/// /// Run every command inside the `frontend` container
+/// #[derive(Debug)]
+/// struct DockerExec;
+///
+/// impl ProcessSpawner for DockerExec {
+///     fn spawn(&self, command: &SpawnCommand) -> std::io::Result<Box<dyn SpawnedProcess>> {
+///         let mut args = vec!["exec".to_string(), "-i".to_string(), "frontend".to_string()];
+///         args.push(command.program.clone());
+///         args.extend(command.args.iter().cloned());
+///         LocalSpawner.spawn(&SpawnCommand {
+///             program: "docker".to_string(),
+///             args,
+///             ..command.clone()
+///         })
+///     }
+/// }
+///
+/// let config = Heisenberg::new()
+///     .process_spawner(DockerExec)
+///     .spa("./dist")
+///     .build();
+/// ```
+pub trait ProcessSpawner: Send + Sync + std::fmt::Debug {
+    /// Start `command`, with its stdout and stderr captured
+    fn spawn(&self, command: &SpawnCommand) -> io::Result<Box<dyn SpawnedProcess>>;
+
+    /// Whether processes run on this machine, so the programs they need can
+    /// be looked for on `PATH` before spawning
+    fn is_local(&self) -> bool {
+        false
+    }
+}
+
+/// A process started by a [`ProcessSpawner`]
+pub trait SpawnedProcess: Send {
+    /// The process ID on this machine, if it runs here
+    fn id(&self) -> Option<u32>;
+
+    /// The process's stdout and stderr, handed out once
+    fn take_output(&mut self) -> Vec<OutputStream>;
+
+    /// Wait for the process to exit, returning its exit code, or `None` if
+    /// it had none, like when killed by a signal
+    fn wait(&mut self) -> BoxFuture<'_, io::Result<Option<i32>>>;
+
+    /// Stop the process along with everything it started, returning its exit
+    /// code if it had one
+    fn terminate(&mut self) -> BoxFuture<'_, Option<i32>>;
+}
+
+/// Spawns processes on this machine, the default
+///
+/// On Unix each dev server leads its own process group, so stopping it takes
+/// down the node processes it started; on Windows its process tree is
+/// killed. [`ResourceLimits`] apply as described there.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalSpawner;
+
+impl ProcessSpawner for LocalSpawner {
+    fn spawn(&self, command: &SpawnCommand) -> io::Result<Box<dyn SpawnedProcess>> {
+        let mut cmd = Command::new(&command.program);
+        match command.args.as_slice() {
+            // cmd.exe parses its own command line, which quoting the script as
+            // one argument would garble
+            #[cfg(windows)]
+            [flag, script] if command.program.eq_ignore_ascii_case("cmd") && flag == "/C" => {
+                cmd.arg(flag).raw_arg(script);
+            }
+            args => {
+                cmd.args(args);
+            }
+        }
+        cmd.current_dir(&command.working_dir)
+            .envs(command.env.iter().cloned())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // A last resort if the runtime shuts down before the supervising
+            // task can stop the process
+            .kill_on_drop(true);
+        if !command.inherit_stdin {
+            cmd.stdin(Stdio::null());
+        }
+
+        // Give the child its own process group so shutdown can signal the whole
+        // group, including node grandchildren spawned by shell-wrapped commands
+        #[cfg(unix)]
+        cmd.process_group(0);
+        #[cfg(unix)]
+        crate::services::limits::apply_on_start(&mut cmd, command.resource_limits);
+
+        let child = cmd.spawn()?;
+        // `None` once the child has been reaped; a pid of 0 would make the
+        // group signals below hit our own process group
+        let pid = child.id();
+        #[cfg(windows)]
+        if let Some(Err(e)) =
+            pid.map(|pid| crate::services::limits::apply_to_process(pid, command.resource_limits))
+        {
+            #[cfg(feature = "logging")]
+            tracing::warn!(program = %command.program, error = %e, "Failed to limit the process's resources");
+            #[cfg(not(feature = "logging"))]
+            eprintln!(
+                "Warning: Failed to limit the resources of {}: {}",
+                command.program, e
+            );
+        }
+        Ok(Box::new(LocalProcess { child, pid }))
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+}
+
+/// A process started by [`LocalSpawner`]
+struct LocalProcess {
+    child: Child,
+    pid: Option<u32>,
+}
+
+impl SpawnedProcess for LocalProcess {
+    fn id(&self) -> Option<u32> {
+        self.pid
+    }
+
+    fn take_output(&mut self) -> Vec<OutputStream> {
+        let stdout = self
+            .child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as OutputStream);
+        let stderr = self
+            .child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as OutputStream);
+        stdout.into_iter().chain(stderr).collect()
+    }
+
+    fn wait(&mut self) -> BoxFuture<'_, io::Result<Option<i32>>> {
+        Box::pin(async move { Ok(self.child.wait().await?.code()) })
+    }
+
+    fn terminate(&mut self) -> BoxFuture<'_, Option<i32>> {
+        Box::pin(terminate_process_tree(&mut self.child, self.pid))
+    }
+}
+
+/// Terminate a child process along with everything it spawned
+///
+/// Without a `pid` the child has already exited and been reaped, so only it
+/// is waited for.
+async fn terminate_process_tree(child: &mut Child, pid: Option<u32>) -> Option<i32> {
+    // On Windows, `npm run dev` launches node as a grandchild. Killing only the
+    // npm shim leaves node holding the dev server port, so take down the tree.
+    #[cfg(windows)]
+    if let Some(pid) = pid {
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+    }
+
+    // The child leads its own process group (see `LocalSpawner::spawn`), so a
+    // negative pid signals every process in it. Ask politely first so dev
+    // servers can release their ports, then force-kill whatever is left.
+    #[cfg(unix)]
+    if let Some(pid) = pid {
+        let pgid = pid as libc::pid_t;
+        unsafe {
+            libc::kill(-pgid, libc::SIGTERM);
+        }
+
+        let _ = tokio::time::timeout(GROUP_SHUTDOWN_GRACE, child.wait()).await;
+
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+
+    let _ = child.start_kill();
+    child.wait().await.ok().and_then(|status| status.code())
+}
@@ -1,57 +1,741 @@
 //! Static file serving for production mode
 
+use crate::core::config::{EtagStrategy, HeaderRule, SymlinkPolicy};
 use crate::error::HeisenbergError;
-use hyper::{Response, StatusCode};
+use crate::services::headers::HEADERS_FILE;
+use crate::services::redirects::REDIRECTS_FILE;
+use crate::utils::encoding::{self, Encoding};
+use hyper::body::Bytes;
+use hyper::header::HeaderValue;
+use hyper::{header, Response, StatusCode};
 // use rust_embed::RustEmbed; // Will be used when we add actual embedded assets
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Static file service using embedded assets
+/// Static file service serving a built frontend directory
+#[derive(Debug, Clone)]
 pub struct StaticFileService {
-    #[allow(dead_code)] // Will be used when we add actual embedded assets
+    root: PathBuf,
     fallback_file: Option<String>,
+    cache: Option<AssetCache>,
+    etag: EtagStrategy,
+    symlinks: SymlinkPolicy,
+    /// Dotfiles and dot-directories that may be served
+    allowed_dotfiles: Vec<String>,
+    directory_listing: bool,
+    header_rules: Vec<HeaderRule>,
+    /// Content hashes by file, for [`EtagStrategy::Strong`]
+    strong_etags: Arc<Mutex<HashMap<PathBuf, StrongEtag>>>,
+    #[cfg(feature = "mmap")]
+    mmap_threshold: Option<u64>,
+    #[cfg(feature = "sri")]
+    subresource_integrity: bool,
 }
 
 impl StaticFileService {
-    /// Create a new static file service
-    pub fn new(fallback_file: Option<String>) -> Self {
-        Self { fallback_file }
+    /// Create a new static file service rooted at a build directory
+    pub fn new(root: impl Into<PathBuf>, fallback_file: Option<String>) -> Self {
+        Self {
+            root: root.into(),
+            fallback_file,
+            cache: None,
+            etag: EtagStrategy::default(),
+            symlinks: SymlinkPolicy::default(),
+            allowed_dotfiles: vec![".well-known".to_string()],
+            directory_listing: false,
+            header_rules: Vec::new(),
+            strong_etags: Arc::default(),
+            #[cfg(feature = "mmap")]
+            mmap_threshold: None,
+            #[cfg(feature = "sri")]
+            subresource_integrity: false,
+        }
+    }
+
+    /// Keep file contents in an in-memory cache instead of reading the disk
+    /// on every request
+    ///
+    /// The cache handle can be shared between services to give them a single
+    /// byte budget.
+    pub fn with_cache(mut self, cache: AssetCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Choose how the `ETag` header is computed, weak from size and
+    /// modification time by default
+    pub fn with_etag(mut self, strategy: EtagStrategy) -> Self {
+        self.etag = strategy;
+        self
+    }
+
+    /// Choose which symlinks below the root are followed, only those that
+    /// stay inside it by default
+    pub fn with_symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Serve these files and directories whose names start with a dot,
+    /// instead of only `.well-known`
+    pub fn with_allowed_dotfiles<I, N>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        self.allowed_dotfiles = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Answer requests for directories without an `index.html` with an
+    /// HTML list of their contents, ahead of the fallback file
+    ///
+    /// Meant for internal tools; hidden dotfiles stay hidden.
+    pub fn with_directory_listing(mut self) -> Self {
+        self.directory_listing = true;
+        self
+    }
+
+    /// Set headers on files whose path in the root matches a rule's glob
+    ///
+    /// Later rules win over earlier ones for the same header.
+    pub fn with_header_rules(mut self, rules: Vec<HeaderRule>) -> Self {
+        self.header_rules = rules;
+        self
+    }
+
+    /// Memory-map files of at least `bytes` instead of reading them into
+    /// the heap
+    ///
+    /// Suits large assets like videos, wasm modules and source maps. Mapped
+    /// files bypass the asset cache. The build directory must not be
+    /// modified while serving: truncating a mapped file crashes the process.
+    #[cfg(feature = "mmap")]
+    pub fn with_mmap_threshold(mut self, bytes: u64) -> Self {
+        self.mmap_threshold = Some(bytes);
+        self
+    }
+
+    /// Add `integrity` attributes to scripts and stylesheets in served HTML
+    ///
+    /// Hashes the referenced files on every HTML response; pair with a cache
+    /// to avoid re-reading them from disk.
+    #[cfg(feature = "sri")]
+    pub fn with_subresource_integrity(mut self) -> Self {
+        self.subresource_integrity = true;
+        self
+    }
+
+    /// Serve a file by request path
+    ///
+    /// Directories resolve to their `index.html`. Paths that don't name a file
+    /// are answered with the fallback file so client-side routes work.
+    pub async fn serve_file(&self, path: &str) -> Result<Response<Bytes>, HeisenbergError> {
+        self.serve_file_or(path, self.fallback_file.as_deref())
+            .await
+    }
+
+    /// Serve a file by request path, falling back to a specific file
+    ///
+    /// Like [`serve_file`](Self::serve_file), with the fallback chosen by the
+    /// caller instead of the one the service was created with.
+    pub async fn serve_file_or(
+        &self,
+        path: &str,
+        fallback: Option<&str>,
+    ) -> Result<Response<Bytes>, HeisenbergError> {
+        self.serve_encoded(path, fallback, None).await
+    }
+
+    /// Serve a file in the best encoding the client accepts
+    ///
+    /// Like [`serve_file_or`](Self::serve_file_or), but a precompressed
+    /// `<file>.br` or `<file>.gz` next to the file is served instead when
+    /// `accept_encoding` allows it, chosen by [`encoding::accepted`]. Every
+    /// response for a file with such variants, compressed or not, carries
+    /// `Vary: Accept-Encoding`.
+    pub async fn serve_encoded(
+        &self,
+        path: &str,
+        fallback: Option<&str>,
+        accept_encoding: Option<&str>,
+    ) -> Result<Response<Bytes>, HeisenbergError> {
+        let accepted = encoding::accepted(accept_encoding);
+        if let Some(file) = self.resolve(path) {
+            if let Some(response) = self.respond(&file, &accepted).await {
+                return Ok(response);
+            }
+        }
+        if self.directory_listing {
+            if let Some(response) = self.list_directory(path).await {
+                return Ok(response);
+            }
+        }
+
+        if let Some(fallback) = fallback {
+            if let Some(response) = self.respond(&self.root.join(fallback), &accepted).await {
+                return Ok(response);
+            }
+        }
+
+        Err(HeisenbergError::file_not_found(
+            path,
+            "• Check if the file exists in the build directory\n• Verify the frontend build completed successfully\n• Ensure the embed directory path is correct\n• For SPAs, missing files should fall back to index.html"
+        ))
+    }
+
+    /// Whether a request path names a file that would be served, without
+    /// the fallback
+    pub async fn has_file(&self, path: &str) -> bool {
+        let Some(file) = self.resolve(path) else {
+            return false;
+        };
+        tokio::fs::metadata(&file)
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+            && self.symlinks_allow(&file).await
+    }
+
+    /// Map a request path onto a file below the root, rejecting traversal,
+    /// dotfiles that aren't allowed and the `_redirects` and `_headers`
+    /// files
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let mut file = self.resolve_entry(path)?;
+        if file.is_dir() {
+            file.push("index.html");
+        }
+        Some(file)
+    }
+
+    /// Like [`resolve`](Self::resolve), without looking for `index.html` in
+    /// directories
+    fn resolve_entry(&self, path: &str) -> Option<PathBuf> {
+        let decoded = percent_decode(path)?;
+        let relative = Path::new(decoded.trim_start_matches('/'));
+
+        let mut file = self.root.clone();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => {
+                    if !self.dotfile_allowed(&part.to_string_lossy()) {
+                        return None;
+                    }
+                    file.push(part);
+                }
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+        // Server configuration in the build isn't part of the site
+        if file == self.root.join(REDIRECTS_FILE) || file == self.root.join(HEADERS_FILE) {
+            return None;
+        }
+        Some(file)
     }
 
-    /// Serve a file by path
-    pub fn serve_file(&self, path: &str) -> Result<Response<String>, HeisenbergError> {
-        // For now, just return a simple response
-        // Will be enhanced with actual rust-embed integration
-        if path == "/" || path == "/index.html" {
-            Ok(Response::builder()
+    fn dotfile_allowed(&self, name: &str) -> bool {
+        !name.starts_with('.') || self.allowed_dotfiles.iter().any(|n| n == name)
+    }
+
+    /// An HTML index of a directory, with subdirectories first
+    async fn list_directory(&self, path: &str) -> Option<Response<Bytes>> {
+        let dir = self.resolve_entry(path)?;
+        if !tokio::fs::metadata(&dir).await.ok()?.is_dir() || !self.symlinks_allow(&dir).await {
+            return None;
+        }
+
+        let mut entries = Vec::new();
+        let mut read_dir = tokio::fs::read_dir(&dir).await.ok()?;
+        while let Ok(Some(entry)) = read_dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !self.dotfile_allowed(&name) {
+                continue;
+            }
+            let is_dir = tokio::fs::metadata(entry.path())
+                .await
+                .is_ok_and(|metadata| metadata.is_dir());
+            entries.push((!is_dir, name));
+        }
+        entries.sort();
+
+        // Relative links resolve against the last segment unless the path
+        // ends with a slash, so `/docs` links to `docs/guide.html`
+        let base = match path.rsplit('/').next() {
+            Some(segment) if !segment.is_empty() => format!("{}/", segment),
+            _ => String::new(),
+        };
+        let title = html_escape(&format!("Index of {}", percent_decode(path)?));
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<ul>\n"
+        );
+        if dir != self.root {
+            html.push_str(&format!("<li><a href=\"{}../\">../</a></li>\n", base));
+        }
+        for (is_file, name) in entries {
+            let slash = if is_file { "" } else { "/" };
+            html.push_str(&format!(
+                "<li><a href=\"{}{}{}\">{}{}</a></li>\n",
+                base,
+                percent_encode(&name),
+                slash,
+                html_escape(&name),
+                slash
+            ));
+        }
+        html.push_str("</ul>\n</body>\n</html>\n");
+
+        Some(
+            Response::builder()
                 .status(StatusCode::OK)
-                .header("content-type", "text/html")
-                .body("<html><body><h1>Heisenberg Static Server</h1></body></html>".to_string())
-                .unwrap())
-        } else {
-            Err(HeisenbergError::file_not_found(
-                path,
-                "• Check if the file exists in the embedded assets\n• Verify the frontend build completed successfully\n• Ensure the embed directory path is correct\n• For SPAs, missing files should fall back to index.html"
-            ))
+                .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+                .body(Bytes::from(html))
+                .unwrap(),
+        )
+    }
+
+    /// Read a file, through the cache if there is one
+    async fn read(&self, file: &Path) -> Option<Bytes> {
+        if let Some(contents) = self.cache.as_ref().and_then(|cache| cache.get(file)) {
+            return Some(contents);
+        }
+        if !self.symlinks_allow(file).await {
+            return None;
+        }
+
+        #[cfg(feature = "mmap")]
+        if let Some(threshold) = self.mmap_threshold {
+            let metadata = tokio::fs::metadata(file).await.ok()?;
+            if metadata.is_file() && metadata.len() >= threshold {
+                return map_file(file);
+            }
+        }
+
+        let contents = Bytes::from(tokio::fs::read(file).await.ok()?);
+        if let Some(cache) = &self.cache {
+            cache.insert(file.to_path_buf(), contents.clone());
+        }
+        Some(contents)
+    }
+
+    /// Whether the symlink policy lets a file below the root be read
+    async fn symlinks_allow(&self, file: &Path) -> bool {
+        match self.symlinks {
+            SymlinkPolicy::Follow => true,
+            SymlinkPolicy::WithinRoot => {
+                let Ok(target) = tokio::fs::canonicalize(file).await else {
+                    // Doesn't exist, so reading it fails anyway
+                    return true;
+                };
+                tokio::fs::canonicalize(&self.root)
+                    .await
+                    .is_ok_and(|root| target.starts_with(root))
+            }
+            SymlinkPolicy::Deny => {
+                let Ok(relative) = file.strip_prefix(&self.root) else {
+                    return false;
+                };
+                let mut path = self.root.clone();
+                for component in relative.components() {
+                    path.push(component);
+                    let linked = tokio::fs::symlink_metadata(&path)
+                        .await
+                        .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                    if linked {
+                        return false;
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    async fn respond(&self, file: &Path, accepted: &[Encoding]) -> Option<Response<Bytes>> {
+        #[cfg(feature = "sri")]
+        if self.adds_integrity(file) {
+            let contents = self.read(file).await?;
+            if let Some(html) = self.with_integrity(file, &contents).await {
+                // The body depends on the referenced assets too, so only its
+                // own hash identifies it
+                let etag = match self.etag {
+                    EtagStrategy::Off => None,
+                    EtagStrategy::Weak => Some(format!("W/\"{:016x}\"", content_hash(&html))),
+                    EtagStrategy::Strong => Some(format!("\"{:016x}\"", content_hash(&html))),
+                };
+                return Some(self.file_response(file, html, etag));
+            }
+        }
+
+        let variants = self.precompressed(file).await;
+        let mut response = match self.compressed_response(file, &variants, accepted).await {
+            Some(response) => response,
+            None => {
+                let contents = self.read(file).await?;
+                let etag = self.file_etag(file, &contents).await;
+                self.file_response(file, contents, etag)
+            }
+        };
+        if !variants.is_empty() {
+            encoding::vary_on_accept_encoding(response.headers_mut());
+        }
+        Some(response)
+    }
+
+    /// The file's most preferred precompressed variant the client accepts
+    async fn compressed_response(
+        &self,
+        file: &Path,
+        variants: &[Encoding],
+        accepted: &[Encoding],
+    ) -> Option<Response<Bytes>> {
+        let &encoding = accepted
+            .iter()
+            .find(|encoding| variants.contains(encoding))?;
+        // A leftover variant of a deleted file isn't served
+        if !tokio::fs::metadata(file).await.ok()?.is_file() {
+            return None;
+        }
+        let variant = variant_file(file, encoding);
+        let contents = self.read(&variant).await?;
+        let etag = self.file_etag(&variant, &contents).await;
+        let mut response = self.file_response(file, contents, etag);
+        response.headers_mut().insert(
+            header::CONTENT_ENCODING,
+            HeaderValue::from_static(encoding.token()),
+        );
+        Some(response)
+    }
+
+    /// The encodings a file has precompressed variants in
+    async fn precompressed(&self, file: &Path) -> Vec<Encoding> {
+        let mut variants = Vec::new();
+        for encoding in Encoding::ALL {
+            let variant = variant_file(file, encoding);
+            let cached = self
+                .cache
+                .as_ref()
+                .is_some_and(|cache| cache.contains(&variant));
+            if cached
+                || tokio::fs::metadata(&variant)
+                    .await
+                    .is_ok_and(|m| m.is_file())
+            {
+                variants.push(encoding);
+            }
+        }
+        variants
+    }
+
+    /// The ETag for a file served as it is on disk
+    async fn file_etag(&self, file: &Path, contents: &Bytes) -> Option<String> {
+        if self.etag == EtagStrategy::Off {
+            return None;
+        }
+        let metadata = tokio::fs::metadata(file).await.ok()?;
+        let modified = metadata.modified().ok()?;
+        if self.etag == EtagStrategy::Weak {
+            let since_epoch = modified.duration_since(UNIX_EPOCH).ok()?;
+            return Some(format!(
+                "W/\"{:x}-{:x}\"",
+                metadata.len(),
+                since_epoch.as_nanos()
+            ));
+        }
+
+        let mut strong_etags = self.strong_etags.lock().unwrap();
+        if let Some(known) = strong_etags.get(file) {
+            if known.modified == modified && known.len == metadata.len() {
+                return Some(known.etag.clone());
+            }
+        }
+        let etag = format!("\"{:016x}\"", content_hash(contents));
+        strong_etags.insert(
+            file.to_path_buf(),
+            StrongEtag {
+                modified,
+                len: metadata.len(),
+                etag: etag.clone(),
+            },
+        );
+        Some(etag)
+    }
+
+    /// An HTML file with integrity hashes added, if enabled and the file
+    /// references any assets
+    #[cfg(feature = "sri")]
+    async fn with_integrity(&self, file: &Path, contents: &Bytes) -> Option<Bytes> {
+        use crate::utils::sri;
+
+        if !self.adds_integrity(file) {
+            return None;
+        }
+        let html = std::str::from_utf8(contents).ok()?;
+
+        let mut hashes = HashMap::new();
+        for url in sri::asset_references(html) {
+            let Some(asset) = self.asset_file(file, &url) else {
+                continue;
+            };
+            if let Some(asset_contents) = self.read(&asset).await {
+                hashes.insert(url, sri::integrity(&asset_contents));
+            }
+        }
+        if hashes.is_empty() {
+            return None;
+        }
+        Some(Bytes::from(sri::add_integrity(html, &hashes)))
+    }
+
+    /// Whether a file is HTML that gets integrity hashes, and so is never
+    /// served from a precompressed variant
+    #[cfg(feature = "sri")]
+    fn adds_integrity(&self, file: &Path) -> bool {
+        self.subresource_integrity && file.extension().and_then(|ext| ext.to_str()) == Some("html")
+    }
+
+    /// The file an asset URL in an HTML file points to
+    #[cfg(feature = "sri")]
+    fn asset_file(&self, html_file: &Path, url: &str) -> Option<PathBuf> {
+        let path = url.split(['?', '#']).next()?;
+        if path.starts_with('/') {
+            return self.resolve(path);
+        }
+        let dir = html_file.parent()?.strip_prefix(&self.root).ok()?;
+        self.resolve(&format!("/{}/{}", dir.to_string_lossy(), path))
+    }
+
+    fn file_response(&self, file: &Path, contents: Bytes, etag: Option<String>) -> Response<Bytes> {
+        let mut response = Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, self.detect_mime_type(file))
+            .body(contents)
+            .unwrap();
+        if let Some(etag) = etag.and_then(|etag| HeaderValue::from_str(&etag).ok()) {
+            response.headers_mut().insert(header::ETAG, etag);
+        }
+        self.apply_header_rules(file, &mut response);
+        response
+    }
+
+    fn apply_header_rules(&self, file: &Path, response: &mut Response<Bytes>) {
+        if self.header_rules.is_empty() {
+            return;
+        }
+        let Ok(relative) = file.strip_prefix(&self.root) else {
+            return;
+        };
+        let path = format!("/{}", crate::utils::paths::normalize_path(relative));
+        for rule in &self.header_rules {
+            if !crate::utils::paths::glob_matches(&rule.glob, &path) {
+                continue;
+            }
+            if let (Ok(name), Ok(value)) = (
+                header::HeaderName::from_bytes(rule.name.as_bytes()),
+                HeaderValue::from_str(&rule.value),
+            ) {
+                response.headers_mut().insert(name, value);
+            }
         }
     }
 
     /// Detect MIME type from file extension
-    #[allow(dead_code)] // Will be used when we add actual file serving
-    fn detect_mime_type(&self, path: &str) -> &'static str {
-        let ext = Path::new(path)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("");
+    fn detect_mime_type(&self, path: &Path) -> &'static str {
+        let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
 
         match ext {
-            "html" => "text/html",
-            "css" => "text/css",
-            "js" => "application/javascript",
-            "json" => "application/json",
+            "html" => "text/html; charset=utf-8",
+            "css" => "text/css; charset=utf-8",
+            "js" | "mjs" => "application/javascript; charset=utf-8",
+            "json" | "map" => "application/json; charset=utf-8",
+            "txt" => "text/plain; charset=utf-8",
             "png" => "image/png",
             "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
             "svg" => "image/svg+xml",
+            "ico" => "image/x-icon",
+            "webp" => "image/webp",
+            "woff" => "font/woff",
+            "woff2" => "font/woff2",
+            "ttf" => "font/ttf",
+            "wasm" => "application/wasm",
             _ => "application/octet-stream",
         }
     }
 }
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Encode a file name for use as a URL path segment
+fn percent_encode(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// The precompressed variant of a file, `app.js.br` for `app.js`
+fn variant_file(file: &Path, encoding: Encoding) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".");
+    name.push(encoding.extension());
+    PathBuf::from(name)
+}
+
+/// A strong ETag and the file version it was computed for
+#[derive(Debug)]
+struct StrongEtag {
+    modified: SystemTime,
+    len: u64,
+    etag: String,
+}
+
+/// 64-bit FNV-1a, stable across builds and platforms so every server
+/// computes the same ETag for the same file
+fn content_hash(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Map a file into memory as zero-copy `Bytes`
+#[cfg(feature = "mmap")]
+fn map_file(file: &Path) -> Option<Bytes> {
+    let file = std::fs::File::open(file).ok()?;
+    // Safety: the mapping is read-only, and the build directory is documented
+    // as not being modified while it is served
+    let map = unsafe { memmap2::Mmap::map(&file) }.ok()?;
+    Some(Bytes::from_owner(map))
+}
+
+/// In-memory LRU cache of file contents with a byte budget
+///
+/// Cloning the cache gives another handle to the same entries. Files larger
+/// than the whole budget are never cached, and the least recently used
+/// entries are evicted to make room for new ones.
+#[derive(Debug, Clone)]
+pub struct AssetCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    budget: usize,
+    used: usize,
+    tick: u64,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    contents: Bytes,
+    last_used: u64,
+}
+
+impl AssetCache {
+    /// Create a cache holding at most `budget` bytes of file contents
+    pub fn new(budget: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CacheState {
+                budget,
+                used: 0,
+                tick: 0,
+                entries: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Bytes of file contents currently cached
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().used
+    }
+
+    /// Number of cached files
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether a file is cached
+    pub fn contains(&self, file: &Path) -> bool {
+        self.state.lock().unwrap().entries.contains_key(file)
+    }
+
+    fn get(&self, file: &Path) -> Option<Bytes> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(file)?;
+        entry.last_used = tick;
+        Some(entry.contents.clone())
+    }
+
+    fn insert(&self, file: PathBuf, contents: Bytes) {
+        let mut state = self.state.lock().unwrap();
+        if contents.len() > state.budget {
+            return;
+        }
+
+        if let Some(old) = state.entries.remove(&file) {
+            state.used -= old.contents.len();
+        }
+        while state.used + contents.len() > state.budget {
+            let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = state.entries.remove(&oldest) {
+                state.used -= evicted.contents.len();
+            }
+        }
+
+        state.tick += 1;
+        state.used += contents.len();
+        let last_used = state.tick;
+        state.entries.insert(
+            file,
+            CacheEntry {
+                contents,
+                last_used,
+            },
+        );
+    }
+}
+
+/// Decode `%XX` escapes in a request path
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,127 @@
+//! Customizing the look of the built-in error pages
+
+/// Colors, logo, title and extra advice for the pages shown while a dev
+/// server is unavailable
+///
+/// Every part is optional; unset parts keep the built-in look.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::ErrorPageTheme;
+///
+/// let theme = ErrorPageTheme::new()
+///     .title("Acme Portal")
+///     .logo("https://cdn.acme.example/logo.svg")
+///     .accent_color("#0b7285")
+///     .background_color("#f8f9fa")
+///     .troubleshooting("Ask in #frontend-help on Slack");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorPageTheme {
+    /// Document title, replacing titles like "Development Server Unavailable"
+    pub title: Option<String>,
+    /// URL of an image shown above the message
+    pub logo: Option<String>,
+    /// CSS color for the headings and the message box border
+    pub accent_color: Option<String>,
+    /// CSS color for the page background
+    pub background_color: Option<String>,
+    /// Extra troubleshooting bullets, as plain text, after the built-in ones
+    pub troubleshooting: Vec<String>,
+}
+
+impl ErrorPageTheme {
+    /// A theme that keeps the built-in look
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use this document title on every error page
+    pub fn title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Show the image at this URL above the message
+    pub fn logo<S: Into<String>>(mut self, url: S) -> Self {
+        self.logo = Some(url.into());
+        self
+    }
+
+    /// Color headings and the message box border, e.g. `"#0b7285"`
+    pub fn accent_color<S: Into<String>>(mut self, color: S) -> Self {
+        self.accent_color = Some(color.into());
+        self
+    }
+
+    /// Color the page background, e.g. `"rgb(248, 249, 250)"`
+    pub fn background_color<S: Into<String>>(mut self, color: S) -> Self {
+        self.background_color = Some(color.into());
+        self
+    }
+
+    /// Add a troubleshooting bullet, such as where to ask for help
+    pub fn troubleshooting<S: Into<String>>(mut self, tip: S) -> Self {
+        self.troubleshooting.push(tip.into());
+        self
+    }
+
+    /// The page's `<title>` text, escaped
+    pub(crate) fn document_title(&self, default: &str) -> String {
+        escape(self.title.as_deref().unwrap_or(default))
+    }
+
+    /// CSS rules overriding the built-in colors
+    ///
+    /// Colors with characters that could end the rule, like `;` or `}`, are
+    /// left out.
+    pub(crate) fn css(&self) -> String {
+        let mut css = String::new();
+        if let Some(color) = self.background_color.as_deref().filter(|c| is_css_color(c)) {
+            css.push_str(&format!("body {{ background: {}; }}\n", color));
+        }
+        if let Some(color) = self.accent_color.as_deref().filter(|c| is_css_color(c)) {
+            css.push_str(&format!(
+                "h1, h2 {{ color: {0}; }}\n.error {{ border-color: {0}; }}\n",
+                color
+            ));
+        }
+        css
+    }
+
+    /// The logo `<img>`, or nothing
+    pub(crate) fn logo_html(&self) -> String {
+        self.logo
+            .as_deref()
+            .map(|url| {
+                format!(
+                    r#"<img src="{}" alt="" style="max-height: 48px; margin-bottom: 20px;">"#,
+                    escape(url)
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    /// The extra troubleshooting bullets as `<li>` elements
+    pub(crate) fn bullets(&self) -> String {
+        self.troubleshooting
+            .iter()
+            .map(|tip| format!("<li>{}</li>", escape(tip)))
+            .collect()
+    }
+}
+
+fn is_css_color(color: &str) -> bool {
+    !color.is_empty()
+        && color
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "#(),.% -".contains(c))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,170 @@
+//! Keepalive for proxied WebSocket connections
+//!
+//! Proxied WebSockets are tunneled as bytes. Load balancers, corporate
+//! proxies and port forwarders close connections that carry nothing for a
+//! while, which drops HMR sockets between edits. With keepalive on, the
+//! tunnel follows the frames in each direction and slips pings in between
+//! them; both peers answer with pongs, which the other side ignores.
+
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::{Instant, MissedTickBehavior};
+
+/// An unmasked ping with no payload, as a server sends it to the browser
+const PING_TO_CLIENT: [u8; 2] = [0x89, 0x00];
+
+/// A masked ping with no payload, as a client sends it to the dev server
+const PING_TO_SERVER: [u8; 6] = [0x89, 0x80, 0, 0, 0, 0];
+
+/// Pings and idle timeouts for proxied WebSocket connections
+///
+/// Both are off by default, leaving the tunnel to relay bytes as they come.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::services::websocket::WebSocketKeepalive;
+/// use std::time::Duration;
+///
+/// let keepalive = WebSocketKeepalive {
+///     ping_interval: Some(Duration::from_secs(20)),
+///     idle_timeout: Some(Duration::from_secs(120)),
+/// };
+/// assert!(keepalive.is_enabled());
+/// assert!(!WebSocketKeepalive::default().is_enabled());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WebSocketKeepalive {
+    /// How often both peers are pinged
+    pub ping_interval: Option<Duration>,
+    /// How long a connection may carry nothing in either direction before it
+    /// is closed; with pings on, that means a peer stopped answering them
+    pub idle_timeout: Option<Duration>,
+}
+
+impl WebSocketKeepalive {
+    /// Whether pings or the idle timeout are on
+    pub fn is_enabled(&self) -> bool {
+        self.ping_interval.is_some() || self.idle_timeout.is_some()
+    }
+}
+
+/// Relay a WebSocket connection between the browser and the dev server
+/// until either side closes it, or it is idle for too long
+pub(crate) async fn relay<C, U>(mut client: C, mut upstream: U, keepalive: WebSocketKeepalive)
+where
+    C: AsyncRead + AsyncWrite + Unpin,
+    U: AsyncRead + AsyncWrite + Unpin,
+{
+    if !keepalive.is_enabled() {
+        let _ = tokio::io::copy_bidirectional(&mut client, &mut upstream).await;
+        return;
+    }
+
+    let (mut client_read, mut client_write) = tokio::io::split(client);
+    let (mut upstream_read, mut upstream_write) = tokio::io::split(upstream);
+    let mut to_client = FrameTracker::default();
+    let mut to_upstream = FrameTracker::default();
+    let mut client_buf = vec![0; 8192];
+    let mut upstream_buf = vec![0; 8192];
+
+    // Far enough away to never fire when a setting is off
+    let never = Duration::from_secs(60 * 60 * 24 * 365);
+    let ping_interval = keepalive.ping_interval.unwrap_or(never);
+    let mut pings = tokio::time::interval_at(Instant::now() + ping_interval, ping_interval);
+    pings.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let idle_timeout = keepalive.idle_timeout.unwrap_or(never);
+    let mut last_activity = Instant::now();
+
+    loop {
+        tokio::select! {
+            read = client_read.read(&mut client_buf) => {
+                let Ok(n @ 1..) = read else { break };
+                to_upstream.advance(&client_buf[..n]);
+                if upstream_write.write_all(&client_buf[..n]).await.is_err() {
+                    break;
+                }
+                last_activity = Instant::now();
+            }
+            read = upstream_read.read(&mut upstream_buf) => {
+                let Ok(n @ 1..) = read else { break };
+                to_client.advance(&upstream_buf[..n]);
+                if client_write.write_all(&upstream_buf[..n]).await.is_err() {
+                    break;
+                }
+                last_activity = Instant::now();
+            }
+            _ = pings.tick(), if keepalive.ping_interval.is_some() => {
+                // Sent only between frames; a connection mid-frame isn't idle
+                if to_client.at_boundary() && client_write.write_all(&PING_TO_CLIENT).await.is_err() {
+                    break;
+                }
+                if to_upstream.at_boundary() && upstream_write.write_all(&PING_TO_SERVER).await.is_err() {
+                    break;
+                }
+            }
+            _ = tokio::time::sleep_until(last_activity + idle_timeout), if keepalive.idle_timeout.is_some() => {
+                break;
+            }
+        }
+    }
+    let _ = client_write.shutdown().await;
+    let _ = upstream_write.shutdown().await;
+}
+
+/// Tracks where a stream of WebSocket frames is, so control frames can be
+/// sent between frames
+///
+/// RFC 6455 allows control frames anywhere between frames, including between
+/// the fragments of a message.
+#[derive(Debug, Default)]
+struct FrameTracker {
+    /// The header of the frame being read, until it is complete
+    header: Vec<u8>,
+    /// Bytes of the current frame's masking key and payload still to come
+    remaining: u64,
+}
+
+impl FrameTracker {
+    fn advance(&mut self, mut bytes: &[u8]) {
+        while let Some((&first, rest)) = bytes.split_first() {
+            if self.remaining > 0 {
+                let n = self.remaining.min(bytes.len() as u64);
+                self.remaining -= n;
+                bytes = &bytes[n as usize..];
+                continue;
+            }
+            self.header.push(first);
+            bytes = rest;
+            if let Some(remaining) = frame_remaining(&self.header) {
+                self.remaining = remaining;
+                self.header.clear();
+            }
+        }
+    }
+
+    fn at_boundary(&self) -> bool {
+        self.remaining == 0 && self.header.is_empty()
+    }
+}
+
+/// The length of a frame's masking key and payload, once `header` holds all
+/// of the frame's header
+fn frame_remaining(header: &[u8]) -> Option<u64> {
+    let second = *header.get(1)?;
+    let masked = second & 0x80 != 0;
+    let (extended, payload) = match second & 0x7f {
+        126 => (2, None),
+        127 => (8, None),
+        len => (0, Some(u64::from(len))),
+    };
+    if header.len() < 2 + extended {
+        return None;
+    }
+    let payload = payload.unwrap_or_else(|| {
+        header[2..2 + extended]
+            .iter()
+            .fold(0, |len, &byte| (len << 8) | u64::from(byte))
+    });
+    Some(payload + if masked { 4 } else { 0 })
+}
@@ -0,0 +1,432 @@
+//! Test utilities for applications built on Heisenberg
+//!
+//! Enable the `test-utils` feature in your dev-dependencies to integration-test
+//! a Heisenberg setup without a frontend toolchain:
+//!
+//! - [`MockDevServer`] stands in for Vite and friends with scripted responses
+//! - [`TestSpaFixture`] creates a throwaway frontend project with a built `dist/`
+//! - [`served_by`] and the `assert_*` helpers report which handler answered
+//!
+//! ```rust,ignore
+//! use heisenberg::core::mode::Mode;
+//! use heisenberg::test::{assert_proxied, MockDevServer, MockResponse};
+//! use heisenberg::{Heisenberg, HeisenbergLayer};
+//! use tower::ServiceExt;
+//!
+//! #[tokio::test]
+//! async fn spa_routes_are_proxied() {
+//!     let dev_server = MockDevServer::start().await.unwrap();
+//!     dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+//!
+//!     let config = Heisenberg::new()
+//!         .mode_override(Mode::Development)
+//!         .spa("./dist")
+//!         .dev_server(&dev_server.url())
+//!         .build();
+//!     let app = my_app().layer(HeisenbergLayer::new(config));
+//!
+//!     let response = app.oneshot(get("/dashboard")).await.unwrap();
+//!     assert_proxied(&response);
+//!     assert_eq!(dev_server.requests_to("/dashboard").len(), 1);
+//! }
+//! ```
+
+pub use crate::core::router::HandlerKind;
+
+use hyper::Response;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Which handler produced a response, if Heisenberg produced it at all
+///
+/// Returns `None` for responses that came from the inner service.
+pub fn served_by<B>(response: &Response<B>) -> Option<HandlerKind> {
+    response.extensions().get::<HandlerKind>().copied()
+}
+
+/// Assert that a response was proxied to the dev server
+#[track_caller]
+pub fn assert_proxied<B>(response: &Response<B>) {
+    assert_eq!(
+        served_by(response),
+        Some(HandlerKind::Proxy),
+        "expected the response to be proxied to the dev server"
+    );
+}
+
+/// Assert that a response was served from the built frontend assets
+#[track_caller]
+pub fn assert_static<B>(response: &Response<B>) {
+    assert_eq!(
+        served_by(response),
+        Some(HandlerKind::StaticFiles),
+        "expected the response to be served from static files"
+    );
+}
+
+/// Assert that a response came from the inner service, not Heisenberg
+#[track_caller]
+pub fn assert_inner<B>(response: &Response<B>) {
+    assert_eq!(
+        served_by(response),
+        None,
+        "expected the response to come from the inner service"
+    );
+}
+
+/// A scripted response returned by [`MockDevServer`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// Response headers
+    pub headers: Vec<(String, String)>,
+    /// Response body
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// Create an empty response with the given status
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// A 200 response with an HTML body
+    pub fn html(body: impl Into<String>) -> Self {
+        Self::new(200)
+            .header("content-type", "text/html; charset=utf-8")
+            .body(body.into())
+    }
+
+    /// A 404 response
+    pub fn not_found() -> Self {
+        Self::new(404).body("Not Found")
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set the response body
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+/// A request received by [`MockDevServer`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    /// Request method
+    pub method: String,
+    /// Request path, including any query string
+    pub path: String,
+    /// Request headers, with lowercased names
+    pub headers: Vec<(String, String)>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// First value of a header, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct MockState {
+    responses: HashMap<String, MockResponse>,
+    fallback: MockResponse,
+    requests: Vec<RecordedRequest>,
+}
+
+/// An in-process HTTP server standing in for a frontend dev server
+///
+/// Paths answer with the response scripted for them via [`respond`], or the
+/// [`fallback`] (404 by default). Every request is recorded. Note that the
+/// proxy health-checks the dev server with `GET /` before proxying.
+///
+/// The server stops when dropped.
+///
+/// [`respond`]: MockDevServer::respond
+/// [`fallback`]: MockDevServer::fallback
+#[derive(Debug)]
+pub struct MockDevServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>,
+}
+
+impl MockDevServer {
+    /// Start a mock dev server on a free localhost port
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState {
+            responses: HashMap::new(),
+            fallback: MockResponse::not_found(),
+            requests: Vec::new(),
+        }));
+
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(handle_connection(stream, task_state.clone()));
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to pass to `.dev_server()`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Script the response for a path
+    ///
+    /// A path with a query string only matches requests with that exact query;
+    /// a bare path matches regardless of query.
+    pub fn respond(&self, path: impl Into<String>, response: MockResponse) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .insert(path.into(), response);
+        self
+    }
+
+    /// Set the response for paths without a scripted response
+    pub fn fallback(&self, response: MockResponse) -> &Self {
+        self.state.lock().unwrap().fallback = response;
+        self
+    }
+
+    /// All requests received so far
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Requests received for a path, ignoring query strings
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|request| request.path.split('?').next() == Some(path))
+            .collect()
+    }
+}
+
+impl Drop for MockDevServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        let path_only = request.path.split('?').next().unwrap_or("/");
+        let response = state
+            .responses
+            .get(&request.path)
+            .or_else(|| state.responses.get(path_only))
+            .unwrap_or(&state.fallback)
+            .clone();
+        state.requests.push(request);
+        response
+    };
+
+    let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        response.body.len()
+    ));
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let head_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break pos;
+        }
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let chunked = headers
+        .iter()
+        .any(|(name, value)| name == "transfer-encoding" && value.contains("chunked"));
+
+    let mut body = buffer[head_end + 4..].to_vec();
+    if chunked {
+        body = loop {
+            if let Some(data) = dechunk(&body) {
+                break data;
+            }
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                break Vec::new();
+            }
+            body.extend_from_slice(&chunk[..read]);
+        };
+    } else {
+        while body.len() < content_length {
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..read]);
+        }
+        body.truncate(content_length);
+    }
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+/// The data of a complete `Transfer-Encoding: chunked` body, or `None`
+/// while more of it is to come
+fn dechunk(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    loop {
+        let line_end = raw.windows(2).position(|window| window == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Some(data);
+        }
+        data.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size + 2..)?;
+    }
+}
+
+/// A temporary frontend project with a built `dist/` directory
+///
+/// Contains a `package.json` with Vite scripts and a `dist/index.html`. The
+/// directory is removed when the fixture is dropped.
+#[derive(Debug)]
+pub struct TestSpaFixture {
+    temp_dir: TempDir,
+    dist_dir: PathBuf,
+}
+
+impl TestSpaFixture {
+    /// Create a new fixture with a basic SPA structure
+    pub fn new() -> std::io::Result<Self> {
+        let temp_dir = TempDir::new()?;
+        let dist_dir = temp_dir.path().join("dist");
+        std::fs::create_dir_all(&dist_dir)?;
+
+        let package_json = r#"{
+  "name": "test-spa",
+  "version": "1.0.0",
+  "scripts": {
+    "dev": "vite",
+    "build": "vite build"
+  }
+}"#;
+        std::fs::write(temp_dir.path().join("package.json"), package_json)?;
+
+        let index_html = r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Test SPA</title>
+</head>
+<body>
+    <div id="app">Test SPA Content</div>
+</body>
+</html>"#;
+        std::fs::write(dist_dir.join("index.html"), index_html)?;
+
+        Ok(Self { temp_dir, dist_dir })
+    }
+
+    /// Write a file into `dist/`, creating parent directories as needed
+    pub fn add_file(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<PathBuf> {
+        let file = self.dist_dir.join(path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file, contents)?;
+        Ok(file)
+    }
+
+    /// Get the path to the dist directory
+    pub fn dist_path(&self) -> &PathBuf {
+        &self.dist_dir
+    }
+
+    /// Get the working directory path
+    pub fn working_dir(&self) -> &Path {
+        self.temp_dir.path()
+    }
+
+    /// Get the path to package.json
+    pub fn package_json_path(&self) -> PathBuf {
+        self.temp_dir.path().join("package.json")
+    }
+}
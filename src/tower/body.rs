@@ -0,0 +1,57 @@
+//! Handing streamed dev server responses to the app's body type
+
+use crate::services::proxy::ProxyBody;
+use http_body_util::combinators::UnsyncBoxBody;
+use hyper::body::Bytes;
+use hyper::header;
+use hyper::{Response, StatusCode};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Convert a proxied response to the inner service's body type
+///
+/// The service can only make a `ResBody` from `Bytes`, except for Axum's
+/// body type with the `axum` feature, which can hold a stream. Streamed
+/// bodies stay streamed as an Axum body and are read in full otherwise.
+pub(crate) async fn into_res_body<ResBody>(response: Response<ProxyBody>) -> Response<ResBody>
+where
+    ResBody: From<Bytes> + 'static,
+{
+    let (parts, body) = response.into_parts();
+    let body = match body {
+        ProxyBody::Full(body) => ResBody::from(body),
+        ProxyBody::Streaming(body) => match streamed(body) {
+            Ok(body) => body,
+            Err(body) => match ProxyBody::Streaming(body).collect().await {
+                Ok(body) => ResBody::from(body),
+                Err(e) => {
+                    return Response::builder()
+                        .status(StatusCode::BAD_GATEWAY)
+                        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+                        .body(ResBody::from(Bytes::from(format!(
+                            "Failed to read the dev server's response: {}",
+                            e
+                        ))))
+                        .unwrap()
+                }
+            },
+        },
+    };
+    Response::from_parts(parts, body)
+}
+
+/// `body` as a `ResBody`, if that is a type that can stream it
+fn streamed<ResBody: 'static>(
+    body: UnsyncBoxBody<Bytes, BoxError>,
+) -> Result<ResBody, UnsyncBoxBody<Bytes, BoxError>> {
+    #[cfg(feature = "axum")]
+    {
+        let mut converted: Option<ResBody> = None;
+        let slot: &mut dyn std::any::Any = &mut converted;
+        if let Some(slot) = slot.downcast_mut::<Option<axum_core::body::Body>>() {
+            *slot = Some(axum_core::body::Body::new(body));
+            return Ok(converted.expect("the body was just converted"));
+        }
+    }
+    Err(body)
+}
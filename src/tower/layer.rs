@@ -1,27 +1,223 @@
 //! Tower layer implementation
 
 use crate::core::config::Heisenberg;
-use crate::tower::service::HeisenbergService;
+use crate::core::mode::Mode;
+use crate::core::router::Router;
+use crate::core::stats::RouteStats;
+use crate::error::HeisenbergError;
+use crate::services::DevServerHandle;
+use crate::tower::service::{HeisenbergService, ServiceState};
+use crate::tower::{shutdown, RouteHandle, ShutdownGuard};
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
 use tower_layer::Layer;
 
 /// Tower layer for Heisenberg dual-mode serving
 #[derive(Debug, Clone)]
 pub struct HeisenbergLayer {
     config: Heisenberg,
+    // Created on first use and shared by every service this layer wraps
+    state: Arc<OnceLock<Arc<ServiceState>>>,
 }
 
 impl HeisenbergLayer {
     /// Create a new Heisenberg layer
+    ///
+    /// # Panics
+    ///
+    /// In CI mode, panics right away if the configuration is invalid. Outside
+    /// CI mode the error is logged and the layer passes every request to the
+    /// inner service; use [`try_new`](Self::try_new) to handle the error
+    /// instead.
+    ///
+    /// Also panics in production mode if a route's
+    /// [`MissingBuild`](crate::core::config::MissingBuild) policy rejects its
+    /// build directory, or its build command fails.
     pub fn new(config: Heisenberg) -> Self {
-        Self { config }
+        if config.is_ci_mode() {
+            return Self::try_new(config)
+                .unwrap_or_else(|e| panic!("Invalid Heisenberg configuration: {}", e));
+        }
+        if let Err(e) = Self::check(&config) {
+            report_invalid(&e);
+            return Self::create(without_routes(config));
+        }
+        if let Err(e) = config.ensure_builds() {
+            panic!("Frontend build not ready: {}", e);
+        }
+        Self::create(config)
     }
+
+    /// Create a new Heisenberg layer, checking the configuration and, in
+    /// production mode, the build directories first
+    pub fn try_new(config: Heisenberg) -> Result<Self, HeisenbergError> {
+        Self::check(&config)?;
+        config.ensure_builds()?;
+        Ok(Self::create(config))
+    }
+
+    /// Check the configuration and that its routes compile
+    fn check(config: &Heisenberg) -> Result<(), HeisenbergError> {
+        config.validate()?;
+        Router::new(config.routes().to_vec(), config.mode())?;
+        Ok(())
+    }
+
+    fn create(config: Heisenberg) -> Self {
+        if config.mode() == Mode::Development {
+            crate::utils::banner::print_route_banner(&config);
+
+            if let Some(addr) = config.global_settings.server_addr {
+                crate::utils::network::print_server_urls(addr);
+            }
+
+            #[cfg(feature = "qr")]
+            if config.global_settings.print_qr_code && config.global_settings.interactive_enabled()
+            {
+                crate::utils::qr::print_network_qr(config.global_settings.server_addr);
+            }
+        }
+
+        Self {
+            config,
+            state: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Handle to the dev servers this layer starts on demand
+    ///
+    /// Lets application code list, stop or restart a route's dev server at
+    /// runtime, for example from an admin endpoint. The handle shares the
+    /// layer's processes, so its changes are seen by every wrapped service.
+    /// `None` in production mode and CI mode, where no dev servers are spawned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use heisenberg::{Heisenberg, HeisenbergLayer};
+    ///
+    /// # async fn run() -> Result<(), heisenberg::HeisenbergError> {
+    /// let layer = HeisenbergLayer::new(Heisenberg::new().spa("./web/dist").build());
+    ///
+    /// if let Some(dev_servers) = layer.dev_servers() {
+    ///     for status in dev_servers.status().await {
+    ///         println!("{}: running = {}", status.pattern, status.running);
+    ///     }
+    ///     dev_servers.restart("/*").await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn dev_servers(&self) -> Option<DevServerHandle> {
+        self.state().dev_servers()
+    }
+
+    /// Handle for adding and removing SPA routes at runtime
+    ///
+    /// For multi-tenant platforms where a new tenant gets its own sub-path
+    /// and build directory without restarting the server. See [`RouteHandle`].
+    pub fn routes(&self) -> RouteHandle {
+        RouteHandle::new(self.state().clone())
+    }
+
+    /// Latencies of the SPA requests served per route
+    ///
+    /// Proxied and static requests are counted separately, from when
+    /// Heisenberg takes over a request until its response is ready. Routes
+    /// are listed in the order they were added, including ones that haven't
+    /// served anything yet.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::{Heisenberg, HeisenbergLayer};
+    ///
+    /// let layer = HeisenbergLayer::new(Heisenberg::new().spa("./web/dist").build());
+    ///
+    /// for route in layer.stats() {
+    ///     if let Some(p99) = route.static_files.quantile(0.99) {
+    ///         println!("{}: p99 {:?} over {} requests", route.pattern, p99, route.static_files.count);
+    ///     }
+    /// }
+    /// ```
+    pub fn stats(&self) -> Vec<RouteStats> {
+        self.state().stats()
+    }
+
+    /// Guard that stops this layer's dev servers when dropped
+    ///
+    /// See [`ShutdownGuard`]. Does nothing in production mode and CI mode.
+    pub fn shutdown_guard(&self) -> ShutdownGuard {
+        ShutdownGuard::new(self.dev_servers())
+    }
+
+    /// A future for `axum::serve(...).with_graceful_shutdown(...)` that
+    /// waits for `signal` and then stops this layer's dev servers
+    ///
+    /// The dev servers stop as soon as the signal arrives, before the server
+    /// waits for open connections to finish. That closes the hot reload
+    /// WebSockets proxied to them, which would otherwise keep the graceful
+    /// shutdown waiting indefinitely. Requests arriving in the meantime get
+    /// the dev server error page; no dev server is started again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use axum::Router;
+    /// use heisenberg::{Heisenberg, HeisenbergLayer};
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let layer = HeisenbergLayer::new(Heisenberg::new().spa("./web/dist").build());
+    /// let shutdown = layer.shutdown_signal(tokio::signal::ctrl_c());
+    /// let app = Router::new().layer(layer);
+    ///
+    /// let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+    /// axum::serve(listener, app)
+    ///     .with_graceful_shutdown(shutdown)
+    ///     .await
+    /// # }
+    /// ```
+    pub fn shutdown_signal<F>(&self, signal: F) -> impl Future<Output = ()> + Send + 'static
+    where
+        F: Future + Send + 'static,
+    {
+        shutdown::after(signal, self.shutdown_guard())
+    }
+
+    fn state(&self) -> &Arc<ServiceState> {
+        self.state.get_or_init(|| {
+            let state = ServiceState::new(&self.config).unwrap_or_else(|e| {
+                report_invalid(&e);
+                ServiceState::new(&without_routes(self.config.clone()))
+                    .expect("a configuration without routes is valid")
+            });
+            Arc::new(state)
+        })
+    }
+}
+
+/// The configuration with its routes dropped, so every request goes to the
+/// inner service
+fn without_routes(mut config: Heisenberg) -> Heisenberg {
+    config.routes.clear();
+    config
+}
+
+/// Report a configuration the layer can't serve
+fn report_invalid(error: &HeisenbergError) {
+    #[cfg(feature = "logging")]
+    tracing::error!(error = %error, "Invalid Heisenberg configuration; passing every request to the inner service");
+    #[cfg(not(feature = "logging"))]
+    eprintln!(
+        "Error: Invalid Heisenberg configuration; passing every request to the inner service: {}",
+        error
+    );
 }
 
 impl<S> Layer<S> for HeisenbergLayer {
     type Service = HeisenbergService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        HeisenbergService::new(inner, self.config.clone())
-            .expect("Failed to create HeisenbergService with router")
+        HeisenbergService::with_state(inner, self.state().clone())
     }
 }
@@ -1,8 +1,14 @@
 //! Tower integration for Heisenberg
 
+mod body;
 pub mod future;
 pub mod layer;
+pub mod overlay;
+pub mod routes;
 pub mod service;
+pub mod shutdown;
 
 pub use layer::HeisenbergLayer;
+pub use routes::RouteHandle;
 pub use service::HeisenbergService;
+pub use shutdown::ShutdownGuard;
@@ -0,0 +1,118 @@
+//! In-browser error overlay for development mode
+//!
+//! Proxied HTML pages get a small script that polls the status endpoint and
+//! shows a dismissible overlay while a dev server is unreachable, so a dev
+//! server restart mid-session doesn't fail silently.
+
+use hyper::header::{self, HeaderMap};
+
+/// Prefix for Heisenberg's own development endpoints
+pub const INTERNAL_PREFIX: &str = "/__heisenberg";
+
+/// Path of the overlay script
+pub const OVERLAY_SCRIPT_PATH: &str = "/__heisenberg/overlay.js";
+
+/// Path of the JSON status endpoint the overlay polls
+pub const STATUS_PATH: &str = "/__heisenberg/status";
+
+/// Tag injected into proxied HTML
+const SCRIPT_TAG: &str = r#"<script type="module" src="/__heisenberg/overlay.js"></script>"#;
+
+/// Overlay script served at [`OVERLAY_SCRIPT_PATH`]
+pub const OVERLAY_SCRIPT: &str = r#"// Heisenberg development error overlay
+const POLL_MS = 2000;
+let dismissed = null;
+let host = null;
+
+function show(message) {
+  if (message === dismissed) return;
+  if (!host) {
+    host = document.createElement('heisenberg-error-overlay');
+    const root = host.attachShadow({ mode: 'open' });
+    root.innerHTML = `
+      <style>
+        .backdrop { position: fixed; inset: 0; z-index: 2147483647; background: rgba(0, 0, 0, 0.66);
+          display: flex; align-items: flex-start; justify-content: center; padding-top: 10vh;
+          font-family: ui-monospace, SFMono-Regular, Menlo, monospace; }
+        .panel { background: #181818; color: #d8d8d8; border-top: 6px solid #ff5555; border-radius: 6px;
+          max-width: 720px; width: calc(100% - 40px); padding: 24px; box-shadow: 0 8px 30px rgba(0, 0, 0, 0.4); }
+        h1 { margin: 0 0 12px; color: #ff5555; font-size: 16px; }
+        pre { white-space: pre-wrap; margin: 0 0 16px; font-size: 14px; }
+        p { margin: 0; color: #999; font-size: 13px; }
+        button { float: right; background: none; border: 0; color: #999; font-size: 20px; cursor: pointer; }
+      </style>
+      <div class="backdrop">
+        <div class="panel">
+          <button title="Dismiss">&times;</button>
+          <h1>Development server unavailable</h1>
+          <pre></pre>
+          <p>Requests are failing to reach the frontend dev server. This overlay closes once it responds again.</p>
+        </div>
+      </div>`;
+    root.querySelector('button').addEventListener('click', () => {
+      dismissed = root.querySelector('pre').textContent;
+      hide();
+    });
+  }
+  host.shadowRoot.querySelector('pre').textContent = message;
+  if (!host.isConnected) document.body.appendChild(host);
+}
+
+function hide() {
+  if (host && host.isConnected) host.remove();
+}
+
+async function poll() {
+  try {
+    const res = await fetch('/__heisenberg/status', { cache: 'no-store' });
+    const status = await res.json();
+    const failing = status.routes.filter((route) => !route.healthy || route.error);
+    if (failing.length > 0) {
+      show(failing.map((route) => {
+        const output = (route.output || []).slice(-20);
+        const summary = `${route.dev_server}: ${route.error || 'not responding'}`;
+        return output.length > 0 ? `${summary}\n\n${output.join('\n')}` : summary;
+      }).join('\n\n'));
+    } else {
+      dismissed = null;
+      hide();
+    }
+  } catch (e) {
+    show('Lost connection to the backend server');
+  }
+  setTimeout(poll, POLL_MS);
+}
+
+poll();
+"#;
+
+/// Insert the overlay script tag into an HTML document
+///
+/// The tag goes right before `</head>`, falling back to `</body>` and then the
+/// end of the document.
+pub fn inject_overlay(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let position = lower
+        .find("</head>")
+        .or_else(|| lower.find("</body>"))
+        .unwrap_or(html.len());
+
+    let mut injected = String::with_capacity(html.len() + SCRIPT_TAG.len());
+    injected.push_str(&html[..position]);
+    injected.push_str(SCRIPT_TAG);
+    injected.push_str(&html[position..]);
+    injected
+}
+
+/// Whether a request is a browser navigation rather than a subresource fetch
+pub fn is_document_request(headers: &HeaderMap) -> bool {
+    if let Some(dest) = headers.get("sec-fetch-dest") {
+        return matches!(dest.as_bytes(), b"document" | b"iframe");
+    }
+
+    headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(|accept| accept.contains("text/html"))
+        .unwrap_or(false)
+}
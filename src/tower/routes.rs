@@ -0,0 +1,71 @@
+//! Adding and removing routes at runtime
+
+use crate::core::config::SpaRouteConfig;
+use crate::error::HeisenbergError;
+use crate::tower::service::ServiceState;
+use std::sync::Arc;
+
+/// Handle for changing a layer's SPA routes while it serves requests
+///
+/// Every clone and every service of the layer sees the same routes. Changes
+/// swap the router in one step: requests already in flight finish with the
+/// routes they started with, and later requests see the new ones.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::core::config::SpaRouteConfig;
+/// use heisenberg::{Heisenberg, HeisenbergLayer};
+///
+/// let layer = HeisenbergLayer::new(Heisenberg::new().spa("./dist").pattern("/app/*").build());
+/// let routes = layer.routes();
+///
+/// // A new tenant signs up
+/// routes.add(SpaRouteConfig {
+///     pattern: "/acme/*".to_string(),
+///     embed_dir: "./tenants/acme/dist".into(),
+///     ..Default::default()
+/// })?;
+/// assert!(routes.contains("/acme/*"));
+///
+/// routes.remove("/acme/*")?;
+/// # Ok::<(), heisenberg::HeisenbergError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RouteHandle {
+    state: Arc<ServiceState>,
+}
+
+impl RouteHandle {
+    pub(crate) fn new(state: Arc<ServiceState>) -> Self {
+        Self { state }
+    }
+
+    /// Start serving a route
+    ///
+    /// The route is validated like configured routes; a duplicate pattern is
+    /// an error. In production its
+    /// [`MissingBuild`](crate::core::config::MissingBuild) policy is applied
+    /// before the route goes live. In development its dev server starts on
+    /// the first request, as for configured routes.
+    pub fn add(&self, route: SpaRouteConfig) -> Result<(), HeisenbergError> {
+        self.state.add_route(route)
+    }
+
+    /// Stop serving a route, returning its configuration
+    ///
+    /// A dev server Heisenberg started for the route is stopped.
+    pub fn remove(&self, pattern: &str) -> Result<SpaRouteConfig, HeisenbergError> {
+        self.state.remove_route(pattern)
+    }
+
+    /// Whether a route with this pattern is being served
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.list().iter().any(|route| route.pattern == pattern)
+    }
+
+    /// The routes currently served
+    pub fn list(&self) -> Vec<SpaRouteConfig> {
+        self.state.routes()
+    }
+}
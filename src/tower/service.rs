@@ -1,45 +1,1196 @@
 //! Tower service implementation
 
-use crate::core::config::Heisenberg;
-use crate::core::mode::detect_mode;
-use crate::core::router::Router;
+use crate::core::config::{
+    GlobalSettings, Heisenberg, MissingAsset, SpaRouteConfig, TrailingSlash,
+};
+use crate::core::events::{emit, HeisenbergEvent};
+use crate::core::mode::Mode;
+use crate::core::router::{unknown_pattern, HandlerKind, RouteHandler, Router};
+use crate::core::stats::{RouteLatencies, RouteStats};
+use crate::error::HeisenbergError;
+use crate::services::client;
+use crate::services::headers::load_headers;
+use crate::services::proxy::{ForwardedBody, ProxyBody, ProxyFailure};
+use crate::services::redirects::load_redirects;
+use crate::services::{
+    AssetCache, DevServerHandle, ProcessStats, ProxyService, RedirectRule, StaticFileService,
+};
+use crate::tower::body::into_res_body;
 use crate::tower::future::HeisenbergFuture;
-use hyper::{Request, Response, StatusCode};
+use crate::tower::overlay;
+use crate::tower::RouteHandle;
+use crate::utils::request_id::{self, REQUEST_ID_HEADER};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use hyper::header::{self, HeaderMap, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode, Uri};
+use std::collections::HashMap;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use std::task::{Context, Poll};
+use std::time::Instant;
 use tower::Service;
 #[cfg(feature = "logging")]
-use tracing::debug;
+use tracing::{debug, info_span, warn, Instrument};
+
+/// Largest request body buffered so the inner service can be tried first and
+/// the body still forwarded to the dev server afterwards, unless configured
+/// otherwise with [`Heisenberg::max_request_body`]
+pub const MAX_BUFFERED_BODY: usize = crate::core::config::DEFAULT_MAX_REQUEST_BODY;
 
 /// Heisenberg Tower service
+///
+/// Requests go to the inner service first, so application routes always take
+/// precedence. When the inner service answers 404 for a path matching an SPA
+/// route, Heisenberg serves the request instead: proxied to the dev server in
+/// development, or from the build directory in production.
+///
+/// In development, request bodies on SPA routes are buffered (up to
+/// [`MAX_BUFFERED_BODY`], or the configured `max_request_body`) so form
+/// actions and uploads the inner service doesn't handle can be forwarded to
+/// the dev server intact. Larger bodies only ever reach the inner service;
+/// if it doesn't handle them either, the answer is `413 Payload Too Large`.
+/// Routes with
+/// [`inner_service_first(false)`](crate::SpaRouteBuilder::inner_service_first)
+/// skip the inner service and stream bodies to the dev server instead.
 #[derive(Debug, Clone)]
 pub struct HeisenbergService<S> {
     inner: S,
-    router: Arc<Mutex<Router>>,
+    state: Arc<ServiceState>,
 }
 
 impl<S> HeisenbergService<S> {
     /// Create a new Heisenberg service
-    pub fn new(inner: S, config: Heisenberg) -> Result<Self, crate::error::HeisenbergError> {
-        let mode = detect_mode();
+    pub fn new(inner: S, config: Heisenberg) -> Result<Self, HeisenbergError> {
+        Ok(Self::with_state(
+            inner,
+            Arc::new(ServiceState::new(&config)?),
+        ))
+    }
+
+    /// Create a service sharing state with the other services of a layer
+    pub(crate) fn with_state(inner: S, state: Arc<ServiceState>) -> Self {
+        Self { inner, state }
+    }
+
+    /// Handle to the dev servers this service starts on demand
+    ///
+    /// `None` in production mode and CI mode, where no dev servers are
+    /// spawned. See [`HeisenbergLayer::dev_servers`](crate::HeisenbergLayer::dev_servers).
+    pub fn dev_servers(&self) -> Option<DevServerHandle> {
+        self.state.dev_servers()
+    }
+
+    /// Handle for adding and removing SPA routes while the service runs
+    ///
+    /// See [`HeisenbergLayer::routes`](crate::HeisenbergLayer::routes).
+    pub fn routes(&self) -> RouteHandle {
+        RouteHandle::new(self.state.clone())
+    }
+
+    /// Latencies of the SPA requests served per route
+    ///
+    /// See [`HeisenbergLayer::stats`](crate::HeisenbergLayer::stats).
+    pub fn stats(&self) -> Vec<RouteStats> {
+        self.state.stats()
+    }
+}
+
+/// State shared by every service a layer creates
+///
+/// Frameworks like Axum call `Layer::layer` once per route, so anything that
+/// must be seen across routes, like proxy failures, lives here.
+#[derive(Debug)]
+pub(crate) struct ServiceState {
+    /// Replaced as a whole when routes are added or removed, so a request
+    /// sees either the old routes or the new ones
+    routing: Snapshot<Routing>,
+    /// Held while routes are added or removed, so concurrent changes don't
+    /// overwrite each other. Requests never take it.
+    updating: Mutex<()>,
+    mode: Mode,
+    error_overlay: bool,
+    request_ids: bool,
+    /// Latency histograms by route pattern, created on a route's first
+    /// request
+    latencies: RwLock<HashMap<String, Arc<RouteLatencies>>>,
+}
+
+/// A value that is read and replaced as a whole, like `ArcSwap`
+///
+/// Readers clone the current `Arc` and release the lock right away, so no
+/// lock is held while a request is served. The lock only guards swapping one
+/// pointer, which can't be left half done, so a panic elsewhere never poisons
+/// it for later requests.
+#[derive(Debug)]
+struct Snapshot<T> {
+    current: RwLock<Arc<T>>,
+}
+
+impl<T> Snapshot<T> {
+    fn new(value: T) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(value)),
+        }
+    }
+
+    fn load(&self) -> Arc<T> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn store(&self, value: T) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(value);
+    }
+}
+
+/// The router and the services for the routes it matches
+#[derive(Debug, Clone)]
+struct Routing {
+    router: Router,
+    handlers: RouteServices,
+}
+
+impl ServiceState {
+    pub(crate) fn new(config: &Heisenberg) -> Result<Self, HeisenbergError> {
+        let mode = config.mode();
         let router = Router::new(config.routes().to_vec(), mode)?;
+        emit(
+            &config.global_settings().events,
+            HeisenbergEvent::ModeDetected { mode },
+        );
 
         Ok(Self {
-            inner,
-            router: Arc::new(Mutex::new(router)),
+            routing: Snapshot::new(Routing {
+                router,
+                handlers: RouteServices::new(config, mode),
+            }),
+            updating: Mutex::default(),
+            mode,
+            error_overlay: config.global_settings().error_overlay,
+            request_ids: config.global_settings().request_ids,
+            latencies: RwLock::default(),
+        })
+    }
+
+    pub(crate) fn dev_servers(&self) -> Option<DevServerHandle> {
+        self.routing()
+            .handlers
+            .dev_servers
+            .as_ref()
+            .map(|dev_servers| dev_servers.handle.clone())
+    }
+
+    /// The current routes, unaffected by changes made while it's in use
+    fn routing(&self) -> Arc<Routing> {
+        self.routing.load()
+    }
+
+    pub(crate) fn routes(&self) -> Vec<SpaRouteConfig> {
+        self.routing().router.routes().to_vec()
+    }
+
+    /// Latency statistics of every current route, in the order they were added
+    pub(crate) fn stats(&self) -> Vec<RouteStats> {
+        let latencies = self
+            .latencies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.routes()
+            .iter()
+            .map(|route| match latencies.get(&route.pattern) {
+                Some(route_latencies) => route_latencies.snapshot(&route.pattern),
+                None => RouteStats {
+                    pattern: route.pattern.clone(),
+                    ..Default::default()
+                },
+            })
+            .collect()
+    }
+
+    /// The histograms of a route, created if it has none yet
+    fn latencies(&self, pattern: &str) -> Arc<RouteLatencies> {
+        if let Some(route_latencies) = self
+            .latencies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(pattern)
+        {
+            return route_latencies.clone();
+        }
+        self.latencies
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(pattern.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Start serving a new route
+    ///
+    /// The route is validated like the configured ones, including its
+    /// [`MissingBuild`](crate::core::config::MissingBuild) policy in
+    /// production.
+    pub(crate) fn add_route(&self, route: SpaRouteConfig) -> Result<(), HeisenbergError> {
+        route.validate()?;
+        let (settings, ci_mode) = {
+            let routing = self.routing();
+            (routing.handlers.settings.clone(), routing.handlers.ci_mode)
+        };
+        if self.mode == Mode::Production {
+            crate::services::build::check_build(&route, ci_mode, &settings.events)?;
+        }
+
+        // Reading `_redirects` and `_headers` happens here, before the swap,
+        // so requests keep being served meanwhile
+        let _updating = self.updating.lock().unwrap_or_else(PoisonError::into_inner);
+        let routing = self.routing();
+        let mut router = routing.router.clone();
+        router.insert(route.clone())?;
+        let mut handlers = routing.handlers.clone();
+        handlers.add_route(&route);
+        self.routing.store(Routing { router, handlers });
+        Ok(())
+    }
+
+    /// Stop serving a route, stopping its dev server if one was started
+    pub(crate) fn remove_route(&self, pattern: &str) -> Result<SpaRouteConfig, HeisenbergError> {
+        let _updating = self.updating.lock().unwrap_or_else(PoisonError::into_inner);
+        let routing = self.routing();
+        let mut router = routing.router.clone();
+        let removed = router
+            .remove(pattern)
+            .ok_or_else(|| unknown_pattern(pattern, routing.router.routes()))?;
+        let mut handlers = routing.handlers.clone();
+        handlers.remove_route(pattern)?;
+        self.routing.store(Routing { router, handlers });
+        self.latencies
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(pattern);
+        Ok(removed)
+    }
+
+    /// Serve one of Heisenberg's own development endpoints
+    async fn internal_response(&self, method: &Method, path: &str) -> Option<Response<Bytes>> {
+        if self.mode != Mode::Development || method != Method::GET {
+            return None;
+        }
+
+        match path {
+            overlay::OVERLAY_SCRIPT_PATH if self.error_overlay => Some(
+                Response::builder()
+                    .header(
+                        header::CONTENT_TYPE,
+                        "application/javascript; charset=utf-8",
+                    )
+                    .header(header::CACHE_CONTROL, "no-cache")
+                    .body(Bytes::from_static(overlay::OVERLAY_SCRIPT.as_bytes()))
+                    .unwrap(),
+            ),
+            overlay::STATUS_PATH => {
+                let routing = self.routing();
+                let mut routes = Vec::new();
+                for (pattern, proxy) in &routing.handlers.proxies {
+                    let stats = routing
+                        .handlers
+                        .dev_server_stats(pattern)
+                        .unwrap_or_default();
+                    routes.push(serde_json::json!({
+                        "pattern": pattern,
+                        "dev_server": proxy.target_url(),
+                        "healthy": proxy.is_healthy().await,
+                        "error": proxy.last_error(),
+                        "output": routing.handlers.dev_server_output(pattern),
+                        "pid": stats.pid,
+                        "cpu_percent": stats.cpu_percent,
+                        "memory_bytes": stats.memory_bytes,
+                    }));
+                }
+                let status = serde_json::json!({ "mode": "development", "routes": routes });
+
+                Some(
+                    Response::builder()
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .header(header::CACHE_CONTROL, "no-store")
+                        .body(Bytes::from(status.to_string()))
+                        .unwrap(),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Proxy and static file services for each route, keyed by route pattern
+///
+/// Clones share the services, so a route keeps its proxy state and dev
+/// server when other routes are added or removed.
+#[derive(Debug, Clone)]
+struct RouteServices {
+    proxies: HashMap<String, Arc<ProxyService>>,
+    static_files: HashMap<String, StaticFileService>,
+    /// Static file services for the embed directories routes'
+    /// `resolve_params` callbacks picked, by pattern and directory
+    resolved_static_files: Arc<Mutex<ResolvedStaticFiles>>,
+    /// Each production route's rewrites, then its `_redirects` rules
+    redirects: HashMap<String, Arc<Vec<RedirectRule>>>,
+    dev_servers: Option<Arc<DevServers>>,
+    settings: GlobalSettings,
+    mode: Mode,
+    ci_mode: bool,
+    cache: Option<AssetCache>,
+}
+
+type ResolvedStaticFiles = HashMap<(String, PathBuf), Arc<StaticFileService>>;
+
+/// Dev servers started on demand, the first time a request hits their route
+///
+/// A 3-SPA project only launches the dev servers for the routes actually in
+/// use. Routes whose dev server already responds are left alone.
+#[derive(Debug)]
+struct DevServers {
+    handle: DevServerHandle,
+    /// Whether startup was attempted, per route pattern
+    attempted: Mutex<HashMap<String, Arc<tokio::sync::Mutex<bool>>>>,
+}
+
+impl DevServers {
+    /// Start the route's dev server unless that was already attempted
+    ///
+    /// The first request waits for the dev server to become healthy. Failures
+    /// are recorded on the proxy, so they show in the error page and overlay.
+    /// If the dev server announced a different port, the proxy follows it.
+    async fn ensure_started(&self, route: &SpaRouteConfig, proxy: &ProxyService) {
+        self.start_once(route, proxy).await;
+        if let Some(url) = self.handle.dev_server_url_if_moved(&route.pattern) {
+            if url != proxy.target_url() {
+                proxy.set_target_url(url);
+            }
+        }
+    }
+
+    async fn start_once(&self, route: &SpaRouteConfig, proxy: &ProxyService) {
+        let attempted = self
+            .attempted
+            .lock()
+            .unwrap()
+            .entry(route.pattern.clone())
+            .or_default()
+            .clone();
+        let mut attempted = attempted.lock().await;
+        if *attempted {
+            return;
+        }
+        *attempted = true;
+
+        if proxy.is_healthy().await {
+            return;
+        }
+        if let Err(e) = self.handle.start_route(route).await {
+            #[cfg(feature = "logging")]
+            warn!(pattern = %route.pattern, error = %e, "Failed to start dev server");
+            #[cfg(not(feature = "logging"))]
+            eprintln!(
+                "Warning: Failed to start dev server for {}: {}",
+                route.pattern, e
+            );
+            proxy.record_error(e.to_string());
+        }
+    }
+}
+
+impl RouteServices {
+    /// The first rewrite or `_redirects` rule of a route that applies to a static path,
+    /// with where it sends the request
+    ///
+    /// A rule without `!` is passed over when a file exists at the path.
+    async fn redirect_rule(
+        &self,
+        pattern: &str,
+        static_files: &StaticFileService,
+        path: &str,
+    ) -> Option<(&RedirectRule, String)> {
+        let rules = self.redirects.get(pattern)?;
+        let mut file_exists = None;
+        for rule in rules.iter() {
+            let Some(target) = rule.target(path) else {
+                continue;
+            };
+            if !rule.force {
+                let exists = match file_exists {
+                    Some(exists) => exists,
+                    None => *file_exists.insert(static_files.has_file(path).await),
+                };
+                if exists {
+                    continue;
+                }
+            }
+            return Some((rule, target));
+        }
+        None
+    }
+
+    /// Recent output of the route's dev server, if Heisenberg started one
+    fn dev_server_output(&self, pattern: &str) -> Vec<String> {
+        self.dev_servers
+            .as_ref()
+            .map(|dev_servers| dev_servers.handle.output(pattern))
+            .unwrap_or_default()
+    }
+
+    fn dev_server_stats(&self, pattern: &str) -> Option<ProcessStats> {
+        self.dev_servers
+            .as_ref()
+            .and_then(|dev_servers| dev_servers.handle.process_stats(pattern))
+    }
+
+    fn new(config: &Heisenberg, mode: Mode) -> Self {
+        let ci_mode = config.is_ci_mode();
+        let cache_size = config.global_settings().asset_cache_size;
+
+        let mut services = Self {
+            proxies: HashMap::new(),
+            static_files: HashMap::new(),
+            resolved_static_files: Arc::default(),
+            redirects: HashMap::new(),
+            dev_servers: (mode == Mode::Development && !ci_mode).then(|| {
+                Arc::new(DevServers {
+                    handle: DevServerHandle::new(config),
+                    attempted: Mutex::default(),
+                })
+            }),
+            settings: config.global_settings().clone(),
+            mode,
+            ci_mode,
+            cache: (cache_size > 0).then(|| AssetCache::new(cache_size)),
+        };
+        for route in config.routes() {
+            services.add_route(route);
+        }
+        services
+    }
+
+    /// Create the proxy or static file service for a route
+    fn add_route(&mut self, route: &SpaRouteConfig) {
+        match self.mode {
+            Mode::Development => {
+                let client = client::for_route(
+                    route,
+                    &self.settings.http_client,
+                    self.settings.system_proxy,
+                );
+                let max_response_body = route.max_response_body.or(self.settings.max_response_body);
+                let timeout = route.proxy_timeout.unwrap_or(self.settings.proxy_timeout);
+                let mut proxy =
+                    ProxyService::with_client(route.dev_proxy_url.clone(), client.clone())
+                        .with_health_check(route.health_check.clone())
+                        .websocket_keepalive(route.websocket_keepalive)
+                        .max_response_body(max_response_body)
+                        .buffer_responses(route.buffer_proxied)
+                        .buffer_html(self.settings.error_overlay)
+                        .timeout(timeout)
+                        .preserve_host(route.preserve_host)
+                        .rewrite_dev_urls(route.rewrite_dev_urls)
+                        .refresh_interval(route.error_page_refresh)
+                        .with_theme(self.settings.error_page_theme.clone())
+                        .with_events(self.settings.events.clone());
+                if self.ci_mode {
+                    proxy = proxy.without_health_check();
+                }
+                if let Some(inspector) = &route.body_inspector {
+                    proxy = proxy.with_body_inspector(inspector.clone());
+                }
+                if let Some(url) = &route.fallback_dev_url {
+                    let mut fallback = ProxyService::with_client(url.clone(), client)
+                        .websocket_keepalive(route.websocket_keepalive)
+                        .max_response_body(max_response_body)
+                        .buffer_responses(route.buffer_proxied)
+                        .buffer_html(self.settings.error_overlay)
+                        .timeout(timeout)
+                        .rewrite_dev_urls(route.rewrite_dev_urls)
+                        .refresh_interval(route.error_page_refresh)
+                        .with_theme(self.settings.error_page_theme.clone())
+                        .with_events(self.settings.events.clone());
+                    if let Some(inspector) = &route.body_inspector {
+                        fallback = fallback.with_body_inspector(inspector.clone());
+                    }
+                    proxy = proxy.with_fallback(fallback);
+                }
+                self.proxies.insert(route.pattern.clone(), Arc::new(proxy));
+            }
+            Mode::Production => {
+                let static_files = self.static_file_service(route, &route.embed_dir);
+                self.static_files
+                    .insert(route.pattern.clone(), static_files);
+                let rewrites = route.rewrites.iter().map(|(from, to)| RedirectRule {
+                    from: from.clone(),
+                    to: to.clone(),
+                    status: 200,
+                    force: true,
+                });
+                let redirects: Vec<_> = rewrites.chain(load_redirects(&route.embed_dir)).collect();
+                if !redirects.is_empty() {
+                    self.redirects
+                        .insert(route.pattern.clone(), Arc::new(redirects));
+                }
+            }
+        }
+    }
+
+    /// The static file service for a route's files in `embed_dir`
+    fn static_file_service(&self, route: &SpaRouteConfig, embed_dir: &Path) -> StaticFileService {
+        let mut static_files =
+            StaticFileService::new(embed_dir.to_path_buf(), route.fallback_file.clone())
+                .with_etag(self.settings.etag)
+                .with_symlinks(route.symlinks)
+                .with_allowed_dotfiles(route.allowed_dotfiles.iter().cloned())
+                .with_header_rules(
+                    // Configured rules come last so they win over the file's
+                    load_headers(embed_dir)
+                        .into_iter()
+                        .chain(route.header_rules.iter().cloned())
+                        .collect(),
+                );
+        if route.directory_listing {
+            static_files = static_files.with_directory_listing();
+        }
+        if let Some(cache) = &self.cache {
+            static_files = static_files.with_cache(cache.clone());
+        }
+        #[cfg(feature = "mmap")]
+        if let Some(threshold) = self.settings.mmap_threshold {
+            static_files = static_files.with_mmap_threshold(threshold);
+        }
+        #[cfg(feature = "sri")]
+        if self.settings.subresource_integrity {
+            static_files = static_files.with_subresource_integrity();
+        }
+        static_files
+    }
+
+    /// The static file service for an embed directory a route's
+    /// `resolve_params` callback picked, created the first time it is used
+    ///
+    /// `None` if the directory doesn't exist. Only existing directories get
+    /// a service, so requests for made-up tenants can't grow the map.
+    fn resolved_static_files(
+        &self,
+        route: &SpaRouteConfig,
+        embed_dir: PathBuf,
+    ) -> Option<Arc<StaticFileService>> {
+        let mut resolved = self
+            .resolved_static_files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let key = (route.pattern.clone(), embed_dir);
+        if let Some(static_files) = resolved.get(&key) {
+            return Some(static_files.clone());
+        }
+        if !key.1.is_dir() {
+            return None;
+        }
+        let static_files = Arc::new(self.static_file_service(route, &key.1));
+        resolved.insert(key, static_files.clone());
+        Some(static_files)
+    }
+
+    /// Drop a route's services and stop its dev server
+    fn remove_route(&mut self, pattern: &str) -> Result<(), HeisenbergError> {
+        self.proxies.remove(pattern);
+        self.static_files.remove(pattern);
+        self.resolved_static_files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|(resolved, _), _| resolved != pattern);
+        self.redirects.remove(pattern);
+        if let Some(dev_servers) = &self.dev_servers {
+            dev_servers.attempted.lock().unwrap().remove(pattern);
+            dev_servers.handle.stop_route(pattern);
+        }
+        Ok(())
+    }
+
+    /// Proxy a WebSocket upgrade to the route's dev server
+    async fn proxy_websocket(
+        &self,
+        config: &SpaRouteConfig,
+        uri: &Uri,
+        headers: &HeaderMap,
+        client: hyper::upgrade::OnUpgrade,
+    ) -> Option<Response<Bytes>> {
+        let proxy = self.proxies.get(&config.pattern)?;
+        if let Some(dev_servers) = &self.dev_servers {
+            dev_servers.ensure_started(config, proxy).await;
+        }
+        let path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+        let mut response = match proxy
+            .forward_upgrade(&config.target(path).upstream_path, headers, client)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => plain_text(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+        response.extensions_mut().insert(HandlerKind::Proxy);
+        Some(response)
+    }
+
+    /// Serve a request with the handler the router picked
+    ///
+    /// Returns `None` when the handler has nothing to serve, in which case the
+    /// inner service's response stands.
+    async fn serve(
+        &self,
+        handler: &RouteHandler,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: RequestBody,
+        error_overlay: bool,
+    ) -> Option<Response<ProxyBody>> {
+        let config = handler.config();
+        let pattern = &config.pattern;
+        if config.is_unslashed_root(uri.path()) {
+            match config.trailing_slash {
+                TrailingSlash::ServeBoth => {}
+                TrailingSlash::PassThrough => return None,
+                TrailingSlash::Redirect => {
+                    let mut response = redirect_with_slash(uri);
+                    add_route_headers(&mut response, config);
+                    response.extensions_mut().insert(handler.kind());
+                    return Some(response.map(ProxyBody::Full));
+                }
+            }
+        }
+
+        let response = match handler {
+            RouteHandler::Proxy(_) => {
+                let (body, too_large) = match body {
+                    RequestBody::Empty => (ForwardedBody::Empty, None),
+                    RequestBody::Buffered(bytes) => (ForwardedBody::Full(bytes), None),
+                    RequestBody::Streaming(body, too_large) => {
+                        (ForwardedBody::Streaming(body), Some(too_large))
+                    }
+                    RequestBody::Consumed => {
+                        return Some(
+                            plain_text(
+                                StatusCode::PAYLOAD_TOO_LARGE,
+                                "Request body too large".to_string(),
+                            )
+                            .map(ProxyBody::Full),
+                        )
+                    }
+                };
+                let proxy = self.proxies.get(pattern)?;
+                if let Some(dev_servers) = &self.dev_servers {
+                    dev_servers.ensure_started(config, proxy).await;
+                }
+                let path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+                let path = config.target(path).upstream_path;
+                let forwarded = proxy.forward_streaming(method, &path, headers, body).await;
+                if too_large.is_some_and(|too_large| too_large.load(Ordering::Relaxed)) {
+                    return Some(
+                        plain_text(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            "Request body too large".to_string(),
+                        )
+                        .map(ProxyBody::Full),
+                    );
+                }
+                let forwarded = match forwarded {
+                    Ok(response) => match response.into_parts() {
+                        (parts, ProxyBody::Full(body)) => Ok(Response::from_parts(parts, body)),
+                        // Streamed responses are passed on as they are
+                        (parts, body) => {
+                            let response = Response::from_parts(parts, body);
+                            return Some(finish(response, method, config, handler.kind()));
+                        }
+                    },
+                    Err(e) => Err(e),
+                };
+                match forwarded {
+                    Ok(response) if response.extensions().get::<ProxyFailure>().is_some() => {
+                        // Only navigations get the full error page; a failed
+                        // script or fetch gets a short message (and the overlay)
+                        if is_grpc_web(headers) {
+                            grpc_web_unavailable(
+                                response.status(),
+                                headers,
+                                &proxy.last_error().unwrap_or_default(),
+                            )
+                        } else if wants_json(headers) {
+                            json_proxy_error(
+                                response.status(),
+                                &proxy.last_error().unwrap_or_default(),
+                                &format!("{}{}", proxy.target_url(), path),
+                            )
+                        } else if overlay::is_document_request(headers) {
+                            with_dev_server_output(response, &self.dev_server_output(pattern))
+                        } else {
+                            plain_text(response.status(), proxy.last_error().unwrap_or_default())
+                        }
+                    }
+                    Ok(response) if error_overlay && is_html(&response) => {
+                        response.map(|body| match std::str::from_utf8(&body) {
+                            Ok(html) => Bytes::from(overlay::inject_overlay(html)),
+                            Err(_) => body,
+                        })
+                    }
+                    Ok(response) => response,
+                    Err(e) if is_grpc_web(headers) => {
+                        grpc_web_unavailable(StatusCode::BAD_GATEWAY, headers, &e.to_string())
+                    }
+                    Err(e) if wants_json(headers) => json_proxy_error(
+                        StatusCode::BAD_GATEWAY,
+                        &e.to_string(),
+                        &format!("{}{}", proxy.target_url(), path),
+                    ),
+                    Err(e) => plain_text(StatusCode::BAD_GATEWAY, e.to_string()),
+                }
+            }
+            RouteHandler::StaticFiles(_) => {
+                if method != Method::GET && method != Method::HEAD {
+                    return None;
+                }
+                let resolved;
+                let mut static_files = self.static_files.get(pattern)?;
+                if config.param_resolver.is_some() {
+                    let embed_dir = config.target(uri.path()).embed_dir;
+                    if embed_dir != config.embed_dir {
+                        resolved = self.resolved_static_files(config, embed_dir)?;
+                        static_files = &resolved;
+                    }
+                }
+                let mut path = config.static_path(uri.path());
+                let mut status = None;
+                if let Some((rule, target)) = self.redirect_rule(pattern, static_files, &path).await
+                {
+                    if rule.is_redirect() {
+                        let mut response = redirect_to(rule.status, config, &target, uri);
+                        add_route_headers(&mut response, config);
+                        response.extensions_mut().insert(handler.kind());
+                        return Some(response.map(ProxyBody::Full));
+                    }
+                    path = target.split('?').next().unwrap_or("/").to_string();
+                    status = StatusCode::from_u16(rule.status).ok();
+                }
+                let asset = is_asset_path(uri.path());
+                let navigation = !config.navigation_fallback_only || is_navigation_request(headers);
+                let fallback =
+                    if !navigation || (asset && config.missing_asset != MissingAsset::Fallback) {
+                        None
+                    } else {
+                        config.fallback_file_for(uri.path())
+                    };
+                let accept_encoding = headers
+                    .get(header::ACCEPT_ENCODING)
+                    .and_then(|value| value.to_str().ok());
+                match static_files
+                    .serve_encoded(&path, fallback.as_deref(), accept_encoding)
+                    .await
+                {
+                    Ok(mut response) => {
+                        if let Some(status) = status {
+                            *response.status_mut() = status;
+                        }
+                        let mut response = revalidate(response, headers);
+                        if let Some(locale) = config
+                            .locale_for(uri.path())
+                            .and_then(|locale| HeaderValue::from_str(locale).ok())
+                        {
+                            response
+                                .headers_mut()
+                                .insert(header::CONTENT_LANGUAGE, locale);
+                        }
+                        response
+                    }
+                    Err(_) if asset && config.missing_asset == MissingAsset::PassThrough => {
+                        return None
+                    }
+                    Err(_)
+                        if !navigation
+                            || (asset && config.missing_asset == MissingAsset::NotFound) =>
+                    {
+                        not_found(headers)
+                    }
+                    Err(_) => return None,
+                }
+            }
+        };
+
+        Some(finish(
+            response.map(ProxyBody::Full),
+            method,
+            config,
+            handler.kind(),
+        ))
+    }
+}
+
+/// Finish a response a route served: drop the body of a `HEAD` response and
+/// add the route's headers
+fn finish(
+    mut response: Response<ProxyBody>,
+    method: &Method,
+    config: &SpaRouteConfig,
+    kind: HandlerKind,
+) -> Response<ProxyBody> {
+    if method == Method::HEAD {
+        *response.body_mut() = ProxyBody::Full(Bytes::new());
+    }
+    add_route_headers(&mut response, config);
+    response.extensions_mut().insert(kind);
+    response
+}
+
+/// Set the headers configured with `.header()` on a route's response
+///
+/// They replace the response's own headers of the same names; a name given
+/// more than once gets every value.
+fn add_route_headers<B>(response: &mut Response<B>, config: &SpaRouteConfig) {
+    // Checked by `SpaRouteConfig::validate`
+    let headers: Vec<_> = config
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                header::HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            ))
+        })
+        .collect();
+    for (name, _) in &headers {
+        response.headers_mut().remove(name);
+    }
+    for (name, value) in headers {
+        response.headers_mut().append(name, value);
+    }
+}
+
+/// What's left of a request body for forwarding to the dev server
+enum RequestBody {
+    /// The request has no body
+    Empty,
+    /// The body was buffered and replayed to the inner service
+    Buffered(Bytes),
+    /// The body goes to the dev server as the browser sends it; the flag is
+    /// set if it turns out to be over the route's limit
+    Streaming(reqwest::Body, Arc<AtomicBool>),
+    /// The body was too large to buffer and went to the inner service only
+    Consumed,
+}
+
+impl RequestBody {
+    /// Stream `body` to the dev server, cutting it off after `limit` bytes
+    fn streaming<B>(body: B, limit: usize) -> Self
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let too_large = Arc::new(AtomicBool::new(false));
+        let stream = RequestStream {
+            body: Mutex::new(Box::pin(Limited::new(body, limit))),
+            too_large: too_large.clone(),
+        };
+        RequestBody::Streaming(reqwest::Body::wrap_stream(stream), too_large)
+    }
+}
+
+/// A request body as the stream reqwest sends, which has to be `Sync`
+///
+/// The body is only ever polled through `&mut`, so the mutex is never locked.
+struct RequestStream<B> {
+    body: Mutex<Pin<Box<Limited<B>>>>,
+    too_large: Arc<AtomicBool>,
+}
+
+impl<B> futures_util::Stream for RequestStream<B>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Item = Result<Bytes, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let body = this.body.get_mut().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            return match std::task::ready!(body.as_mut().poll_frame(cx)) {
+                // Trailers can't be forwarded through reqwest
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => Poll::Ready(Some(Ok(data))),
+                    Err(_) => continue,
+                },
+                Some(Err(e)) => {
+                    if e.is::<LengthLimitError>() {
+                        this.too_large.store(true, Ordering::Relaxed);
+                    }
+                    Poll::Ready(Some(Err(e)))
+                }
+                None => Poll::Ready(None),
+            };
+        }
+    }
+}
+
+/// Whether a request asks to switch to the WebSocket protocol
+fn is_websocket_upgrade(headers: &HeaderMap) -> bool {
+    let lists = |name, token: &str| {
+        headers.get_all(name).iter().any(|value| {
+            value.to_str().is_ok_and(|value| {
+                value
+                    .split(',')
+                    .any(|item| item.trim().eq_ignore_ascii_case(token))
+            })
+        })
+    };
+    lists(header::CONNECTION, "upgrade") && lists(header::UPGRADE, "websocket")
+}
+
+fn content_length<B>(req: &Request<B>) -> Option<usize> {
+    req.headers()
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Whether a path names a file rather than a client-side route
+fn is_asset_path(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .is_some_and(|segment| segment.contains('.'))
+}
+
+/// Whether a request is a browser navigation that should get the SPA shell
+///
+/// Uses `Sec-Fetch-Mode` when the browser sends it, and otherwise an `Accept`
+/// header listing `text/html`. Requests without an `Accept` header count as
+/// navigations; `fetch()` and XHR always send one.
+fn is_navigation_request(headers: &HeaderMap) -> bool {
+    if let Some(mode) = headers.get("sec-fetch-mode") {
+        return mode.as_bytes() == b"navigate";
+    }
+
+    match headers.get(header::ACCEPT) {
+        Some(accept) => accept
+            .to_str()
+            .map(|accept| accept.contains("text/html"))
+            .unwrap_or(false),
+        None => true,
+    }
+}
+
+/// Whether a request comes from code expecting JSON: an `Accept` header
+/// naming a JSON type, or the `X-Requested-With` header XHR libraries send
+fn wants_json(headers: &HeaderMap) -> bool {
+    headers.contains_key("x-requested-with")
+        || headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("json"))
+}
+
+/// A proxy failure as JSON, for fetch and XHR callers
+///
+/// `code` is stable for error handling to switch on; `message` is the
+/// reason from the proxy and `upstream` the URL it tried.
+fn json_proxy_error(status: StatusCode, message: &str, upstream: &str) -> Response<Bytes> {
+    let code = match status {
+        StatusCode::SERVICE_UNAVAILABLE => "dev_server_unavailable",
+        StatusCode::FORBIDDEN => "dev_server_rejected_host",
+        _ => "proxy_error",
+    };
+    let body = serde_json::json!({
+        "error": {
+            "code": code,
+            "message": message,
+            "upstream": upstream,
+        }
+    });
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Bytes::from(body.to_string()))
+        .unwrap()
+}
+
+/// A 404 in the format the client asked for
+fn not_found(headers: &HeaderMap) -> Response<Bytes> {
+    if wants_json(headers) {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Bytes::from_static(br#"{"error":"Not Found"}"#))
+            .unwrap()
+    } else {
+        plain_text(StatusCode::NOT_FOUND, "Not Found".to_string())
+    }
+}
+
+/// `304 Not Modified` instead of the response if the client's
+/// `If-None-Match` already names its ETag
+///
+/// Uses weak comparison, as `If-None-Match` requires.
+fn revalidate(response: Response<Bytes>, headers: &HeaderMap) -> Response<Bytes> {
+    let Some(etag) = response.headers().get(header::ETAG).cloned() else {
+        return response;
+    };
+    let Some(if_none_match) = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return response;
+    };
+
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let current = opaque(etag.to_str().unwrap_or_default());
+    let matches = if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || opaque(candidate) == current);
+    if !matches {
+        return response;
+    }
+
+    let mut not_modified = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag)
+        .body(Bytes::new())
+        .unwrap();
+    // Caches need `Vary` to know which stored variant was revalidated, and
+    // refresh their freshness from `Cache-Control` and `Expires`
+    for name in [header::VARY, header::CACHE_CONTROL, header::EXPIRES] {
+        for value in response.headers().get_all(&name) {
+            not_modified.headers_mut().append(&name, value.clone());
+        }
+    }
+    not_modified
+}
+
+fn is_html<B>(response: &Response<B>) -> bool {
+    response.status().is_success()
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.starts_with("text/html"))
+            .unwrap_or(false)
+}
+
+/// Redirect for a `_redirects` rule, keeping the query unless the target
+/// has its own
+fn redirect_to(status: u16, config: &SpaRouteConfig, target: &str, uri: &Uri) -> Response<Bytes> {
+    let mut location = if target.starts_with('/') && config.strip_static_prefix {
+        format!("{}{}", config.matched_prefix(uri.path()), target)
+    } else {
+        target.to_string()
+    };
+    if let (Some(query), false) = (uri.query(), location.contains('?')) {
+        location = format!("{}?{}", location, query);
+    }
+    Response::builder()
+        .status(StatusCode::from_u16(status).unwrap_or(StatusCode::MOVED_PERMANENTLY))
+        .header(header::LOCATION, location)
+        .body(Bytes::new())
+        .unwrap()
+}
+
+/// Redirect to the same path with a trailing slash, keeping the query
+fn redirect_with_slash(uri: &Uri) -> Response<Bytes> {
+    let location = match uri.query() {
+        Some(query) => format!("{}/?{}", uri.path(), query),
+        None => format!("{}/", uri.path()),
+    };
+    Response::builder()
+        .status(StatusCode::PERMANENT_REDIRECT)
+        .header(header::LOCATION, location)
+        .body(Bytes::new())
+        .unwrap()
+}
+
+/// Add the dev server's recent output to an error page, so crash output shows
+/// in the browser
+fn with_dev_server_output(response: Response<Bytes>, output: &[String]) -> Response<Bytes> {
+    if output.is_empty() {
+        return response;
+    }
+    let escaped = output
+        .join("\n")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let section = format!(
+        r#"<div class="container"><h2>Dev server output</h2><pre style="background: #181818; color: #d8d8d8; padding: 16px; border-radius: 8px; overflow-x: auto;">{}</pre></div>"#,
+        escaped
+    );
+    response.map(|body| {
+        let page = String::from_utf8_lossy(&body);
+        let page = match page.rfind("</body>") {
+            Some(end) => format!("{}{}{}", &page[..end], section, &page[end..]),
+            None => format!("{}{}", page, section),
+        };
+        Bytes::from(page)
+    })
+}
+
+/// Whether a request is a gRPC-web call, whose binary frames and trailer
+/// block must reach the backend or dev server byte for byte
+fn is_grpc_web(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .get(..20)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("application/grpc-web"))
         })
+}
+
+/// A trailers-only gRPC-web response with status `UNAVAILABLE`
+///
+/// gRPC-web clients can't read an HTML or text error page, but they do
+/// surface `grpc-status` and `grpc-message`.
+fn grpc_web_unavailable(status: StatusCode, headers: &HeaderMap, message: &str) -> Response<Bytes> {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (b' '..=b'~').contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
     }
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, headers[header::CONTENT_TYPE].clone())
+        .header("grpc-status", "14")
+        .header("grpc-message", encoded)
+        .body(Bytes::new())
+        .unwrap()
 }
 
-impl<S, B> Service<Request<B>> for HeisenbergService<S>
+fn plain_text(status: StatusCode, message: String) -> Response<Bytes> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Bytes::from(message))
+        .unwrap()
+}
+
+impl<S, B, ResBody> Service<Request<B>> for HeisenbergService<S>
 where
-    S: Service<Request<B>> + Clone + Send + 'static,
-    S::Response: Send + 'static,
+    S: Service<Request<B>, Response = Response<ResBody>> + Clone + Send + 'static,
     S::Future: Send + 'static,
     S::Error: Send + Sync + 'static,
-    B: Send + 'static,
+    B: Body<Data = Bytes> + From<Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    ResBody: From<Bytes> + Send + 'static,
 {
     type Response = S::Response;
     type Error = S::Error;
@@ -50,39 +1201,195 @@ where
         self.inner.poll_ready(cx)
     }
 
-    fn call(&mut self, req: Request<B>) -> Self::Future {
-        let inner = self.inner.clone();
-        let mut inner_service = inner;
-        let router = self.router.clone();
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        // Call the instance that was polled ready, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        // Set before the headers are copied, so the inner service, the dev
+        // server and the response all carry the same ID
+        let request_id = state
+            .request_ids
+            .then(|| request_id::ensure(req.headers_mut()));
+        let method = req.method().clone();
+        let uri = req.uri().clone();
+        let headers = req.headers().clone();
 
-        HeisenbergFuture::new(Box::pin(async move {
-            let path = req.uri().path();
+        #[cfg(feature = "logging")]
+        let span = info_span!(
+            "request",
+            request_id = request_id
+                .as_ref()
+                .and_then(|id| id.to_str().ok())
+                .unwrap_or_default(),
+            method = %method,
+            path = %uri.path(),
+        );
 
-            #[cfg(feature = "logging")]
-            debug!(path = %path, "Processing Heisenberg request");
+        let future = async move {
+            let mut response = handle(req, &mut inner, state, method, uri, headers).await?;
+            if let Some(id) = request_id {
+                response
+                    .headers_mut()
+                    .entry(REQUEST_ID_HEADER)
+                    .or_insert(id);
+            }
+            Ok(response)
+        };
+        #[cfg(feature = "logging")]
+        let future = future.instrument(span);
+        HeisenbergFuture::new(Box::pin(future))
+    }
+}
 
-            // Try to match against Heisenberg routes
-            let route_match = {
-                let mut router_guard = router.lock().unwrap();
-                router_guard.match_route(path).cloned()
-            };
+/// Route a request to Heisenberg or the inner service
+async fn handle<S, B, ResBody>(
+    mut req: Request<B>,
+    inner: &mut S,
+    state: Arc<ServiceState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response<ResBody>, S::Error>
+where
+    S: Service<Request<B>, Response = Response<ResBody>>,
+    B: Body<Data = Bytes> + From<Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    ResBody: From<Bytes> + 'static,
+{
+    if uri.path().starts_with(overlay::INTERNAL_PREFIX) {
+        if let Some(response) = state.internal_response(&method, uri.path()).await {
+            return Ok(response.map(ResBody::from));
+        }
+    }
+
+    // Try to match against Heisenberg routes
+    let routing = state.routing();
+    let handler = routing.router.route_handler(uri.path());
 
-            if let Some(_route_config) = route_match {
-                // TODO: Handle SPA routing (proxy/static files) in next phase
-                // For now, return a placeholder response
-                let _response = Response::builder()
-                    .status(StatusCode::OK)
-                    .header("content-type", "text/html")
-                    .body("Heisenberg SPA route matched - implementation coming soon!")
-                    .unwrap();
-
-                // This is a type conversion issue - we need to handle it properly
-                // For now, fall through to inner service
-                inner_service.call(req).await
+    // An upgraded connection can't be offered to the inner service
+    // first, so WebSockets are routed up front
+    if let Some(RouteHandler::Proxy(config)) = &handler {
+        if is_websocket_upgrade(&headers) && !config.bypasses_websocket(uri.path()) {
+            let client = hyper::upgrade::on(&mut req);
+            if let Some(response) = routing
+                .handlers
+                .proxy_websocket(config, &uri, &headers, client)
+                .await
+            {
+                return Ok(response.map(ResBody::from));
+            }
+        }
+    }
+
+    let max_request_body = handler
+        .as_ref()
+        .and_then(|handler| handler.config().max_request_body)
+        .unwrap_or(routing.handlers.settings.max_request_body);
+    let oversized = content_length(&req).is_some_and(|len| len > max_request_body);
+
+    // Routes that skip the inner service stream bodies to the dev server
+    if let Some(handler @ RouteHandler::Proxy(config)) = &handler {
+        if !config.inner_service_first && !config.passes_through(uri.path()) {
+            let (parts, body) = req.into_parts();
+            let body = if body.is_end_stream() {
+                RequestBody::Empty
+            } else if oversized {
+                RequestBody::Consumed
             } else {
-                // No Heisenberg route matched, pass to inner service
-                inner_service.call(req).await
+                RequestBody::streaming(body, max_request_body)
+            };
+            return match serve_route(&state, &routing, handler, &method, &uri, &headers, body).await
+            {
+                Some(response) => Ok(response),
+                None => {
+                    inner
+                        .call(Request::from_parts(parts, B::from(Bytes::new())))
+                        .await
+                }
+            };
+        }
+    }
+
+    // Buffer bodies bound for the dev server so the inner service can
+    // still be tried first
+    let proxied = matches!(handler, Some(RouteHandler::Proxy(_)));
+    let (req, body) = if !proxied || req.body().is_end_stream() {
+        (req, RequestBody::Empty)
+    } else if oversized {
+        (req, RequestBody::Consumed)
+    } else {
+        let (parts, body) = req.into_parts();
+        match Limited::new(body, max_request_body).collect().await {
+            Ok(collected) => {
+                let bytes = collected.to_bytes();
+                let req = Request::from_parts(parts, B::from(bytes.clone()));
+                if bytes.is_empty() {
+                    (req, RequestBody::Empty)
+                } else {
+                    (req, RequestBody::Buffered(bytes))
+                }
+            }
+            Err(e) => {
+                let response = if e.is::<LengthLimitError>() {
+                    plain_text(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "Request body too large".to_string(),
+                    )
+                } else {
+                    plain_text(
+                        StatusCode::BAD_REQUEST,
+                        format!("Failed to read request body: {}", e),
+                    )
+                };
+                return Ok(response.map(ResBody::from));
             }
-        }))
+        }
+    };
+
+    let response = inner.call(req).await?;
+    if response.status() != StatusCode::NOT_FOUND {
+        return Ok(response);
+    }
+    let Some(handler) = handler else {
+        return Ok(response);
+    };
+    match serve_route(&state, &routing, &handler, &method, &uri, &headers, body).await {
+        Some(spa_response) => Ok(spa_response),
+        None => Ok(response),
+    }
+}
+
+/// Serve a request with the route's handler, recording how long it took
+async fn serve_route<ResBody>(
+    state: &ServiceState,
+    routing: &Routing,
+    handler: &RouteHandler,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    body: RequestBody,
+) -> Option<Response<ResBody>>
+where
+    ResBody: From<Bytes> + 'static,
+{
+    #[cfg(feature = "logging")]
+    debug!(
+        path = %uri.path(),
+        handler = ?handler.kind(),
+        "Serving SPA request"
+    );
+
+    let started = Instant::now();
+    let spa_response = routing
+        .handlers
+        .serve(handler, method, uri, headers, body, state.error_overlay)
+        .await?;
+    let latencies = state.latencies(&handler.config().pattern);
+    match handler.kind() {
+        HandlerKind::Proxy => &latencies.proxy,
+        HandlerKind::StaticFiles => &latencies.static_files,
     }
+    .record(started.elapsed());
+    Some(into_res_body(spa_response).await)
 }
@@ -0,0 +1,58 @@
+//! Stopping a layer's dev servers when the server shuts down
+
+use crate::services::DevServerHandle;
+use std::future::Future;
+#[cfg(feature = "logging")]
+use tracing::{info, warn};
+
+/// Stops a layer's dev servers when dropped or when
+/// [`shutdown`](Self::shutdown) is called
+///
+/// Dev servers otherwise stop when the last service of the layer is dropped,
+/// which a lingering clone, say in a spawned task, can delay past the end of
+/// `main`. Holding a guard in `main` ties their lifetime to it instead.
+/// Once a guard has stopped them, the layer doesn't start dev servers again.
+///
+/// Created by [`HeisenbergLayer::shutdown_guard`](crate::HeisenbergLayer::shutdown_guard).
+#[derive(Debug)]
+#[must_use = "dropping the guard stops the dev servers right away"]
+pub struct ShutdownGuard {
+    dev_servers: Option<DevServerHandle>,
+}
+
+impl ShutdownGuard {
+    pub(crate) fn new(dev_servers: Option<DevServerHandle>) -> Self {
+        Self { dev_servers }
+    }
+
+    /// Stop the dev servers now, waiting until they have exited
+    ///
+    /// Dropping the guard stops them too, without waiting.
+    pub async fn shutdown(mut self) {
+        let Some(dev_servers) = self.dev_servers.take() else {
+            return;
+        };
+        #[cfg(feature = "logging")]
+        info!("Shutting down dev servers");
+        if let Err(e) = dev_servers.close().await {
+            #[cfg(feature = "logging")]
+            warn!(error = %e, "Failed to stop dev servers");
+            #[cfg(not(feature = "logging"))]
+            eprintln!("Warning: Failed to stop dev servers: {}", e);
+        }
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        if let Some(dev_servers) = self.dev_servers.take() {
+            dev_servers.close_in_background();
+        }
+    }
+}
+
+/// Wait for `signal`, then stop the dev servers the guard covers
+pub(crate) async fn after<F: Future>(signal: F, guard: ShutdownGuard) {
+    signal.await;
+    guard.shutdown().await;
+}
@@ -0,0 +1,86 @@
+//! Startup banner describing the configured SPA routes
+
+use crate::core::config::Heisenberg;
+use crate::core::mode::Mode;
+#[cfg(feature = "logging")]
+use tracing::info;
+
+/// Render a summary of every SPA route: pattern, where requests go, and for
+/// development mode the dev command and its working directory
+pub fn route_banner(config: &Heisenberg) -> String {
+    let mode = config.mode();
+    let mut banner = format!(
+        "  Heisenberg {} mode, {} SPA route{}",
+        mode_name(mode),
+        config.routes().len(),
+        if config.routes().len() == 1 { "" } else { "s" }
+    );
+    if mode == Mode::Development && config.is_ci_mode() {
+        banner.push_str(" (CI mode: dev servers are not started)");
+    }
+    if config.is_offline() {
+        banner.push_str(" (offline: serving build directories)");
+    }
+    banner.push('\n');
+
+    let width = config
+        .routes()
+        .iter()
+        .map(|route| route.pattern.len())
+        .max()
+        .unwrap_or(0);
+
+    for route in config.routes() {
+        let line = match mode {
+            Mode::Development if route.uses_staging() => format!(
+                "  ➜  {:width$}  → {}  (staging)\n",
+                route.pattern, route.dev_proxy_url,
+            ),
+            Mode::Development if route.docker_host.is_some() => format!(
+                "  ➜  {:width$}  → {}  (on the Docker host)\n",
+                route.pattern, route.dev_proxy_url,
+            ),
+            Mode::Development => format!(
+                "  ➜  {:width$}  → {}  ({} in {})\n",
+                route.pattern,
+                route.dev_proxy_url,
+                route.dev_command.join(" "),
+                route.working_dir.display(),
+            ),
+            Mode::Production => format!(
+                "  ➜  {:width$}  → {}\n",
+                route.pattern,
+                route.embed_dir.display(),
+            ),
+        };
+        banner.push_str(&line);
+    }
+    banner
+}
+
+/// Print the route banner, or log one event per route with the `logging`
+/// feature
+pub fn print_route_banner(config: &Heisenberg) {
+    #[cfg(feature = "logging")]
+    for route in config.routes() {
+        info!(
+            pattern = %route.pattern,
+            mode = mode_name(config.mode()),
+            dev_server = %route.dev_proxy_url,
+            dev_command = %route.dev_command.join(" "),
+            working_dir = %route.working_dir.display(),
+            embed_dir = %route.embed_dir.display(),
+            "SPA route"
+        );
+    }
+
+    #[cfg(not(feature = "logging"))]
+    print!("{}", route_banner(config));
+}
+
+fn mode_name(mode: Mode) -> &'static str {
+    match mode {
+        Mode::Development => "development",
+        Mode::Production => "production",
+    }
+}
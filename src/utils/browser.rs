@@ -1,32 +1,291 @@
 //! Cross-platform browser opening functionality
 
 use crate::error::HeisenbergError;
+use std::collections::HashSet;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+#[cfg(feature = "logging")]
+use tracing::warn;
+
+/// Policy controlling when and how the browser is opened in development mode
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::BrowserPolicy;
+/// use std::time::Duration;
+///
+/// let policy = BrowserPolicy::new()
+///     .once_per_session()
+///     .delay(Duration::from_secs(2))
+///     .browser("firefox")
+///     .incognito();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BrowserPolicy {
+    /// Open at most once per URL for the lifetime of the Rust process,
+    /// even if the dev server is restarted
+    pub once_per_session: bool,
+    /// How long to wait after the dev server becomes healthy
+    pub delay: Duration,
+    /// Browser to launch instead of the platform default (e.g. `"firefox"`,
+    /// `"google-chrome"`, or an application name like `"Safari"` on macOS)
+    pub browser: Option<String>,
+    /// Open in a private/incognito window. Requires `browser` to be set,
+    /// since the platform default opener has no way to request it.
+    pub incognito: bool,
+    /// Path to open instead of the server root (e.g. `"/admin/dashboard"`)
+    pub path: Option<String>,
+}
+
+impl BrowserPolicy {
+    /// Create a policy that opens the default browser immediately every time
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only open the browser the first time the dev server becomes healthy
+    pub fn once_per_session(mut self) -> Self {
+        self.once_per_session = true;
+        self
+    }
+
+    /// Wait this long after the dev server becomes healthy before opening
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Launch a specific browser instead of the platform default
+    pub fn browser<S: Into<String>>(mut self, browser: S) -> Self {
+        self.browser = Some(browser.into());
+        self
+    }
+
+    /// Open in a private/incognito window
+    pub fn incognito(mut self) -> Self {
+        self.incognito = true;
+        self
+    }
+
+    /// Open this path instead of the server root
+    pub fn path<S: Into<String>>(mut self, path: S) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Resolve the URL to open for a server at `base_url`
+    pub fn target_url(&self, base_url: &str) -> String {
+        match self.path.as_deref() {
+            Some(path) => format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            None => base_url.to_string(),
+        }
+    }
+}
+
+/// URLs already opened during this session
+fn opened_urls() -> &'static Mutex<HashSet<String>> {
+    static OPENED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    OPENED.get_or_init(|| Mutex::new(HashSet::new()))
+}
 
 /// Open a URL in the default browser
 pub async fn open_browser(url: &str) -> Result<(), HeisenbergError> {
+    open_browser_with(url, &BrowserPolicy::default()).await
+}
+
+/// Open a URL according to a [`BrowserPolicy`]
+///
+/// Returns `Ok(())` without doing anything when the policy only allows one
+/// open per session and this URL was already opened, or when `HEISENBERG_CI`
+/// is set.
+pub async fn open_browser_with(url: &str, policy: &BrowserPolicy) -> Result<(), HeisenbergError> {
+    if crate::core::mode::detect_ci_mode() {
+        return Ok(());
+    }
+
+    if policy.once_per_session && !opened_urls().lock().unwrap().insert(url.to_string()) {
+        return Ok(());
+    }
+
+    if !policy.delay.is_zero() {
+        tokio::time::sleep(policy.delay).await;
+    }
+
     let result = tokio::task::spawn_blocking({
         let url = url.to_string();
-        move || open_browser_sync(&url)
+        let policy = policy.clone();
+        move || match browser_command(&url, &policy) {
+            Some(command) => open_with_command(&command),
+            None if open_from_env(&url) => Ok(()),
+            None => open_browser_sync(&url),
+        }
     })
     .await
-    .map_err(|e| HeisenbergError::config(
+    .map_err(|e| HeisenbergError::browser(
         format!("Failed to spawn browser task: {}", e),
         "• This is an internal error with async task spawning\n• Try disabling browser opening with .open_browser(false)\n• Report this issue if it persists"
-    ))?;
+    ).with_source(e))?;
 
     result
 }
 
+/// Build the command line for launching a specific browser.
+///
+/// Returns `None` when the policy doesn't name a browser, meaning the platform
+/// default opener should be used instead.
+pub fn browser_command(url: &str, policy: &BrowserPolicy) -> Option<Vec<String>> {
+    let browser = policy.browser.as_deref()?;
+    let mut browser_args = Vec::new();
+    if policy.incognito {
+        browser_args.push(incognito_flag(browser).to_string());
+    }
+    browser_args.push(url.to_string());
+
+    let mut command: Vec<String> = Vec::new();
+    if cfg!(target_os = "macos") {
+        // macOS browsers are application bundles rather than binaries on PATH
+        command.extend(["open", "-na", browser, "--args"].map(String::from));
+    } else if cfg!(target_os = "windows") {
+        // `start` resolves browsers through the registry's App Paths
+        command.extend(["cmd", "/c", "start", "", browser].map(String::from));
+    } else {
+        command.push(browser.to_string());
+    }
+    command.extend(browser_args);
+
+    Some(command)
+}
+
+/// Private browsing flag understood by the given browser
+fn incognito_flag(browser: &str) -> &'static str {
+    let browser = browser.to_lowercase();
+    if browser.contains("firefox") {
+        "--private-window"
+    } else if browser.contains("edge") {
+        "--inprivate"
+    } else {
+        // Chrome, Chromium, Brave, Vivaldi and other Chromium derivatives
+        "--incognito"
+    }
+}
+
+/// Command lines a `BROWSER` environment variable value lists for `url`
+///
+/// As with Python's `webbrowser` and most dev tools, `BROWSER` holds one or
+/// more commands separated like `PATH` entries, by `:` (`;` on Windows), to
+/// try in order. `%s` in a command stands for the URL and `%%` for a literal
+/// `%`; commands without `%s` get the URL as their last argument.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::browser::browser_env_commands;
+///
+/// # #[cfg(unix)]
+/// assert_eq!(
+///     browser_env_commands("firefox --new-tab:chromium %s", "http://localhost:5173"),
+///     [
+///         vec!["firefox", "--new-tab", "http://localhost:5173"],
+///         vec!["chromium", "http://localhost:5173"],
+///     ]
+/// );
+/// ```
+pub fn browser_env_commands(browser: &str, url: &str) -> Vec<Vec<String>> {
+    let separator = if cfg!(windows) { ';' } else { ':' };
+    browser
+        .split(separator)
+        .filter_map(|command| {
+            let mut has_url = false;
+            let mut words: Vec<String> = command
+                .split_whitespace()
+                .map(|word| {
+                    let escaped = word.replace("%%", "\0");
+                    has_url |= escaped.contains("%s");
+                    escaped.replace("%s", url).replace('\0', "%")
+                })
+                .collect();
+            if words.is_empty() {
+                return None;
+            }
+            if !has_url {
+                words.push(url.to_string());
+            }
+            Some(words)
+        })
+        .collect()
+}
+
+/// Open `url` with the first command in `BROWSER` that starts, returning
+/// whether one did
+fn open_from_env(url: &str) -> bool {
+    let Ok(browser) = std::env::var("BROWSER") else {
+        return false;
+    };
+    browser_env_commands(&browser, url)
+        .iter()
+        .any(|command| match open_with_command(command) {
+            Ok(()) => true,
+            Err(e) => {
+                #[cfg(feature = "logging")]
+                warn!(error = %e, "Failed to open browser from BROWSER");
+                #[cfg(not(feature = "logging"))]
+                eprintln!("Warning: Failed to open browser from BROWSER: {}", e);
+                false
+            }
+        })
+}
+
+/// Command line opening `url` in the Windows default browser from WSL
+///
+/// Uses `wslview` from wslu when `wslview` is true, since WSL distros often
+/// have no `xdg-open`. Otherwise goes through `cmd.exe /C start`, escaping
+/// the characters `cmd` would treat specially, like the `&` of a query.
+pub fn wsl_command(url: &str, wslview: bool) -> Vec<String> {
+    if wslview {
+        return vec!["wslview".to_string(), url.to_string()];
+    }
+    let mut escaped = String::with_capacity(url.len());
+    for c in url.chars() {
+        if "&|<>^()%!\"".contains(c) {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    ["cmd.exe", "/C", "start", &escaped]
+        .map(String::from)
+        .to_vec()
+}
+
+/// Launch a browser command built by [`browser_command`]
+fn open_with_command(command: &[String]) -> Result<(), HeisenbergError> {
+    Command::new(&command[0])
+        .args(&command[1..])
+        .spawn()
+        .map_err(|e| {
+            HeisenbergError::browser(
+                format!("Failed to launch browser '{}': {}", command.join(" "), e),
+                "• Check that the browser is installed and on your PATH\n• On macOS, use the application name (e.g. 'Google Chrome')\n• Remove .browser() from the BrowserPolicy to use the default browser\n• Disable browser opening with .open_browser(false)"
+            ).with_source(e)
+        })?;
+
+    Ok(())
+}
+
 /// Synchronous browser opening implementation
 fn open_browser_sync(url: &str) -> Result<(), HeisenbergError> {
     #[cfg(target_os = "macos")]
     {
         Command::new("open").arg(url).spawn().map_err(|e| {
-            HeisenbergError::config(
+            HeisenbergError::browser(
                 format!("Failed to open browser on macOS: {}", e),
                 "• Ensure the 'open' command is available\n• Check if a default browser is set\n• Try opening the URL manually to test\n• Disable browser opening with .open_browser(false)"
-            )
+            ).with_source(e)
         })?;
     }
 
@@ -36,26 +295,46 @@ fn open_browser_sync(url: &str) -> Result<(), HeisenbergError> {
             .args(["/c", "start", "", url])
             .spawn()
             .map_err(|e| {
-                HeisenbergError::config(
+                HeisenbergError::browser(
                     format!("Failed to open browser on Windows: {}", e),
                     "• Ensure the 'start' command is available\n• Check if a default browser is set\n• Try opening the URL manually to test\n• Disable browser opening with .open_browser(false)"
-                )
+                ).with_source(e)
             })?;
     }
 
+    #[cfg(target_os = "linux")]
+    if crate::core::mode::detect_wsl() {
+        let wslview = crate::utils::paths::find_program("wslview").is_some();
+        let command = wsl_command(url, wslview);
+        let mut launch = Command::new(&command[0]);
+        launch.args(&command[1..]);
+        // cmd.exe warns when started in a Linux directory, which it sees as
+        // a UNC path
+        if !wslview && std::path::Path::new("/mnt/c").is_dir() {
+            launch.current_dir("/mnt/c");
+        }
+        launch.spawn().map_err(|e| {
+            HeisenbergError::browser(
+                format!("Failed to open browser from WSL: {}", e),
+                "• Install wslu for 'wslview' (sudo apt install wslu)\n• Check that Windows interop is enabled, so cmd.exe can run\n• Try opening the URL manually to test\n• Disable browser opening with .open_browser(false)"
+            ).with_source(e)
+        })?;
+        return Ok(());
+    }
+
     #[cfg(target_os = "linux")]
     {
         Command::new("xdg-open").arg(url).spawn().map_err(|e| {
-            HeisenbergError::config(
+            HeisenbergError::browser(
                 format!("Failed to open browser on Linux: {}", e),
                 "• Ensure 'xdg-open' is installed (usually part of xdg-utils)\n• Check if a default browser is set\n• Try opening the URL manually to test\n• Disable browser opening with .open_browser(false)"
-            )
+            ).with_source(e)
         })?;
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
-        return Err(HeisenbergError::config(
+        return Err(HeisenbergError::browser(
             "Browser opening not supported on this platform",
             "• This platform is not supported for automatic browser opening\n• Disable browser opening with .open_browser(false)\n• Open the URL manually after starting the server"
         ));
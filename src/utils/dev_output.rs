@@ -0,0 +1,97 @@
+//! Recognizing what dev servers print to their terminal
+
+/// Phrases frontend dev servers print once they accept requests
+///
+/// Matched case-insensitively against lines with color codes removed.
+const READY_MARKERS: &[&str] = &[
+    // Vite: "VITE v5.0.0  ready in 312 ms" and "➜  Local:   http://localhost:5173/"
+    " ready in ",
+    "local:",
+    // Next.js: "ready - started server on 0.0.0.0:3000" and "✓ Ready in 2.1s"
+    "ready - started server on",
+    "✓ ready",
+    // webpack-dev-server and Create React App
+    "compiled successfully",
+    "project is running at",
+    // SvelteKit, Astro, Angular and generic servers
+    "server running at",
+    "listening on",
+];
+
+/// Remove ANSI escape sequences, which dev servers use for colors
+pub fn strip_ansi(line: &str) -> String {
+    let mut clean = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            clean.push(c);
+            continue;
+        }
+        // CSI sequences ("\x1b[1;32m") end at their first letter
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    clean
+}
+
+/// Whether a line of dev server output says the server is ready
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::dev_output::is_ready_line;
+///
+/// assert!(is_ready_line("  \u{1b}[32m➜\u{1b}[39m  Local:   http://localhost:5173/"));
+/// assert!(is_ready_line("ready - started server on 0.0.0.0:3000, url: http://localhost:3000"));
+/// assert!(!is_ready_line("> vite"));
+/// ```
+pub fn is_ready_line(line: &str) -> bool {
+    let line = strip_ansi(line).to_lowercase();
+    READY_MARKERS.iter().any(|marker| line.contains(marker))
+}
+
+/// The local URL a ready line announces, like Vite's `Local:` line
+///
+/// Only loopback and wildcard hosts count, so Vite's `Network:` line and
+/// URLs in ordinary log output are ignored. The path is dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::dev_output::announced_url;
+///
+/// assert_eq!(
+///     announced_url("  ➜  Local:   http://localhost:5174/"),
+///     Some("http://localhost:5174".to_string())
+/// );
+/// assert_eq!(announced_url("  ➜  Network: http://192.168.1.20:5174/"), None);
+/// ```
+pub fn announced_url(line: &str) -> Option<String> {
+    if !is_ready_line(line) {
+        return None;
+    }
+    let line = strip_ansi(line);
+    let start = line.find("http://").or_else(|| line.find("https://"))?;
+    let candidate = line[start..]
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .next()?;
+    let url = reqwest::Url::parse(candidate).ok()?;
+    let host = url.host_str()?;
+    if !matches!(
+        host,
+        "localhost" | "127.0.0.1" | "[::1]" | "0.0.0.0" | "[::]"
+    ) {
+        return None;
+    }
+    Some(format!(
+        "{}://{}:{}",
+        url.scheme(),
+        host,
+        url.port_or_known_default()?
+    ))
+}
@@ -0,0 +1,455 @@
+//! Dev server settings read from bundler config files
+//!
+//! Config files are JavaScript or TypeScript, which Heisenberg doesn't run.
+//! Instead a light scanner picks out settings written as plain literals, like
+//! `server: { port: 3000 }`, and skips anything computed, such as
+//! `port: Number(process.env.PORT)`.
+
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Names Vite looks for its config file under, in its order
+pub const VITE_CONFIG_FILES: &[&str] = &[
+    "vite.config.js",
+    "vite.config.mjs",
+    "vite.config.ts",
+    "vite.config.cjs",
+    "vite.config.mts",
+    "vite.config.cts",
+];
+
+/// Names webpack CLI looks for its config file under
+pub const WEBPACK_CONFIG_FILES: &[&str] = &[
+    "webpack.config.js",
+    "webpack.config.mjs",
+    "webpack.config.cjs",
+    "webpack.config.ts",
+];
+
+/// Dev server settings found in a bundler config file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DevServerConfig {
+    /// Port the dev server listens on
+    pub port: Option<u16>,
+    /// Host the dev server listens on; `0.0.0.0` for all addresses, as with
+    /// Vite's `host: true`
+    pub host: Option<String>,
+    /// Public path the app is served under, like `/app/`, or the URL it is
+    /// served from
+    pub base: Option<String>,
+}
+
+/// Read the Vite config file in `dir`, if there is one
+pub fn read_vite_config(dir: &Path) -> Option<DevServerConfig> {
+    VITE_CONFIG_FILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+        .map(|source| parse_vite_config(&source))
+}
+
+/// Pick `server.port`, `server.host` and `base` out of a Vite config
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::dev_server_config::parse_vite_config;
+///
+/// let config = parse_vite_config(
+///     "export default defineConfig({ base: '/app/', server: { port: 3000, host: true } })",
+/// );
+/// assert_eq!(config.port, Some(3000));
+/// assert_eq!(config.host.as_deref(), Some("0.0.0.0"));
+/// assert_eq!(config.base.as_deref(), Some("/app/"));
+/// ```
+pub fn parse_vite_config(source: &str) -> DevServerConfig {
+    let settings = settings(&tokenize(source));
+    let find = |path: &[&str], key: &str| find(&settings, path, key);
+    DevServerConfig {
+        port: find(&["server"], "port").and_then(Token::port),
+        host: find(&["server"], "host").and_then(|host| match host {
+            Token::Str(host) => Some(host.clone()),
+            Token::Word("true") => Some("0.0.0.0".to_string()),
+            _ => None,
+        }),
+        base: find(&[], "base").and_then(Token::text),
+    }
+}
+
+/// Read the webpack config file in `dir`, if there is one
+pub fn read_webpack_config(dir: &Path) -> Option<DevServerConfig> {
+    WEBPACK_CONFIG_FILES
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+        .map(|source| parse_webpack_config(&source))
+}
+
+/// Pick `devServer.port`, `devServer.host` and the public path out of a
+/// webpack config
+///
+/// The public path is `output.publicPath`, or else
+/// `devServer.devMiddleware.publicPath`; `"auto"` is skipped. The special
+/// `local-ip` hosts, which listen on the LAN address, are skipped too.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::dev_server_config::parse_webpack_config;
+///
+/// let config = parse_webpack_config(
+///     "module.exports = { output: { publicPath: '/app/' }, devServer: { port: 8081 } }",
+/// );
+/// assert_eq!(config.port, Some(8081));
+/// assert_eq!(config.base.as_deref(), Some("/app/"));
+/// ```
+pub fn parse_webpack_config(source: &str) -> DevServerConfig {
+    let settings = settings(&tokenize(source));
+    let find = |path: &[&str], key: &str| find(&settings, path, key);
+    DevServerConfig {
+        port: find(&["devServer"], "port").and_then(|port| match port {
+            Token::Str(port) => port.parse().ok(),
+            port => port.port(),
+        }),
+        host: find(&["devServer"], "host")
+            .and_then(Token::text)
+            .filter(|host| !host.starts_with("local-ip")),
+        base: find(&["output"], "publicPath")
+            .or_else(|| find(&["devServer", "devMiddleware"], "publicPath"))
+            .and_then(Token::text)
+            .filter(|base| base != "auto"),
+    }
+}
+
+/// Read the settings of a Create React App project in `dir`
+///
+/// `react-scripts start` takes its port and host from `PORT` and `HOST`, and
+/// serves the app under `PUBLIC_URL` or the path of `homepage` in
+/// `package.json`. The variables are read from the environment, then from
+/// the project's env files.
+pub fn read_create_react_app(dir: &Path) -> DevServerConfig {
+    let env_files = crate::utils::load_env_files(dir);
+    let var = |name: &str| {
+        std::env::var(name).ok().or_else(|| {
+            env_files
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        })
+    };
+    let homepage = || {
+        let package_json = std::fs::read_to_string(dir.join("package.json")).ok()?;
+        let package_json: Value = serde_json::from_str(&package_json).ok()?;
+        package_json.get("homepage")?.as_str().map(String::from)
+    };
+    let base = var("PUBLIC_URL").or_else(homepage).and_then(|url| {
+        if url.starts_with('/') {
+            Some(url)
+        } else {
+            reqwest::Url::parse(&url)
+                .ok()
+                .map(|url| url.path().to_string())
+        }
+    });
+    DevServerConfig {
+        port: var("PORT").and_then(|port| port.trim().parse().ok()),
+        host: var("HOST").filter(|host| !host.trim().is_empty()),
+        base,
+    }
+}
+
+/// A project of an Angular workspace, found from its build output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AngularProject {
+    /// Project name, as passed to `ng serve`
+    pub name: String,
+    /// Directory holding `angular.json`
+    pub workspace_dir: PathBuf,
+    /// Whether the workspace has other projects, so `ng serve` needs the
+    /// name
+    pub multi_project: bool,
+    /// Settings of the project's `serve` target
+    pub dev_server: DevServerConfig,
+}
+
+/// Find the Angular project that builds into `build_dir`
+///
+/// The `angular.json` is looked for in `build_dir` and its parents, and the
+/// project is the one whose `outputPath` is `build_dir`, or its `browser`
+/// directory as written by the application builder. As the output doesn't
+/// exist before the first build, paths are compared as written, relative to
+/// the current directory. The `serve` target's port and host, defaulting to
+/// 4200, come from its options and default configuration, and the base from
+/// `servePath` or `baseHref`.
+pub fn find_angular_project(build_dir: &Path) -> Option<AngularProject> {
+    let build_dir = clean(&std::env::current_dir().ok()?.join(build_dir));
+    build_dir.ancestors().find_map(|workspace_dir| {
+        let workspace = std::fs::read_to_string(workspace_dir.join("angular.json")).ok()?;
+        let workspace: Value = serde_json::from_str(&workspace).ok()?;
+        let projects = workspace.get("projects")?.as_object()?;
+        let (name, project) = projects.iter().find(|(name, project)| {
+            angular_outputs(workspace_dir, name, project).contains(&build_dir)
+        })?;
+        Some(AngularProject {
+            name: name.clone(),
+            workspace_dir: workspace_dir.to_path_buf(),
+            multi_project: projects.len() > 1,
+            dev_server: angular_dev_server(project),
+        })
+    })
+}
+
+/// The directories a project of the workspace in `dir` may build into
+fn angular_outputs(dir: &Path, name: &str, project: &Value) -> Vec<PathBuf> {
+    let output = project.pointer("/architect/build/options/outputPath");
+    let (base, browser) = match output {
+        Some(Value::String(base)) => (base.clone(), "browser".to_string()),
+        Some(Value::Object(output)) => (
+            output
+                .get("base")
+                .and_then(Value::as_str)
+                .map_or_else(|| format!("dist/{}", name), String::from),
+            output
+                .get("browser")
+                .and_then(Value::as_str)
+                .unwrap_or("browser")
+                .to_string(),
+        ),
+        _ => (format!("dist/{}", name), "browser".to_string()),
+    };
+    let base = clean(&dir.join(base));
+    vec![base.join(browser), base]
+}
+
+/// The settings of a project's `serve` target
+fn angular_dev_server(project: &Value) -> DevServerConfig {
+    let serve = project.pointer("/architect/serve");
+    let options = serve.and_then(|serve| serve.get("options"));
+    let configuration = serve.and_then(|serve| {
+        let name = serve.get("defaultConfiguration")?.as_str()?;
+        serve.get("configurations")?.get(name)
+    });
+    // The default configuration overrides the options
+    let option = |key: &str| {
+        configuration
+            .and_then(|configuration| configuration.get(key))
+            .or_else(|| options.and_then(|options| options.get(key)))
+    };
+    let base_href = project
+        .pointer("/architect/build/options/baseHref")
+        .and_then(Value::as_str);
+    DevServerConfig {
+        port: Some(
+            option("port")
+                .and_then(Value::as_u64)
+                .and_then(|port| u16::try_from(port).ok())
+                .unwrap_or(4200),
+        ),
+        host: option("host").and_then(Value::as_str).map(String::from),
+        base: option("servePath")
+            .and_then(Value::as_str)
+            .or(base_href)
+            .map(String::from),
+    }
+}
+
+/// `path` with `.` and `..` resolved as written, without following links
+fn clean(path: &Path) -> PathBuf {
+    let mut clean = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                clean.pop();
+            }
+            component => clean.push(component),
+        }
+    }
+    clean
+}
+
+/// The host to reach a dev server listening on `host` at
+///
+/// Wildcard addresses are reached on `localhost`, and IPv6 addresses are
+/// bracketed for use in a URL.
+pub fn connect_host(host: &str) -> String {
+    match host {
+        "" | "0.0.0.0" | "::" | "[::]" => "localhost".to_string(),
+        host if host.contains(':') && !host.starts_with('[') => format!("[{}]", host),
+        host => host.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    /// An identifier, keyword or number, including dotted ones like
+    /// `process.env.PORT`
+    Word(&'a str),
+    /// A string literal, unescaped
+    Str(String),
+    /// Any other character, including those opening template literals and
+    /// regular expressions, whose contents are skipped
+    Punct(char),
+}
+
+impl Token<'_> {
+    fn port(&self) -> Option<u16> {
+        match self {
+            Token::Word(word) => word.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<String> {
+        match self {
+            Token::Str(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// A `key: value` property whose value is a single literal
+#[derive(Debug)]
+struct Setting<'a> {
+    /// Keys of the objects, and arrays, it is nested in
+    path: Vec<String>,
+    key: String,
+    value: Token<'a>,
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '$' | '.');
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '/' if chars.peek().map(|&(_, c)| c) == Some('/') => {
+                chars.by_ref().find(|&(_, c)| c == '\n');
+            }
+            '/' if chars.peek().map(|&(_, c)| c) == Some('*') => {
+                chars.next();
+                let mut star = false;
+                for (_, c) in chars.by_ref() {
+                    if star && c == '/' {
+                        break;
+                    }
+                    star = c == '*';
+                }
+            }
+            // A regular expression, unless it follows an operand
+            '/' if !matches!(
+                tokens.last(),
+                Some(Token::Word(_) | Token::Str(_) | Token::Punct(')' | ']' | '}'))
+            ) =>
+            {
+                let mut class = false;
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '[' => class = true,
+                        ']' => class = false,
+                        '/' if !class => break,
+                        '\n' => break,
+                        _ => {}
+                    }
+                }
+                tokens.push(Token::Punct('/'));
+            }
+            '\'' | '"' | '`' => {
+                let mut text = String::new();
+                let mut interpolated = false;
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                text.push(escaped);
+                            }
+                        }
+                        '$' if c == '`' && chars.peek().map(|&(_, c)| c) == Some('{') => {
+                            interpolated = true;
+                        }
+                        next if next == c => break,
+                        next => text.push(next),
+                    }
+                }
+                tokens.push(if interpolated {
+                    Token::Punct('`')
+                } else {
+                    Token::Str(text)
+                });
+            }
+            c if is_word(c) => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, next)) = chars.peek() {
+                    if !is_word(next) {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token::Word(&source[start..end]));
+            }
+            c => tokens.push(Token::Punct(c)),
+        }
+    }
+    tokens
+}
+
+fn settings<'a>(tokens: &[Token<'a>]) -> Vec<Setting<'a>> {
+    // The key each open bracket was the value of, if any
+    let mut stack: Vec<Option<String>> = Vec::new();
+    let mut settings = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('{' | '[' | '(') => {
+                let key = match i.checked_sub(1).map(|colon| &tokens[colon]) {
+                    Some(Token::Punct(':')) if i >= 2 => property_key(tokens, i - 2),
+                    _ => None,
+                };
+                stack.push(key);
+            }
+            Token::Punct('}' | ']' | ')') => {
+                stack.pop();
+            }
+            Token::Punct(':') if i >= 1 => {
+                let (Some(key), Some(value), Some(Token::Punct(',' | '}'))) = (
+                    property_key(tokens, i - 1),
+                    tokens.get(i + 1),
+                    tokens.get(i + 2),
+                ) else {
+                    continue;
+                };
+                if matches!(value, Token::Word(_) | Token::Str(_)) {
+                    settings.push(Setting {
+                        path: stack.iter().flatten().cloned().collect(),
+                        key,
+                        value: value.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    settings
+}
+
+/// The value of the first setting `key` in the object at `path`
+fn find<'s, 'a>(settings: &'s [Setting<'a>], path: &[&str], key: &str) -> Option<&'s Token<'a>> {
+    settings
+        .iter()
+        .find(|setting| setting.path == path && setting.key == key)
+        .map(|setting| &setting.value)
+}
+
+/// The name of the property whose key is at `index`, if the key starts one rather than
+/// being part of a ternary or type annotation
+fn property_key(tokens: &[Token<'_>], index: usize) -> Option<String> {
+    let starts_property = index == 0 || matches!(tokens[index - 1], Token::Punct('{' | ','));
+    match &tokens[index] {
+        _ if !starts_property => None,
+        Token::Word(word) if !word.contains('.') => Some(word.to_string()),
+        Token::Str(text) => Some(text.clone()),
+        _ => None,
+    }
+}
@@ -0,0 +1,118 @@
+//! Choosing a content encoding from a request's `Accept-Encoding`
+
+use hyper::header::{self, HeaderMap, HeaderValue};
+
+/// A content coding static files can be precompressed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// `br`, served from `<file>.br`
+    Brotli,
+    /// `gzip`, served from `<file>.gz`
+    Gzip,
+}
+
+impl Encoding {
+    /// Every encoding, most preferred first
+    pub const ALL: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+    /// The `Content-Encoding` token
+    pub fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    /// Extension of precompressed files, `br` for `app.js.br`
+    pub fn extension(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gz",
+        }
+    }
+}
+
+/// The encodings a client accepts, most preferred first
+///
+/// Follows the quality values in `Accept-Encoding`, with brotli ahead of
+/// gzip when they are equal. `*` covers encodings not listed by name and
+/// `q=0` rules one out. Without the header only uncompressed responses are
+/// acceptable.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::encoding::{accepted, Encoding};
+///
+/// assert_eq!(accepted(Some("gzip, deflate, br")), [Encoding::Brotli, Encoding::Gzip]);
+/// assert_eq!(accepted(Some("gzip, br;q=0.5")), [Encoding::Gzip, Encoding::Brotli]);
+/// assert_eq!(accepted(Some("gzip")), [Encoding::Gzip]);
+/// assert!(accepted(None).is_empty());
+/// ```
+pub fn accepted(accept_encoding: Option<&str>) -> Vec<Encoding> {
+    let Some(accept_encoding) = accept_encoding else {
+        return Vec::new();
+    };
+
+    let mut named = Vec::new();
+    let mut wildcard = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let token = parts.next().unwrap_or("").trim();
+        let quality = parts
+            .find_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                if !name.trim().eq_ignore_ascii_case("q") {
+                    return None;
+                }
+                value.trim().parse::<f32>().ok()
+            })
+            .unwrap_or(1.0);
+        if token == "*" {
+            wildcard = Some(quality);
+        } else {
+            named.push((token.to_ascii_lowercase(), quality));
+        }
+    }
+
+    let mut encodings: Vec<(Encoding, f32)> = Encoding::ALL
+        .into_iter()
+        .filter_map(|encoding| {
+            // `x-gzip` is an old alias browsers no longer send, but proxies do
+            let quality = named
+                .iter()
+                .find(|(token, _)| {
+                    token == encoding.token() || (encoding == Encoding::Gzip && token == "x-gzip")
+                })
+                .map(|(_, quality)| *quality)
+                .or(wildcard)?;
+            (quality > 0.0).then_some((encoding, quality))
+        })
+        .collect();
+    // Stable, so equal qualities keep the order of `Encoding::ALL`
+    encodings.sort_by(|a, b| b.1.total_cmp(&a.1));
+    encodings
+        .into_iter()
+        .map(|(encoding, _)| encoding)
+        .collect()
+}
+
+/// Mark a response as depending on `Accept-Encoding`
+///
+/// Needed on every response for a file that has compressed variants,
+/// including the uncompressed one, so shared caches such as CDNs don't hand
+/// a brotli body to a client that can't decode it. Existing `Vary` values
+/// are kept.
+pub fn vary_on_accept_encoding(headers: &mut HeaderMap) {
+    let listed = headers.get_all(header::VARY).iter().any(|value| {
+        value.to_str().is_ok_and(|value| {
+            value.split(',').any(|name| {
+                let name = name.trim();
+                name == "*" || name.eq_ignore_ascii_case("accept-encoding")
+            })
+        })
+    });
+    if !listed {
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+}
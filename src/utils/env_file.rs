@@ -0,0 +1,101 @@
+//! `.env` file loading for dev server processes
+
+use std::path::Path;
+
+/// Env files loaded for dev servers, lowest precedence first.
+///
+/// This mirrors Vite's lookup order so a dev server started by Heisenberg sees
+/// the same variables as one started from a terminal.
+pub const ENV_FILES: &[&str] = &[
+    ".env",
+    ".env.local",
+    ".env.development",
+    ".env.development.local",
+];
+
+/// Load variables from the env files in `dir`.
+///
+/// Later files override earlier ones. Variables already set in the current
+/// process environment are left out so they keep precedence, matching the
+/// behavior of Vite and Next.js. Missing or unreadable files are skipped.
+pub fn load_env_files(dir: &Path) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+
+    for file in ENV_FILES {
+        let Ok(content) = std::fs::read_to_string(dir.join(file)) else {
+            continue;
+        };
+
+        for (key, value) in parse_env_file(&content) {
+            if std::env::var_os(&key).is_some() {
+                continue;
+            }
+            match vars.iter_mut().find(|(existing, _)| *existing == key) {
+                Some(entry) => entry.1 = value,
+                None => vars.push((key, value)),
+            }
+        }
+    }
+
+    vars
+}
+
+/// Parse the contents of a `.env` file into key/value pairs.
+///
+/// Supports `#` comments, an optional `export` prefix, single-quoted literal
+/// values, and double-quoted values with `\n`, `\t`, `\"` and `\\` escapes.
+pub fn parse_env_file(content: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+
+        vars.push((key.to_string(), parse_value(value.trim())));
+    }
+
+    vars
+}
+
+/// Parse a single value, handling quoting and inline comments
+fn parse_value(raw: &str) -> String {
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(other) => value.push(other),
+                    None => value.push('\\'),
+                },
+                _ => value.push(c),
+            }
+        }
+        return value;
+    }
+
+    if let Some(rest) = raw.strip_prefix('\'') {
+        return rest.split('\'').next().unwrap_or_default().to_string();
+    }
+
+    // Unquoted values end at an inline comment
+    match raw.find(" #") {
+        Some(pos) => raw[..pos].trim_end().to_string(),
+        None => raw.to_string(),
+    }
+}
@@ -0,0 +1,88 @@
+//! Comparing a build directory against the frontend sources it comes from
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Directories and files under the working directory that feed the build
+const SOURCE_ENTRIES: &[&str] = &["src", "public", "index.html", "package.json"];
+
+/// Directories never searched for sources
+const SKIPPED_DIRS: &[&str] = &["node_modules", ".git"];
+
+/// How a build directory relates to the frontend sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildState {
+    /// The build directory doesn't exist or holds no files
+    Missing,
+    /// A source file changed after the newest built file was written
+    Stale {
+        /// The most recently modified source file
+        newest_source: PathBuf,
+    },
+    /// The build is at least as new as the sources, or there are no sources
+    Fresh,
+}
+
+/// Compare `embed_dir` against the sources in `working_dir`
+///
+/// Sources are `src/`, `public/`, `index.html`, `package.json` and
+/// `*.config.*` files such as `vite.config.ts`.
+pub fn build_state(embed_dir: &Path, working_dir: &Path) -> BuildState {
+    let Some((built, _)) = newest_file(embed_dir) else {
+        return BuildState::Missing;
+    };
+
+    let mut sources: Vec<PathBuf> = SOURCE_ENTRIES
+        .iter()
+        .map(|entry| working_dir.join(entry))
+        .collect();
+    if let Ok(entries) = std::fs::read_dir(working_dir) {
+        sources.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && is_config_file(path)),
+        );
+    }
+
+    let newest_source = sources
+        .iter()
+        // The build directory may sit inside a source directory, like public/
+        .filter(|source| !embed_dir.starts_with(source))
+        .filter_map(|source| newest_file(source))
+        .max_by_key(|(modified, _)| *modified);
+
+    match newest_source {
+        Some((modified, path)) if modified > built => BuildState::Stale {
+            newest_source: path,
+        },
+        _ => BuildState::Fresh,
+    }
+}
+
+fn is_config_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(".config."))
+}
+
+/// The modification time and path of the newest file at or below `path`
+fn newest_file(path: &Path) -> Option<(SystemTime, PathBuf)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.is_file() {
+        return Some((metadata.modified().ok()?, path.to_path_buf()));
+    }
+
+    let skipped = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| SKIPPED_DIRS.contains(&name));
+    if !metadata.is_dir() || skipped {
+        return None;
+    }
+    std::fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| newest_file(&entry.path()))
+        .max_by_key(|(modified, _)| *modified)
+}
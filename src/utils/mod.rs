@@ -1,8 +1,22 @@
 //! Utility functions for Heisenberg
 
+pub mod banner;
 pub mod browser;
+pub mod dev_output;
+pub mod dev_server_config;
+pub mod encoding;
+pub mod env_file;
+pub mod freshness;
+pub mod network;
 pub mod package_json;
+pub mod package_manager;
 pub mod paths;
+#[cfg(feature = "qr")]
+pub mod qr;
+pub mod request_id;
+#[cfg(feature = "sri")]
+pub mod sri;
 
-pub use browser::open_browser;
+pub use browser::{open_browser, open_browser_with, BrowserPolicy};
+pub use env_file::load_env_files;
 pub use package_json::{infer_from_build_dir, InferredConfig, PackageJson};
@@ -0,0 +1,153 @@
+//! Network address helpers for startup output
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+/// Best-effort detection of this machine's LAN address.
+///
+/// Connecting a UDP socket doesn't send any packets, but it makes the OS pick
+/// the interface it would route external traffic through, which is the
+/// address other devices on the network can reach.
+pub fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+
+    if ip.is_loopback() || ip.is_unspecified() {
+        None
+    } else {
+        Some(ip)
+    }
+}
+
+/// URL other devices on the network can use to reach a server bound to `addr`.
+///
+/// Returns `None` when the server is bound to loopback only (and so isn't
+/// reachable from the network) or when no LAN address can be found.
+pub fn network_url(addr: SocketAddr) -> Option<String> {
+    let ip = addr.ip();
+    if ip.is_loopback() {
+        return None;
+    }
+
+    let ip = if ip.is_unspecified() { lan_ip()? } else { ip };
+    Some(format!("http://{}", SocketAddr::new(ip, addr.port())))
+}
+
+/// IPv4 addresses of all non-loopback network interfaces.
+///
+/// Link-local addresses are skipped since they're rarely what a phone or
+/// another laptop on the same Wi-Fi should connect to.
+pub fn lan_ips() -> Vec<IpAddr> {
+    let mut ips: Vec<IpAddr> = if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|iface| !iface.is_loopback())
+        .filter_map(|iface| match iface.ip() {
+            IpAddr::V4(ip) if !ip.is_link_local() => Some(IpAddr::V4(ip)),
+            _ => None,
+        })
+        .collect();
+
+    ips.sort();
+    ips.dedup();
+    ips
+}
+
+/// URL for reaching a server bound to `addr` from this machine
+pub fn local_url(addr: SocketAddr) -> String {
+    if addr.ip().is_unspecified() || addr.ip() == IpAddr::V4(Ipv4Addr::LOCALHOST) {
+        format!("http://localhost:{}", addr.port())
+    } else {
+        format!("http://{}", addr)
+    }
+}
+
+/// All URLs other devices on the network can use to reach a server bound to `addr`
+pub fn network_urls(addr: SocketAddr) -> Vec<String> {
+    let ip = addr.ip();
+    if ip.is_loopback() {
+        Vec::new()
+    } else if ip.is_unspecified() {
+        lan_ips()
+            .into_iter()
+            .map(|ip| format!("http://{}", SocketAddr::new(ip, addr.port())))
+            .collect()
+    } else {
+        vec![format!("http://{}", addr)]
+    }
+}
+
+/// URL GitHub Codespaces or Gitpod forward a port of this machine to
+///
+/// Browsers outside the codespace or workspace can't reach its `localhost`,
+/// so each port is published at a URL of its own, like
+/// `https://<codespace>-5173.app.github.dev` or
+/// `https://5173-<workspace>.gitpod.io`. `None` elsewhere.
+pub fn forwarded_url(port: u16) -> Option<String> {
+    let var = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    if let Some(codespace) = var("CODESPACE_NAME") {
+        let domain = var("GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN")
+            .unwrap_or_else(|| "app.github.dev".to_string());
+        return Some(format!("https://{}-{}.{}", codespace, port, domain));
+    }
+    let workspace = var("GITPOD_WORKSPACE_URL")?;
+    let host = workspace
+        .trim_end_matches('/')
+        .strip_prefix("https://")?
+        .to_string();
+    Some(format!("https://{}-{}", port, host))
+}
+
+/// `url` as a browser on the developer's machine can open it
+///
+/// A `localhost` URL becomes its [`forwarded_url`] in Codespaces and Gitpod,
+/// keeping the path and query. Other URLs, and every URL elsewhere, are
+/// returned unchanged.
+pub fn browser_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    let local = matches!(
+        parsed.host_str(),
+        Some("localhost" | "127.0.0.1" | "[::1]" | "0.0.0.0")
+    );
+    let forwarded = parsed
+        .port_or_known_default()
+        .filter(|_| local)
+        .and_then(forwarded_url);
+    let Some(mut forwarded) = forwarded else {
+        return url.to_string();
+    };
+    forwarded.push_str(parsed.path());
+    if let Some(query) = parsed.query() {
+        forwarded.push('?');
+        forwarded.push_str(query);
+    }
+    forwarded
+}
+
+/// Print local and network URLs for a server bound to `addr`, in the style
+/// of frontend dev servers
+///
+/// In Codespaces and Gitpod the forwarded URL is printed instead of the
+/// network ones, which browsers outside can't reach.
+pub fn print_server_urls(addr: SocketAddr) {
+    println!("  ➜  Local:   {}", local_url(addr));
+    if let Some(url) = forwarded_url(addr.port()) {
+        println!("  ➜  Forwarded: {}", url);
+        return;
+    }
+
+    let network = network_urls(addr);
+    if network.is_empty() {
+        println!("  ➜  Network: use 0.0.0.0 as the bind address to expose");
+    }
+    for url in network {
+        println!("  ➜  Network: {}", url);
+    }
+}
@@ -1,6 +1,11 @@
 //! Package.json parsing and inference utilities
 
 use crate::error::HeisenbergError;
+use crate::utils::dev_server_config::{
+    find_angular_project, read_create_react_app, read_vite_config, read_webpack_config,
+    DevServerConfig,
+};
+use crate::utils::package_manager::PackageManager;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -18,18 +23,60 @@ pub struct PackageJson {
 
 /// Infer development configuration from a build directory path
 pub fn infer_from_build_dir(build_dir: &Path) -> Result<InferredConfig, HeisenbergError> {
-    let working_dir = infer_working_dir(build_dir)?;
+    let angular = find_angular_project(build_dir);
+    let working_dir = match &angular {
+        Some(project) => project.workspace_dir.clone(),
+        None => infer_working_dir(build_dir)?,
+    };
     let package_json_path = find_package_json(&working_dir)?;
-    let package_json = parse_package_json(&package_json_path)?;
-    let dev_command = infer_dev_command(&package_json);
-    let dev_port = infer_dev_port(&package_json);
+    let json = read_package_json(&package_json_path)?;
+    let package_json = parse_package_json(&json);
+    let package_manager = json
+        .get("packageManager")
+        .and_then(Value::as_str)
+        .and_then(PackageManager::parse);
+    let (dev_command, dev_server) = match angular {
+        // `ng serve` alone serves the default project
+        Some(project) if project.multi_project => (
+            ScriptCommand {
+                env: Vec::new(),
+                args: vec![
+                    "npx".to_string(),
+                    "ng".to_string(),
+                    "serve".to_string(),
+                    project.name,
+                ],
+            },
+            project.dev_server,
+        ),
+        Some(project) => (infer_dev_command(&package_json), project.dev_server),
+        None => (
+            infer_dev_command(&package_json),
+            package_json_path
+                .parent()
+                .map(|dir| infer_dev_server(dir, &package_json))
+                .unwrap_or_default(),
+        ),
+    };
+    let dev_port = infer_dev_port(&package_json, &dev_server);
+    let host = dev_server.host.as_deref().map_or_else(
+        || "localhost".to_string(),
+        crate::utils::dev_server_config::connect_host,
+    );
+    // Relative bases like "./" and CDN URLs don't say where the app is served
+    let base = dev_server
+        .base
+        .filter(|base| base.starts_with('/') && !base.trim_end_matches('/').is_empty());
 
     Ok(InferredConfig {
         working_dir,
         package_json_path,
-        dev_command,
+        dev_command: dev_command.args,
+        dev_env: dev_command.env,
         dev_port,
-        dev_url: format!("http://localhost:{}", dev_port),
+        dev_url: format!("http://{}:{}", host, dev_port),
+        base,
+        package_manager,
     })
 }
 
@@ -42,10 +89,17 @@ pub struct InferredConfig {
     pub package_json_path: PathBuf,
     /// Inferred development command
     pub dev_command: Vec<String>,
+    /// Variables the dev script assigns, like `NODE_ENV=development`
+    pub dev_env: Vec<(String, String)>,
     /// Inferred development server port
     pub dev_port: u16,
     /// Inferred development server URL
     pub dev_url: String,
+    /// Public path the app is served under, from the bundler config, like
+    /// `/app/`
+    pub base: Option<String>,
+    /// Package manager pinned by the `packageManager` field
+    pub package_manager: Option<PackageManager>,
 }
 
 /// Infer working directory from build directory path
@@ -55,7 +109,7 @@ fn infer_working_dir(build_dir: &Path) -> Result<PathBuf, HeisenbergError> {
         .map_err(|e| HeisenbergError::config(
             format!("Cannot resolve build directory: {}", e),
             "• Check if the build directory path exists\n• Ensure you have read permissions\n• Use an absolute path or verify the relative path is correct"
-        ))?;
+        ).with_source(e))?;
 
     // Common build directory names to strip
     let build_names = ["dist", "build", "out", "public", "www"];
@@ -94,16 +148,19 @@ fn find_package_json(start_dir: &Path) -> Result<PathBuf, HeisenbergError> {
     ))
 }
 
-/// Parse package.json file
-fn parse_package_json(path: &Path) -> Result<PackageJson, HeisenbergError> {
+/// Read package.json file
+fn read_package_json(path: &Path) -> Result<Value, HeisenbergError> {
     let content = std::fs::read_to_string(path)
         .map_err(|e| HeisenbergError::config(
             format!("Cannot read package.json: {}", e),
             "• Check file permissions on package.json\n• Ensure the file exists and is readable\n• Verify the path is correct"
-        ))?;
+        ).with_source(e))?;
 
-    let json: Value = serde_json::from_str(&content)?;
+    Ok(serde_json::from_str(&content)?)
+}
 
+/// Parse package.json contents
+fn parse_package_json(json: &Value) -> PackageJson {
     let scripts = json
         .get("scripts")
         .and_then(|s| s.as_object())
@@ -120,15 +177,15 @@ fn parse_package_json(path: &Path) -> Result<PackageJson, HeisenbergError> {
         .and_then(|v| v.as_str())
         .map(String::from);
 
-    Ok(PackageJson {
+    PackageJson {
         scripts,
         name,
         version,
-    })
+    }
 }
 
 /// Infer development command from package.json scripts
-fn infer_dev_command(package_json: &PackageJson) -> Vec<String> {
+fn infer_dev_command(package_json: &PackageJson) -> ScriptCommand {
     // Priority order for dev commands
     let command_priorities = ["dev", "start", "serve"];
 
@@ -146,25 +203,138 @@ fn infer_dev_command(package_json: &PackageJson) -> Vec<String> {
     }
 
     // Default fallback
-    vec!["npm".to_string(), "run".to_string(), "dev".to_string()]
+    ScriptCommand {
+        env: Vec::new(),
+        args: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+    }
 }
 
-/// Parse a command string into arguments
-fn parse_command(command: &str) -> Vec<String> {
-    // Simple parsing - split on whitespace
-    // TODO: Handle quoted arguments properly
-    command.split_whitespace().map(String::from).collect()
+/// A package.json script split into its program and arguments
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScriptCommand {
+    /// Variables the script assigns before its program, as in
+    /// `NODE_ENV=development vite` or `cross-env NODE_ENV=development vite`
+    pub env: Vec<(String, String)>,
+    /// The program and its arguments
+    pub args: Vec<String>,
+}
+
+/// Parse a command string into arguments, the way a POSIX shell splits words
+///
+/// Single quotes keep their contents as is, double quotes allow `\"`, `\\`,
+/// `\$` and `` \` `` escapes, and a backslash outside quotes escapes the next
+/// character. Leading `NAME=value` words, also after `cross-env`, are
+/// variables for the program rather than arguments.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::package_json::parse_command;
+///
+/// let command = parse_command(r#"cross-env NODE_ENV=dev vite --config "my config.js""#);
+/// assert_eq!(command.env, [("NODE_ENV".to_string(), "dev".to_string())]);
+/// assert_eq!(command.args, ["vite", "--config", "my config.js"]);
+/// ```
+pub fn parse_command(command: &str) -> ScriptCommand {
+    let mut words = shell_words(command).into_iter().peekable();
+    let mut env = Vec::new();
+    loop {
+        match words.peek() {
+            Some(word) if word == "cross-env" && env.is_empty() => {}
+            Some(word) => match env_assignment(word) {
+                Some(assignment) => env.push(assignment),
+                None => break,
+            },
+            None => break,
+        }
+        words.next();
+    }
+    ScriptCommand {
+        env,
+        args: words.collect(),
+    }
 }
 
-/// Infer development server port from package.json scripts
-fn infer_dev_port(package_json: &PackageJson) -> u16 {
-    // Look for port numbers in dev scripts
+/// Split `command` into words, removing quotes and escapes
+fn shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(escaped @ ('"' | '\\' | '$' | '`')) => word.push(escaped),
+                            Some(other) => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                            None => word.push('\\'),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// The variable a `NAME=value` word assigns
+fn env_assignment(word: &str) -> Option<(String, String)> {
+    let (name, value) = word.split_once('=')?;
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then(|| (name.to_string(), value.to_string()))
+}
+
+/// Read the dev server settings from the bundler config in `dir`
+fn infer_dev_server(dir: &Path, package_json: &PackageJson) -> DevServerConfig {
+    if let Some(config) = read_vite_config(dir).or_else(|| read_webpack_config(dir)) {
+        return config;
+    }
+    let react_scripts = package_json
+        .scripts
+        .values()
+        .any(|script| script.contains("react-scripts"));
+    if react_scripts {
+        return read_create_react_app(dir);
+    }
+    DevServerConfig::default()
+}
+
+/// Infer development server port from package.json scripts and the bundler
+/// config
+fn infer_dev_port(package_json: &PackageJson, dev_server: &DevServerConfig) -> u16 {
+    // Look for port numbers in dev scripts, which override the config file
     for script in package_json.scripts.values() {
         if let Some(port) = extract_port_from_script(script) {
             return port;
         }
     }
 
+    if let Some(port) = dev_server.port {
+        return port;
+    }
+
     // Common defaults based on tools
     for script in package_json.scripts.values() {
         if script.contains("vite") {
@@ -211,8 +381,11 @@ impl InferredConfig {
             working_dir,
             package_json_path: PathBuf::new(), // Empty path indicates no package.json found
             dev_command: vec!["npm".to_string(), "run".to_string(), "dev".to_string()],
+            dev_env: Vec::new(),
             dev_port: 5173, // Vite default
             dev_url: "http://localhost:5173".to_string(),
+            base: None,
+            package_manager: None,
         }
     }
 }
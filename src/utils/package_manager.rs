@@ -0,0 +1,95 @@
+//! The package manager a project pins in `package.json`
+
+use crate::utils::paths::find_program;
+use std::path::Path;
+
+/// Package managers a project can pin, as `"packageManager": "pnpm@9.1.0"`
+const PACKAGE_MANAGERS: &[&str] = &["npm", "pnpm", "yarn", "bun"];
+
+/// Those of [`PACKAGE_MANAGERS`] corepack can provide
+const COREPACK_MANAGERS: &[&str] = &["npm", "pnpm", "yarn"];
+
+/// A package manager pinned by a project's `packageManager` field
+///
+/// Commands that run a package script with another manager, like `npm run
+/// dev` in a pnpm project, switch to the pinned one. Commands run by npm,
+/// pnpm or yarn then go through corepack when it is installed, so they use
+/// the pinned version rather than whichever is on `PATH`; without corepack
+/// the manager on `PATH` runs them.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::package_manager::PackageManager;
+///
+/// let pnpm = PackageManager::parse("pnpm@9.1.0+sha512.abc").unwrap();
+/// assert_eq!(pnpm.name, "pnpm");
+/// assert_eq!(pnpm.version.as_deref(), Some("9.1.0"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageManager {
+    /// `npm`, `pnpm`, `yarn` or `bun`
+    pub name: String,
+    /// The pinned version, without its hash
+    pub version: Option<String>,
+}
+
+impl PackageManager {
+    /// Parse a `packageManager` field, like `pnpm@9.1.0`
+    ///
+    /// Returns `None` for managers Heisenberg doesn't know.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let (name, version) = match spec.trim().split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec.trim(), None),
+        };
+        if !PACKAGE_MANAGERS.contains(&name) {
+            return None;
+        }
+        let version = version
+            .map(|version| version.split('+').next().unwrap_or_default())
+            .filter(|version| !version.is_empty())
+            .map(String::from);
+        Some(Self {
+            name: name.to_string(),
+            version,
+        })
+    }
+
+    /// `command` as run with this package manager
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use heisenberg::utils::package_manager::PackageManager;
+    ///
+    /// let bun = PackageManager::parse("bun@1.1.0").unwrap();
+    /// assert_eq!(bun.command(&["npm", "run", "dev"]), ["bun", "run", "dev"]);
+    /// assert_eq!(bun.command(&["vite", "--port", "3000"]), ["vite", "--port", "3000"]);
+    /// ```
+    pub fn command<S: AsRef<str>>(&self, command: &[S]) -> Vec<String> {
+        let mut command: Vec<String> = command.iter().map(|arg| arg.as_ref().to_string()).collect();
+        let Some(program) = command.first().map(|program| program_name(program)) else {
+            return command;
+        };
+        let runs_script = command.get(1).map(String::as_str) == Some("run");
+        if PACKAGE_MANAGERS.contains(&program.as_str()) && program != self.name && runs_script {
+            command[0] = self.name.clone();
+        } else if program != self.name {
+            return command;
+        }
+        if COREPACK_MANAGERS.contains(&self.name.as_str()) && find_program("corepack").is_some() {
+            command.insert(0, "corepack".to_string());
+        }
+        command
+    }
+}
+
+/// The name of `program` without its directory and extension, like `npm` for
+/// `C:\Program Files\nodejs\npm.cmd`
+fn program_name(program: &str) -> String {
+    let name = Path::new(program)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase());
+    name.unwrap_or_default()
+}
@@ -1,8 +1,75 @@
 //! Path manipulation utilities
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Normalize a path for cross-platform compatibility
 pub fn normalize_path(path: &Path) -> String {
     path.to_string_lossy().replace('\\', "/")
 }
+
+/// Find a program the way a shell would
+///
+/// A name with a path separator, like `./bin/dev`, is returned as is;
+/// others are looked up in the directories of `PATH`, trying the
+/// extensions in `PATHEXT` on Windows.
+pub fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') || program.contains(std::path::MAIN_SEPARATOR) {
+        return Some(PathBuf::from(program));
+    }
+    let extensions: Vec<String> = if cfg!(windows) {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(str::to_string)
+            .chain([String::new()])
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    std::env::split_paths(&std::env::var_os("PATH")?).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|extension| dir.join(format!("{}{}", program, extension)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Whether a path matches a glob like `/assets/*.js` or `*.webmanifest`
+///
+/// `*` matches within one path segment, `**` across segments and `?` any
+/// single character other than `/`. A glob without a `/` is matched against
+/// the last segment only, so `*.webmanifest` matches at any depth.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::paths::glob_matches;
+///
+/// assert!(glob_matches("/assets/*.js", "/assets/index-4f2a.js"));
+/// assert!(!glob_matches("/assets/*.js", "/assets/chunks/vendor.js"));
+/// assert!(glob_matches("/assets/**.js", "/assets/chunks/vendor.js"));
+/// assert!(glob_matches("*.webmanifest", "/icons/site.webmanifest"));
+/// ```
+pub fn glob_matches(glob: &str, path: &str) -> bool {
+    let path = if glob.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    matches_from(glob.as_bytes(), path.as_bytes())
+}
+
+fn matches_from(glob: &[u8], path: &[u8]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=path.len()).any(|skip| matches_from(rest, &path[skip..])),
+        [b'*', rest @ ..] => {
+            let segment = path.iter().position(|&c| c == b'/').unwrap_or(path.len());
+            (0..=segment).any(|skip| matches_from(rest, &path[skip..]))
+        }
+        [b'?', rest @ ..] => {
+            matches!(path.first(), Some(&c) if c != b'/') && matches_from(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && matches_from(rest, &path[1..]),
+    }
+}
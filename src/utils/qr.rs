@@ -0,0 +1,46 @@
+//! Terminal QR codes for opening the app on mobile devices
+
+use crate::utils::network::{forwarded_url, network_url};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use std::net::SocketAddr;
+#[cfg(feature = "logging")]
+use tracing::warn;
+
+/// Render `data` as a QR code using Unicode half blocks.
+///
+/// Returns `None` if the data is too long to encode.
+pub fn render_qr(data: &str) -> Option<String> {
+    let code = QrCode::new(data.as_bytes()).ok()?;
+    Some(code.render::<unicode::Dense1x2>().quiet_zone(true).build())
+}
+
+/// Print the LAN URL and its QR code for a server bound to `addr`
+///
+/// In Codespaces and Gitpod, the code is for the forwarded URL instead.
+pub fn print_network_qr(addr: Option<SocketAddr>) {
+    let Some(addr) = addr else {
+        #[cfg(feature = "logging")]
+        warn!("QR code requested but no server address configured");
+        #[cfg(not(feature = "logging"))]
+        eprintln!(
+            "Warning: QR code requested but no server address configured; use .server_addr()"
+        );
+        return;
+    };
+
+    let Some(url) = forwarded_url(addr.port()).or_else(|| network_url(addr)) else {
+        #[cfg(feature = "logging")]
+        warn!(server_addr = %addr, "Server is not reachable from the network; bind to 0.0.0.0 for a QR code");
+        #[cfg(not(feature = "logging"))]
+        eprintln!(
+            "Warning: Server at {} is not reachable from the network; bind to 0.0.0.0 for a QR code",
+            addr
+        );
+        return;
+    };
+
+    if let Some(qr) = render_qr(&url) {
+        println!("\n  Scan to open {} on your phone:\n\n{}", url, qr);
+    }
+}
@@ -0,0 +1,63 @@
+//! Request IDs for correlating frontend, proxy and backend logs
+
+use hyper::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming request ID that is kept rather than replaced
+pub const MAX_REQUEST_ID_LEN: usize = 200;
+
+/// A new random request ID: 32 lowercase hex digits
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::request_id::generate;
+///
+/// let id = generate();
+/// assert_eq!(id.len(), 32);
+/// assert_ne!(id, generate());
+/// ```
+pub fn generate() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    // Each `RandomState` is seeded randomly, so hashing a counter gives
+    // unpredictable IDs without a random number dependency
+    let half = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(count);
+        hasher.write_u64(salt);
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", half(0), half(1))
+}
+
+/// The request ID a client or upstream proxy sent, if it is usable
+///
+/// IDs that are empty, longer than [`MAX_REQUEST_ID_LEN`] or contain
+/// anything other than visible ASCII are ignored.
+pub fn incoming(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+}
+
+/// Keep the incoming request ID or set a new one, returning it
+pub fn ensure(headers: &mut HeaderMap) -> HeaderValue {
+    let id = match incoming(headers) {
+        Some(id) => HeaderValue::from_str(id),
+        None => HeaderValue::from_str(&generate()),
+    }
+    .expect("request IDs are visible ASCII");
+    headers.insert(REQUEST_ID_HEADER, id.clone());
+    id
+}
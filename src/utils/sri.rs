@@ -0,0 +1,165 @@
+//! Subresource Integrity hashes for scripts and stylesheets in HTML
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha384};
+use std::collections::HashMap;
+
+/// The `integrity` attribute value for a file's contents
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::sri::integrity;
+///
+/// assert!(integrity(b"console.log('hi')").starts_with("sha384-"));
+/// ```
+pub fn integrity(contents: &[u8]) -> String {
+    format!("sha384-{}", STANDARD.encode(Sha384::digest(contents)))
+}
+
+/// URLs of the local scripts and stylesheets an HTML document loads
+///
+/// Covers `<script src>` and `<link href>` with a `rel` of `stylesheet`,
+/// `modulepreload` or `preload`. Tags that already carry an `integrity`
+/// attribute and absolute URLs such as CDN links are left out.
+///
+/// # Examples
+///
+/// ```rust
+/// use heisenberg::utils::sri::asset_references;
+///
+/// let html = r#"<script type="module" src="/assets/index.js"></script>
+/// <link rel="stylesheet" href="/assets/index.css">
+/// <script src="https://cdn.example.com/lib.js"></script>"#;
+/// assert_eq!(asset_references(html), ["/assets/index.js", "/assets/index.css"]);
+/// ```
+pub fn asset_references(html: &str) -> Vec<String> {
+    hashable_tags(html).into_iter().map(|tag| tag.url).collect()
+}
+
+/// Add `integrity` attributes to the tags [`asset_references`] finds
+///
+/// `hashes` maps asset URLs to their [`integrity`]. Tags whose URL isn't in
+/// the map are left unchanged, so a missing file doesn't block the page.
+pub fn add_integrity(html: &str, hashes: &HashMap<String, String>) -> String {
+    let mut rewritten = String::with_capacity(html.len());
+    let mut copied = 0;
+    for tag in hashable_tags(html) {
+        let Some(hash) = hashes.get(&tag.url) else {
+            continue;
+        };
+        rewritten.push_str(&html[copied..tag.insert_at]);
+        rewritten.push_str(&format!(" integrity=\"{}\"", hash));
+        copied = tag.insert_at;
+    }
+    rewritten.push_str(&html[copied..]);
+    rewritten
+}
+
+/// A tag that can get an `integrity` attribute
+struct HashableTag {
+    /// Byte offset the attribute goes at, after the last existing attribute
+    insert_at: usize,
+    url: String,
+}
+
+fn hashable_tags(html: &str) -> Vec<HashableTag> {
+    let mut tags = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = html[pos..].find('<') {
+        let start = pos + offset;
+        let Some(length) = html[start..].find('>') else {
+            break;
+        };
+        let end = start + length;
+        pos = end + 1;
+
+        let tag = &html[start + 1..end];
+        let name = tag
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+        let attributes = attributes(&tag[name.len()..]);
+        let attribute = |wanted: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let url = if name.eq_ignore_ascii_case("script") {
+            attribute("src")
+        } else if name.eq_ignore_ascii_case("link") && loads_subresource(attribute("rel")) {
+            attribute("href")
+        } else {
+            None
+        };
+        let Some(url) = url else {
+            continue;
+        };
+        if attribute("integrity").is_some() || url.is_empty() || is_absolute(url) {
+            continue;
+        }
+
+        tags.push(HashableTag {
+            insert_at: start + 1 + tag.trim_end_matches('/').trim_end().len(),
+            url: url.to_string(),
+        });
+    }
+    tags
+}
+
+/// Whether a `<link>` with this `rel` loads a script or stylesheet
+fn loads_subresource(rel: Option<&str>) -> bool {
+    rel.unwrap_or("").split_ascii_whitespace().any(|token| {
+        ["stylesheet", "modulepreload", "preload"]
+            .iter()
+            .any(|kind| token.eq_ignore_ascii_case(kind))
+    })
+}
+
+/// URLs with a scheme or host point somewhere other than the build directory
+fn is_absolute(url: &str) -> bool {
+    url.starts_with("//") || reqwest::Url::parse(url).is_ok()
+}
+
+/// Parse `name="value" name='value' name=value name` into lowercase names
+/// and values
+fn attributes(source: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = source.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            let (parsed, remaining) = match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_equals[1..];
+                    let close = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..close], inner.get(close + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after_equals
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after_equals.len());
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+            value = parsed.to_string();
+            rest = remaining;
+        }
+
+        if !name.is_empty() {
+            attributes.push((name, value));
+        }
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+    }
+    attributes
+}
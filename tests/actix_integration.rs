@@ -2,7 +2,11 @@
 
 #![cfg(feature = "actix")]
 
+mod common;
+
+use actix_web::body::{BodySize, MessageBody};
 use actix_web::test;
+use common::TestSpaFixture;
 use heisenberg::{adapters::actix::serve_spa, Heisenberg};
 
 #[actix_web::test]
@@ -10,7 +14,8 @@ async fn test_actix_serve_spa_basic() {
     // Force production mode for testing
     std::env::set_var("HEISENBERG_MODE", "production");
 
-    let config = Heisenberg::new().spa("./test-dist").build();
+    let fixture = TestSpaFixture::new().expect("Failed to create test fixture");
+    let config = Heisenberg::new().spa(fixture.dist_path()).build();
     let req = test::TestRequest::get().uri("/").to_http_request();
 
     // Test that the function can be called without panicking
@@ -23,7 +28,8 @@ async fn test_actix_path_matching() {
     // Force production mode for testing
     std::env::set_var("HEISENBERG_MODE", "production");
 
-    let config = Heisenberg::new().spa("./test-dist").build();
+    let fixture = TestSpaFixture::new().expect("Failed to create test fixture");
+    let config = Heisenberg::new().spa(fixture.dist_path()).build();
 
     // Test basic route
     let req = test::TestRequest::get().uri("/").to_http_request();
@@ -35,3 +41,48 @@ async fn test_actix_path_matching() {
     let result = serve_spa(&req, &config).await;
     assert!(result.is_ok());
 }
+
+#[actix_web::test]
+async fn test_actix_streams_files_with_content_length() {
+    std::env::set_var("HEISENBERG_MODE", "production");
+
+    let fixture = TestSpaFixture::new().expect("Failed to create test fixture");
+    let contents = vec![b'x'; 256 * 1024];
+    fixture.add_file("assets/big.js", &contents).unwrap();
+    let config = Heisenberg::new().spa(fixture.dist_path()).build();
+    let req = test::TestRequest::get()
+        .uri("/assets/big.js")
+        .to_http_request();
+
+    let response = serve_spa(&req, &config).await.unwrap();
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/javascript; charset=utf-8"
+    );
+    assert_eq!(
+        response.body().size(),
+        BodySize::Sized(contents.len() as u64)
+    );
+    let body = actix_web::body::to_bytes(response.into_body())
+        .await
+        .unwrap();
+    assert_eq!(body.len(), contents.len());
+}
+
+#[actix_web::test]
+async fn test_actix_applies_route_headers() {
+    std::env::set_var("HEISENBERG_MODE", "production");
+
+    let fixture = TestSpaFixture::new().expect("Failed to create test fixture");
+    let config = Heisenberg::new()
+        .spa(fixture.dist_path())
+        .header("Service-Worker-Allowed", "/")
+        .build();
+    let req = test::TestRequest::get().uri("/").to_http_request();
+
+    let response = serve_spa(&req, &config).await.unwrap();
+    assert_eq!(
+        response.headers().get("service-worker-allowed").unwrap(),
+        "/"
+    );
+}
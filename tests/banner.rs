@@ -0,0 +1,66 @@
+//! Tests for the startup route banner
+
+use heisenberg::core::mode::Mode;
+use heisenberg::utils::banner::route_banner;
+use heisenberg::Heisenberg;
+
+#[test]
+fn test_banner_lists_every_dev_route() {
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .spa("./admin-dist")
+        .pattern("/admin/*")
+        .dev_server("http://localhost:3001")
+        .dev_command(["npm", "run", "dev:admin"])
+        .working_dir("./admin-frontend")
+        .spa("./landing-dist")
+        .pattern("/*")
+        .dev_server("http://localhost:3000")
+        .dev_command(["npm", "run", "dev"])
+        .working_dir("./landing-frontend")
+        .build();
+
+    let banner = route_banner(&config);
+
+    assert!(banner.contains("development mode, 2 SPA routes"));
+    assert!(banner
+        .contains("/admin/*  → http://localhost:3001  (npm run dev:admin in ./admin-frontend)"));
+    assert!(
+        banner.contains("/*        → http://localhost:3000  (npm run dev in ./landing-frontend)")
+    );
+}
+
+#[test]
+fn test_banner_in_production_and_ci_mode() {
+    let config = Heisenberg::new()
+        .mode_override(Mode::Production)
+        .spa("./dist")
+        .build();
+    let banner = route_banner(&config);
+    assert!(banner.contains("production mode, 1 SPA route\n"));
+    assert!(banner.contains("/*  → ./dist"));
+
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .ci_mode(true)
+        .spa("./dist")
+        .build();
+    assert!(route_banner(&config).contains("CI mode: dev servers are not started"));
+}
+
+#[test]
+fn test_banner_marks_routes_proxied_to_staging() {
+    let config = Heisenberg::new()
+        .staging(true)
+        .spa("./admin-dist")
+        .pattern("/admin/*")
+        .staging_url("https://staging.example.com")
+        .spa("./dist")
+        .build();
+
+    let banner = route_banner(&config);
+
+    assert!(banner.contains("development mode"));
+    assert!(banner.contains("/admin/*  → https://staging.example.com  (staging)"));
+    assert!(banner.contains("/*        → http://localhost:5173  (npm run dev in .)"));
+}
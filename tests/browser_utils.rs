@@ -0,0 +1,106 @@
+//! Tests for browser opening utilities
+
+use heisenberg::utils::browser::{
+    browser_command, browser_env_commands, wsl_command, BrowserPolicy,
+};
+
+#[test]
+fn test_default_policy_uses_platform_opener() {
+    assert!(browser_command("http://localhost:5173", &BrowserPolicy::new()).is_none());
+}
+
+#[test]
+fn test_specific_browser_command() {
+    let policy = BrowserPolicy::new().browser("firefox");
+    let command = browser_command("http://localhost:5173", &policy).unwrap();
+
+    assert!(command.contains(&"firefox".to_string()));
+    assert_eq!(command.last().unwrap(), "http://localhost:5173");
+    assert!(!command.iter().any(|arg| arg.starts_with("--private")));
+}
+
+#[test]
+fn test_incognito_flags_per_browser() {
+    let cases = [
+        ("firefox", "--private-window"),
+        ("google-chrome", "--incognito"),
+        ("Microsoft Edge", "--inprivate"),
+        ("brave-browser", "--incognito"),
+    ];
+
+    for (browser, flag) in cases {
+        let policy = BrowserPolicy::new().browser(browser).incognito();
+        let command = browser_command("http://localhost:3000", &policy).unwrap();
+        let url_index = command.len() - 1;
+
+        assert_eq!(command[url_index - 1], flag, "wrong flag for {}", browser);
+        assert_eq!(command[url_index], "http://localhost:3000");
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_linux_launches_browser_binary_directly() {
+    let policy = BrowserPolicy::new().browser("chromium").incognito();
+    let command = browser_command("http://localhost:5173", &policy).unwrap();
+
+    assert_eq!(
+        command,
+        vec!["chromium", "--incognito", "http://localhost:5173"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_browser_env_var_lists_commands() {
+    let url = "http://localhost:5173";
+    assert_eq!(
+        browser_env_commands("open -a Firefox %s::w3m", url),
+        [vec!["open", "-a", "Firefox", url], vec!["w3m", url]]
+    );
+    assert_eq!(
+        browser_env_commands("echo %%s=%s", url),
+        [vec!["echo", "%s=http://localhost:5173"]]
+    );
+    assert_eq!(
+        browser_env_commands("printf %%s", url),
+        [vec!["printf", "%s", url]]
+    );
+    assert!(browser_env_commands("", url).is_empty());
+}
+
+#[test]
+fn test_wsl_opens_the_windows_browser() {
+    let url = "http://localhost:5173/?tab=users&sort=name";
+    assert_eq!(wsl_command(url, true), ["wslview", url]);
+    assert_eq!(
+        wsl_command(url, false),
+        [
+            "cmd.exe",
+            "/C",
+            "start",
+            "http://localhost:5173/?tab=users^&sort=name"
+        ]
+    );
+}
+
+#[test]
+fn test_target_url_defaults_to_root() {
+    let policy = BrowserPolicy::new();
+    assert_eq!(
+        policy.target_url("http://localhost:5173"),
+        "http://localhost:5173"
+    );
+}
+
+#[test]
+fn test_target_url_joins_path() {
+    for (base, path) in [
+        ("http://localhost:5173", "/app/"),
+        ("http://localhost:5173/", "app/"),
+        ("http://localhost:5173/", "/app/"),
+    ] {
+        let policy = BrowserPolicy::new().path(path);
+        assert_eq!(policy.target_url(base), "http://localhost:5173/app/");
+    }
+}
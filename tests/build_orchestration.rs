@@ -0,0 +1,170 @@
+//! Tests for production build checks
+
+use heisenberg::core::config::{MissingBuild, SpaRouteConfig};
+use heisenberg::services::build::ensure_built;
+use heisenberg::utils::freshness::{build_state, BuildState};
+use heisenberg::ErrorKind;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+/// Write a file and backdate it by `age`
+fn write_aged(path: &Path, content: &str, age: Duration) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+    set_mtime(path, SystemTime::now() - age);
+}
+
+#[cfg(unix)]
+fn set_mtime(path: &Path, time: SystemTime) {
+    use std::os::unix::ffi::OsStrExt;
+
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let stamp = libc::timeval {
+        tv_sec: secs as libc::time_t,
+        tv_usec: 0,
+    };
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+    assert_eq!(
+        unsafe { libc::utimes(path.as_ptr(), [stamp, stamp].as_ptr()) },
+        0
+    );
+}
+
+#[cfg(not(unix))]
+fn set_mtime(_path: &Path, _time: SystemTime) {
+    // Without utimes, rely on files written later being newer
+    std::thread::sleep(Duration::from_millis(20));
+}
+
+fn route(project: &TempDir, policy: MissingBuild) -> SpaRouteConfig {
+    SpaRouteConfig {
+        embed_dir: project.path().join("dist"),
+        working_dir: project.path().to_path_buf(),
+        missing_build: policy,
+        ..Default::default()
+    }
+}
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+#[test]
+fn test_build_state_compares_sources_and_output() {
+    let project = TempDir::new().unwrap();
+    let dist = project.path().join("dist");
+    assert_eq!(build_state(&dist, project.path()), BuildState::Missing);
+
+    fs::create_dir_all(&dist).unwrap();
+    assert_eq!(build_state(&dist, project.path()), BuildState::Missing);
+
+    write_aged(&project.path().join("src/main.ts"), "v1", 2 * HOUR);
+    write_aged(&dist.join("assets/index.js"), "built", HOUR);
+    assert_eq!(build_state(&dist, project.path()), BuildState::Fresh);
+
+    // Dependencies and their timestamps don't count
+    write_aged(
+        &project.path().join("node_modules/vite/index.js"),
+        "",
+        Duration::ZERO,
+    );
+    assert_eq!(build_state(&dist, project.path()), BuildState::Fresh);
+
+    let config = project.path().join("vite.config.ts");
+    write_aged(&config, "export default {}", Duration::ZERO);
+    assert_eq!(
+        build_state(&dist, project.path()),
+        BuildState::Stale {
+            newest_source: config
+        }
+    );
+}
+
+#[test]
+fn test_serve_policy_never_complains() {
+    let project = TempDir::new().unwrap();
+    ensure_built(&route(&project, MissingBuild::Serve), false).unwrap();
+}
+
+#[test]
+fn test_fail_policy_names_the_build_command() {
+    let project = TempDir::new().unwrap();
+    write_aged(&project.path().join("dist/index.html"), "old", 2 * HOUR);
+    write_aged(&project.path().join("src/App.tsx"), "new", Duration::ZERO);
+
+    let error = ensure_built(&route(&project, MissingBuild::Fail), false).unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::ConfigInvalid);
+    let message = error.to_string();
+    assert!(message.contains("is older than"));
+    assert!(message.contains("App.tsx"));
+    assert!(message.contains("Run `npm run build` in"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_build_policy_runs_the_build_command() {
+    let project = TempDir::new().unwrap();
+    let mut route = route(&project, MissingBuild::Build);
+    route.build_command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        "mkdir -p dist && echo built > dist/index.html".to_string(),
+    ];
+
+    ensure_built(&route, false).unwrap();
+    assert_eq!(
+        fs::read_to_string(project.path().join("dist/index.html")).unwrap(),
+        "built\n"
+    );
+
+    // Already fresh, so nothing runs again
+    route.build_command = vec!["false".to_string()];
+    ensure_built(&route, false).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_build_policy_reports_failures_and_respects_ci_mode() {
+    let project = TempDir::new().unwrap();
+    let marker = project.path().join("built");
+    let mut route = route(&project, MissingBuild::Build);
+    route.build_command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("touch {}; exit 2", marker.display()),
+    ];
+
+    let error = ensure_built(&route, true).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConfigInvalid);
+    assert!(!marker.exists());
+
+    let error = ensure_built(&route, false).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ProcessSpawnFailed);
+    assert!(error.to_string().contains("failed with"));
+    assert!(marker.exists());
+}
+
+#[cfg(feature = "tower")]
+#[test]
+fn test_layer_checks_builds_in_production_only() {
+    use heisenberg::core::mode::Mode;
+    use heisenberg::{Heisenberg, HeisenbergLayer};
+
+    let project = TempDir::new().unwrap();
+    let config = |mode| {
+        Heisenberg::new()
+            .mode_override(mode)
+            .spa(project.path().join("dist"))
+            .working_dir(project.path())
+            .on_missing_build(MissingBuild::Fail)
+            .build()
+    };
+
+    let error = HeisenbergLayer::try_new(config(Mode::Production)).unwrap_err();
+    assert!(error.to_string().contains("is missing or empty"));
+    assert!(HeisenbergLayer::try_new(config(Mode::Development)).is_ok());
+}
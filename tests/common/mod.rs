@@ -1,5 +1,56 @@
 //! Common test utilities and fixtures
 
-pub mod test_utils;
+// Each test file uses only some of the helpers
+#![allow(dead_code, unused_imports)]
 
-pub use test_utils::TestSpaFixture;
+use heisenberg::core::config::SpaRouteBuilder;
+use heisenberg::core::mode::Mode;
+use heisenberg::Heisenberg;
+
+pub use heisenberg::test::TestSpaFixture;
+
+/// A dev server URL nothing listens on
+pub fn closed_port_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    format!("http://{}", listener.local_addr().unwrap())
+}
+
+/// A development-mode route for `./dist` proxying to `dev_server`, to
+/// configure further
+pub fn dev_route(dev_server: &str) -> SpaRouteBuilder {
+    Heisenberg::new()
+        .mode_override(Mode::Development)
+        .spa("./dist")
+        .dev_server(dev_server)
+}
+
+/// A development-mode configuration proxying `./dist` to `dev_server`
+pub fn dev_config(dev_server: &str) -> Heisenberg {
+    dev_route(dev_server).build()
+}
+
+/// GET `/dashboard` through a layer for `config` over an app that 404s
+#[cfg(feature = "tower")]
+pub async fn get_dashboard(config: Heisenberg) -> hyper::Response<hyper::body::Bytes> {
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::{Request, Response, StatusCode};
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+    ServiceBuilder::new()
+        .layer(heisenberg::HeisenbergLayer::new(config))
+        .service(service_fn(|_: Request<Full<Bytes>>| async {
+            let not_found = Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Bytes::new());
+            Ok::<_, std::convert::Infallible>(not_found.unwrap())
+        }))
+        .oneshot(
+            Request::builder()
+                .uri("/dashboard")
+                .body(Full::default())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
@@ -59,7 +59,7 @@ fn test_browser_opening_configuration() {
 
     let routes = config.routes();
     assert_eq!(routes.len(), 1);
-    assert_eq!(routes[0].open_browser, true);
+    assert!(routes[0].open_browser);
     assert_eq!(routes[0].dev_proxy_url, "http://localhost:3000");
 }
 
@@ -68,7 +68,7 @@ fn test_browser_opening_default_false() {
     let config = Heisenberg::new().spa("./dist").build();
     let routes = config.routes();
 
-    assert_eq!(routes[0].open_browser, false); // Conservative default
+    assert!(!routes[0].open_browser); // Conservative default
 }
 
 #[test]
@@ -122,3 +122,441 @@ fn test_validation_duplicate_patterns() {
 
     assert!(config.validate().is_err());
 }
+
+#[test]
+fn test_browser_policy_configuration() {
+    use heisenberg::utils::BrowserPolicy;
+    use std::time::Duration;
+
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .browser_policy(
+            BrowserPolicy::new()
+                .once_per_session()
+                .delay(Duration::from_secs(2))
+                .browser("firefox"),
+        )
+        .build();
+
+    let route = &config.routes()[0];
+    assert!(route.open_browser);
+    assert!(route.browser_policy.once_per_session);
+    assert_eq!(route.browser_policy.delay, Duration::from_secs(2));
+    assert_eq!(route.browser_policy.browser.as_deref(), Some("firefox"));
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn test_validation_incognito_requires_browser() {
+    use heisenberg::utils::BrowserPolicy;
+
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .browser_policy(BrowserPolicy::new().incognito())
+        .build();
+
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("requires a specific browser"));
+}
+
+#[test]
+fn test_open_browser_to_path() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .dev_server("http://localhost:5173/")
+        .open_browser_to("/admin/dashboard")
+        .build();
+
+    let route = &config.routes()[0];
+    assert!(route.open_browser);
+    assert_eq!(
+        route.browser_policy.target_url(&route.dev_proxy_url),
+        "http://localhost:5173/admin/dashboard"
+    );
+}
+
+#[test]
+fn test_server_addr_configuration() {
+    let config = Heisenberg::new()
+        .server_addr(([0, 0, 0, 0], 3000))
+        .spa("./dist")
+        .build();
+
+    assert_eq!(
+        config.global_settings().server_addr,
+        Some("0.0.0.0:3000".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_mode_override_resolution() {
+    use heisenberg::core::mode::Mode;
+
+    let config = Heisenberg::new()
+        .mode_override(Mode::Production)
+        .spa("./dist")
+        .build();
+
+    assert_eq!(config.mode(), Mode::Production);
+}
+
+#[test]
+fn test_ci_mode_configuration() {
+    let config = Heisenberg::new().ci_mode(true).spa("./dist").build();
+
+    assert!(config.global_settings().ci_mode);
+    assert!(config.is_ci_mode());
+}
+
+#[test]
+fn test_try_build_validates() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .dev_server("http://localhost:5173")
+        .try_build()
+        .unwrap();
+    assert_eq!(config.routes().len(), 1);
+
+    let err = Heisenberg::new()
+        .spa("./dist")
+        .dev_server("localhost:5173")
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("http://"));
+
+    let err = Heisenberg::new()
+        .spa("./admin")
+        .pattern("/admin/*")
+        .spa("./other")
+        .pattern("/admin/*")
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("Duplicate route pattern"));
+}
+
+#[test]
+fn test_strip_prefix_rewrites_proxy_paths() {
+    let config = Heisenberg::new()
+        .spa("./admin/dist")
+        .pattern("/admin/*")
+        .strip_prefix(true)
+        .build();
+    let route = &config.routes()[0];
+
+    assert_eq!(route.pattern_prefix(), "/admin");
+    assert_eq!(route.proxy_path("/admin/users?page=2"), "/users?page=2");
+    assert_eq!(route.proxy_path("/admin"), "/");
+    assert_eq!(route.proxy_path("/admin?tab=1"), "/?tab=1");
+    assert_eq!(route.proxy_path("/administrator"), "/administrator");
+}
+
+#[test]
+fn test_pattern_params_are_captured_for_the_resolver() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .pattern("/:tenant/app/*")
+        .strip_prefix(true)
+        .resolve_params(|params, target| {
+            let tenant = params.get("tenant").unwrap_or_default();
+            target.embed_dir = PathBuf::from("./tenants").join(tenant);
+            target.upstream_path = format!("/{}{}", tenant, target.upstream_path);
+        })
+        .build();
+    let route = &config.routes()[0];
+
+    let params = route.params("/acme/app/settings?tab=1");
+    assert_eq!(params.get("tenant"), Some("acme"));
+    assert_eq!(params.iter().collect::<Vec<_>>(), [("tenant", "acme")]);
+    assert!(route.params("/acme/other").is_empty());
+    assert_eq!(route.matched_prefix("/acme/app/settings"), "/acme/app");
+    assert!(route.is_unslashed_root("/acme/app"));
+    assert_eq!(
+        route.static_path("/acme/app/assets/app.js"),
+        "/assets/app.js"
+    );
+    assert_eq!(
+        route.proxy_path("/acme/app/settings?tab=1"),
+        "/settings?tab=1"
+    );
+
+    let target = route.target("/acme/app/settings?tab=1");
+    assert_eq!(target.embed_dir, PathBuf::from("./tenants/acme"));
+    assert_eq!(target.upstream_path, "/acme/settings?tab=1");
+}
+
+#[test]
+fn test_rewrite_path_replaces_prefix() {
+    let config = Heisenberg::new()
+        .spa("./admin/dist")
+        .pattern("/admin/*")
+        .rewrite_path("/admin", "/dashboard")
+        .build();
+    let route = &config.routes()[0];
+
+    assert_eq!(route.proxy_path("/admin/users"), "/dashboard/users");
+    assert_eq!(route.proxy_path("/admin"), "/dashboard");
+    assert_eq!(route.proxy_path("/other"), "/other");
+
+    let unchanged = Heisenberg::new().spa("./dist").build();
+    assert_eq!(unchanged.routes()[0].proxy_path("/users"), "/users");
+
+    let err = Heisenberg::new()
+        .spa("./dist")
+        .rewrite_path("admin", "/")
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("must start with '/'"));
+}
+
+#[test]
+fn test_dev_commands_split_setup_steps_from_dev_server() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .dev_commands([
+            vec!["npm", "run", "codegen"],
+            vec!["node", "scripts/env.js"],
+            vec!["npm", "run", "dev"],
+        ])
+        .build();
+    let route = &config.routes()[0];
+    assert_eq!(
+        route.setup_commands,
+        [
+            vec!["npm", "run", "codegen"],
+            vec!["node", "scripts/env.js"]
+        ]
+    );
+    assert_eq!(route.dev_command, ["npm", "run", "dev"]);
+
+    // A single step is just the dev command
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .dev_commands([["pnpm", "dev"]])
+        .build();
+    assert!(config.routes()[0].setup_commands.is_empty());
+    assert_eq!(config.routes()[0].dev_command, ["pnpm", "dev"]);
+
+    let err = Heisenberg::new()
+        .spa("./dist")
+        .dev_commands([vec![], vec!["npm", "run", "dev"]])
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("Setup commands cannot be empty"));
+}
+
+#[test]
+fn test_route_headers_are_validated() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .header("Service-Worker-Allowed", "/")
+        .header("Link", "</app.css>; rel=preload")
+        .build();
+    assert_eq!(
+        config.routes()[0].headers,
+        [
+            ("Service-Worker-Allowed".to_string(), "/".to_string()),
+            ("Link".to_string(), "</app.css>; rel=preload".to_string()),
+        ]
+    );
+    assert!(config.validate().is_ok());
+
+    for (name, value) in [("Bad Header", "1"), ("X-Ok", "line\nbreak")] {
+        let config = Heisenberg::new().spa("./dist").header(name, value).build();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("Invalid header"), "{}", err);
+    }
+}
+
+#[test]
+fn test_rewrites_are_validated() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .rewrite("/old-path/*", "/index.html")
+        .build();
+    assert_eq!(
+        config.routes()[0].rewrites,
+        [("/old-path/*".to_string(), "/index.html".to_string())]
+    );
+    assert!(config.validate().is_ok());
+
+    for (from, to) in [("old/*", "/index.html"), ("/old", "https://example.com/")] {
+        let config = Heisenberg::new().spa("./dist").rewrite(from, to).build();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("Invalid rewrite"), "{}", err);
+    }
+}
+
+#[test]
+fn test_locale_prefixes() {
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .pattern("/app/*")
+        .locales(["en", "de"])
+        .build();
+    let route = &config.routes()[0];
+    assert_eq!(route.locale_for("/app/de/users"), Some("de"));
+    assert_eq!(route.locale_for("/app/en"), Some("en"));
+    assert_eq!(route.locale_for("/app/fr/users"), None);
+    assert_eq!(route.locale_for("/app/dev"), None);
+    assert_eq!(route.static_path("/app/de/assets/app.js"), "/assets/app.js");
+    assert_eq!(route.static_path("/app/de"), "/");
+    assert_eq!(route.static_path("/app/assets/app.js"), "/assets/app.js");
+    assert_eq!(
+        route.fallback_file_for("/app/de/users").as_deref(),
+        Some("index.html")
+    );
+
+    let config = Heisenberg::new()
+        .spa("./dist")
+        .locale_dirs(["en", "de"])
+        .fallback_for("/de/legacy/*", "legacy.html")
+        .build();
+    let route = &config.routes()[0];
+    assert_eq!(route.static_path("/de/main.js"), "/de/main.js");
+    assert_eq!(
+        route.fallback_file_for("/de/users").as_deref(),
+        Some("de/index.html")
+    );
+    assert_eq!(
+        route.fallback_file_for("/users").as_deref(),
+        Some("en/index.html")
+    );
+    assert_eq!(
+        route.fallback_file_for("/de/legacy/page").as_deref(),
+        Some("legacy.html")
+    );
+
+    let err = Heisenberg::new()
+        .spa("./dist")
+        .locales(["/en/"])
+        .try_build()
+        .unwrap_err();
+    assert!(err.to_string().contains("single path segments"));
+}
+
+#[test]
+fn test_routes_are_named_by_pattern_or_prefix() {
+    let config = Heisenberg::new()
+        .spa("./admin")
+        .pattern("/admin/*")
+        .spa("./dist")
+        .build();
+    let (admin, app) = (&config.routes()[0], &config.routes()[1]);
+
+    for name in ["/admin/*", "/admin", "admin", "admin/", " admin "] {
+        assert!(admin.is_named(name), "{}", name);
+    }
+    assert!(!admin.is_named("adm"));
+    assert!(!admin.is_named(""));
+    assert!(app.is_named("/*"));
+    assert!(app.is_named("/"));
+    assert!(!app.is_named("admin"));
+}
+
+#[test]
+fn test_disable_routes_env_var_leaves_out_routes_in_development() {
+    use heisenberg::core::mode::Mode;
+
+    // Patterns no other test uses, since the variable is process-wide
+    let config = |mode| {
+        Heisenberg::new()
+            .mode_override(mode)
+            .spa("./admin")
+            .pattern("/disabled-admin/*")
+            .spa("./docs")
+            .pattern("/disabled-docs/*")
+            .spa("./app")
+            .pattern("/kept-app/*")
+            .build()
+    };
+    let patterns = |config: Heisenberg| {
+        config
+            .routes()
+            .iter()
+            .map(|route| route.pattern.clone())
+            .collect::<Vec<_>>()
+    };
+
+    std::env::set_var(
+        "HEISENBERG_DISABLE_ROUTES",
+        "disabled-admin, /disabled-docs/*",
+    );
+    let development = patterns(config(Mode::Development));
+    let production = patterns(config(Mode::Production));
+    std::env::remove_var("HEISENBERG_DISABLE_ROUTES");
+
+    assert_eq!(development, ["/kept-app/*"]);
+    assert_eq!(production.len(), 3);
+}
+
+#[test]
+fn test_disabled_routes_are_dropped_with_their_settings() {
+    let config = |devtools: bool| {
+        Heisenberg::new()
+            .spa("./dist")
+            .dev_server("http://localhost:3000")
+            .spa("./devtools/dist")
+            .pattern("/devtools/*")
+            .enabled(devtools)
+            .dev_server("http://localhost:3001")
+            .spa("./admin/dist")
+            .pattern("/admin/*")
+            .build()
+    };
+
+    let enabled = config(true);
+    assert_eq!(enabled.routes().len(), 3);
+    assert_eq!(enabled.routes()[1].dev_proxy_url, "http://localhost:3001");
+
+    let disabled = config(false);
+    let patterns: Vec<_> = disabled
+        .routes()
+        .iter()
+        .map(|route| route.pattern.as_str())
+        .collect();
+    assert_eq!(patterns, ["/*", "/admin/*"]);
+    assert_eq!(disabled.routes()[0].dev_proxy_url, "http://localhost:3000");
+}
+
+#[test]
+fn test_docker_host_moves_localhost_dev_servers() {
+    use heisenberg::core::config::DockerHost;
+    use heisenberg::core::mode::Mode;
+
+    let config = |mode: Mode, docker_host: DockerHost| {
+        Heisenberg::new()
+            .mode_override(mode)
+            .docker_host(docker_host)
+            .spa("./dist")
+            .dev_server("http://localhost:5173")
+            .fallback_dev_server("http://127.0.0.1:5174/")
+            .spa("./admin/dist")
+            .pattern("/admin/*")
+            .dev_server("https://devbox.internal:5173")
+            .build()
+    };
+    let host = || DockerHost::Host("host.docker.internal".to_string());
+
+    let moved = config(Mode::Development, host());
+    let route = &moved.routes()[0];
+    assert_eq!(route.dev_proxy_url, "http://host.docker.internal:5173");
+    assert_eq!(
+        route.fallback_dev_url.as_deref(),
+        Some("http://host.docker.internal:5174/")
+    );
+    assert_eq!(route.docker_host.as_deref(), Some("host.docker.internal"));
+    // Dev servers on other hosts are reached as configured
+    assert_eq!(
+        moved.routes()[1].dev_proxy_url,
+        "https://devbox.internal:5173"
+    );
+    assert_eq!(moved.routes()[1].docker_host, None);
+
+    for kept in [
+        config(Mode::Development, DockerHost::Off),
+        config(Mode::Production, host()),
+    ] {
+        assert_eq!(kept.routes()[0].dev_proxy_url, "http://localhost:5173");
+        assert_eq!(kept.routes()[0].docker_host, None);
+    }
+}
@@ -0,0 +1,75 @@
+//! Tests for dev server output parsing
+
+use heisenberg::utils::dev_output::{is_ready_line, strip_ansi};
+
+#[test]
+fn test_strip_ansi_removes_color_codes() {
+    assert_eq!(
+        strip_ansi("\u{1b}[32m\u{1b}[1mVITE\u{1b}[22m v5.0.0\u{1b}[39m ready"),
+        "VITE v5.0.0 ready"
+    );
+    assert_eq!(strip_ansi("plain ➜ text"), "plain ➜ text");
+}
+
+#[test]
+fn test_framework_ready_lines() {
+    let ready = [
+        "  VITE v5.0.8  ready in 312 ms",
+        "  ➜  Local:   http://localhost:5173/",
+        "ready - started server on 0.0.0.0:3000, url: http://localhost:3000",
+        " ✓ Ready in 2.1s",
+        "Compiled successfully!",
+        "<i> [webpack-dev-server] Project is running at:",
+        "Server running at http://localhost:4321/",
+        "Listening on http://localhost:8080",
+    ];
+    for line in ready {
+        assert!(is_ready_line(line), "{:?} should be a ready line", line);
+    }
+}
+
+#[test]
+fn test_other_output_is_not_ready() {
+    let not_ready = [
+        "> my-app@0.0.0 dev",
+        "> vite",
+        "Port 5173 is in use, trying another one...",
+        "error when starting dev server:",
+        "",
+    ];
+    for line in not_ready {
+        assert!(
+            !is_ready_line(line),
+            "{:?} should not be a ready line",
+            line
+        );
+    }
+}
+
+#[test]
+fn test_announced_urls() {
+    use heisenberg::utils::dev_output::announced_url;
+
+    assert_eq!(
+        announced_url("  \u{1b}[32m➜\u{1b}[39m  \u{1b}[1mLocal\u{1b}[22m:   \u{1b}[36mhttp://localhost:\u{1b}[1m5174\u{1b}[22m/\u{1b}[39m"),
+        Some("http://localhost:5174".to_string())
+    );
+    assert_eq!(
+        announced_url("ready - started server on 0.0.0.0:3001, url: http://localhost:3001"),
+        Some("http://localhost:3001".to_string())
+    );
+    assert_eq!(
+        announced_url("  - Local:        http://127.0.0.1:3000/app"),
+        Some("http://127.0.0.1:3000".to_string())
+    );
+    // Other hosts and ordinary log lines aren't announcements
+    assert_eq!(
+        announced_url("  ➜  Network: http://192.168.1.20:5174/"),
+        None
+    );
+    assert_eq!(
+        announced_url("[vite] http proxy error: http://localhost:8080/api"),
+        None
+    );
+    assert_eq!(announced_url("  VITE v5.0.8  ready in 312 ms"), None);
+}
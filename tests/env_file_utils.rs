@@ -0,0 +1,85 @@
+//! Tests for .env file utilities
+
+use heisenberg::utils::env_file::{load_env_files, parse_env_file};
+use std::fs;
+use tempfile::TempDir;
+
+fn pairs(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+    vars.iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_basic_assignments() {
+    let vars = parse_env_file("API_URL=http://localhost:8080\nDEBUG=true\n");
+    assert_eq!(
+        vars,
+        pairs(&[("API_URL", "http://localhost:8080"), ("DEBUG", "true")])
+    );
+}
+
+#[test]
+fn test_parse_comments_export_and_blank_lines() {
+    let content =
+        "# comment\n\nexport TOKEN=abc # trailing comment\n  SPACED = value  \nnot a var\n";
+    let vars = parse_env_file(content);
+    assert_eq!(vars, pairs(&[("TOKEN", "abc"), ("SPACED", "value")]));
+}
+
+#[test]
+fn test_parse_quoted_values() {
+    let content = "DOUBLE=\"hello # world\\nnext\"\nSINGLE='raw \\n value'\nEMPTY=\n";
+    let vars = parse_env_file(content);
+    assert_eq!(
+        vars,
+        pairs(&[
+            ("DOUBLE", "hello # world\nnext"),
+            ("SINGLE", "raw \\n value"),
+            ("EMPTY", ""),
+        ])
+    );
+}
+
+#[test]
+fn test_load_env_files_precedence() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join(".env"),
+        "HEISENBERG_ENV_A=env\nHEISENBERG_ENV_B=env\nHEISENBERG_ENV_C=env\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join(".env.local"),
+        "HEISENBERG_ENV_B=local\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join(".env.development"),
+        "HEISENBERG_ENV_C=development\n",
+    )
+    .unwrap();
+
+    let vars = load_env_files(temp_dir.path());
+    assert_eq!(
+        vars,
+        pairs(&[
+            ("HEISENBERG_ENV_A", "env"),
+            ("HEISENBERG_ENV_B", "local"),
+            ("HEISENBERG_ENV_C", "development"),
+        ])
+    );
+}
+
+#[test]
+fn test_load_env_files_keeps_process_environment() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(temp_dir.path().join(".env"), "PATH=/overridden\n").unwrap();
+
+    assert!(load_env_files(temp_dir.path()).is_empty());
+}
+
+#[test]
+fn test_load_env_files_missing_dir() {
+    assert!(load_env_files(std::path::Path::new("/nonexistent/path")).is_empty());
+}
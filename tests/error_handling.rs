@@ -42,3 +42,121 @@ fn test_error_from_json() {
         _ => panic!("Expected PackageJsonParse variant"),
     }
 }
+
+#[test]
+fn test_errors_keep_their_source() {
+    use std::error::Error;
+
+    let err = HeisenbergError::process("Failed to spawn dev server", "Check the command")
+        .with_source(io::Error::new(io::ErrorKind::NotFound, "npm: not found"));
+    let source = err.source().expect("the io error is kept");
+    assert_eq!(source.to_string(), "npm: not found");
+    assert!(source.downcast_ref::<io::Error>().is_some());
+
+    assert!(
+        HeisenbergError::config("invalid setting", "Check your configuration")
+            .source()
+            .is_none()
+    );
+    assert!(HeisenbergError::NoRouteMatch
+        .with_source(io::Error::new(io::ErrorKind::Other, "ignored"))
+        .source()
+        .is_none());
+}
+
+#[test]
+fn test_error_kinds() {
+    use heisenberg::error::ErrorKind;
+
+    assert_eq!(
+        HeisenbergError::config("bad", "hint").kind(),
+        ErrorKind::ConfigInvalid
+    );
+    assert_eq!(
+        HeisenbergError::file_not_found("app.js", "hint").kind(),
+        ErrorKind::AssetMissing
+    );
+    assert_eq!(
+        HeisenbergError::process("spawn failed", "hint").kind(),
+        ErrorKind::ProcessSpawnFailed
+    );
+    assert_eq!(
+        HeisenbergError::health_check("timed out", "hint").kind(),
+        ErrorKind::DevServerUnreachable
+    );
+    assert_eq!(
+        HeisenbergError::browser("no browser", "hint").kind(),
+        ErrorKind::BrowserOpenFailed
+    );
+    assert_eq!(
+        HeisenbergError::NoRouteMatch.kind(),
+        ErrorKind::NoRouteMatch
+    );
+    assert_eq!(
+        HeisenbergError::from(io::Error::new(io::ErrorKind::Other, "io")).kind(),
+        ErrorKind::Io
+    );
+    assert_eq!(
+        HeisenbergError::from(serde_json::from_str::<serde_json::Value>("{").unwrap_err()).kind(),
+        ErrorKind::PackageJsonInvalid
+    );
+}
+
+#[tokio::test]
+async fn test_connection_failures_are_unreachable() {
+    use heisenberg::error::ErrorKind;
+
+    // Nothing listens on the discard port
+    let err = reqwest::get("http://127.0.0.1:9").await.unwrap_err();
+
+    assert_eq!(
+        HeisenbergError::from(err).kind(),
+        ErrorKind::DevServerUnreachable
+    );
+}
+
+#[test]
+fn test_errors_convert_to_responses() {
+    use hyper::body::Bytes;
+    use hyper::{Response, StatusCode};
+
+    let cases = [
+        (HeisenbergError::NoRouteMatch, StatusCode::NOT_FOUND),
+        (
+            HeisenbergError::file_not_found("dist/app.js", "• Run the build"),
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            HeisenbergError::health_check("not responding", "• Start it"),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (
+            HeisenbergError::process("npm missing", "• Install Node"),
+            StatusCode::SERVICE_UNAVAILABLE,
+        ),
+        (
+            HeisenbergError::config("bad pattern", "• Fix it"),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ];
+    for (err, status) in cases {
+        assert_eq!(err.status_code(), status, "{}", err);
+        let message = err.to_string();
+        let response = Response::<Bytes>::from(err);
+        assert_eq!(response.status(), status);
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(response.body(), message.as_bytes());
+    }
+}
+
+#[cfg(feature = "axum")]
+#[test]
+fn test_errors_are_axum_responses() {
+    use axum_core::response::IntoResponse;
+
+    let response = HeisenbergError::NoRouteMatch.into_response();
+    assert_eq!(response.status(), hyper::StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,173 @@
+//! Tests for the development error overlay
+
+#![cfg(feature = "tower")]
+
+mod common;
+
+use common::{closed_port_url, dev_config};
+use heisenberg::core::mode::Mode;
+use heisenberg::test::{MockDevServer, MockResponse};
+use heisenberg::tower::overlay::{inject_overlay, is_document_request, OVERLAY_SCRIPT_PATH};
+use heisenberg::{Heisenberg, HeisenbergLayer};
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::header::HeaderMap;
+use hyper::{Request, Response, StatusCode};
+use std::convert::Infallible;
+use tower::{service_fn, Layer, ServiceExt};
+
+async fn not_found(_req: Request<Full<Bytes>>) -> Result<Response<Bytes>, Infallible> {
+    Ok(Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Bytes::new())
+        .unwrap())
+}
+
+fn get(path: &str, accept: &str) -> Request<Full<Bytes>> {
+    Request::builder()
+        .uri(path)
+        .header("accept", accept)
+        .body(Full::default())
+        .unwrap()
+}
+
+async fn call(layer: &HeisenbergLayer, req: Request<Full<Bytes>>) -> Response<Bytes> {
+    layer
+        .layer(service_fn(not_found))
+        .oneshot(req)
+        .await
+        .unwrap()
+}
+
+fn body(response: &Response<Bytes>) -> String {
+    String::from_utf8_lossy(response.body()).into_owned()
+}
+
+#[test]
+fn test_inject_overlay_placement() {
+    let html = "<html><head><title>App</title></head><body></body></html>";
+    let injected = inject_overlay(html);
+    assert!(injected
+        .contains(r#"<script type="module" src="/__heisenberg/overlay.js"></script></head>"#));
+
+    let no_head = inject_overlay("<BODY>app</BODY>");
+    assert!(no_head.ends_with("</script></BODY>"));
+
+    let fragment = inject_overlay("<div>app</div>");
+    assert!(fragment.starts_with("<div>app</div><script"));
+}
+
+#[test]
+fn test_document_request_detection() {
+    let mut headers = HeaderMap::new();
+    assert!(!is_document_request(&headers));
+
+    headers.insert("accept", "text/html,application/xhtml+xml".parse().unwrap());
+    assert!(is_document_request(&headers));
+
+    headers.insert("sec-fetch-dest", "script".parse().unwrap());
+    assert!(!is_document_request(&headers));
+
+    headers.insert("sec-fetch-dest", "document".parse().unwrap());
+    assert!(is_document_request(&headers));
+}
+
+#[tokio::test]
+async fn test_proxied_html_gets_overlay_script() {
+    let dev_server = MockDevServer::start().await.unwrap();
+    dev_server.respond(
+        "/",
+        MockResponse::html("<html><head></head><body>app</body></html>"),
+    );
+    dev_server.respond(
+        "/src/main.js",
+        MockResponse::new(200)
+            .header("content-type", "text/javascript")
+            .body("export {}"),
+    );
+    let layer = HeisenbergLayer::new(dev_config(&dev_server.url()));
+
+    let page = call(&layer, get("/", "text/html")).await;
+    assert!(body(&page).contains(OVERLAY_SCRIPT_PATH));
+
+    let script = call(&layer, get("/src/main.js", "*/*")).await;
+    assert_eq!(body(&script), "export {}");
+
+    let overlay = call(&layer, get(OVERLAY_SCRIPT_PATH, "*/*")).await;
+    assert_eq!(overlay.status(), StatusCode::OK);
+    assert!(body(&overlay).contains("/__heisenberg/status"));
+}
+
+#[tokio::test]
+async fn test_overlay_can_be_disabled() {
+    let dev_server = MockDevServer::start().await.unwrap();
+    dev_server.respond("/", MockResponse::html("<html><head></head></html>"));
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .error_overlay(false)
+        .spa("./dist")
+        .dev_server(&dev_server.url())
+        .build();
+    let layer = HeisenbergLayer::new(config);
+
+    let page = call(&layer, get("/", "text/html")).await;
+    assert_eq!(body(&page), "<html><head></head></html>");
+
+    let overlay = call(&layer, get(OVERLAY_SCRIPT_PATH, "*/*")).await;
+    assert_eq!(overlay.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_failures_only_render_error_page_for_documents() {
+    let layer = HeisenbergLayer::new(dev_config(&closed_port_url()));
+
+    let page = call(&layer, get("/", "text/html")).await;
+    assert_eq!(page.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body(&page).contains("<!DOCTYPE html>"));
+
+    let script = call(&layer, get("/src/main.js", "*/*")).await;
+    assert_eq!(script.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        script.headers()["content-type"],
+        "text/plain; charset=utf-8"
+    );
+    assert!(body(&script).contains("is not responding"));
+}
+
+#[tokio::test]
+async fn test_status_reports_failures_across_services() {
+    let dev_server = closed_port_url();
+    let layer = HeisenbergLayer::new(dev_config(&dev_server));
+
+    // Axum wraps each route separately; a failure seen by one wrapped service
+    // must show up in the status served by another
+    let _ = layer
+        .layer(service_fn(not_found))
+        .oneshot(get("/app", "*/*"))
+        .await
+        .unwrap();
+    let status = call(&layer, get("/__heisenberg/status", "*/*")).await;
+
+    assert_eq!(status.headers()["content-type"], "application/json");
+    let status: serde_json::Value = serde_json::from_slice(status.body()).unwrap();
+    let route = &status["routes"][0];
+    assert_eq!(route["dev_server"], dev_server.as_str());
+    assert_eq!(route["healthy"], false);
+    assert!(route["error"]
+        .as_str()
+        .unwrap()
+        .contains("is not responding"));
+}
+
+#[tokio::test]
+async fn test_no_internal_endpoints_in_production() {
+    let config = Heisenberg::new()
+        .mode_override(Mode::Production)
+        .spa("./dist")
+        .fallback_file("missing.html")
+        .build();
+    let layer = HeisenbergLayer::new(config);
+
+    let status = call(&layer, get("/__heisenberg/status", "*/*")).await;
+    assert_eq!(status.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,205 @@
+//! Tests for lifecycle event subscription
+
+mod common;
+
+use common::closed_port_url;
+use heisenberg::core::config::SpaRouteConfig;
+use heisenberg::core::events::HeisenbergEvent;
+use heisenberg::core::mode::Mode;
+use heisenberg::services::ProcessManager;
+use heisenberg::test::{MockDevServer, TestSpaFixture};
+use heisenberg::Heisenberg;
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+
+async fn next_event(events: &mut Receiver<HeisenbergEvent>) -> HeisenbergEvent {
+    tokio::time::timeout(Duration::from_secs(3), events.recv())
+        .await
+        .expect("no event within 3s")
+        .unwrap()
+}
+
+#[cfg(unix)]
+fn shell_route(script: &str, dev_server: String) -> SpaRouteConfig {
+    SpaRouteConfig {
+        dev_command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+        working_dir: std::env::temp_dir(),
+        dev_proxy_url: dev_server,
+        ..Default::default()
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_process_start_and_exit_events() {
+    let config = Heisenberg::new();
+    let mut events = config.events();
+    let manager = ProcessManager::with_settings(config.global_settings());
+
+    let route = shell_route("exit 3", closed_port_url());
+    manager.start_process("/app/*", &route).await.unwrap_err();
+
+    match next_event(&mut events).await {
+        HeisenbergEvent::ProcessStarted { pattern, pid } => {
+            assert_eq!(pattern, "/app/*");
+            assert!(pid > 0);
+        }
+        other => panic!("expected ProcessStarted, got {:?}", other),
+    }
+    assert_eq!(
+        next_event(&mut events).await,
+        HeisenbergEvent::ProcessExited {
+            pattern: "/app/*".to_string(),
+            code: Some(3),
+        }
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unexpected_exits_are_reported_without_polling() {
+    let dev_server = MockDevServer::start().await.unwrap();
+    let config = Heisenberg::new();
+    let mut events = config.events();
+    let manager = ProcessManager::with_settings(config.global_settings());
+
+    let route = shell_route("sleep 0.3; exit 2", dev_server.url());
+    manager.start_process("/*", &route).await.unwrap();
+
+    assert!(matches!(
+        next_event(&mut events).await,
+        HeisenbergEvent::ProcessStarted { .. }
+    ));
+    assert_eq!(
+        next_event(&mut events).await,
+        HeisenbergEvent::ProcessExited {
+            pattern: "/*".to_string(),
+            code: Some(2),
+        }
+    );
+}
+
+#[cfg(feature = "tower")]
+mod layer {
+    use super::*;
+    use heisenberg::HeisenbergLayer;
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::{Request, Response, StatusCode};
+    use std::convert::Infallible;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    async fn not_found(_req: Request<Full<Bytes>>) -> Result<Response<Bytes>, Infallible> {
+        Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Bytes::new())
+            .unwrap())
+    }
+
+    fn get(path: &str) -> Request<Full<Bytes>> {
+        Request::builder().uri(path).body(Full::default()).unwrap()
+    }
+
+    fn ci_config(dev_server: &str) -> Heisenberg {
+        Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(dev_server)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_layer_reports_mode() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .working_dir(fixture.dist_path())
+            .build();
+        let mut events = config.events();
+
+        let _service = HeisenbergLayer::new(config).layer(service_fn(not_found));
+
+        assert_eq!(
+            next_event(&mut events).await,
+            HeisenbergEvent::ModeDetected {
+                mode: Mode::Production
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_reports_outdated_builds() {
+        let project = tempfile::TempDir::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(project.path().join("dist"))
+            .working_dir(project.path())
+            .build();
+        let mut events = config.events();
+
+        let _layer = HeisenbergLayer::new(config);
+
+        assert_eq!(
+            next_event(&mut events).await,
+            HeisenbergEvent::OutdatedBuild {
+                pattern: "/*".to_string(),
+                newest_source: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_health_changes_and_proxy_errors() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = ci_config(&dev_server.url());
+        let mut events = config.events();
+        let layer = HeisenbergLayer::new(config);
+
+        layer
+            .layer(service_fn(not_found))
+            .oneshot(get("/"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_event(&mut events).await,
+            HeisenbergEvent::ModeDetected { .. }
+        ));
+        assert_eq!(
+            next_event(&mut events).await,
+            HeisenbergEvent::HealthChanged {
+                dev_server: dev_server.url(),
+                healthy: true,
+            }
+        );
+
+        // Only changes are reported
+        let dev_server_url = dev_server.url();
+        layer
+            .layer(service_fn(not_found))
+            .oneshot(get("/"))
+            .await
+            .unwrap();
+        drop(dev_server);
+        layer
+            .layer(service_fn(not_found))
+            .oneshot(get("/"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            next_event(&mut events).await,
+            HeisenbergEvent::HealthChanged {
+                dev_server: dev_server_url.clone(),
+                healthy: false,
+            }
+        );
+        match next_event(&mut events).await {
+            HeisenbergEvent::ProxyError { dev_server, .. } => {
+                assert_eq!(dev_server, dev_server_url)
+            }
+            other => panic!("expected ProxyError, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Tests for building a configuration from environment variables
+//!
+//! The variables are process-wide, so this file holds a single test that
+//! sets them in turn.
+
+use heisenberg::Heisenberg;
+use std::env;
+use std::net::SocketAddr;
+
+#[test]
+fn test_from_env_reads_routes_as_dsl_or_json() {
+    env::remove_var("HEISENBERG_SERVER_ADDR");
+
+    env::set_var(
+        "HEISENBERG_ROUTES",
+        "/admin/*=./admin/dist@http://localhost:5174; /*=./dist@http://localhost:5173",
+    );
+    let config = Heisenberg::from_env().unwrap();
+    let routes = config.routes();
+    assert_eq!(routes.len(), 2);
+    assert_eq!(routes[0].pattern, "/admin/*");
+    assert_eq!(routes[0].embed_dir.to_str(), Some("./admin/dist"));
+    assert_eq!(routes[0].dev_proxy_url, "http://localhost:5174");
+    assert_eq!(routes[1].pattern, "/*");
+    assert_eq!(routes[1].dev_proxy_url, "http://localhost:5173");
+    assert_eq!(config.global_settings().server_addr, None);
+
+    env::set_var(
+        "HEISENBERG_ROUTES",
+        r#"[{"pattern": "/app/*", "dir": "./app/dist", "dev_server": "http://localhost:4000",
+             "dev_command": "npm run dev", "build_command": ["npm", "run", "build"],
+             "working_dir": "./app", "preserve_host": true, "open_browser": true}]"#,
+    );
+    env::set_var("HEISENBERG_SERVER_ADDR", "0.0.0.0:3000");
+    let config = Heisenberg::from_env().unwrap();
+    let route = &config.routes()[0];
+    assert_eq!(route.pattern, "/app/*");
+    assert_eq!(route.dev_proxy_url, "http://localhost:4000");
+    assert_eq!(route.dev_command, ["npm", "run", "dev"]);
+    assert_eq!(route.build_command, ["npm", "run", "build"]);
+    assert_eq!(route.working_dir.to_str(), Some("./app"));
+    assert!(route.preserve_host);
+    assert!(route.open_browser);
+    let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
+    assert_eq!(config.global_settings().server_addr, Some(addr));
+
+    for invalid in [
+        "",
+        "./dist",
+        "/*=./dist@localhost:5173",
+        "[{\"dir\": \"./dist\", \"port\": 5173}]",
+        "[{\"pattern\": \"/*\"}]",
+        "[{\"dir\": \"./dist\", \"open_browser\": \"yes\"}]",
+        "[{\"dir\": ",
+    ] {
+        env::set_var("HEISENBERG_ROUTES", invalid);
+        let error = Heisenberg::from_env().unwrap_err();
+        assert!(
+            error.to_string().contains("Configuration error"),
+            "{}",
+            invalid
+        );
+    }
+
+    env::set_var("HEISENBERG_ROUTES", "/*=./dist");
+    env::set_var("HEISENBERG_SERVER_ADDR", "localhost");
+    assert!(Heisenberg::from_env().is_err());
+
+    env::remove_var("HEISENBERG_ROUTES");
+    env::remove_var("HEISENBERG_SERVER_ADDR");
+}
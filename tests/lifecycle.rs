@@ -0,0 +1,438 @@
+//! Tests for the eager startup API
+
+#![cfg(unix)]
+
+mod common;
+
+use heisenberg::core::config::DockerHost;
+use heisenberg::core::mode::Mode;
+use heisenberg::services::limits::ResourceLimits;
+use heisenberg::test::{MockDevServer, MockResponse};
+use heisenberg::{ErrorKind, Heisenberg};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+/// A local URL whose port is free for the test to claim later
+fn free_port_url() -> (String, u16) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    (format!("http://127.0.0.1:{}", port), port)
+}
+
+/// Stand in for a dev server: start answering on `port` once `log` exists
+fn serve_once_started(log: PathBuf, port: u16) {
+    tokio::spawn(async move {
+        while !log.exists() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await;
+        }
+    });
+}
+
+/// A dev command that appends a line to `log` per start and keeps running
+fn logging_command(log: &Path) -> Vec<String> {
+    vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("echo started >> {}; sleep 30", log.display()),
+    ]
+}
+
+fn starts(log: &Path) -> usize {
+    std::fs::read_to_string(log)
+        .map(|content| content.lines().count())
+        .unwrap_or(0)
+}
+
+/// Wait for the dev command to have logged `expected` starts
+///
+/// The stand-in keeps answering across a restart, so the health check can
+/// pass before the new process has written its line.
+async fn wait_for_starts(log: &Path, expected: usize) -> usize {
+    for _ in 0..250 {
+        if starts(log) >= expected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    starts(log)
+}
+
+fn dev_config(dev_server: &str, temp_dir: &TempDir, log: &Path) -> Heisenberg {
+    let mut config = common::dev_route(dev_server)
+        .pattern("/admin/*")
+        .dev_command(logging_command(log))
+        .working_dir(temp_dir.path())
+        .build();
+    config.global_settings.process_startup_timeout = Duration::from_secs(10);
+    config
+}
+
+#[tokio::test]
+async fn test_start_waits_for_health_and_restart_reruns_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let (url, port) = free_port_url();
+    serve_once_started(log.clone(), port);
+
+    let handle = dev_config(&url, &temp_dir, &log).start().await.unwrap();
+
+    assert_eq!(starts(&log), 1);
+    assert!(handle.is_running("/admin/*"));
+    let status = handle.status().await;
+    assert_eq!(status.len(), 1);
+    assert_eq!(status[0].pattern, "/admin/*");
+    assert_eq!(status[0].dev_server, url);
+    assert!(status[0].running);
+    assert!(status[0].healthy);
+    assert!(status[0].uptime.is_some());
+
+    handle.restart("/admin/*").await.unwrap();
+    assert_eq!(wait_for_starts(&log, 2).await, 2);
+    assert!(handle.is_running("/admin/*"));
+
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_start_reuses_running_dev_servers() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let dev_server = MockDevServer::start().await.unwrap();
+
+    let handle = dev_config(&dev_server.url(), &temp_dir, &log)
+        .start()
+        .await
+        .unwrap();
+
+    assert_eq!(starts(&log), 0);
+    let status = handle.status().await;
+    assert!(!status[0].running);
+    assert!(status[0].healthy);
+    assert_eq!(status[0].uptime, None);
+}
+
+#[tokio::test]
+async fn test_health_ok_when_decides_what_counts_as_healthy() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let dev_server = MockDevServer::start().await.unwrap();
+    dev_server.respond("/healthz", MockResponse::new(200));
+
+    let config = |header: &'static str| {
+        Heisenberg::new()
+            .mode_override(Mode::Development)
+            .process_startup_timeout(Duration::from_secs(1))
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .dev_command(logging_command(&log))
+            .working_dir(temp_dir.path())
+            .health_check_path("/healthz")
+            .health_ok_when(move |status, headers| {
+                status == hyper::StatusCode::OK && headers.contains_key(header)
+            })
+            .build()
+    };
+
+    // Any response used to do, but /healthz lacks the header, so the dev
+    // command is started and never becomes healthy
+    let error = config("x-app-ready").start().await.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::DevServerUnreachable);
+    assert_eq!(starts(&log), 1);
+
+    dev_server.respond(
+        "/healthz",
+        MockResponse::new(200).header("x-app-ready", "1"),
+    );
+    let handle = config("x-app-ready").start().await.unwrap();
+    assert!(handle.status().await[0].healthy);
+    assert_eq!(starts(&log), 1);
+    assert!(dev_server
+        .requests()
+        .iter()
+        .all(|request| request.path == "/healthz"));
+}
+
+#[tokio::test]
+async fn test_health_check_with_replaces_the_request() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let marker = log.clone();
+
+    // Nothing listens on the dev server URL; the log the dev command writes
+    // is what tells it is up
+    let handle = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .process_startup_timeout(Duration::from_secs(10))
+        .spa("./dist")
+        .dev_server("http://127.0.0.1:9")
+        .dev_command(logging_command(&log))
+        .working_dir(temp_dir.path())
+        .health_check_path("/never-requested")
+        .health_check_with(move |url| {
+            let marker = marker.clone();
+            async move { url == "http://127.0.0.1:9" && marker.exists() }
+        })
+        .build()
+        .start()
+        .await
+        .unwrap();
+
+    assert_eq!(starts(&log), 1);
+    assert!(handle.status().await[0].healthy);
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_start_spawns_nothing_in_production_or_ci() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let (url, _) = free_port_url();
+
+    let production = dev_config(&url, &temp_dir, &log).mode_override(Mode::Production);
+    production.start().await.unwrap();
+
+    let ci = dev_config(&url, &temp_dir, &log).ci_mode(true);
+    let handle = ci.start().await.unwrap();
+
+    assert_eq!(starts(&log), 0);
+    assert_eq!(handle.patterns().collect::<Vec<_>>(), ["/admin/*"]);
+}
+
+#[tokio::test]
+async fn test_start_spawns_nothing_for_dev_servers_on_the_docker_host() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let dev_server = MockDevServer::start().await.unwrap();
+    let url = format!("http://localhost:{}", dev_server.addr().port());
+
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .docker_host(DockerHost::Host("127.0.0.1".to_string()))
+        .spa("./dist")
+        .dev_server(&url)
+        .dev_command(logging_command(&log))
+        .working_dir(temp_dir.path())
+        .build();
+
+    let handle = config.start().await.unwrap();
+
+    assert_eq!(starts(&log), 0);
+    let status = handle.status().await;
+    assert_eq!(status[0].dev_server, dev_server.url());
+    assert!(status[0].healthy);
+}
+
+#[tokio::test]
+async fn test_dev_resource_limits_apply_to_the_dev_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("limits.log");
+    let (url, port) = free_port_url();
+    serve_once_started(log.clone(), port);
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .spa("./dist")
+        .dev_server(&url)
+        .dev_command([
+            "sh".to_string(),
+            "-c".to_string(),
+            format!(
+                "echo $(nice) $(ulimit -d) $(ulimit -t) > {}; sleep 30",
+                log.display()
+            ),
+        ])
+        .dev_resource_limits(
+            ResourceLimits::new()
+                .nice(5)
+                .max_memory(512 * 1024 * 1024)
+                .max_cpu_time(Duration::from_secs(600)),
+        )
+        .working_dir(temp_dir.path())
+        .build();
+
+    let handle = config.start().await.unwrap();
+
+    let limits = std::fs::read_to_string(&log).unwrap();
+    let limits: Vec<&str> = limits.split_whitespace().collect();
+    let nice: i32 = limits[0].parse().unwrap();
+    assert!(nice >= 5, "niceness {}", nice);
+    // ulimit reports the data segment in KiB
+    assert_eq!(limits[1..], ["524288", "600"]);
+    handle.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_start_reports_dev_commands_that_exit() {
+    let temp_dir = TempDir::new().unwrap();
+    let (url, _) = free_port_url();
+    let config = Heisenberg::new()
+        .mode_override(Mode::Development)
+        .spa("./dist")
+        .dev_server(&url)
+        .dev_command(["sh", "-c", "exit 1"])
+        .working_dir(temp_dir.path())
+        .build();
+
+    let error = config.start().await.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ProcessSpawnFailed);
+    assert!(error.to_string().contains("exited before"));
+}
+
+#[tokio::test]
+async fn test_restart_rejects_unknown_patterns() {
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("starts.log");
+    let dev_server = MockDevServer::start().await.unwrap();
+    let handle = dev_config(&dev_server.url(), &temp_dir, &log)
+        .start()
+        .await
+        .unwrap();
+
+    let error = handle.restart("/app/*").await.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConfigInvalid);
+    assert!(error.to_string().contains("/admin/*"));
+}
+
+#[cfg(feature = "tower")]
+mod layer {
+    use super::*;
+    use heisenberg::HeisenbergLayer;
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::{Request, Response, StatusCode};
+    use std::convert::Infallible;
+    use tower::{service_fn, Layer, ServiceExt};
+
+    async fn not_found(_req: Request<Full<Bytes>>) -> Result<Response<Bytes>, Infallible> {
+        Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Bytes::new())
+            .unwrap())
+    }
+
+    fn get(path: &str) -> Request<Full<Bytes>> {
+        Request::builder().uri(path).body(Full::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_layer_exposes_lazily_started_dev_servers() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("starts.log");
+        let (url, port) = free_port_url();
+        serve_once_started(log.clone(), port);
+        let layer = HeisenbergLayer::new(dev_config(&url, &temp_dir, &log));
+        let dev_servers = layer.dev_servers().unwrap();
+        assert!(!dev_servers.is_running("/admin/*"));
+
+        let response = layer
+            .layer(service_fn(not_found))
+            .oneshot(get("/admin/users"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(dev_servers.is_running("/admin/*"));
+        assert!(dev_servers.uptime("/admin/*").is_some());
+
+        // Stopped dev servers stay stopped until restarted explicitly
+        let service = layer.layer(service_fn(not_found));
+        dev_servers.stop("/admin/*").await.unwrap();
+        service.oneshot(get("/admin/users")).await.unwrap();
+        assert!(!dev_servers.is_running("/admin/*"));
+        assert_eq!(starts(&log), 1);
+
+        layer
+            .dev_servers()
+            .unwrap()
+            .restart("/admin/*")
+            .await
+            .unwrap();
+        assert!(dev_servers.is_running("/admin/*"));
+        assert_eq!(wait_for_starts(&log, 2).await, 2);
+        assert!(dev_servers.stop("/app/*").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_signal_stops_dev_servers_before_axum_returns() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("starts.log");
+        let (url, port) = free_port_url();
+        serve_once_started(log.clone(), port);
+        let layer = HeisenbergLayer::new(dev_config(&url, &temp_dir, &log));
+        let dev_servers = layer.dev_servers().unwrap();
+        let (trigger, signal) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = layer.shutdown_signal(signal);
+        let app = axum::Router::new()
+            .route("/api/health", axum::routing::get(|| async { "ok" }))
+            .layer(layer);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app)
+                .with_graceful_shutdown(shutdown)
+                .await
+        });
+
+        let response = reqwest::get(format!("http://{}/admin/users", addr))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert!(dev_servers.is_running("/admin/*"));
+
+        trigger.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(10), server)
+            .await
+            .expect("graceful shutdown finished")
+            .unwrap()
+            .unwrap();
+        assert!(!dev_servers.is_running("/admin/*"));
+        assert!(dev_servers.is_shut_down());
+        assert!(dev_servers.restart("/admin/*").await.is_err());
+        assert_eq!(starts(&log), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropping_the_shutdown_guard_stops_dev_servers() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("starts.log");
+        let (url, port) = free_port_url();
+        serve_once_started(log.clone(), port);
+        let layer = HeisenbergLayer::new(dev_config(&url, &temp_dir, &log));
+        let guard = layer.shutdown_guard();
+        let service = layer.layer(service_fn(not_found));
+        service.clone().oneshot(get("/admin/users")).await.unwrap();
+        let dev_servers = layer.dev_servers().unwrap();
+        assert!(dev_servers.is_running("/admin/*"));
+
+        drop(guard);
+
+        assert!(!dev_servers.is_running("/admin/*"));
+        // Later requests don't bring the dev server back
+        service.oneshot(get("/admin/settings")).await.unwrap();
+        assert!(!dev_servers.is_running("/admin/*"));
+        assert_eq!(starts(&log), 1);
+    }
+
+    #[tokio::test]
+    async fn test_layer_has_no_dev_servers_in_production_or_ci() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("starts.log");
+        let (url, _) = free_port_url();
+
+        let production = dev_config(&url, &temp_dir, &log).mode_override(Mode::Production);
+        assert!(HeisenbergLayer::new(production).dev_servers().is_none());
+
+        let ci = dev_config(&url, &temp_dir, &log).ci_mode(true);
+        let service = HeisenbergLayer::new(ci).layer(service_fn(not_found));
+        assert!(service.dev_servers().is_none());
+    }
+}
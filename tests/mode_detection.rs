@@ -1,6 +1,9 @@
 //! Mode detection tests
 
-use heisenberg::core::mode::{detect_mode, Mode};
+use heisenberg::core::mode::{
+    detect_ci_mode, detect_docker_host, detect_interactive, detect_mode, detect_offline_mode,
+    detect_staging, Mode,
+};
 use std::env;
 use std::sync::Mutex;
 
@@ -72,3 +75,119 @@ fn test_mode_detection_default_fallback() {
     #[cfg(not(debug_assertions))]
     assert_eq!(mode, Mode::Production);
 }
+
+#[test]
+fn test_ci_mode_env_var() {
+    let _guard = ENV_MUTEX.lock().unwrap();
+
+    env::remove_var("HEISENBERG_CI");
+    assert!(!detect_ci_mode());
+
+    for value in ["1", "true", "YES"] {
+        env::set_var("HEISENBERG_CI", value);
+        assert!(detect_ci_mode(), "Failed for value: {}", value);
+    }
+
+    env::set_var("HEISENBERG_CI", "0");
+    assert!(!detect_ci_mode());
+
+    env::remove_var("HEISENBERG_CI");
+}
+
+#[test]
+fn test_staging_mode_env_var() {
+    let _guard = ENV_MUTEX.lock().unwrap();
+
+    for value in ["staging", "STAGE"] {
+        env::set_var("HEISENBERG_MODE", value);
+        assert!(detect_staging(), "Failed for value: {}", value);
+        assert_eq!(detect_mode(), Mode::Development);
+    }
+    let config = heisenberg::Heisenberg::new()
+        .spa("./dist")
+        .staging_url("https://staging.example.com")
+        .build();
+    assert_eq!(
+        config.routes()[0].dev_proxy_url,
+        "https://staging.example.com"
+    );
+
+    env::set_var("HEISENBERG_MODE", "development");
+    assert!(!detect_staging());
+
+    env::remove_var("HEISENBERG_MODE");
+}
+
+#[test]
+fn test_offline_env_var_forces_production() {
+    let _guard = ENV_MUTEX.lock().unwrap();
+    let config = || {
+        heisenberg::Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .build()
+    };
+
+    env::remove_var("HEISENBERG_OFFLINE");
+    assert!(!detect_offline_mode());
+    assert_eq!(config().mode(), Mode::Development);
+
+    for value in ["1", "true", "YES"] {
+        env::set_var("HEISENBERG_OFFLINE", value);
+        assert!(detect_offline_mode(), "Failed for value: {}", value);
+        assert_eq!(config().mode(), Mode::Production);
+    }
+
+    env::set_var("HEISENBERG_OFFLINE", "0");
+    assert!(!detect_offline_mode());
+
+    env::remove_var("HEISENBERG_OFFLINE");
+}
+
+#[test]
+fn test_interactive_env_var_overrides_terminal_detection() {
+    let _guard = ENV_MUTEX.lock().unwrap();
+
+    for value in ["1", "true", "YES"] {
+        env::set_var("HEISENBERG_INTERACTIVE", value);
+        assert!(detect_interactive(), "Failed for value: {}", value);
+    }
+    for value in ["0", "false", "no"] {
+        env::set_var("HEISENBERG_INTERACTIVE", value);
+        assert!(!detect_interactive(), "Failed for value: {}", value);
+    }
+
+    env::remove_var("HEISENBERG_INTERACTIVE");
+    let settings = heisenberg::Heisenberg::new()
+        .interactive(false)
+        .global_settings;
+    assert!(!settings.interactive_enabled());
+}
+
+#[test]
+fn test_docker_host_env_var() {
+    let _guard = ENV_MUTEX.lock().unwrap();
+    let dev_server = || {
+        heisenberg::Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .dev_server("http://localhost:5173")
+            .dev_command(["cargo", "--version"])
+            .build()
+            .routes()[0]
+            .dev_proxy_url
+            .clone()
+    };
+
+    env::set_var("HEISENBERG_DOCKER_HOST", " 172.17.0.1 ");
+    assert_eq!(detect_docker_host().as_deref(), Some("172.17.0.1"));
+    assert_eq!(dev_server(), "http://172.17.0.1:5173");
+
+    env::set_var("HEISENBERG_DOCKER_HOST", "off");
+    assert_eq!(dev_server(), "http://localhost:5173");
+
+    env::set_var("HEISENBERG_DOCKER_HOST", "");
+    assert_eq!(detect_docker_host(), None);
+
+    env::remove_var("HEISENBERG_DOCKER_HOST");
+}
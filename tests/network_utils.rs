@@ -0,0 +1,116 @@
+//! Tests for network address helpers
+
+use heisenberg::utils::network::{
+    browser_url, forwarded_url, lan_ips, local_url, network_url, network_urls,
+};
+use std::net::SocketAddr;
+
+#[test]
+fn test_loopback_has_no_network_url() {
+    let addr: SocketAddr = "127.0.0.1:3000".parse().unwrap();
+    assert_eq!(network_url(addr), None);
+}
+
+#[test]
+fn test_specific_address_is_used_directly() {
+    let addr: SocketAddr = "192.168.1.20:3000".parse().unwrap();
+    assert_eq!(
+        network_url(addr).as_deref(),
+        Some("http://192.168.1.20:3000")
+    );
+}
+
+#[test]
+fn test_unspecified_address_resolves_to_lan_ip() {
+    let addr: SocketAddr = "0.0.0.0:3000".parse().unwrap();
+
+    // Sandboxes without a network have no LAN address to find
+    if let Some(url) = network_url(addr) {
+        assert!(url.starts_with("http://"));
+        assert!(url.ends_with(":3000"));
+        assert!(!url.contains("0.0.0.0"));
+    }
+}
+
+#[test]
+fn test_local_url() {
+    let cases = [
+        ("0.0.0.0:3000", "http://localhost:3000"),
+        ("127.0.0.1:8080", "http://localhost:8080"),
+        ("192.168.1.20:3000", "http://192.168.1.20:3000"),
+    ];
+
+    for (addr, expected) in cases {
+        assert_eq!(local_url(addr.parse().unwrap()), expected);
+    }
+}
+
+#[test]
+fn test_network_urls_for_loopback_bind() {
+    assert!(network_urls("127.0.0.1:3000".parse().unwrap()).is_empty());
+}
+
+#[test]
+fn test_network_urls_for_specific_bind() {
+    assert_eq!(
+        network_urls("10.0.0.5:3000".parse().unwrap()),
+        vec!["http://10.0.0.5:3000"]
+    );
+}
+
+#[test]
+fn test_network_urls_for_unspecified_bind() {
+    let urls = network_urls("0.0.0.0:3000".parse().unwrap());
+
+    assert_eq!(urls.len(), lan_ips().len());
+    for url in urls {
+        assert!(url.ends_with(":3000"));
+        assert!(!url.contains("127.0.0.1"));
+    }
+}
+
+/// Codespaces and Gitpod are detected from the environment, so one test
+/// covers both to keep the variables from racing
+#[test]
+fn test_forwarded_urls_in_codespaces_and_gitpod() {
+    for name in [
+        "CODESPACE_NAME",
+        "GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN",
+        "GITPOD_WORKSPACE_URL",
+    ] {
+        std::env::remove_var(name);
+    }
+    assert_eq!(forwarded_url(5173), None);
+    assert_eq!(
+        browser_url("http://localhost:5173"),
+        "http://localhost:5173"
+    );
+
+    std::env::set_var("CODESPACE_NAME", "octocat-fluffy-spoon");
+    std::env::set_var("GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN", "app.github.dev");
+    assert_eq!(
+        forwarded_url(5173).as_deref(),
+        Some("https://octocat-fluffy-spoon-5173.app.github.dev")
+    );
+    assert_eq!(
+        browser_url("http://127.0.0.1:3000/admin?tab=users"),
+        "https://octocat-fluffy-spoon-3000.app.github.dev/admin?tab=users"
+    );
+    // Only local URLs are forwarded
+    assert_eq!(
+        browser_url("https://staging.example.com/"),
+        "https://staging.example.com/"
+    );
+    std::env::remove_var("CODESPACE_NAME");
+    std::env::remove_var("GITHUB_CODESPACES_PORT_FORWARDING_DOMAIN");
+
+    std::env::set_var(
+        "GITPOD_WORKSPACE_URL",
+        "https://octocat-demo-abc123.ws-eu114.gitpod.io/",
+    );
+    assert_eq!(
+        browser_url("http://localhost:5173"),
+        "https://5173-octocat-demo-abc123.ws-eu114.gitpod.io/"
+    );
+    std::env::remove_var("GITPOD_WORKSPACE_URL");
+}
@@ -1,5 +1,6 @@
 //! Tests for package.json utilities
 
+use heisenberg::utils::package_manager::PackageManager;
 use heisenberg::utils::{infer_from_build_dir, PackageJson};
 use std::fs;
 use tempfile::TempDir;
@@ -58,3 +59,208 @@ fn test_infer_with_temp_structure() {
     assert_eq!(config.dev_port, 3000);
     assert_eq!(config.dev_url, "http://localhost:3000");
 }
+
+#[test]
+fn test_infer_reads_the_vite_config() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    let dist_dir = project_dir.join("dist");
+    fs::create_dir_all(&dist_dir).unwrap();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"scripts": {"dev": "vite"}}"#,
+    )
+    .unwrap();
+    fs::write(
+        project_dir.join("vite.config.ts"),
+        r#"import { defineConfig } from 'vite'
+// server: { port: 1111 }
+export default defineConfig(({ mode }) => ({
+  base: "/app/",
+  plugins: [react({ server: { port: 2222 } })],
+  resolve: { alias: { '@': `${__dirname}/src` } },
+  preview: { port: 4173 },
+  server: {
+    proxy: { '/api': { target: 'http://localhost:8080', rewrite: (p) => p.replace(/^\/api/, '') } },
+    host: '127.0.0.1',
+    port: 4000,
+  },
+}))
+"#,
+    )
+    .unwrap();
+
+    let config = infer_from_build_dir(&dist_dir).unwrap();
+    assert_eq!(config.dev_port, 4000);
+    assert_eq!(config.dev_url, "http://127.0.0.1:4000");
+    assert_eq!(config.base.as_deref(), Some("/app/"));
+    let heisenberg = heisenberg::Heisenberg::new().spa(&dist_dir).build();
+    assert_eq!(heisenberg.routes()[0].pattern, "/app/*");
+
+    // A port on the command line wins, and computed values are skipped
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"scripts": {"dev": "vite --port 3000"}}"#,
+    )
+    .unwrap();
+    fs::write(
+        project_dir.join("vite.config.js"),
+        "export default { base: './', server: { port: Number(process.env.PORT), host: true } }",
+    )
+    .unwrap();
+    let config = infer_from_build_dir(&dist_dir).unwrap();
+    assert_eq!(config.dev_url, "http://localhost:3000");
+    assert_eq!(config.base, None);
+}
+
+#[test]
+fn test_infer_reads_webpack_and_create_react_app_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    let build_dir = project_dir.join("build");
+    fs::create_dir_all(&build_dir).unwrap();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"homepage": "https://example.github.io/shop", "scripts": {"start": "react-scripts start"}}"#,
+    )
+    .unwrap();
+    fs::write(project_dir.join(".env.development"), "PORT=3456\n").unwrap();
+
+    let config = infer_from_build_dir(&build_dir).unwrap();
+    assert_eq!(config.dev_port, 3456);
+    assert_eq!(config.base.as_deref(), Some("/shop"));
+
+    fs::write(
+        project_dir.join("webpack.config.js"),
+        r#"module.exports = (env) => ({
+  module: { rules: [{ test: /\.tsx?$/, use: 'ts-loader' }, { test: /['"]/, type: 'asset' }] },
+  output: { path: path.resolve(__dirname, 'build'), publicPath: '/static/' },
+  devServer: { port: '8081', historyApiFallback: true },
+});
+"#,
+    )
+    .unwrap();
+    let config = infer_from_build_dir(&build_dir).unwrap();
+    assert_eq!(config.dev_url, "http://localhost:8081");
+    assert_eq!(config.base.as_deref(), Some("/static/"));
+}
+
+#[test]
+fn test_infer_finds_the_angular_project() {
+    let temp_dir = TempDir::new().unwrap();
+    let workspace = temp_dir.path();
+    fs::write(
+        workspace.join("package.json"),
+        r#"{"scripts": {"ng": "ng", "start": "ng serve"}}"#,
+    )
+    .unwrap();
+    fs::write(
+        workspace.join("angular.json"),
+        r#"{
+  "projects": {
+    "admin": {
+      "architect": {
+        "build": {"options": {"outputPath": "dist/admin"}},
+        "serve": {"options": {"port": 4300}}
+      }
+    },
+    "my-app": {
+      "architect": {
+        "build": {"options": {"outputPath": {"base": "dist/my-app"}, "baseHref": "/shop/"}},
+        "serve": {
+          "defaultConfiguration": "development",
+          "configurations": {"development": {"port": 4201}}
+        }
+      }
+    }
+  }
+}"#,
+    )
+    .unwrap();
+
+    // Nothing has been built yet
+    let config = infer_from_build_dir(&workspace.join("dist/my-app/browser")).unwrap();
+    assert_eq!(config.working_dir, workspace);
+    assert_eq!(config.dev_command, ["npx", "ng", "serve", "my-app"]);
+    assert_eq!(config.dev_url, "http://localhost:4201");
+    assert_eq!(config.base.as_deref(), Some("/shop/"));
+
+    let config = infer_from_build_dir(&workspace.join("dist/admin")).unwrap();
+    assert_eq!(config.dev_command, ["npx", "ng", "serve", "admin"]);
+    assert_eq!(config.dev_port, 4300);
+    assert_eq!(config.base, None);
+}
+
+#[test]
+fn test_infer_reads_the_package_manager() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path();
+    let dist_dir = project_dir.join("dist");
+    fs::create_dir_all(&dist_dir).unwrap();
+    fs::write(
+        project_dir.join("package.json"),
+        r#"{"packageManager": "pnpm@9.1.0+sha512.0123abcd", "scripts": {"dev": "vite"}}"#,
+    )
+    .unwrap();
+
+    let config = infer_from_build_dir(&dist_dir).unwrap();
+    let pnpm = config.package_manager.unwrap();
+    assert_eq!(pnpm.name, "pnpm");
+    assert_eq!(pnpm.version.as_deref(), Some("9.1.0"));
+
+    // Scripts switch to pnpm, through corepack if it is installed
+    let command = pnpm.command(&["npm", "run", "dev"]);
+    assert!(command.ends_with(&["pnpm", "run", "dev"].map(String::from)));
+    assert!(command.len() == 3 || command[0] == "corepack");
+    assert_eq!(pnpm.command(&["npm", "install"]), ["npm", "install"]);
+    assert_eq!(pnpm.command(&["vite"]), ["vite"]);
+
+    assert_eq!(PackageManager::parse("left-pad@1.0.0"), None);
+    assert_eq!(PackageManager::parse("yarn").unwrap().version, None);
+}
+
+#[test]
+fn test_scripts_are_split_like_a_shell_would() {
+    use heisenberg::utils::package_json::parse_command;
+
+    let command = parse_command(r#"vite --config "my config.js" --base='/a b/' ./dir\ name"#);
+    assert!(command.env.is_empty());
+    assert_eq!(
+        command.args,
+        [
+            "vite",
+            "--config",
+            "my config.js",
+            "--base=/a b/",
+            "./dir name"
+        ]
+    );
+
+    let command = parse_command(r#"NODE_ENV=dev DEBUG="vite:*" vite --mode "a \"b\"" ''"#);
+    assert_eq!(
+        command.env,
+        [
+            ("NODE_ENV".to_string(), "dev".to_string()),
+            ("DEBUG".to_string(), "vite:*".to_string())
+        ]
+    );
+    assert_eq!(command.args, ["vite", "--mode", "a \"b\"", ""]);
+
+    let temp_dir = TempDir::new().unwrap();
+    let dist_dir = temp_dir.path().join("dist");
+    fs::create_dir_all(&dist_dir).unwrap();
+    fs::write(
+        temp_dir.path().join("package.json"),
+        r#"{"scripts": {"dev": "cross-env NODE_ENV=development PORT=4100 webpack serve"}}"#,
+    )
+    .unwrap();
+    let config = infer_from_build_dir(&dist_dir).unwrap();
+    assert_eq!(config.dev_command, ["webpack", "serve"]);
+    assert_eq!(
+        config.dev_env,
+        [
+            ("NODE_ENV".to_string(), "development".to_string()),
+            ("PORT".to_string(), "4100".to_string())
+        ]
+    );
+}
@@ -0,0 +1,595 @@
+//! Tests for frontend process management
+
+use heisenberg::core::config::SpaRouteConfig;
+use heisenberg::services::spawner::{
+    BoxFuture, OutputStream, ProcessSpawner, SpawnCommand, SpawnedProcess,
+};
+use heisenberg::services::ProcessManager;
+use httpmock::MockServer;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Wait for a file written by a spawned shell to appear and read it
+async fn read_when_written(path: &std::path::Path) -> String {
+    for _ in 0..50 {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            if !content.trim().is_empty() {
+                return content.trim().to_string();
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("{} was never written", path.display());
+}
+
+#[cfg(unix)]
+fn pid_is_alive(pid: &str) -> bool {
+    // Killed orphans linger as zombies until init reaps them
+    if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        return !stat.contains(") Z ");
+    }
+
+    std::process::Command::new("kill")
+        .args(["-0", pid])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stop_process_kills_grandchildren() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let pid_file = temp_dir.path().join("grandchild.pid");
+
+    // Mimic `npm run dev`: a shell that backgrounds the real server process
+    let command = vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+    ];
+
+    let route = SpaRouteConfig {
+        dev_command: command,
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    manager
+        .start_process("app", &route)
+        .await
+        .expect("process should start");
+
+    let grandchild_pid = read_when_written(&pid_file).await;
+    assert!(pid_is_alive(&grandchild_pid));
+
+    manager.stop_process("app").await.unwrap();
+
+    assert!(!manager.is_process_running("app"));
+
+    // Signal delivery is asynchronous; give the kernel a moment to finish
+    for _ in 0..20 {
+        if !pid_is_alive(&grandchild_pid) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(
+        !pid_is_alive(&grandchild_pid),
+        "grandchild {} outlived its dev server",
+        grandchild_pid
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_stopping_a_stubborn_process_does_not_block_the_runtime() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let route = SpaRouteConfig {
+        // Ignores SIGTERM, so stopping waits out the grace period
+        dev_command: vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "trap '' TERM; sleep 30".to_string(),
+        ],
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        ..Default::default()
+    };
+    let manager = ProcessManager::new();
+    manager.start_process("app", &route).await.unwrap();
+
+    // This test runs on a single-threaded runtime, so the ticker only
+    // advances while the stop is waiting asynchronously
+    let ticks = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let ticker = tokio::spawn({
+        let ticks = ticks.clone();
+        async move {
+            loop {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+        }
+    });
+    manager.stop_process("app").await.unwrap();
+    ticker.abort();
+
+    assert!(!manager.is_process_running("app"));
+    assert!(ticks.load(std::sync::atomic::Ordering::SeqCst) >= 10);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_env_files_passed_to_dev_command() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let output = temp_dir.path().join("env.out");
+
+    std::fs::write(
+        temp_dir.path().join(".env"),
+        "HEISENBERG_TEST_API=from-env\nHEISENBERG_TEST_MODE=base\n",
+    )
+    .unwrap();
+    std::fs::write(
+        temp_dir.path().join(".env.development"),
+        "HEISENBERG_TEST_MODE=development\n",
+    )
+    .unwrap();
+
+    let route = SpaRouteConfig {
+        dev_command: vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!(
+                "echo \"$HEISENBERG_TEST_API $HEISENBERG_TEST_MODE\" > {}; sleep 30",
+                output.display()
+            ),
+        ],
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        load_env_files: true,
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    manager.start_process("app", &route).await.unwrap();
+
+    assert_eq!(read_when_written(&output).await, "from-env development");
+    manager.stop_all_processes().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ci_mode_never_spawns() {
+    let temp_dir = TempDir::new().unwrap();
+    let marker = temp_dir.path().join("spawned");
+
+    let route = SpaRouteConfig {
+        dev_command: vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            format!("touch {}", marker.display()),
+        ],
+        working_dir: temp_dir.path().to_path_buf(),
+        // Nothing listens here, so waiting on health would hit the timeout
+        dev_proxy_url: "http://127.0.0.1:9".to_string(),
+        open_browser: true,
+        ..Default::default()
+    };
+
+    let config = heisenberg::Heisenberg::new().ci_mode(true);
+    let manager = ProcessManager::with_settings(config.global_settings());
+    assert!(manager.is_ci_mode());
+
+    tokio::time::timeout(Duration::from_secs(1), manager.start_process("app", &route))
+        .await
+        .expect("CI mode waited on the dev server")
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!marker.exists());
+    assert!(!manager.is_process_running("app"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_recent_output_survives_process_exit() {
+    let temp_dir = TempDir::new().unwrap();
+    let route = SpaRouteConfig {
+        dev_command: vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "for i in 1 2 3 4 5; do echo line$i; done; sleep 0.2; echo crashed >&2; exit 1"
+                .to_string(),
+        ],
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: "http://127.0.0.1:9".to_string(),
+        ..Default::default()
+    };
+
+    let config = heisenberg::Heisenberg::new().dev_output_lines(3);
+    let manager = ProcessManager::with_settings(config.global_settings());
+    assert!(manager.recent_output("app").is_empty());
+
+    let error = manager.start_process("app", &route).await.unwrap_err();
+    assert!(error.to_string().contains("exited before"));
+    assert!(!manager.is_process_running("app"));
+
+    // The reader threads may still be draining the pipes
+    for _ in 0..20 {
+        if manager.recent_output("app").last().map(String::as_str) == Some("crashed") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(manager.recent_output("app"), ["line4", "line5", "crashed"]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ready_line_ends_startup_wait() {
+    let temp_dir = TempDir::new().unwrap();
+    let route = SpaRouteConfig {
+        dev_command: vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            r"printf '\033[32mVITE v5.0.0\033[39m  ready in 120 ms\n'; sleep 30".to_string(),
+        ],
+        working_dir: temp_dir.path().to_path_buf(),
+        // Nothing listens here, so only the ready line can end the wait
+        dev_proxy_url: "http://127.0.0.1:9".to_string(),
+        ..Default::default()
+    };
+
+    let config = heisenberg::Heisenberg::new().process_startup_timeout(Duration::from_secs(20));
+    let manager = ProcessManager::with_settings(config.global_settings());
+
+    tokio::time::timeout(Duration::from_secs(5), manager.start_process("app", &route))
+        .await
+        .expect("startup ignored the ready line")
+        .unwrap();
+
+    assert!(manager.is_process_running("app"));
+    assert_eq!(
+        manager.recent_output("app"),
+        ["VITE v5.0.0  ready in 120 ms"]
+    );
+    manager.stop_all_processes().await.unwrap();
+}
+
+#[cfg(unix)]
+fn sh(script: String) -> Vec<String> {
+    vec!["sh".to_string(), "-c".to_string(), script]
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_setup_commands_run_in_order_before_dev_command() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let log = temp_dir.path().join("steps.log");
+    let append = |word: &str| format!("echo {} >> {}", word, log.display());
+
+    let route = SpaRouteConfig {
+        setup_commands: vec![
+            sh(append("codegen")),
+            sh(format!("sleep 0.2; {}", append("env"))),
+        ],
+        dev_command: sh(format!("{}; sleep 30", append("dev"))),
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    manager.start_process("app", &route).await.unwrap();
+
+    for _ in 0..50 {
+        if std::fs::read_to_string(&log).unwrap().lines().count() == 3 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap(),
+        "codegen\nenv\ndev\n"
+    );
+    manager.stop_all_processes().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_failed_setup_command_stops_startup() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let marker = temp_dir.path().join("dev-started");
+
+    let route = SpaRouteConfig {
+        setup_commands: vec![sh(
+            "echo 'codegen: schema.graphql not found' >&2; exit 4".to_string()
+        )],
+        dev_command: sh(format!("touch {}; sleep 30", marker.display())),
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    let error = manager.start_process("app", &route).await.unwrap_err();
+
+    let message = error.to_string();
+    assert!(message.contains("Setup command 'sh -c"));
+    assert!(message.contains("schema.graphql not found"));
+    assert!(!manager.is_process_running("app"));
+    assert!(!marker.exists());
+    assert_eq!(
+        manager.recent_output("app"),
+        ["codegen: schema.graphql not found"]
+    );
+}
+
+#[tokio::test]
+async fn test_missing_programs_are_reported_before_spawning() {
+    let temp_dir = TempDir::new().unwrap();
+    let bin = temp_dir.path().join("node_modules/.bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::write(bin.join("heisenberg-test-vite"), "").unwrap();
+
+    let route = SpaRouteConfig {
+        dev_command: vec!["heisenberg-test-vite".to_string()],
+        working_dir: temp_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    let manager = ProcessManager::new();
+    let message = manager
+        .start_process("app", &route)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("Dev command not found: heisenberg-test-vite"));
+    assert!(message.contains("node_modules/.bin"));
+    assert!(!manager.is_process_running("app"));
+
+    let route = SpaRouteConfig {
+        setup_commands: vec![vec!["heisenberg-test-codegen".to_string()]],
+        ..route
+    };
+    let message = manager
+        .start_process("app", &route)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("Dev command not found: heisenberg-test-codegen"));
+}
+
+#[test]
+fn test_engines_ranges() {
+    use heisenberg::services::preflight::satisfies;
+
+    for (version, range) in [
+        ("20.11.1", ">=18"),
+        ("v18.19.0", "^18.17.0"),
+        ("18.0.0", ">= 16 < 19"),
+        ("9.1.0", "9.x"),
+        ("9.1.4", "~9.1"),
+        ("0.3.2", "^0.3.1"),
+        ("16.20.0", "^14 || ^16"),
+        ("18.5.0", "16 - 18"),
+        ("22.0.0", "*"),
+        ("22.0.0", "not a range"),
+    ] {
+        assert!(satisfies(version, range), "{} {}", version, range);
+    }
+    for (version, range) in [
+        ("16.20.0", ">=18"),
+        ("19.0.0", "^18.17.0"),
+        ("0.4.0", "^0.3.1"),
+        ("9.2.0", "~9.1"),
+        ("20.0.0", "^14 || ^16"),
+        ("19.0.0", "16 - 18"),
+        ("8.0.0", ">8"),
+    ] {
+        assert!(!satisfies(version, range), "{} {}", version, range);
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_script_variables_override_env_files() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let output = temp_dir.path().join("env.out");
+    std::fs::write(
+        temp_dir.path().join(".env"),
+        "HEISENBERG_TEST_SCRIPT_MODE=from-file\n",
+    )
+    .unwrap();
+
+    let route = SpaRouteConfig {
+        dev_command: sh(format!(
+            "echo \"$HEISENBERG_TEST_SCRIPT_MODE\" > {}; sleep 30",
+            output.display()
+        )),
+        dev_env: vec![(
+            "HEISENBERG_TEST_SCRIPT_MODE".to_string(),
+            "from-script".to_string(),
+        )],
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        load_env_files: true,
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    manager.start_process("app", &route).await.unwrap();
+
+    assert_eq!(read_when_written(&output).await, "from-script");
+    manager.stop_all_processes().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_dev_shell_runs_the_script_with_the_shell() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let output = temp_dir.path().join("shell.out");
+
+    let config = heisenberg::Heisenberg::new()
+        .spa(temp_dir.path().join("dist"))
+        .dev_shell(format!(
+            "echo codegen > {out} && echo \"dev on $((3000 + 1))\" >> {out}; sleep 30",
+            out = output.display()
+        ))
+        .working_dir(temp_dir.path())
+        .dev_server(&dev_server.base_url())
+        .build();
+
+    let manager = ProcessManager::new();
+    manager
+        .start_process("app", &config.routes()[0])
+        .await
+        .unwrap();
+
+    for _ in 0..50 {
+        if read_when_written(&output).await.lines().count() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(read_when_written(&output).await, "codegen\ndev on 3001");
+    manager.stop_all_processes().await.unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_process_stats_cover_the_running_dev_server() {
+    let dev_server = MockServer::start_async().await;
+    let temp_dir = TempDir::new().unwrap();
+    let pid_file = temp_dir.path().join("pid");
+    let route = SpaRouteConfig {
+        dev_command: sh(format!("echo $$ > {}; sleep 30", pid_file.display())),
+        working_dir: temp_dir.path().to_path_buf(),
+        dev_proxy_url: dev_server.base_url(),
+        ..Default::default()
+    };
+
+    let manager = ProcessManager::new();
+    assert_eq!(manager.process_stats("app"), None);
+    manager.start_process("app", &route).await.unwrap();
+
+    let pid = read_when_written(&pid_file).await;
+    let stats = manager.process_stats("app").unwrap();
+    assert_eq!(stats.pid.map(|pid| pid.to_string()), Some(pid));
+    #[cfg(feature = "process-stats")]
+    {
+        // The sleep it started counts too
+        assert!(stats.memory_bytes.unwrap() > 0);
+        assert!(stats.cpu_percent.unwrap() >= 0.0);
+    }
+
+    manager.stop_all_processes().await.unwrap();
+    assert_eq!(manager.process_stats("app"), None);
+}
+
+/// Records what it is asked to spawn instead of starting processes
+#[derive(Debug, Default)]
+struct FakeSpawner {
+    spawned: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+struct FakeProcess {
+    /// Setup commands exit at once, dev servers run until terminated
+    dev_server: bool,
+    spawned: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+impl ProcessSpawner for FakeSpawner {
+    fn spawn(&self, command: &SpawnCommand) -> std::io::Result<Box<dyn SpawnedProcess>> {
+        let mut line = vec![command.program.clone()];
+        line.extend(command.args.iter().cloned());
+        self.spawned.lock().unwrap().push(line.join(" "));
+        Ok(Box::new(FakeProcess {
+            dev_server: command.inherit_stdin,
+            spawned: self.spawned.clone(),
+        }))
+    }
+}
+
+impl SpawnedProcess for FakeProcess {
+    fn id(&self) -> Option<u32> {
+        None
+    }
+
+    fn take_output(&mut self) -> Vec<OutputStream> {
+        let text: &[u8] = if self.dev_server {
+            b"VITE v5.0.0  ready in 80 ms\n"
+        } else {
+            b"generated 3 files\n"
+        };
+        vec![Box::new(std::io::Cursor::new(text))]
+    }
+
+    fn wait(&mut self) -> BoxFuture<'_, std::io::Result<Option<i32>>> {
+        let dev_server = self.dev_server;
+        Box::pin(async move {
+            if dev_server {
+                std::future::pending::<()>().await;
+            }
+            Ok(Some(0))
+        })
+    }
+
+    fn terminate(&mut self) -> BoxFuture<'_, Option<i32>> {
+        self.spawned.lock().unwrap().push("terminated".to_string());
+        Box::pin(async { Some(143) })
+    }
+}
+
+#[tokio::test]
+async fn test_custom_spawner_runs_every_process() {
+    let temp_dir = TempDir::new().unwrap();
+    let spawner = FakeSpawner::default();
+    let spawned = spawner.spawned.clone();
+
+    // Neither program exists here, which a remote spawner doesn't mind
+    let config = heisenberg::Heisenberg::new()
+        .process_spawner(spawner)
+        .spa(temp_dir.path().join("dist"))
+        .dev_commands([
+            vec!["remote-codegen", "--out", "src/gen"],
+            vec!["remote-vite", "--port", "5173"],
+        ])
+        .working_dir(temp_dir.path())
+        .dev_server("http://127.0.0.1:9")
+        .build();
+
+    let manager = ProcessManager::with_settings(config.global_settings());
+    tokio::time::timeout(
+        Duration::from_secs(5),
+        manager.start_process("app", &config.routes()[0]),
+    )
+    .await
+    .expect("startup ignored the fake's ready line")
+    .unwrap();
+
+    assert!(manager.is_process_running("app"));
+    assert_eq!(
+        manager.recent_output("app"),
+        ["generated 3 files", "VITE v5.0.0  ready in 80 ms"]
+    );
+    manager.stop_all_processes().await.unwrap();
+    assert!(!manager.is_process_running("app"));
+    assert_eq!(
+        *spawned.lock().unwrap(),
+        [
+            "remote-codegen --out src/gen",
+            "remote-vite --port 5173",
+            "terminated"
+        ]
+    );
+}
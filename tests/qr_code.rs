@@ -0,0 +1,27 @@
+//! QR code rendering tests
+
+#![cfg(feature = "qr")]
+
+use heisenberg::utils::qr::render_qr;
+use heisenberg::Heisenberg;
+
+#[test]
+fn test_render_qr() {
+    let qr = render_qr("http://192.168.1.20:3000").unwrap();
+    let lines: Vec<_> = qr.lines().collect();
+
+    // Half-block rendering packs two modules per character row
+    assert!(lines.len() > 10);
+    assert!(qr.contains('█') || qr.contains('▀') || qr.contains('▄'));
+}
+
+#[test]
+fn test_qr_code_configuration() {
+    let config = Heisenberg::new()
+        .server_addr(([0, 0, 0, 0], 3000))
+        .qr_code(true)
+        .spa("./dist")
+        .build();
+
+    assert!(config.global_settings().print_qr_code);
+}
@@ -14,6 +14,7 @@ fn create_test_route(pattern: &str, embed_dir: &str) -> SpaRouteConfig {
         working_dir: PathBuf::from("."),
         fallback_file: Some("index.html".to_string()),
         open_browser: false,
+        ..Default::default()
     }
 }
 
@@ -32,7 +33,7 @@ fn test_exact_route_matching() {
         create_test_route("/app", "./app/dist"),
     ];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
     assert!(router.match_route("/admin").is_some());
     assert!(router.match_route("/app").is_some());
@@ -46,7 +47,7 @@ fn test_prefix_route_matching() {
         create_test_route("/api/*", "./api/dist"),
     ];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
     assert!(router.match_route("/admin").is_some());
     assert!(router.match_route("/admin/").is_some());
@@ -59,7 +60,7 @@ fn test_prefix_route_matching() {
 fn test_catch_all_route() {
     let routes = vec![create_test_route("/*", "./dist")];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
     assert!(router.match_route("/").is_some());
     assert!(router.match_route("/anything").is_some());
@@ -74,7 +75,7 @@ fn test_route_priority() {
         create_test_route("/admin/users", "./users"), // Exact (highest priority)
     ];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
     // Exact match should win over prefix and catch-all
     let matched = router.match_route("/admin/users").unwrap();
@@ -90,18 +91,51 @@ fn test_route_priority() {
 }
 
 #[test]
-fn test_route_caching() {
+fn test_repeated_matches() {
     let routes = vec![create_test_route("/admin/*", "./admin")];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
-    // First match should populate cache
+    // Lookups don't depend on earlier ones
     assert!(router.match_route("/admin/users").is_some());
-
-    // Second match should use cache (we can't directly test this, but it should work)
     assert!(router.match_route("/admin/users").is_some());
 }
 
+#[test]
+fn test_most_specific_route_wins() {
+    let routes = vec![
+        create_test_route("/*", "./dist"),
+        create_test_route("/api/*", "./api"),
+        create_test_route("/api/v1/*", "./v1"),
+        create_test_route("/api/v1/health", "./health"),
+    ];
+
+    let router = Router::new(routes, Mode::Development).unwrap();
+    let pattern = |path| router.match_route(path).unwrap().pattern.clone();
+
+    assert_eq!(pattern("/api/v1/health"), "/api/v1/health");
+    assert_eq!(pattern("/api/v1/health/deep"), "/api/v1/*");
+    assert_eq!(pattern("/api/v1"), "/api/v1/*");
+    assert_eq!(pattern("/api/v2/users"), "/api/*");
+    assert_eq!(pattern("/apiary"), "/*");
+    assert_eq!(pattern("/"), "/*");
+}
+
+#[test]
+fn test_many_routes() {
+    let routes: Vec<_> = (0..50)
+        .map(|i| create_test_route(&format!("/app{}/*", i), "./dist"))
+        .collect();
+
+    let router = Router::new(routes, Mode::Development).unwrap();
+
+    assert_eq!(
+        router.match_route("/app42/settings").unwrap().pattern,
+        "/app42/*"
+    );
+    assert!(router.match_route("/app50").is_none());
+}
+
 #[test]
 fn test_invalid_patterns() {
     let routes = vec![SpaRouteConfig {
@@ -112,6 +146,7 @@ fn test_invalid_patterns() {
         working_dir: PathBuf::from("."),
         fallback_file: Some("index.html".to_string()),
         open_browser: false,
+        ..Default::default()
     }];
 
     let router = Router::new(routes, Mode::Development);
@@ -122,7 +157,7 @@ fn test_invalid_patterns() {
 fn test_route_handler_development_mode() {
     let routes = vec![create_test_route("/admin/*", "./admin/dist")];
 
-    let mut router = Router::new(routes, Mode::Development).unwrap();
+    let router = Router::new(routes, Mode::Development).unwrap();
 
     let handler = router.route_handler("/admin/users").unwrap();
     match handler {
@@ -138,7 +173,7 @@ fn test_route_handler_development_mode() {
 fn test_route_handler_production_mode() {
     let routes = vec![create_test_route("/admin/*", "./admin/dist")];
 
-    let mut router = Router::new(routes, Mode::Production).unwrap();
+    let router = Router::new(routes, Mode::Production).unwrap();
 
     let handler = router.route_handler("/admin/users").unwrap();
     match handler {
@@ -164,3 +199,87 @@ fn test_route_validation_duplicate_patterns() {
         assert!(e.to_string().contains("Duplicate route pattern"));
     }
 }
+
+#[test]
+fn test_insert_and_remove_routes() {
+    let mut router =
+        Router::new(vec![create_test_route("/*", "./dist")], Mode::Production).unwrap();
+
+    router
+        .insert(create_test_route("/admin/*", "./admin/dist"))
+        .unwrap();
+    assert_eq!(
+        router.match_route("/admin/users").unwrap().pattern,
+        "/admin/*"
+    );
+
+    // Rejected like in Router::new, leaving the router as it was
+    let err = router
+        .insert(create_test_route("/admin/*", "./other/dist"))
+        .unwrap_err();
+    assert!(err.to_string().contains("Duplicate route pattern"));
+    assert!(router.insert(create_test_route("", "./dist")).is_err());
+    assert_eq!(router.routes().len(), 2);
+
+    let removed = router.remove("/admin/*").unwrap();
+    assert_eq!(removed.embed_dir, PathBuf::from("./admin/dist"));
+    assert_eq!(router.match_route("/admin/users").unwrap().pattern, "/*");
+    assert!(router.remove("/admin/*").is_none());
+}
+
+#[test]
+fn test_router_handle_swaps_routes() {
+    use heisenberg::core::router::RouterHandle;
+
+    let handle = RouterHandle::new(Router::new(Vec::new(), Mode::Development).unwrap());
+    let snapshot = handle.snapshot();
+
+    handle
+        .insert(create_test_route("/plugins/charts/*", "./charts/dist"))
+        .unwrap();
+    let shared = handle.clone();
+    assert!(matches!(
+        shared.route_handler("/plugins/charts/"),
+        Some(RouteHandler::Proxy(_))
+    ));
+    // Earlier snapshots keep their routes
+    assert!(snapshot.route_handler("/plugins/charts/").is_none());
+
+    assert!(handle
+        .insert(create_test_route("/plugins/charts/*", "./x"))
+        .is_err());
+    handle.remove("/plugins/charts/*").unwrap();
+    assert!(shared.route_handler("/plugins/charts/").is_none());
+    let err = handle.remove("/plugins/charts/*").unwrap_err();
+    assert!(err.to_string().contains("No route with pattern"));
+}
+
+#[test]
+fn test_param_segments_match_any_segment() {
+    let routes = vec![
+        create_test_route("/admin/*", "./admin/dist"),
+        create_test_route("/:tenant/app/*", "./tenants"),
+        create_test_route("/:tenant/about", "./about"),
+    ];
+
+    let router = Router::new(routes, Mode::Production).unwrap();
+
+    let matched = |path| router.match_route(path).map(|route| route.pattern.as_str());
+    assert_eq!(matched("/acme/app/settings"), Some("/:tenant/app/*"));
+    assert_eq!(matched("/acme/app"), Some("/:tenant/app/*"));
+    assert_eq!(matched("/acme/about"), Some("/:tenant/about"));
+    // Literal segments win over parameters
+    assert_eq!(matched("/admin/app/settings"), Some("/admin/*"));
+    assert_eq!(matched("/acme/other"), None);
+    assert_eq!(matched("/../app/settings"), None);
+    assert_eq!(matched("//app/settings"), None);
+}
+
+#[test]
+fn test_invalid_pattern_params_are_rejected() {
+    for pattern in ["/:/app/*", "/:tenant/:tenant/*"] {
+        let err =
+            Router::new(vec![create_test_route(pattern, "./dist")], Mode::Production).unwrap_err();
+        assert!(err.to_string().contains("Invalid parameter"), "{}", pattern);
+    }
+}
@@ -0,0 +1,427 @@
+//! Tests for static file serving
+
+mod common;
+
+use common::TestSpaFixture;
+use heisenberg::core::config::{EtagStrategy, HeaderRule, SymlinkPolicy};
+use heisenberg::services::{AssetCache, StaticFileService};
+
+#[tokio::test]
+async fn test_cached_files_are_served_from_memory() {
+    let fixture = TestSpaFixture::new().unwrap();
+    let file = fixture.add_file("app.js", "console.log('v1')").unwrap();
+    let cache = AssetCache::new(1024);
+    let service = StaticFileService::new(fixture.dist_path(), None).with_cache(cache.clone());
+
+    let response = service.serve_file("/app.js").await.unwrap();
+    assert_eq!(response.body(), "console.log('v1')");
+    assert!(cache.contains(&file));
+    assert_eq!(cache.size(), "console.log('v1')".len());
+
+    // A cache hit doesn't touch the disk
+    std::fs::remove_file(&file).unwrap();
+    let response = service.serve_file("/app.js").await.unwrap();
+    assert_eq!(response.body(), "console.log('v1')");
+}
+
+#[tokio::test]
+async fn test_cache_evicts_least_recently_used_files() {
+    let fixture = TestSpaFixture::new().unwrap();
+    let a = fixture.add_file("a.js", vec![b'a'; 40]).unwrap();
+    let b = fixture.add_file("b.js", vec![b'b'; 40]).unwrap();
+    let c = fixture.add_file("c.js", vec![b'c'; 40]).unwrap();
+    let big = fixture.add_file("big.js", vec![b'x'; 200]).unwrap();
+    let cache = AssetCache::new(100);
+    let service = StaticFileService::new(fixture.dist_path(), None).with_cache(cache.clone());
+
+    service.serve_file("/a.js").await.unwrap();
+    service.serve_file("/b.js").await.unwrap();
+    service.serve_file("/a.js").await.unwrap();
+    service.serve_file("/c.js").await.unwrap();
+
+    assert!(cache.contains(&a));
+    assert!(!cache.contains(&b));
+    assert!(cache.contains(&c));
+    assert_eq!(cache.size(), 80);
+
+    // Files over the whole budget are served but never cached
+    let response = service.serve_file("/big.js").await.unwrap();
+    assert_eq!(response.body().len(), 200);
+    assert!(!cache.contains(&big));
+    assert_eq!(cache.len(), 2);
+}
+
+#[tokio::test]
+async fn test_precompressed_variants_follow_accept_encoding() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file("app.js", "console.log('plain')").unwrap();
+    fixture.add_file("app.js.br", "brotli bytes").unwrap();
+    fixture.add_file("app.js.gz", "gzip bytes").unwrap();
+    let service = StaticFileService::new(fixture.dist_path(), None);
+
+    let serve = |accept_encoding| service.serve_encoded("/app.js", None, accept_encoding);
+    let brotli = serve(Some("gzip, deflate, br")).await.unwrap();
+    assert_eq!(brotli.body(), "brotli bytes");
+    assert_eq!(brotli.headers()["content-encoding"], "br");
+    assert_eq!(
+        brotli.headers()["content-type"],
+        "application/javascript; charset=utf-8"
+    );
+
+    let gzip = serve(Some("gzip, deflate")).await.unwrap();
+    assert_eq!(gzip.body(), "gzip bytes");
+    assert_eq!(gzip.headers()["content-encoding"], "gzip");
+    assert_ne!(gzip.headers()["etag"], brotli.headers()["etag"]);
+
+    let plain = serve(Some("br;q=0, gzip;q=0")).await.unwrap();
+    assert_eq!(plain.body(), "console.log('plain')");
+    assert!(!plain.headers().contains_key("content-encoding"));
+
+    // Every variant says it depends on Accept-Encoding, the plain one too
+    for response in [&brotli, &gzip, &plain] {
+        assert_eq!(response.headers()["vary"], "accept-encoding");
+    }
+    let plain = service.serve_file("/app.js").await.unwrap();
+    assert_eq!(plain.headers()["vary"], "accept-encoding");
+}
+
+#[tokio::test]
+async fn test_files_without_variants_do_not_vary() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file("app.js", "console.log('plain')").unwrap();
+    fixture.add_file("orphan.js.br", "brotli bytes").unwrap();
+    let service = StaticFileService::new(fixture.dist_path(), None);
+
+    let response = service
+        .serve_encoded("/app.js", None, Some("br"))
+        .await
+        .unwrap();
+    assert_eq!(response.body(), "console.log('plain')");
+    assert!(!response.headers().contains_key("vary"));
+
+    // A variant whose original is gone isn't served
+    assert!(service
+        .serve_encoded("/orphan.js", None, Some("br"))
+        .await
+        .is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_policies() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempfile::TempDir::new().unwrap();
+    std::fs::write(outside.path().join("secret.txt"), "outside").unwrap();
+    let fixture = TestSpaFixture::new().unwrap();
+    let inside = fixture.add_file("real/app.js", "inside").unwrap();
+    let dist = fixture.dist_path();
+    symlink(inside.parent().unwrap(), dist.join("linked")).unwrap();
+    symlink(outside.path(), dist.join("release")).unwrap();
+
+    let service = |policy| StaticFileService::new(dist, None).with_symlinks(policy);
+
+    let within = service(SymlinkPolicy::WithinRoot);
+    assert_eq!(
+        within.serve_file("/linked/app.js").await.unwrap().body(),
+        "inside"
+    );
+    assert!(within.serve_file("/release/secret.txt").await.is_err());
+
+    let follow = service(SymlinkPolicy::Follow);
+    assert_eq!(
+        follow
+            .serve_file("/release/secret.txt")
+            .await
+            .unwrap()
+            .body(),
+        "outside"
+    );
+
+    let deny = service(SymlinkPolicy::Deny);
+    assert!(deny.serve_file("/linked/app.js").await.is_err());
+    assert_eq!(
+        deny.serve_file("/real/app.js").await.unwrap().body(),
+        "inside"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_root_may_be_a_symlink() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file("app.js", "current release").unwrap();
+    let links = tempfile::TempDir::new().unwrap();
+    let current = links.path().join("current");
+    std::os::unix::fs::symlink(fixture.dist_path(), &current).unwrap();
+
+    for policy in [SymlinkPolicy::WithinRoot, SymlinkPolicy::Deny] {
+        let service = StaticFileService::new(&current, None).with_symlinks(policy);
+        let response = service.serve_file("/app.js").await.unwrap();
+        assert_eq!(response.body(), "current release");
+    }
+}
+
+#[tokio::test]
+async fn test_dotfiles_are_not_served_unless_allowed() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file(".env", "SECRET=1").unwrap();
+    fixture.add_file(".git/config", "[core]").unwrap();
+    fixture
+        .add_file(".well-known/security.txt", "Contact: x")
+        .unwrap();
+    fixture.add_file(".htaccess", "Deny from all").unwrap();
+
+    let service = StaticFileService::new(fixture.dist_path(), None);
+    assert!(service.serve_file("/.env").await.is_err());
+    assert!(service.serve_file("/%2Eenv").await.is_err());
+    assert!(service.serve_file("/.git/config").await.is_err());
+    assert!(service.serve_file("/.htaccess").await.is_err());
+    let response = service
+        .serve_file("/.well-known/security.txt")
+        .await
+        .unwrap();
+    assert_eq!(response.body(), "Contact: x");
+
+    let service =
+        StaticFileService::new(fixture.dist_path(), None).with_allowed_dotfiles([".htaccess"]);
+    assert!(service.serve_file("/.htaccess").await.is_ok());
+    assert!(service
+        .serve_file("/.well-known/security.txt")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_directory_listing() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file("reports/2024 q1.html", "q1").unwrap();
+    fixture.add_file("reports/archive/old.html", "old").unwrap();
+    fixture.add_file("reports/.env", "SECRET=1").unwrap();
+    fixture
+        .add_file("docs/index.html", "<h1>Docs</h1>")
+        .unwrap();
+
+    let plain = StaticFileService::new(fixture.dist_path(), None);
+    assert!(plain.serve_file("/reports/").await.is_err());
+
+    let service = StaticFileService::new(fixture.dist_path(), None).with_directory_listing();
+    let response = service.serve_file("/reports/").await.unwrap();
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/html; charset=utf-8"
+    );
+    let html = String::from_utf8_lossy(response.body()).into_owned();
+    assert!(html.contains("<title>Index of /reports/</title>"));
+    assert!(html.contains(r#"<a href="../">../</a>"#));
+    assert!(html.contains(r#"<a href="archive/">archive/</a>"#));
+    assert!(html.contains(r#"<a href="2024%20q1.html">2024 q1.html</a>"#));
+    assert!(html.find("archive/").unwrap() < html.find("2024 q1.html").unwrap());
+    assert!(!html.contains(".env"));
+
+    // Without a trailing slash, links go through the directory's name
+    let response = service.serve_file("/reports").await.unwrap();
+    assert!(String::from_utf8_lossy(response.body()).contains(r#"href="reports/archive/""#));
+
+    // Directories with an index.html are served as before
+    let response = service.serve_file("/docs/").await.unwrap();
+    assert_eq!(response.body(), "<h1>Docs</h1>");
+}
+
+#[tokio::test]
+async fn test_header_rules_match_file_globs() {
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture.add_file("assets/index-4f2a.js", "app").unwrap();
+    fixture
+        .add_file("assets/chunks/vendor.js", "vendor")
+        .unwrap();
+    fixture.add_file("icons/site.webmanifest", "{}").unwrap();
+    let rule = |glob: &str, name: &str, value: &str| HeaderRule {
+        glob: glob.to_string(),
+        name: name.to_string(),
+        value: value.to_string(),
+    };
+    let service = StaticFileService::new(fixture.dist_path(), Some("index.html".into()))
+        .with_header_rules(vec![
+            rule("/assets/*.js", "Cache-Control", "max-age=60"),
+            rule(
+                "/assets/*.js",
+                "Cache-Control",
+                "public, max-age=31536000, immutable",
+            ),
+            rule("*.webmanifest", "Content-Type", "application/manifest+json"),
+            rule("*.html", "Cache-Control", "no-cache"),
+        ]);
+
+    let response = service.serve_file("/assets/index-4f2a.js").await.unwrap();
+    assert_eq!(
+        response.headers()["cache-control"],
+        "public, max-age=31536000, immutable"
+    );
+    let response = service
+        .serve_file("/assets/chunks/vendor.js")
+        .await
+        .unwrap();
+    assert!(!response.headers().contains_key("cache-control"));
+    let response = service.serve_file("/icons/site.webmanifest").await.unwrap();
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/manifest+json"
+    );
+
+    // Rules follow the file served, so the fallback gets the HTML rule
+    let response = service.serve_file("/users/42").await.unwrap();
+    assert_eq!(response.headers()["cache-control"], "no-cache");
+}
+
+#[tokio::test]
+async fn test_strong_etags_follow_contents() {
+    let one = TestSpaFixture::new().unwrap();
+    let two = TestSpaFixture::new().unwrap();
+    one.add_file("app.js", "console.log('v1')").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let file = two.add_file("app.js", "console.log('v1')").unwrap();
+    let etag = |service: &StaticFileService| {
+        let service = service.clone();
+        async move {
+            let response = service.serve_file("/app.js").await.unwrap();
+            response.headers()["etag"].to_str().unwrap().to_string()
+        }
+    };
+    let first = StaticFileService::new(one.dist_path(), None).with_etag(EtagStrategy::Strong);
+    let second = StaticFileService::new(two.dist_path(), None).with_etag(EtagStrategy::Strong);
+
+    // Same contents, different timestamps: same strong ETag, different weak ones
+    assert_eq!(etag(&first).await, etag(&second).await);
+    assert!(!etag(&first).await.starts_with("W/"));
+    assert_ne!(
+        etag(&StaticFileService::new(one.dist_path(), None)).await,
+        etag(&StaticFileService::new(two.dist_path(), None)).await
+    );
+
+    std::fs::write(&file, "console.log('v2')").unwrap();
+    assert_ne!(etag(&first).await, etag(&second).await);
+}
+
+#[cfg(feature = "mmap")]
+#[tokio::test]
+async fn test_large_files_are_memory_mapped() {
+    let fixture = TestSpaFixture::new().unwrap();
+    let video = fixture.add_file("video.mp4", vec![7u8; 4096]).unwrap();
+    fixture.add_file("small.js", "tiny").unwrap();
+    let cache = AssetCache::new(1024 * 1024);
+    let service = StaticFileService::new(fixture.dist_path(), None)
+        .with_cache(cache.clone())
+        .with_mmap_threshold(1024);
+
+    let response = service.serve_file("/video.mp4").await.unwrap();
+    assert_eq!(response.body().len(), 4096);
+    assert!(response.body().iter().all(|&byte| byte == 7));
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/octet-stream"
+    );
+    assert!(!cache.contains(&video));
+
+    let response = service.serve_file("/small.js").await.unwrap();
+    assert_eq!(response.body(), "tiny");
+    assert_eq!(cache.len(), 1);
+}
+
+#[cfg(feature = "sri")]
+#[tokio::test]
+async fn test_html_gets_integrity_attributes() {
+    use heisenberg::utils::sri::integrity;
+
+    let fixture = TestSpaFixture::new().unwrap();
+    fixture
+        .add_file("assets/index.js", "console.log('app')")
+        .unwrap();
+    fixture.add_file("assets/index.css", "body{}").unwrap();
+    fixture
+        .add_file(
+            "index.html",
+            r#"<html><head>
+<script type="module" crossorigin src="/assets/index.js?v=1"></script>
+<link rel="stylesheet" href="./assets/index.css" />
+<link rel="icon" href="/favicon.ico">
+<script src="https://cdn.example.com/lib.js"></script>
+<script src="/assets/index.js" integrity="sha384-pinned"></script>
+<script src="/assets/missing.js"></script>
+</head><body><div id="root"></div></body></html>"#,
+        )
+        .unwrap();
+    let service = StaticFileService::new(fixture.dist_path(), Some("index.html".to_string()))
+        .with_subresource_integrity();
+
+    let response = service.serve_file("/dashboard").await.unwrap();
+    let html = std::str::from_utf8(response.body()).unwrap();
+    assert!(html.contains(&format!(
+        r#"src="/assets/index.js?v=1" integrity="{}"></script>"#,
+        integrity(b"console.log('app')")
+    )));
+    assert!(html.contains(&format!(
+        r#"href="./assets/index.css" integrity="{}" />"#,
+        integrity(b"body{}")
+    )));
+    assert!(html.contains(r#"<link rel="icon" href="/favicon.ico">"#));
+    assert!(html.contains(r#"<script src="https://cdn.example.com/lib.js"></script>"#));
+    assert!(html.contains(r#"integrity="sha384-pinned"></script>"#));
+    assert!(html.contains(r#"<script src="/assets/missing.js"></script>"#));
+    assert_eq!(html.matches("integrity=").count(), 3);
+
+    // Assets themselves are served untouched
+    let response = service.serve_file("/assets/index.js").await.unwrap();
+    assert_eq!(response.body(), "console.log('app')");
+}
+
+#[cfg(feature = "sri")]
+#[test]
+fn test_integrity_is_base64_sha384() {
+    assert_eq!(
+        heisenberg::utils::sri::integrity(b""),
+        "sha384-OLBgp1GsljhM2TJ+sbHjaiH9txEUvgdDTAzHv2P24donTt6/529l+9Ua0vFImLlb"
+    );
+}
+
+#[test]
+fn test_redirects_file_parsing() {
+    use heisenberg::services::redirects::parse_redirects;
+
+    let rules = parse_redirects(
+        "# comment\n\n/a  /b\n/c  /d  302!\nhttps://old.example.com/*  /:splat\n/e  /f  200  Country=us\n/api/*  https://api.example.com/:splat  200\n",
+    );
+    assert_eq!(rules.len(), 2);
+    assert_eq!((rules[0].status, rules[0].force), (301, false));
+    assert_eq!((rules[1].status, rules[1].force), (302, true));
+    assert!(rules[1].is_redirect());
+}
+
+#[test]
+fn test_headers_file_parsing() {
+    use heisenberg::services::headers::parse_headers;
+
+    let rules = parse_headers(
+        "# Security\n/*\n  X-Frame-Options: DENY\n  Link: </a.css>; rel=preload\n  Link: </b.js>; rel=preload\n\n/blog/:slug/\n  Cache-Control: no-cache\n  not a header\nassets/*\n  X-Skipped: yes\n",
+    );
+    assert_eq!(
+        rules,
+        [
+            HeaderRule {
+                glob: "/**".to_string(),
+                name: "X-Frame-Options".to_string(),
+                value: "DENY".to_string(),
+            },
+            HeaderRule {
+                glob: "/**".to_string(),
+                name: "Link".to_string(),
+                value: "</a.css>; rel=preload, </b.js>; rel=preload".to_string(),
+            },
+            HeaderRule {
+                glob: "/blog/*/index.html".to_string(),
+                name: "Cache-Control".to_string(),
+                value: "no-cache".to_string(),
+            },
+        ]
+    );
+}
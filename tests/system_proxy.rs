@@ -0,0 +1,78 @@
+//! Tests for using the proxy environment variables
+//!
+//! The variables are process-wide and read once by `reqwest`, so this file
+//! holds a single test that sets them before any client is built.
+
+#![cfg(feature = "tower")]
+
+mod common;
+
+use common::get_dashboard;
+use heisenberg::core::mode::Mode;
+use heisenberg::services::client::SystemProxy;
+use heisenberg::test::{assert_proxied, MockDevServer, MockResponse};
+use heisenberg::Heisenberg;
+use hyper::StatusCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+/// Stand in for a corporate proxy that can't reach the developer's machine,
+/// counting the connections it gets
+async fn unreachable_proxy() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            let _ = stream
+                .write_all(
+                    b"HTTP/1.1 502 Bad Gateway\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                )
+                .await;
+        }
+    });
+    (url, connections)
+}
+
+#[tokio::test]
+async fn test_local_dev_servers_bypass_the_system_proxy_by_default() {
+    let (proxy_url, proxy_connections) = unreachable_proxy().await;
+    std::env::remove_var("NO_PROXY");
+    std::env::remove_var("no_proxy");
+    std::env::set_var("HTTP_PROXY", &proxy_url);
+
+    let dev_server = MockDevServer::start().await.unwrap();
+    dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+    let config = |system_proxy: Option<SystemProxy>| {
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true);
+        let config = match system_proxy {
+            Some(system_proxy) => config.system_proxy(system_proxy),
+            None => config,
+        };
+        config.spa("./dist").dev_server(&dev_server.url()).build()
+    };
+
+    for system_proxy in [
+        None,
+        Some(SystemProxy::ExceptLocal),
+        Some(SystemProxy::Never),
+    ] {
+        let response = get_dashboard(config(system_proxy)).await;
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::OK, "{:?}", system_proxy);
+    }
+    assert_eq!(proxy_connections.load(Ordering::SeqCst), 0);
+    let reached = dev_server.requests_to("/dashboard").len();
+    assert_eq!(reached, 3);
+
+    get_dashboard(config(Some(SystemProxy::Always))).await;
+    assert!(proxy_connections.load(Ordering::SeqCst) > 0);
+    assert_eq!(dev_server.requests_to("/dashboard").len(), reached);
+}
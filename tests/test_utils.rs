@@ -0,0 +1,67 @@
+//! Tests for the public test utilities
+
+#![cfg(feature = "test-utils")]
+
+use heisenberg::test::{MockDevServer, MockResponse, TestSpaFixture};
+
+#[tokio::test]
+async fn test_mock_dev_server_scripted_responses() {
+    let server = MockDevServer::start().await.unwrap();
+    server.respond("/", MockResponse::html("<h1>Home</h1>"));
+    server.respond("/search?q=a", MockResponse::new(200).body("exact"));
+
+    let client = reqwest::Client::new();
+    let home = client.get(server.url()).send().await.unwrap();
+    assert_eq!(home.status(), 200);
+    assert_eq!(home.headers()["content-type"], "text/html; charset=utf-8");
+    assert_eq!(home.text().await.unwrap(), "<h1>Home</h1>");
+
+    let exact = client
+        .get(format!("{}/search?q=a", server.url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(exact.text().await.unwrap(), "exact");
+
+    let missing = client
+        .get(format!("{}/missing", server.url()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn test_mock_dev_server_records_requests() {
+    let server = MockDevServer::start().await.unwrap();
+    server.fallback(MockResponse::new(204));
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("{}/api/login?next=/home", server.url()))
+        .header("X-Test", "yes")
+        .body("user=ada")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+
+    let requests = server.requests_to("/api/login");
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, "/api/login?next=/home");
+    assert_eq!(requests[0].header("x-test"), Some("yes"));
+    assert_eq!(requests[0].body, b"user=ada");
+}
+
+#[test]
+fn test_spa_fixture_layout() {
+    let fixture = TestSpaFixture::new().unwrap();
+
+    assert!(fixture.dist_path().join("index.html").exists());
+    assert!(fixture.package_json_path().exists());
+
+    let file = fixture.add_file("assets/logo.svg", "<svg/>").unwrap();
+    assert_eq!(file, fixture.dist_path().join("assets/logo.svg"));
+    assert_eq!(std::fs::read_to_string(file).unwrap(), "<svg/>");
+}
@@ -0,0 +1,202 @@
+//! Tests for proxying to HTTPS dev servers
+//!
+//! The certificates in `fixtures/tls` come from a test CA and are valid for
+//! `localhost` and `127.0.0.1` until 2125, plus a client certificate from
+//! the same CA.
+
+#![cfg(feature = "tower")]
+
+mod common;
+
+use common::get_dashboard;
+use heisenberg::core::mode::Mode;
+use heisenberg::test::assert_proxied;
+use heisenberg::{ErrorKind, Heisenberg};
+use hyper::StatusCode;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+}
+
+/// An HTTPS dev server answering every request with a page, returning its URL
+async fn https_dev_server() -> String {
+    let identity = native_tls::Identity::from_pkcs8(
+        &std::fs::read(fixture("dev-server.pem")).unwrap(),
+        &std::fs::read(fixture("dev-server.key")).unwrap(),
+    )
+    .unwrap();
+    let acceptor =
+        tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!(
+        "https://localhost:{}",
+        listener.local_addr().unwrap().port()
+    );
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let body = "<h1>Secure</h1>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    url
+}
+
+/// An HTTPS dev server that only answers clients with a certificate from
+/// the test CA, returning its URL
+fn mutual_tls_dev_server() -> String {
+    use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod, SslVerifyMode};
+
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor
+        .set_certificate_chain_file(fixture("dev-server.pem"))
+        .unwrap();
+    acceptor
+        .set_private_key_file(fixture("dev-server.key"), SslFiletype::PEM)
+        .unwrap();
+    acceptor.set_ca_file(fixture("ca.pem")).unwrap();
+    acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    let acceptor = acceptor.build();
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!(
+        "https://localhost:{}",
+        listener.local_addr().unwrap().port()
+    );
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = acceptor.accept(stream.unwrap()) else {
+                continue;
+            };
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            let _ = stream.write_all(
+                b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 7\r\nconnection: close\r\n\r\nmutual!",
+            );
+            let _ = stream.shutdown();
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn test_dev_server_root_ca_verifies_https_dev_servers() {
+    let url = https_dev_server().await;
+    let config = |root_ca: Option<PathBuf>| {
+        let route = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&url);
+        match root_ca {
+            Some(path) => route.dev_server_root_ca(path),
+            None => route,
+        }
+        .build()
+    };
+
+    let response = get_dashboard(config(Some(fixture("ca.pem")))).await;
+    assert_proxied(&response);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.body().starts_with(b"<h1>Secure</h1>"));
+
+    // Without the CA, the certificate isn't trusted
+    let response = get_dashboard(config(None)).await;
+    assert_ne!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_dev_server_root_ca_must_be_a_pem_certificate() {
+    let build = |path: PathBuf| {
+        Heisenberg::new()
+            .spa("./dist")
+            .dev_server("https://localhost:5173")
+            .dev_server_root_ca(path)
+            .try_build()
+    };
+
+    assert!(build(fixture("ca.pem")).is_ok());
+    let missing = build(fixture("missing.pem")).unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::AssetMissing);
+    let error = build(fixture("dev-server.key")).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConfigInvalid);
+    assert!(error.to_string().contains("dev-server.key"));
+}
+
+#[tokio::test]
+async fn test_dev_server_client_identity_is_presented() {
+    let url = mutual_tls_dev_server();
+    let config = |identity: bool| {
+        let route = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&url)
+            .dev_server_root_ca(fixture("ca.pem"));
+        if identity {
+            route.dev_server_client_identity(fixture("client.pem"), fixture("client.key"))
+        } else {
+            route
+        }
+        .build()
+    };
+
+    let response = get_dashboard(config(true)).await;
+    assert_proxied(&response);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "mutual!");
+
+    // The server turns away clients without a certificate
+    let response = get_dashboard(config(false)).await;
+    assert_ne!(response.status(), StatusCode::OK);
+}
+
+#[test]
+fn test_dev_server_client_identity_needs_a_certificate_and_key() {
+    let build = |certificate: &str, key: &str| {
+        Heisenberg::new()
+            .spa("./dist")
+            .dev_server("https://localhost:5173")
+            .dev_server_client_identity(fixture(certificate), fixture(key))
+            .try_build()
+    };
+
+    assert!(build("client.pem", "client.key").is_ok());
+    let missing = build("client.pem", "missing.key").unwrap_err();
+    assert_eq!(missing.kind(), ErrorKind::AssetMissing);
+    // Swapped files
+    let error = build("client.key", "client.pem").unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ConfigInvalid);
+}
@@ -8,10 +8,9 @@ use tower::ServiceBuilder;
 #[test]
 fn test_tower_layer_creation() {
     let config = Heisenberg::new().spa("./test-dist").build();
-    let _layer = HeisenbergLayer::new(config);
 
     // Should be able to create layer without panicking
-    assert!(true); // Layer created successfully
+    let _layer = HeisenbergLayer::new(config);
 }
 
 #[test]
@@ -20,10 +19,9 @@ fn test_tower_layer_with_multiple_routes() {
         .spa("./admin-dist")
         .spa("./app-dist")
         .build();
-    let _layer = HeisenbergLayer::new(config);
 
     // Should handle multiple routes
-    assert!(true); // Layer created successfully
+    let _layer = HeisenbergLayer::new(config);
 }
 
 #[test]
@@ -33,8 +31,25 @@ fn test_service_builder_integration() {
 
     // Should integrate with ServiceBuilder
     let _service_builder = ServiceBuilder::new().layer(layer);
+}
+
+#[test]
+fn test_try_new_rejects_invalid_config() {
+    let config = Heisenberg::new().spa("./dist").pattern("").build();
+
+    assert!(HeisenbergLayer::try_new(config).is_err());
+}
 
-    assert!(true); // ServiceBuilder integration works
+#[test]
+#[should_panic(expected = "Invalid Heisenberg configuration")]
+fn test_ci_mode_surfaces_config_errors_immediately() {
+    let config = Heisenberg::new()
+        .ci_mode(true)
+        .spa("./dist")
+        .pattern("")
+        .build();
+
+    let _layer = HeisenbergLayer::new(config);
 }
 
 #[test]
@@ -46,3 +61,2277 @@ fn test_layer_debug_format() {
     let debug_str = format!("{:?}", layer);
     assert!(debug_str.contains("HeisenbergLayer"));
 }
+
+mod dispatch {
+    use heisenberg::core::config::{MissingAsset, TrailingSlash};
+    use heisenberg::core::mode::Mode;
+    use heisenberg::services::{ErrorPageTheme, ProxyService};
+    use heisenberg::test::{
+        assert_inner, assert_proxied, assert_static, MockDevServer, MockResponse, TestSpaFixture,
+    };
+    use heisenberg::{Heisenberg, HeisenbergLayer};
+    use http_body_util::Full;
+    use hyper::body::Bytes;
+    use hyper::{Method, Request, Response, StatusCode};
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+    /// An app with a single API route; everything else is a 404
+    async fn api(req: Request<Full<Bytes>>) -> Result<Response<Bytes>, Infallible> {
+        let response = if req.uri().path() == "/api/hello" {
+            Response::new(Bytes::from("Hello API!"))
+        } else {
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Bytes::new())
+                .unwrap()
+        };
+        Ok(response)
+    }
+
+    fn get(path: &str) -> Request<Full<Bytes>> {
+        Request::builder().uri(path).body(Full::default()).unwrap()
+    }
+
+    async fn call(config: Heisenberg, req: Request<Full<Bytes>>) -> Response<Bytes> {
+        ServiceBuilder::new()
+            .layer(HeisenbergLayer::new(config))
+            .service(service_fn(api))
+            .oneshot(req)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_invalid_config_passes_requests_to_the_inner_service() {
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa("./dist")
+            .pattern("")
+            .build();
+
+        let response = call(config.clone(), get("/api/hello")).await;
+        assert_eq!(response.body(), "Hello API!");
+
+        let response = call(config, get("/")).await;
+        assert_inner(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_offline_mode_serves_the_build_without_a_dev_server() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("assets/app.js", "console.log('app')")
+            .unwrap();
+        let dead_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .offline(true)
+            .spa(fixture.dist_path())
+            .dev_server(&format!("http://127.0.0.1:{}", dead_port))
+            .dev_command(["false"])
+            .build();
+
+        let response = call(config, get("/assets/app.js")).await;
+
+        assert_static(&response);
+        assert_eq!(response.body(), "console.log('app')");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_params_serves_each_tenant_its_own_build() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("acme/index.html", "<h1>Acme</h1>")
+            .unwrap();
+        fixture
+            .add_file("acme/assets/app.js", "console.log('acme')")
+            .unwrap();
+        let dist = fixture.dist_path().clone();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/:tenant/app/*")
+            .resolve_params(move |params, target| {
+                target.embed_dir = dist.join(params.get("tenant").unwrap_or_default());
+            })
+            .build();
+
+        let response = call(config.clone(), get("/acme/app/assets/app.js")).await;
+        assert_static(&response);
+        assert_eq!(response.body(), "console.log('acme')");
+
+        let mut navigation = get("/acme/app/settings");
+        navigation
+            .headers_mut()
+            .insert("accept", "text/html".parse().unwrap());
+        let response = call(config.clone(), navigation).await;
+        assert_eq!(response.body(), "<h1>Acme</h1>");
+
+        let response = call(config, get("/globex/app/assets/app.js")).await;
+        assert_inner(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_params_rewrites_the_upstream_path() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .pattern("/:tenant/app/*")
+            .dev_server(&dev_server.url())
+            .strip_prefix(true)
+            .resolve_params(|params, target| {
+                target.upstream_path = format!(
+                    "{}?tenant={}",
+                    target.upstream_path,
+                    params.get("tenant").unwrap_or_default()
+                );
+            })
+            .build();
+
+        let response = call(config, get("/acme/app/settings")).await;
+
+        assert_proxied(&response);
+        assert_eq!(dev_server.requests()[0].path, "/settings?tenant=acme");
+    }
+
+    #[tokio::test]
+    async fn test_staging_profile_proxies_to_the_staging_url() {
+        let staging = MockDevServer::start().await.unwrap();
+        staging.respond("/dashboard", MockResponse::html("<h1>Deployed</h1>"));
+        let dead_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Heisenberg::new()
+            .staging(true)
+            .spa("./dist")
+            .dev_server(&format!("http://127.0.0.1:{}", dead_port))
+            .dev_command(["false"])
+            .staging_url(&staging.url())
+            .build();
+
+        let response = call(config, get("/dashboard")).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.body().starts_with(b"<h1>Deployed</h1>"));
+    }
+
+    #[tokio::test]
+    async fn test_inner_routes_take_precedence() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let response = call(config, get("/api/hello")).await;
+
+        assert_inner(&response);
+        assert_eq!(response.body(), "Hello API!");
+    }
+
+    #[tokio::test]
+    async fn test_production_serves_static_files() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("assets/app.js", "console.log('app')")
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let response = call(config, get("/assets/app.js")).await;
+
+        assert_static(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/javascript; charset=utf-8"
+        );
+        assert_eq!(response.body(), "console.log('app')");
+    }
+
+    #[tokio::test]
+    async fn test_production_falls_back_to_index_for_client_routes() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let response = call(config, get("/users/42")).await;
+
+        assert_static(&response);
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+    }
+
+    #[tokio::test]
+    async fn test_production_rejects_path_traversal() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let response = call(config, get("/../package.json")).await;
+
+        assert!(!String::from_utf8_lossy(response.body()).contains("test-spa"));
+    }
+
+    #[tokio::test]
+    async fn test_development_proxies_to_dev_server() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/src/main.js",
+            MockResponse::new(200)
+                .header("content-type", "text/javascript")
+                .body("import './app.js'"),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, get("/src/main.js?v=123")).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.headers()["content-type"], "text/javascript");
+        assert_eq!(response.body(), "import './app.js'");
+
+        let requests = dev_server.requests_to("/src/main.js");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/src/main.js?v=123");
+    }
+
+    #[tokio::test]
+    async fn test_ci_mode_proxies_without_health_checks() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/app", MockResponse::html("<h1>App</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, get("/app")).await;
+
+        assert_proxied(&response);
+        let paths: Vec<_> = dev_server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(paths, ["/app"]);
+    }
+
+    #[tokio::test]
+    async fn test_head_requests_have_no_body() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+        let req = Request::builder()
+            .method(Method::HEAD)
+            .uri("/")
+            .body(Full::default())
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_static(&response);
+        assert!(response.body().is_empty());
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/html; charset=utf-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_form_posts_are_forwarded_with_body() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/login", MockResponse::html("<p>Welcome</p>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+        let body = "--boundary\r\ncontent-disposition: form-data; name=\"user\"\r\n\r\nada\r\n--boundary--\r\n";
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/login?/signin")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .header("content-length", body.len())
+            .body(Full::new(Bytes::from(body)))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_proxied(&response);
+        let requests = dev_server.requests_to("/login");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/login?/signin");
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("multipart/form-data; boundary=boundary")
+        );
+        assert_eq!(requests[0].body, body.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_bodies_without_a_length_header_are_forwarded() {
+        // As over HTTP/2, where neither Content-Length nor
+        // Transfer-Encoding is required
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/login")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Full::new(Bytes::from("user=ada")))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_proxied(&response);
+        let requests = dev_server.requests_to("/login");
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body, b"user=ada");
+    }
+
+    #[tokio::test]
+    async fn test_routes_skipping_the_inner_service_stream_bodies_to_the_dev_server() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/api/hello",
+            MockResponse::new(201).body("from the dev server"),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .inner_service_first(false)
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/api/hello")
+            .body(Full::new(Bytes::from("name=a")))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.body().as_ref(), b"from the dev server");
+        let requests = dev_server.requests_to("/api/hello");
+        assert_eq!(requests[0].header("transfer-encoding"), Some("chunked"));
+        assert_eq!(requests[0].body, b"name=a");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_bodies_over_the_limit_are_rejected() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .inner_service_first(false)
+            .max_request_body(16)
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/upload")
+            .body(Full::new(Bytes::from(vec![b'x'; 32])))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_streamed_body_is_rejected() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/upload")
+            .header("transfer-encoding", "chunked")
+            .body(Full::new(Bytes::from(vec![
+                0u8;
+                heisenberg::tower::service::MAX_BUFFERED_BODY
+                    + 1
+            ])))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(dev_server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_route_request_body_limit_overrides_the_global_one() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .max_request_body(1024 * 1024)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .max_request_body(16)
+            .build();
+        let upload = |len: usize| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/upload")
+                .header("content-length", len.to_string())
+                .body(Full::new(Bytes::from(vec![b'x'; len])))
+                .unwrap()
+        };
+
+        let response = call(config.clone(), upload(17)).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(dev_server.requests().is_empty());
+
+        let response = call(config, upload(16)).await;
+        assert_proxied(&response);
+        assert_eq!(dev_server.requests_to("/upload").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_dev_server_response_is_a_bad_gateway() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/big.js", MockResponse::new(200).body(vec![b'x'; 4096]));
+        dev_server.respond("/small.js", MockResponse::new(200).body("ok"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .max_response_body(1024)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config.clone(), get("/big.js")).await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert!(String::from_utf8_lossy(response.body()).contains("larger than 1024 bytes"));
+
+        let response = call(config, get("/small.js")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().as_ref(), b"ok");
+    }
+
+    #[tokio::test]
+    async fn test_cookies_pass_through_the_proxy() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/login",
+            MockResponse::new(303)
+                .header("location", "/dashboard")
+                .header("set-cookie", "session=abc; Path=/; HttpOnly")
+                .header("set-cookie", "theme=dark; Path=/"),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/login")
+            .header("cookie", "csrf=xyz")
+            .body(Full::default())
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "/dashboard");
+        let cookies: Vec<_> = response.headers().get_all("set-cookie").iter().collect();
+        assert_eq!(
+            cookies,
+            ["session=abc; Path=/; HttpOnly", "theme=dark; Path=/"]
+        );
+
+        let requests = dev_server.requests_to("/login");
+        assert_eq!(requests[0].header("cookie"), Some("csrf=xyz"));
+        assert!(dev_server.requests_to("/dashboard").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_proxied_sees_buffered_bodies() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/api/echo", MockResponse::new(201).body("created"));
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .buffer_proxied(true)
+            .inspect_proxied(move |request, response| {
+                record.lock().unwrap().push(format!(
+                    "{} {} {:?} -> {} {:?}",
+                    request.method,
+                    request.path,
+                    request.body,
+                    response.status().as_u16(),
+                    response.body()
+                ));
+            })
+            .build();
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/api/echo?x=1")
+            .header("content-length", "6")
+            .body(Full::new(Bytes::from("name=a")))
+            .unwrap();
+
+        let response = call(config, req).await;
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [r#"POST /api/echo?x=1 Some(b"name=a") -> 201 b"created""#]
+        );
+    }
+
+    /// A dev server on a raw socket that writes `response` to the first
+    /// connection, then `rest` once `finish` fires
+    async fn scripted_dev_server(
+        response: &'static [u8],
+        rest: &'static [u8],
+    ) -> (String, tokio::sync::oneshot::Sender<()>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (finish, finished) = tokio::sync::oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request).await.unwrap();
+            stream.write_all(response).await.unwrap();
+            let _ = finished.await;
+            let _ = stream.write_all(rest).await;
+        });
+        (url, finish)
+    }
+
+    #[tokio::test]
+    async fn test_streamed_responses_reach_the_browser_before_the_dev_server_finishes() {
+        use http_body_util::BodyExt;
+
+        let (url, finish) = scripted_dev_server(
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n9\r\ndata: 1\n\n\r\n",
+            b"9\r\ndata: 2\n\n\r\n0\r\n\r\n",
+        )
+        .await;
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&url)
+            .build();
+        let not_found = |_req: Request<Full<Bytes>>| async {
+            let response = Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            Ok::<_, Infallible>(response)
+        };
+
+        let service = ServiceBuilder::new()
+            .layer(HeisenbergLayer::new(config))
+            .service(service_fn(not_found));
+
+        let (first, body) = tokio::time::timeout(Duration::from_secs(5), async {
+            let response = service.oneshot(get("/events")).await.unwrap();
+            assert_proxied(&response);
+            let mut body = response.into_body();
+            let first = body.frame().await.unwrap().unwrap();
+            (first, body)
+        })
+        .await
+        .expect("the first event arrives while the dev server is still writing");
+        assert_eq!(first.into_data().unwrap(), "data: 1\n\n");
+        finish.send(()).unwrap();
+        let rest = body.collect().await.unwrap().to_bytes();
+        assert_eq!(rest, "data: 2\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_dev_server_response_cut_short_is_a_bad_gateway() {
+        let (url, _) = scripted_dev_server(
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 100\r\n\r\npartial",
+            b"",
+        )
+        .await;
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&url)
+            .buffer_proxied(true)
+            .build();
+
+        let response = tokio::time::timeout(Duration::from_secs(5), call(config, get("/app.js")))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert!(String::from_utf8_lossy(response.body()).contains("Failed to read the response"));
+    }
+
+    #[tokio::test]
+    async fn test_route_proxy_timeout_overrides_the_global_one() {
+        let (url, _finish) = scripted_dev_server(b"", b"").await;
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .proxy_timeout(Duration::from_secs(60))
+            .spa("./dist")
+            .dev_server(&url)
+            .proxy_timeout(Duration::from_millis(200))
+            .build();
+
+        let response = tokio::time::timeout(Duration::from_secs(5), call(config, get("/")))
+            .await
+            .expect("the route's timeout applies");
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(String::from_utf8_lossy(response.body()).contains("did not respond within 200ms"));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_timeout_only_covers_the_response_headers() {
+        let (url, finish) = scripted_dev_server(
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 10\r\n\r\nhello",
+            b"world",
+        )
+        .await;
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .proxy_timeout(Duration::from_millis(200))
+            .spa("./dist")
+            .dev_server(&url)
+            .build();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            let _ = finish.send(());
+        });
+
+        let response = call(config, get("/slow.txt")).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().as_ref(), b"helloworld");
+    }
+
+    #[tokio::test]
+    async fn test_redirects_to_the_dev_server_stay_on_the_backend() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/account",
+            MockResponse::new(302).header(
+                "location",
+                format!(
+                    "http://localhost:{}/login?next=%2Faccount",
+                    dev_server.addr().port()
+                ),
+            ),
+        );
+        dev_server.respond(
+            "/docs",
+            MockResponse::new(301).header("location", "https://docs.example.com/"),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config.clone(), get("/account")).await;
+        assert_proxied(&response);
+        assert_eq!(response.headers()["location"], "/login?next=%2Faccount");
+
+        // Redirects elsewhere are left alone
+        let response = call(config, get("/docs")).await;
+        assert_eq!(response.headers()["location"], "https://docs.example.com/");
+    }
+
+    fn with_host(path: &str, host: &str) -> Request<Full<Bytes>> {
+        Request::builder()
+            .uri(path)
+            .header("host", host)
+            .header("accept", "text/html")
+            .body(Full::default())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_host_is_rewritten_to_the_dev_server() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/", MockResponse::html("<h1>Home</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        call(config, with_host("/", "myapp.test:3000")).await;
+
+        let host = dev_server.addr().to_string();
+        assert_eq!(dev_server.requests()[0].header("host"), Some(host.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_preserve_host_forwards_the_original_host() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/", MockResponse::html("<h1>Home</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .preserve_host(true)
+            .build();
+
+        call(config, with_host("/", "myapp.test:3000")).await;
+
+        assert_eq!(
+            dev_server.requests()[0].header("host"),
+            Some("myapp.test:3000")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_targets_see_their_own_host() {
+        let remote = MockDevServer::start().await.unwrap();
+        let origin = format!("http://staging.test:{}", remote.addr().port());
+        remote.respond(
+            "/app/account",
+            MockResponse::new(302)
+                .header("location", format!("{}/app/login", origin))
+                .header(
+                    "set-cookie",
+                    "session=1; Domain=staging.test; Path=/; HttpOnly",
+                ),
+        );
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve("staging.test", remote.addr())
+            .build()
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .http_client(client)
+            .spa("./dist")
+            .dev_server(&format!("{}/app/", origin))
+            .build();
+        let request = Request::builder()
+            .uri("/account")
+            .header("host", "localhost:3000")
+            .header("origin", "http://localhost:3000")
+            .header("referer", "http://localhost:3000/settings")
+            .body(Full::default())
+            .unwrap();
+
+        let response = call(config, request).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.headers()["location"], "/login");
+        assert_eq!(
+            response.headers()["set-cookie"],
+            "session=1; Path=/; HttpOnly"
+        );
+        let requests = remote.requests_to("/app/account");
+        let upstream = &requests[0];
+        let host = format!("staging.test:{}", remote.addr().port());
+        assert_eq!(upstream.header("host"), Some(host.as_str()));
+        assert_eq!(upstream.header("origin"), Some(origin.as_str()));
+        let referer = format!("{}/app/settings", origin);
+        assert_eq!(upstream.header("referer"), Some(referer.as_str()));
+        assert_eq!(upstream.header("x-forwarded-host"), Some("localhost:3000"));
+        assert_eq!(upstream.header("x-forwarded-proto"), Some("http"));
+    }
+
+    #[tokio::test]
+    async fn test_dev_server_urls_can_be_rewritten_to_the_backend() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        let port = dev_server.addr().port();
+        dev_server.respond(
+            "/",
+            MockResponse::html(format!(
+                r#"<script type="module" src="http://localhost:{port}/src/main.ts"></script>"#
+            )),
+        );
+        dev_server.respond(
+            "/src/main.ts",
+            MockResponse::new(200)
+                .header("content-type", "text/javascript")
+                .body(format!(
+                    "new WebSocket('ws://127.0.0.1:{port}/'); fetch('http://localhost:{port}0/')"
+                )),
+        );
+        let route = |rewrite| {
+            Heisenberg::new()
+                .mode_override(Mode::Development)
+                .ci_mode(true)
+                .spa("./dist")
+                .dev_server(&dev_server.url())
+                .rewrite_dev_urls(rewrite)
+                .build()
+        };
+
+        let response = call(route(true), with_host("/", "app.test:3000")).await;
+        assert_proxied(&response);
+        assert!(String::from_utf8_lossy(response.body())
+            .contains(r#"src="http://app.test:3000/src/main.ts""#));
+
+        // A longer port is a different origin
+        let response = call(route(true), with_host("/src/main.ts", "app.test:3000")).await;
+        assert_eq!(
+            String::from_utf8_lossy(response.body()),
+            format!("new WebSocket('ws://app.test:3000/'); fetch('http://localhost:{port}0/')")
+        );
+
+        let response = call(route(false), with_host("/", "app.test:3000")).await;
+        assert!(String::from_utf8_lossy(response.body()).contains("localhost"));
+    }
+
+    #[tokio::test]
+    async fn test_host_check_rejection_explains_the_fix() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.fallback(MockResponse::new(403).body(
+            "Blocked request. This host (\"myapp.test\") is not allowed.\nTo allow this host, add \"myapp.test\" to `server.allowedHosts` in vite.config.js.",
+        ));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .preserve_host(true)
+            .build();
+
+        let response = call(config, with_host("/", "myapp.test")).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let page = String::from_utf8_lossy(response.body());
+        assert!(page.contains("Host Not Allowed"));
+        assert!(page.contains("server.allowedHosts"));
+    }
+
+    #[tokio::test]
+    async fn test_strip_prefix_when_proxying_nested_routes() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/users", MockResponse::html("<h1>Users</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./admin/dist")
+            .pattern("/admin/*")
+            .dev_server(&dev_server.url())
+            .strip_prefix(true)
+            .build();
+
+        let response = call(config, get("/admin/users?sort=name")).await;
+
+        assert_proxied(&response);
+        assert_eq!(dev_server.requests()[0].path, "/users?sort=name");
+    }
+
+    #[tokio::test]
+    async fn test_nested_routes_strip_prefix_for_static_files() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("assets/app.js", "console.log('admin')")
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/admin/*")
+            .build();
+
+        let response = call(config, get("/admin/assets/app.js")).await;
+
+        assert_static(&response);
+        assert_eq!(response.body(), "console.log('admin')");
+    }
+
+    #[tokio::test]
+    async fn test_static_prefix_stripping_can_be_disabled() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("admin/assets/app.js", "console.log('nested')")
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/admin/*")
+            .strip_static_prefix(false)
+            .build();
+
+        let response = call(config, get("/admin/assets/app.js")).await;
+
+        assert_static(&response);
+        assert_eq!(response.body(), "console.log('nested')");
+    }
+
+    fn admin_route(fixture: &TestSpaFixture, policy: TrailingSlash) -> Heisenberg {
+        Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/admin/*")
+            .trailing_slash(policy)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_trailing_slash_redirect() {
+        let fixture = TestSpaFixture::new().unwrap();
+
+        let config = admin_route(&fixture, TrailingSlash::Redirect);
+        let response = call(config, get("/admin?tab=users")).await;
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()["location"], "/admin/?tab=users");
+
+        let config = admin_route(&fixture, TrailingSlash::Redirect);
+        let response = call(config, get("/admin/")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_trailing_slash_serve_both_and_pass_through() {
+        let fixture = TestSpaFixture::new().unwrap();
+
+        let config = admin_route(&fixture, TrailingSlash::ServeBoth);
+        let response = call(config, get("/admin")).await;
+        assert_static(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let config = admin_route(&fixture, TrailingSlash::PassThrough);
+        let response = call(config, get("/admin")).await;
+        assert_inner(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_subtree_fallback_files() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("admin.html", "<h1>Admin entry</h1>")
+            .unwrap();
+        fixture.add_file("app.html", "<h1>App entry</h1>").unwrap();
+        let config = || {
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(fixture.dist_path())
+                .fallback_for("/admin/*", "admin.html")
+                .fallback_for("/app/*", "app.html")
+                .build()
+        };
+
+        let response = call(config(), get("/admin/users/42")).await;
+        assert_eq!(response.body(), "<h1>Admin entry</h1>");
+
+        let response = call(config(), get("/app/settings")).await;
+        assert_eq!(response.body(), "<h1>App entry</h1>");
+
+        let response = call(config(), get("/about")).await;
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+    }
+
+    #[tokio::test]
+    async fn test_locale_prefixed_routes() {
+        let shared = TestSpaFixture::new().unwrap();
+        shared
+            .add_file("assets/app.js", "console.log('app')")
+            .unwrap();
+        let config = || {
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(shared.dist_path())
+                .locales(["en", "de"])
+                .build()
+        };
+
+        let response = call(config(), get("/de/users/42")).await;
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+        assert_eq!(response.headers()["content-language"], "de");
+
+        let response = call(config(), get("/de/assets/app.js")).await;
+        assert_eq!(response.body(), "console.log('app')");
+
+        let response = call(config(), get("/users/42")).await;
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+        assert!(!response.headers().contains_key("content-language"));
+
+        let per_locale = TestSpaFixture::new().unwrap();
+        per_locale
+            .add_file("en/index.html", "<h1>Hello</h1>")
+            .unwrap();
+        per_locale
+            .add_file("de/index.html", "<h1>Hallo</h1>")
+            .unwrap();
+        let config = || {
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(per_locale.dist_path())
+                .locale_dirs(["en", "de"])
+                .build()
+        };
+
+        let response = call(config(), get("/de/settings")).await;
+        assert_eq!(response.body(), "<h1>Hallo</h1>");
+        let response = call(config(), get("/en/settings")).await;
+        assert_eq!(response.body(), "<h1>Hello</h1>");
+        let response = call(config(), get("/settings")).await;
+        assert_eq!(response.body(), "<h1>Hello</h1>");
+    }
+
+    #[tokio::test]
+    async fn test_routes_can_be_added_and_removed_at_runtime() {
+        use heisenberg::core::config::SpaRouteConfig;
+
+        let main = TestSpaFixture::new().unwrap();
+        let tenant = TestSpaFixture::new().unwrap();
+        tenant
+            .add_file("index.html", "<h1>Acme dashboard</h1>")
+            .unwrap();
+        let layer = HeisenbergLayer::new(
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(main.dist_path())
+                .pattern("/app/*")
+                .build(),
+        );
+        let app = ServiceBuilder::new()
+            .layer(layer.clone())
+            .service(service_fn(api));
+        let routes = layer.routes();
+
+        let response = app.clone().oneshot(get("/acme/users")).await.unwrap();
+        assert_inner(&response);
+
+        let tenant_route = SpaRouteConfig {
+            pattern: "/acme/*".to_string(),
+            embed_dir: tenant.dist_path().clone(),
+            ..Default::default()
+        };
+        routes.add(tenant_route.clone()).unwrap();
+        assert!(routes.contains("/acme/*"));
+        let response = app.clone().oneshot(get("/acme/users")).await.unwrap();
+        assert_static(&response);
+        assert_eq!(response.body(), "<h1>Acme dashboard</h1>");
+        let response = app.clone().oneshot(get("/app/home")).await.unwrap();
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+
+        let err = routes.add(tenant_route).unwrap_err();
+        assert!(err.to_string().contains("Duplicate route pattern"));
+        let err = routes
+            .add(SpaRouteConfig {
+                pattern: String::new(),
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot be empty"));
+
+        let removed = routes.remove("/acme/*").unwrap();
+        assert_eq!(&removed.embed_dir, tenant.dist_path());
+        let response = app.clone().oneshot(get("/acme/users")).await.unwrap();
+        assert_inner(&response);
+        assert_eq!(routes.list().len(), 1);
+        assert!(routes.remove("/acme/*").is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_route_changes_are_all_kept() {
+        use heisenberg::core::config::SpaRouteConfig;
+
+        let fixture = TestSpaFixture::new().unwrap();
+        let layer = HeisenbergLayer::new(
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(fixture.dist_path())
+                .pattern("/app/*")
+                .build(),
+        );
+        let app = ServiceBuilder::new()
+            .layer(layer.clone())
+            .service(service_fn(api));
+
+        let tenants: Vec<_> = (0..16)
+            .map(|tenant| {
+                let routes = layer.routes();
+                let embed_dir = fixture.dist_path().clone();
+                std::thread::spawn(move || {
+                    routes.add(SpaRouteConfig {
+                        pattern: format!("/tenant-{}/*", tenant),
+                        embed_dir,
+                        ..Default::default()
+                    })
+                })
+            })
+            .collect();
+        // Requests keep being served while the routes change
+        for _ in 0..16 {
+            let response = app.clone().oneshot(get("/app/home")).await.unwrap();
+            assert_static(&response);
+        }
+        for tenant in tenants {
+            tenant.join().unwrap().unwrap();
+        }
+
+        assert_eq!(layer.routes().list().len(), 17);
+        let response = app.clone().oneshot(get("/tenant-7/home")).await.unwrap();
+        assert_static(&response);
+    }
+
+    #[tokio::test]
+    async fn test_dotfiles_in_the_build_are_hidden() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file(".env", "SECRET=1").unwrap();
+        fixture
+            .add_file(".well-known/assetlinks.json", "[]")
+            .unwrap();
+        let route = |allow: &[&str]| {
+            let mut builder = Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(fixture.dist_path());
+            for name in allow {
+                builder = builder.allow_dotfile(*name);
+            }
+            builder.build()
+        };
+
+        let response = call(route(&[]), get("/.env")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(!String::from_utf8_lossy(response.body()).contains("SECRET"));
+        assert_static(&call(route(&[]), get("/.well-known/assetlinks.json")).await);
+
+        let response = call(route(&[".env"]), get("/.env")).await;
+        assert_eq!(response.body(), "SECRET=1");
+    }
+
+    #[tokio::test]
+    async fn test_route_headers_apply_in_both_modes() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/sw.js",
+            MockResponse::new(200)
+                .header("content-type", "text/javascript")
+                .header("service-worker-allowed", "/app/")
+                .body("self.skipWaiting()"),
+        );
+        let route = |mode| {
+            Heisenberg::new()
+                .mode_override(mode)
+                .ci_mode(true)
+                .spa(fixture.dist_path())
+                .dev_server(&dev_server.url())
+                .header("Service-Worker-Allowed", "/")
+                .header("X-Frontend", "a")
+                .header("X-Frontend", "b")
+                .build()
+        };
+
+        let response = call(route(Mode::Production), get("/")).await;
+        assert_static(&response);
+        assert_eq!(response.headers()["service-worker-allowed"], "/");
+        let values: Vec<_> = response.headers().get_all("x-frontend").iter().collect();
+        assert_eq!(values, ["a", "b"]);
+
+        let response = call(route(Mode::Development), get("/sw.js")).await;
+        assert_proxied(&response);
+        let values: Vec<_> = response
+            .headers()
+            .get_all("service-worker-allowed")
+            .iter()
+            .collect();
+        assert_eq!(values, ["/"]);
+
+        // The inner service's responses are its own
+        let response = call(route(Mode::Production), get("/api/hello")).await;
+        assert_inner(&response);
+        assert!(!response.headers().contains_key("service-worker-allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_header_rules_survive_revalidation() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file("assets/app.js", "app").unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .header_rule("/assets/*", "Cache-Control", "immutable")
+            .build();
+
+        let response = call(config.clone(), get("/assets/app.js")).await;
+        assert_eq!(response.headers()["cache-control"], "immutable");
+
+        let etag = response.headers()["etag"].to_str().unwrap();
+        let response = call(
+            config,
+            with_headers("/assets/app.js", &[("if-none-match", etag)]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()["cache-control"], "immutable");
+    }
+
+    #[tokio::test]
+    async fn test_redirects_file_rules_apply_in_production() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file("docs/start.html", "start").unwrap();
+        fixture.add_file("blog/old.html", "old post").unwrap();
+        fixture
+            .add_file(
+                "_redirects",
+                "# Moved sections\n\
+                 /blog/*  /news/:splat  302\n\
+                 /guide   /docs/start.html  200\n\
+                 /gone    /index.html  410\n\
+                 /legacy  https://old.example.com/  301!\n",
+            )
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let response = call(config.clone(), get("/blog/2024/launch?ref=feed")).await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(response.headers()["location"], "/news/2024/launch?ref=feed");
+
+        // Files win over rules without `!`
+        let response = call(config.clone(), get("/blog/old.html")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "old post");
+
+        let response = call(config.clone(), get("/guide")).await;
+        assert_static(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "start");
+
+        let response = call(config.clone(), get("/gone")).await;
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+
+        let response = call(config.clone(), get("/legacy")).await;
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()["location"], "https://old.example.com/");
+
+        let response = call(config, get("/_redirects")).await;
+        assert!(!String::from_utf8_lossy(response.body()).contains("Moved sections"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_file_paths_are_relative_to_the_route() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file("_redirects", "/old  /new  301\n").unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/app/*")
+            .strip_static_prefix(true)
+            .build();
+
+        let response = call(config, get("/app/old")).await;
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()["location"], "/app/new");
+    }
+
+    #[tokio::test]
+    async fn test_rewrites_apply_before_files_and_redirects() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file("admin.html", "admin shell").unwrap();
+        fixture.add_file("legacy/page.html", "old page").unwrap();
+        fixture
+            .add_file("_redirects", "/admin/*  /login  302\n")
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .rewrite("/legacy/*", "/index.html")
+            .rewrite("/admin/*", "/admin.html")
+            .build();
+
+        let response = call(config.clone(), get("/legacy/page.html")).await;
+        assert_static(&response);
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+
+        let response = call(config, get("/admin/users/42")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "admin shell");
+    }
+
+    #[tokio::test]
+    async fn test_headers_file_rules_apply_to_static_files() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture.add_file("assets/app.js", "app").unwrap();
+        fixture
+            .add_file(
+                "_headers",
+                "/*\n  X-Frame-Options: DENY\n/assets/*\n  Cache-Control: immutable\n",
+            )
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .header_rule("*.js", "Cache-Control", "no-cache")
+            .build();
+
+        // Configured rules win over the file's
+        let response = call(config.clone(), get("/assets/app.js")).await;
+        assert_eq!(response.headers()["x-frame-options"], "DENY");
+        assert_eq!(response.headers()["cache-control"], "no-cache");
+
+        let response = call(config.clone(), get("/dashboard")).await;
+        assert_eq!(response.headers()["x-frame-options"], "DENY");
+        assert!(!response.headers().contains_key("cache-control"));
+
+        let response = call(config, get("/_headers")).await;
+        assert!(!String::from_utf8_lossy(response.body()).contains("X-Frame-Options"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_assets_are_not_answered_with_index() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = |policy| {
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(fixture.dist_path())
+                .missing_asset(policy)
+                .build()
+        };
+
+        let response = call(config(MissingAsset::NotFound), get("/logo.png")).await;
+        assert_static(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = call(config(MissingAsset::PassThrough), get("/logo.png")).await;
+        assert_inner(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = call(config(MissingAsset::Fallback), get("/logo.png")).await;
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+
+        // Client routes still fall back
+        let response = call(config(MissingAsset::NotFound), get("/users/42")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    fn with_headers(path: &str, headers: &[(&str, &str)]) -> Request<Full<Bytes>> {
+        let mut builder = Request::builder().uri(path);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Full::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fallback_only_for_navigation_requests() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = || {
+            Heisenberg::new()
+                .mode_override(Mode::Production)
+                .spa(fixture.dist_path())
+                .build()
+        };
+
+        let navigation = with_headers(
+            "/users/42",
+            &[("accept", "text/html,*/*"), ("sec-fetch-mode", "navigate")],
+        );
+        let response = call(config(), navigation).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let fetch = with_headers(
+            "/users/42",
+            &[("accept", "application/json"), ("sec-fetch-mode", "cors")],
+        );
+        let response = call(config(), fetch).await;
+        assert_static(&response);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()["content-type"], "application/json");
+
+        let xhr = with_headers("/users/42", &[("accept", "*/*")]);
+        let response = call(config(), xhr).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_for_all_requests_when_gating_disabled() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .navigation_fallback_only(false)
+            .build();
+
+        let response = call(config, with_headers("/users/42", &[("accept", "*/*")])).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(String::from_utf8_lossy(response.body()).contains("Test SPA Content"));
+    }
+
+    #[tokio::test]
+    async fn test_static_files_revalidate_with_etags() {
+        use heisenberg::core::config::EtagStrategy;
+
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("assets/app.js", "console.log('app')")
+            .unwrap();
+        for strategy in [EtagStrategy::Weak, EtagStrategy::Strong] {
+            let config = || {
+                Heisenberg::new()
+                    .mode_override(Mode::Production)
+                    .etag(strategy)
+                    .spa(fixture.dist_path())
+                    .build()
+            };
+
+            let response = call(config(), get("/assets/app.js")).await;
+            let etag = response.headers()["etag"].to_str().unwrap().to_string();
+            assert_eq!(etag.starts_with("W/"), strategy == EtagStrategy::Weak);
+
+            let revalidation = with_headers("/assets/app.js", &[("if-none-match", &etag)]);
+            let response = call(config(), revalidation).await;
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(response.headers()["etag"], etag.as_str());
+            assert!(response.body().is_empty());
+
+            let stale = with_headers("/assets/app.js", &[("if-none-match", "\"other\"")]);
+            let response = call(config(), stale).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.body(), "console.log('app')");
+        }
+
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .etag(EtagStrategy::Off)
+            .spa(fixture.dist_path())
+            .build();
+        let response = call(config, get("/assets/app.js")).await;
+        assert!(!response.headers().contains_key("etag"));
+    }
+
+    #[tokio::test]
+    async fn test_precompressed_files_are_negotiated_and_vary() {
+        let fixture = TestSpaFixture::new().unwrap();
+        fixture
+            .add_file("assets/app.js", "console.log('plain')")
+            .unwrap();
+        fixture
+            .add_file("assets/app.js.br", "brotli bytes")
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .build();
+
+        let brotli = call(
+            config.clone(),
+            with_headers("/assets/app.js", &[("accept-encoding", "gzip, br")]),
+        )
+        .await;
+        assert_static(&brotli);
+        assert_eq!(brotli.headers()["content-encoding"], "br");
+        assert_eq!(brotli.headers()["vary"], "accept-encoding");
+
+        let plain = call(
+            config.clone(),
+            with_headers("/assets/app.js", &[("accept-encoding", "gzip")]),
+        )
+        .await;
+        assert_eq!(plain.body(), "console.log('plain')");
+        assert_eq!(plain.headers()["vary"], "accept-encoding");
+
+        let etag = brotli.headers()["etag"].to_str().unwrap();
+        let revalidated = call(
+            config,
+            with_headers(
+                "/assets/app.js",
+                &[("accept-encoding", "br"), ("if-none-match", etag)],
+            ),
+        )
+        .await;
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(revalidated.headers()["vary"], "accept-encoding");
+    }
+
+    #[tokio::test]
+    async fn test_binary_responses_pass_through_the_proxy_untouched() {
+        let png = vec![
+            0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0xff, 0x00, 0xfe,
+        ];
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/logo.png",
+            MockResponse::new(200)
+                .header("content-type", "image/png")
+                .body(png.clone()),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, get("/logo.png")).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.body().as_ref(), png.as_slice());
+    }
+
+    fn grpc_web_call(message: &[u8]) -> Request<Full<Bytes>> {
+        Request::builder()
+            .method(Method::POST)
+            .uri("/greeter.Greeter/SayHello")
+            .header("content-type", "application/grpc-web+proto")
+            .header("x-grpc-web", "1")
+            .header("content-length", message.len())
+            .body(Full::new(Bytes::copy_from_slice(message)))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_grpc_web_calls_pass_through_the_proxy_untouched() {
+        // A length-prefixed message frame, then a trailer frame (flag 0x80)
+        let request = [0x00, 0x00, 0x00, 0x00, 0x03, 0x0a, 0x01, 0xff];
+        let mut reply = vec![0x00, 0x00, 0x00, 0x00, 0x02, 0x0a, 0x00];
+        reply.extend([0x80, 0x00, 0x00, 0x00, 0x0f]);
+        reply.extend(b"grpc-status:0\r\n");
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond(
+            "/greeter.Greeter/SayHello",
+            MockResponse::new(200)
+                .header("content-type", "application/grpc-web+proto")
+                .header("grpc-accept-encoding", "identity")
+                .body(reply.clone()),
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, grpc_web_call(&request)).await;
+
+        assert_proxied(&response);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/grpc-web+proto"
+        );
+        assert_eq!(response.headers()["grpc-accept-encoding"], "identity");
+        assert_eq!(response.body().as_ref(), reply.as_slice());
+        let requests = dev_server.requests_to("/greeter.Greeter/SayHello");
+        assert_eq!(requests[0].body, request);
+        assert_eq!(requests[0].header("x-grpc-web"), Some("1"));
+    }
+
+    #[tokio::test]
+    async fn test_grpc_web_calls_get_grpc_errors_when_the_dev_server_is_down() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&format!("http://127.0.0.1:{}", port))
+            .build();
+
+        let response = call(config, grpc_web_call(&[0, 0, 0, 0, 0])).await;
+
+        assert_proxied(&response);
+        assert_eq!(
+            response.headers()["content-type"],
+            "application/grpc-web+proto"
+        );
+        assert_eq!(response.headers()["grpc-status"], "14");
+        assert!(response.headers().contains_key("grpc-message"));
+        assert!(response.body().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_to_the_fallback_dev_server_while_the_dev_server_is_down() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let fallback = MockDevServer::start().await.unwrap();
+        fallback.respond("/dashboard", MockResponse::new(200).body("staging"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&format!("http://127.0.0.1:{}", port))
+            .fallback_dev_server(&fallback.url())
+            .build();
+
+        let response = call(config, get("/dashboard")).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "staging");
+        assert_eq!(fallback.requests_to("/dashboard").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_error_pages_reload_once_the_dev_server_is_back() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = |interval| {
+            Heisenberg::new()
+                .mode_override(Mode::Development)
+                .ci_mode(true)
+                .spa("./dist")
+                .dev_server(&format!("http://127.0.0.1:{}", port))
+                .error_page_refresh(interval)
+                .build()
+        };
+        let navigation = || with_headers("/", &[("accept", "text/html")]);
+
+        let response = call(config(Duration::from_millis(500)), navigation()).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let page = String::from_utf8_lossy(response.body());
+        assert!(page.contains("setTimeout(reloadWhenReady, 500)"));
+        assert!(page.contains(&format!("\"http://127.0.0.1:{}\"", port)));
+
+        let response = call(config(Duration::ZERO), navigation()).await;
+        assert!(!String::from_utf8_lossy(response.body()).contains("reload"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_requests_get_json_proxy_errors() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&format!("http://127.0.0.1:{}", port))
+            .build();
+
+        for request in [
+            with_headers("/api/user", &[("accept", "application/json")]),
+            with_headers("/api/user", &[("x-requested-with", "XMLHttpRequest")]),
+        ] {
+            let response = call(config.clone(), request).await;
+            assert_proxied(&response);
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(response.headers()["content-type"], "application/json");
+            let error: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(error["error"]["code"], "dev_server_unavailable");
+            assert_eq!(
+                error["error"]["upstream"],
+                format!("http://127.0.0.1:{}/api/user", port)
+            );
+            assert!(!error["error"]["message"].as_str().unwrap().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_error_pages_use_the_theme() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .error_page_theme(
+                ErrorPageTheme::new()
+                    .title("Acme Portal")
+                    .logo("/brand/logo.svg")
+                    .accent_color("#0b7285")
+                    .background_color("red; } body { display: none")
+                    .troubleshooting("Ask in <#frontend-help>"),
+            )
+            .spa("./dist")
+            .dev_server(&format!("http://127.0.0.1:{}", port))
+            .build();
+
+        let response = call(config, with_headers("/", &[("accept", "text/html")])).await;
+        let page = String::from_utf8_lossy(response.body());
+
+        assert!(page.contains("<title>Acme Portal</title>"));
+        assert!(page.contains(r#"<img src="/brand/logo.svg""#));
+        assert!(page.contains("h1, h2 { color: #0b7285; }"));
+        assert!(!page.contains("display: none"));
+        assert!(page.contains("<li>Ask in &lt;#frontend-help&gt;</li>"));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_uses_its_fallback_when_the_health_check_fails() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let fallback = MockDevServer::start().await.unwrap();
+        fallback.respond("/app.js", MockResponse::new(200).body("shared"));
+        let proxy = ProxyService::new(format!("http://127.0.0.1:{}", port))
+            .with_fallback(ProxyService::new(fallback.url()));
+
+        let response = proxy.proxy_request("/app.js").await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body(), "shared");
+        assert!(proxy.last_error().unwrap().contains("not responding"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_dev_servers_start_on_first_matching_request() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let closed_port = |_| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        // Each "dev server" leaves a marker and exits without listening
+        let marker = |name: &str| temp_dir.path().join(name);
+        let command = |name: &str| {
+            vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                format!("touch {}; exit 1", marker(name).display()),
+            ]
+        };
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./admin/dist")
+            .pattern("/admin/*")
+            .dev_server(&closed_port(()))
+            .dev_command(command("admin"))
+            .working_dir(temp_dir.path())
+            .spa("./app/dist")
+            .pattern("/app/*")
+            .dev_server(&closed_port(()))
+            .dev_command(command("app"))
+            .working_dir(temp_dir.path())
+            .build();
+        let service = ServiceBuilder::new()
+            .layer(HeisenbergLayer::new(config))
+            .service(service_fn(api));
+        assert!(!marker("admin").exists());
+
+        let response = service.clone().oneshot(get("/admin/users")).await.unwrap();
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(marker("admin").exists());
+        assert!(!marker("app").exists());
+
+        // Startup is only attempted once per route
+        std::fs::remove_file(marker("admin")).unwrap();
+        service.oneshot(get("/admin/users")).await.unwrap();
+        assert!(!marker("admin").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_error_pages_show_dev_server_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let dev_server = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .dev_server(&dev_server)
+            .dev_command([
+                "sh",
+                "-c",
+                "echo 'Error: Cannot find module <vite>'; sleep 0.1; exit 1",
+            ])
+            .working_dir(temp_dir.path())
+            .build();
+        let service = ServiceBuilder::new()
+            .layer(HeisenbergLayer::new(config))
+            .service(service_fn(api));
+
+        let page = service
+            .clone()
+            .oneshot(with_headers("/", &[("accept", "text/html")]))
+            .await
+            .unwrap();
+        assert_eq!(page.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let page = String::from_utf8_lossy(page.body()).into_owned();
+        assert!(page.contains("Dev server output"));
+        assert!(page.contains("Error: Cannot find module &lt;vite&gt;"));
+
+        let status = service.oneshot(get("/__heisenberg/status")).await.unwrap();
+        let status: serde_json::Value = serde_json::from_slice(status.body()).unwrap();
+        assert_eq!(
+            status["routes"][0]["output"],
+            serde_json::json!(["Error: Cannot find module <vite>"])
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_proxy_follows_announced_dev_server_port() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/", MockResponse::html("<p>moved</p>"));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let configured = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        // Like Vite when its port is taken: it picks another and says so
+        let announce = format!(
+            "echo '  VITE v5.0.8  ready in 90 ms'; echo '  ➜  Local:   http://localhost:{}/'; sleep 30",
+            dev_server.addr().port()
+        );
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .dev_server(&configured)
+            .dev_command(["sh", "-c", announce.as_str()])
+            .working_dir(temp_dir.path())
+            .build();
+        let mut events = config.events();
+        let layer = HeisenbergLayer::new(config);
+        let service = ServiceBuilder::new()
+            .layer(layer.clone())
+            .service(service_fn(api));
+
+        let response = service.oneshot(get("/")).await.unwrap();
+
+        assert_proxied(&response);
+        assert!(String::from_utf8_lossy(response.body()).contains("moved"));
+        let moved_to = format!("http://127.0.0.1:{}", dev_server.addr().port());
+        let dev_servers = layer.dev_servers().unwrap();
+        assert_eq!(dev_servers.dev_server_url("/*"), moved_to);
+        while let Ok(event) = events.try_recv() {
+            if let heisenberg::HeisenbergEvent::DevServerMoved { dev_server, .. } = event {
+                assert_eq!(dev_server, moved_to);
+                return;
+            }
+        }
+        panic!("no DevServerMoved event");
+    }
+
+    #[tokio::test]
+    async fn test_stats_record_latencies_per_route() {
+        let fixture = TestSpaFixture::new().unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Production)
+            .spa(fixture.dist_path())
+            .pattern("/*")
+            .spa(fixture.dist_path())
+            .pattern("/admin/*")
+            .build();
+        let layer = HeisenbergLayer::new(config);
+        let service = ServiceBuilder::new()
+            .layer(layer.clone())
+            .service(service_fn(api));
+
+        for path in ["/", "/about", "/admin/", "/api/hello"] {
+            service.clone().oneshot(get(path)).await.unwrap();
+        }
+
+        let stats = layer.stats();
+        let patterns: Vec<_> = stats.iter().map(|route| route.pattern.as_str()).collect();
+        assert_eq!(patterns, ["/*", "/admin/*"]);
+        let (site, admin) = (&stats[0], &stats[1]);
+        assert_eq!(admin.static_files.count, 1);
+        assert_eq!(site.static_files.count, 2);
+        assert_eq!(site.static_files.buckets.iter().sum::<u64>(), 2);
+        assert!(site.static_files.quantile(0.99).unwrap() <= site.static_files.max);
+        assert!(site.static_files.mean().unwrap() <= site.static_files.max);
+        assert_eq!(site.proxy.count, 0);
+        assert_eq!(site.proxy.mean(), None);
+    }
+
+    #[tokio::test]
+    async fn test_stats_count_proxied_requests() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+        let layer = HeisenbergLayer::new(config);
+        let service = ServiceBuilder::new()
+            .layer(layer.clone())
+            .service(service_fn(api));
+
+        service.oneshot(get("/dashboard")).await.unwrap();
+
+        let stats = layer.stats();
+        assert_eq!(stats[0].proxy.count, 1);
+        assert_eq!(stats[0].static_files.count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dev_servers_redirecting_the_root_are_healthy() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        // Redirects aren't followed, so the health check sees the 302 itself
+        dev_server.respond("/", MockResponse::new(302).header("location", "/app/"));
+        dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, get("/dashboard")).await;
+
+        assert_proxied(&response);
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(dev_server.requests_to("/app/").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_custom_http_client_is_used_for_proxying() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-dev-token", "secret".parse().unwrap());
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let config = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .http_client(client)
+            .spa("./dist")
+            .dev_server(&dev_server.url())
+            .build();
+
+        let response = call(config, get("/dashboard")).await;
+
+        assert_proxied(&response);
+        assert_eq!(
+            dev_server.requests_to("/dashboard")[0].header("x-dev-token"),
+            Some("secret")
+        );
+        // The startup health check went through the same client
+        assert_eq!(
+            dev_server.requests_to("/")[0].header("x-dev-token"),
+            Some("secret")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_ids_are_forwarded_and_echoed() {
+        let dev_server = MockDevServer::start().await.unwrap();
+        dev_server.respond("/dashboard", MockResponse::html("<h1>Dashboard</h1>"));
+        let config = |request_ids| {
+            Heisenberg::new()
+                .mode_override(Mode::Development)
+                .ci_mode(true)
+                .request_ids(request_ids)
+                .spa("./dist")
+                .dev_server(&dev_server.url())
+                .build()
+        };
+
+        let response = call(
+            config(true),
+            with_headers("/dashboard", &[("x-request-id", "trace-42")]),
+        )
+        .await;
+        assert_proxied(&response);
+        assert_eq!(response.headers()["x-request-id"], "trace-42");
+        assert_eq!(
+            dev_server.requests_to("/dashboard")[0].header("x-request-id"),
+            Some("trace-42")
+        );
+
+        // Without one, an ID is generated; the inner service's responses get it too
+        let response = call(config(true), get("/api/hello")).await;
+        assert_inner(&response);
+        let id = response.headers()["x-request-id"].to_str().unwrap();
+        assert_eq!(id.len(), 32);
+        assert!(id.bytes().all(|b| b.is_ascii_hexdigit()));
+
+        let response = call(config(false), get("/dashboard")).await;
+        assert!(!response.headers().contains_key("x-request-id"));
+        assert_eq!(
+            dev_server.requests_to("/dashboard")[1].header("x-request-id"),
+            None
+        );
+    }
+}
+
+mod websockets {
+    use heisenberg::core::mode::Mode;
+    use heisenberg::{Heisenberg, HeisenbergLayer};
+    use http_body_util::{BodyExt, Full};
+    use hyper::body::{Bytes, Incoming};
+    use hyper::{Request, Response, StatusCode};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+    const HANDSHAKE: &str = "Connection: Upgrade\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n";
+
+    /// The app handles its own socket at `/app/ws` as a plain response
+    async fn app(req: Request<Full<Bytes>>) -> Result<Response<Bytes>, Infallible> {
+        let response = if req.uri().path() == "/app/ws" {
+            Response::new(Bytes::from("app socket"))
+        } else {
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Bytes::new())
+                .unwrap()
+        };
+        Ok(response)
+    }
+
+    async fn read_head(stream: &mut (impl AsyncRead + Unpin)) -> String {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8];
+            stream.read_exact(&mut byte).await.unwrap();
+            head.push(byte[0]);
+        }
+        String::from_utf8(head).unwrap()
+    }
+
+    /// A dev server that accepts one WebSocket and echoes what it receives
+    async fn echo_dev_server() -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (head_tx, head_rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = head_tx.send(read_head(&mut stream).await);
+            stream
+                .write_all(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n\r\n")
+                .await
+                .unwrap();
+            let mut buf = [0u8; 64];
+            loop {
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                if n == 0 || stream.write_all(&buf[..n]).await.is_err() {
+                    break;
+                }
+            }
+        });
+        (url, head_rx)
+    }
+
+    /// Serve the layer over real HTTP/1.1 connections, with upgrades
+    async fn serve(config: Heisenberg) -> String {
+        let app = ServiceBuilder::new()
+            .layer(HeisenbergLayer::new(config))
+            .service(service_fn(app));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let app = app.clone();
+                let service = hyper::service::service_fn(move |req: Request<Incoming>| {
+                    let app = app.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = body.collect().await.unwrap().to_bytes();
+                        let response = app
+                            .oneshot(Request::from_parts(parts, Full::new(body)))
+                            .await?;
+                        Ok::<_, Infallible>(response.map(Full::new))
+                    }
+                });
+                tokio::spawn(
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .with_upgrades(),
+                );
+            }
+        });
+        addr
+    }
+
+    fn config(dev_server: &str) -> Heisenberg {
+        Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(dev_server)
+            .websocket_bypass("/app/ws")
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrades_are_relayed_to_the_dev_server() {
+        let (dev_server, dev_head) = echo_dev_server().await;
+        let addr = serve(config(&dev_server)).await;
+
+        let mut client = TcpStream::connect(&addr).await.unwrap();
+        client
+            .write_all(
+                format!(
+                    "GET /socket?room=1 HTTP/1.1\r\nHost: {}\r\n{}",
+                    addr, HANDSHAKE
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let head = read_head(&mut client).await;
+        assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+        assert!(head
+            .to_lowercase()
+            .contains("sec-websocket-accept: s3pplmbitxaq9kygzzhzrbk+xoo="));
+
+        let dev_head = dev_head.await.unwrap().to_lowercase();
+        assert!(dev_head.starts_with("get /socket?room=1 http/1.1"));
+        assert!(dev_head.contains("upgrade: websocket"));
+        assert!(dev_head.contains("sec-websocket-key: dghlihnhbxbszsbub25jzq=="));
+
+        client.write_all(b"ping").await.unwrap();
+        let mut echoed = [0u8; 4];
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.read_exact(&mut echoed),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(&echoed, b"ping");
+    }
+
+    /// Open a proxied WebSocket to `dev_server` with `keepalive` applied
+    async fn keepalive_socket(
+        dev_server: &str,
+        keepalive: impl FnOnce(heisenberg::SpaRouteBuilder) -> heisenberg::SpaRouteBuilder,
+    ) -> TcpStream {
+        let route = Heisenberg::new()
+            .mode_override(Mode::Development)
+            .ci_mode(true)
+            .spa("./dist")
+            .dev_server(dev_server);
+        let addr = serve(keepalive(route).build()).await;
+        let mut client = TcpStream::connect(&addr).await.unwrap();
+        client
+            .write_all(format!("GET /hmr HTTP/1.1\r\nHost: {}\r\n{}", addr, HANDSHAKE).as_bytes())
+            .await
+            .unwrap();
+        assert!(read_head(&mut client).await.starts_with("HTTP/1.1 101"));
+        client
+    }
+
+    #[tokio::test]
+    async fn test_websocket_keepalive_pings_between_frames() {
+        let (dev_server, _) = echo_dev_server().await;
+        let mut client = keepalive_socket(&dev_server, |route| {
+            route.websocket_ping_interval(std::time::Duration::from_millis(100))
+        })
+        .await;
+
+        // A masked text frame with "hi", echoed back as it is
+        let frame = [0x81, 0x82, 1, 2, 3, 4, b'h' ^ 1, b'i' ^ 2];
+        client.write_all(&frame).await.unwrap();
+        let mut echoed = [0u8; 8];
+        client.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(echoed, frame);
+
+        let mut ping = [0u8; 2];
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.read_exact(&mut ping),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(ping, [0x89, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_idle_timeout_closes_quiet_connections() {
+        let (dev_server, _) = echo_dev_server().await;
+        let mut client = keepalive_socket(&dev_server, |route| {
+            route.websocket_idle_timeout(std::time::Duration::from_millis(200))
+        })
+        .await;
+
+        let mut rest = Vec::new();
+        tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.read_to_end(&mut rest),
+        )
+        .await
+        .expect("the idle connection stayed open")
+        .unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_websocket_bypass_reaches_the_inner_service() {
+        let (dev_server, _) = echo_dev_server().await;
+        let addr = serve(config(&dev_server)).await;
+
+        let mut client = TcpStream::connect(&addr).await.unwrap();
+        client
+            .write_all(
+                format!("GET /app/ws HTTP/1.1\r\nHost: {}\r\n{}", addr, HANDSHAKE).as_bytes(),
+            )
+            .await
+            .unwrap();
+        let head = read_head(&mut client).await;
+        assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+        let mut body = [0u8; 10];
+        client.read_exact(&mut body).await.unwrap();
+        assert_eq!(&body, b"app socket");
+    }
+}